@@ -9,6 +9,7 @@ pub struct HttpError {
     error_code: Option<String>,
     headers: std::collections::HashMap<String, String>,
     body: Bytes,
+    extensions: http::Extensions,
 }
 
 impl HttpError {
@@ -32,6 +33,7 @@ impl HttpError {
             headers,
             error_code,
             body,
+            extensions: http::Extensions::new(),
         }
     }
 
@@ -44,6 +46,41 @@ impl HttpError {
     pub fn error_code(&self) -> Option<&str> {
         self.error_code.as_deref()
     }
+
+    /// Get a header of the http error by its lowercase name.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).map(String::as_str)
+    }
+
+    /// Get a reference to the http error's body.
+    pub fn body(&self) -> &Bytes {
+        &self.body
+    }
+
+    /// Get a reference to the extensions a service client attached to the error, such as
+    /// diagnostics of the operation that failed.
+    pub fn extensions(&self) -> &http::Extensions {
+        &self.extensions
+    }
+
+    /// Get a mutable reference to the extensions of the http error.
+    pub fn extensions_mut(&mut self) -> &mut http::Extensions {
+        &mut self.extensions
+    }
+
+    /// Copy the status, headers, and body of the response into a new error, such as for each
+    /// of several operations that failed with the same response.
+    ///
+    /// The extensions are not copied.
+    pub fn duplicate(&self) -> Self {
+        HttpError {
+            status: self.status,
+            error_code: self.error_code.clone(),
+            headers: self.headers.clone(),
+            body: self.body.clone(),
+            extensions: http::Extensions::new(),
+        }
+    }
 }
 
 impl std::fmt::Display for HttpError {
@@ -2,7 +2,9 @@ use crate::headers::{HEADER_DATE, HEADER_VERSION};
 use crate::resources::permission::AuthorizationToken;
 use crate::resources::ResourceType;
 use crate::TimeNonce;
+use azure_core::auth::{TokenCredential, TokenResponse};
 use azure_core::{Context, Policy, PolicyResult, Request};
+use futures::lock::Mutex;
 use hmac::{Hmac, Mac};
 use http::header::AUTHORIZATION;
 use http::HeaderValue;
@@ -14,6 +16,49 @@ use url::form_urlencoded;
 const AZURE_VERSION: &str = "2018-12-31";
 const VERSION: &str = "1.0";
 
+/// How long before its expiration an AAD token is refreshed
+const TOKEN_REFRESH_MARGIN: chrono::Duration = chrono::Duration::minutes(5);
+
+/// The credentials the `AuthorizationPolicy` authenticates with
+#[derive(Clone)]
+pub(crate) enum AuthorizationCredential {
+    /// A primary or resource key signing every request
+    Token(AuthorizationToken),
+    /// Azure Active Directory tokens requested from the credential for the account's url
+    TokenCredential(Arc<dyn TokenCredential>),
+}
+
+impl From<AuthorizationToken> for AuthorizationCredential {
+    fn from(authorization_token: AuthorizationToken) -> Self {
+        AuthorizationCredential::Token(authorization_token)
+    }
+}
+
+impl PartialEq for AuthorizationCredential {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (AuthorizationCredential::Token(a), AuthorizationCredential::Token(b)) => a == b,
+            // credentials are the same only if they are the same instance
+            (
+                AuthorizationCredential::TokenCredential(a),
+                AuthorizationCredential::TokenCredential(b),
+            ) => Arc::as_ptr(a) as *const () == Arc::as_ptr(b) as *const (),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for AuthorizationCredential {}
+
+impl std::fmt::Debug for AuthorizationCredential {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            AuthorizationCredential::Token(authorization_token) => authorization_token.fmt(f),
+            AuthorizationCredential::TokenCredential(_) => write!(f, "TokenCredential"),
+        }
+    }
+}
+
 /// The `AuthorizationPolicy` takes care to authenticate your calls to Azure CosmosDB. Currently it
 /// supports two type of authorization: one at service level and another at resource level (see
 /// [AuthorizationToken] for more info). The policy must be added just before the transport policy
@@ -24,19 +69,50 @@ const VERSION: &str = "1.0";
 /// `AuthorizationPolicy`.
 /// This struct is `Debug` but secrets are encrypted by `AuthorizationToken` so there is no risk of
 /// leaks in debug logs (secrets are stored in cleartext in memory: dumps are still leaky).
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// AAD tokens are cached and shared by the clones of the policy until they are about to expire.
+#[derive(Debug, Clone)]
 pub struct AuthorizationPolicy {
-    authorization_token: AuthorizationToken,
+    credential: AuthorizationCredential,
+    /// The resource AAD tokens are requested for, that is the account url
+    resource: String,
+    aad_token: Arc<Mutex<Option<TokenResponse>>>,
 }
 
 impl AuthorizationPolicy {
-    pub(crate) fn new(authorization_token: AuthorizationToken) -> Self {
+    pub(crate) fn new(credential: AuthorizationCredential, resource: String) -> Self {
         Self {
-            authorization_token,
+            credential,
+            resource,
+            aad_token: Arc::new(Mutex::new(None)),
         }
     }
+
+    /// Returns the cached AAD token, requesting a new one if it is missing or about to expire.
+    async fn aad_token(
+        &self,
+        credential: &dyn TokenCredential,
+    ) -> azure_core::error::Result<String> {
+        // the lock is held while requesting so that concurrent requests share a single refresh
+        let mut aad_token = self.aad_token.lock().await;
+        match &*aad_token {
+            Some(token) if token.expires_on - TOKEN_REFRESH_MARGIN > chrono::Utc::now() => {}
+            _ => {
+                trace!("requesting an AAD token for {}", self.resource);
+                *aad_token = Some(credential.get_token(&self.resource).await?);
+            }
+        }
+        Ok(aad_token.as_ref().unwrap().token.secret().clone())
+    }
 }
 
+impl PartialEq for AuthorizationPolicy {
+    fn eq(&self, other: &Self) -> bool {
+        self.credential == other.credential && self.resource == other.resource
+    }
+}
+
+impl Eq for AuthorizationPolicy {}
+
 #[async_trait::async_trait]
 impl Policy for AuthorizationPolicy {
     async fn send(
@@ -57,17 +133,28 @@ impl Policy for AuthorizationPolicy {
         let uri_path = &request.uri().path_and_query().unwrap().to_string()[1..];
         trace!("uri_path used by AuthorizationPolicy == {:#?}", uri_path);
 
-        let auth = {
-            let resource_link = generate_resource_link(uri_path);
-            trace!("resource_link == {}", resource_link);
-            generate_authorization(
-                &self.authorization_token,
-                &request.method(),
-                ctx.get()
-                    .expect("ResourceType must be in the Context at this point"),
-                resource_link,
-                time_nonce,
-            )
+        let auth = match &self.credential {
+            AuthorizationCredential::TokenCredential(credential) => {
+                let token = self.aad_token(credential.as_ref()).await?;
+                encode_authorization("aad", &token)
+            }
+            AuthorizationCredential::Token(authorization_token) => {
+                let resource_type = ctx
+                    .get()
+                    .expect("ResourceType must be in the Context at this point");
+                let resource_link = match resource_type {
+                    ResourceType::Offers => Cow::Owned(generate_offer_resource_link(uri_path)),
+                    _ => Cow::Borrowed(generate_resource_link(uri_path)),
+                };
+                trace!("resource_link == {}", resource_link);
+                generate_authorization(
+                    authorization_token,
+                    &request.method(),
+                    resource_type,
+                    &resource_link,
+                    time_nonce,
+                )
+            }
         };
 
         trace!(
@@ -114,6 +201,8 @@ pub(crate) fn generate_resource_link(uri: &str) -> &str {
         "/pkranges",
         "/udfs",
         "/triggers",
+        "/conflicts",
+        "/offers",
     ];
 
     // We find the above resource names. If found, we strip it and eagerly return. Note that the
@@ -139,6 +228,15 @@ pub(crate) fn generate_resource_link(uri: &str) -> &str {
     }
 }
 
+/// Offers are addressed by their resource id rather than by name, so the resource link is the
+/// offer id alone and it must be lowercase. Listing and querying offers has an empty resource link.
+fn generate_offer_resource_link(uri: &str) -> String {
+    generate_resource_link(uri)
+        .trim_start_matches("offers")
+        .trim_start_matches('/')
+        .to_lowercase()
+}
+
 /// The CosmosDB authorization can either be "primary" (ie one of the two service-level tokens) or
 /// "resource" (ie a single database). In the first case the signature must be constructed by
 /// signing the HTTP method, resource type, resource link (the relative URI) and the current time.
@@ -163,6 +261,11 @@ pub(crate) fn generate_authorization(
         AuthorizationToken::Resource(key) => ("resource", Cow::Borrowed(key)),
     };
 
+    encode_authorization(authorization_type, &signature)
+}
+
+/// Builds the url encoded authorization header out of the authorization type and its signature.
+fn encode_authorization(authorization_type: &str, signature: &str) -> String {
     let str_unencoded = format!(
         "type={}&ver={}&sig={}",
         authorization_type, VERSION, signature
@@ -218,6 +321,9 @@ fn string_to_sign(
             ResourceType::PartitionKeyRanges => "pkranges",
             ResourceType::UserDefinedFunctions => "udfs",
             ResourceType::Triggers => "triggers",
+            ResourceType::Conflicts => "conflicts",
+            ResourceType::Offers => "offers",
+            ResourceType::DatabaseAccount => "",
         },
         resource_link,
         time_nonce.to_string().to_lowercase()
@@ -239,6 +345,123 @@ fn encode_str_to_sign(data: &str, key: &[u8]) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::mock_endpoint::{MockEndpoint, MockResponse};
+    use crate::prelude::*;
+    use oauth2::AccessToken;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Hands out `token-<n>` tokens expiring after `lifetime`
+    #[derive(Debug)]
+    struct MockCredential {
+        lifetime: chrono::Duration,
+        requests: AtomicUsize,
+        resources: std::sync::Mutex<Vec<String>>,
+    }
+
+    impl MockCredential {
+        fn new(lifetime: chrono::Duration) -> Arc<Self> {
+            Arc::new(Self {
+                lifetime,
+                requests: AtomicUsize::new(0),
+                resources: std::sync::Mutex::new(Vec::new()),
+            })
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl TokenCredential for MockCredential {
+        async fn get_token(&self, resource: &str) -> azure_core::error::Result<TokenResponse> {
+            let n = self.requests.fetch_add(1, Ordering::SeqCst) + 1;
+            self.resources.lock().unwrap().push(resource.to_owned());
+            Ok(TokenResponse::new(
+                AccessToken::new(format!("token-{}", n)),
+                chrono::Utc::now() + self.lifetime,
+            ))
+        }
+    }
+
+    async fn get_database_twice(credential: Arc<MockCredential>) -> MockEndpoint {
+        let database = || {
+            MockResponse::new(
+                200,
+                r#"{"id":"db","_rid":"a","_ts":1,"_self":"b","_etag":"c","_colls":"colls/","_users":"users/"}"#,
+            )
+            .with_cosmos_headers()
+            .with_header("etag", "c")
+        };
+        let endpoint = MockEndpoint::start(vec![database(), database()]);
+        let client = CosmosClient::new_custom_with_token_credential(
+            "mock".to_owned(),
+            credential,
+            endpoint.url(),
+            CosmosOptions::default(),
+        );
+        for _ in 0..2 {
+            client
+                .database_client("db")
+                .get_database()
+                .into_future()
+                .await
+                .unwrap();
+        }
+        endpoint
+    }
+
+    #[tokio::test]
+    async fn authorizes_with_cached_aad_tokens() {
+        let credential = MockCredential::new(chrono::Duration::hours(1));
+
+        let endpoint = get_database_twice(credential.clone()).await;
+
+        assert_eq!(credential.requests.load(Ordering::SeqCst), 1);
+        assert_eq!(*credential.resources.lock().unwrap(), [endpoint.url()]);
+        for request in endpoint.requests() {
+            assert_eq!(
+                request.header("authorization"),
+                Some("type%3Daad%26ver%3D1.0%26sig%3Dtoken-1")
+            );
+            assert!(request.header("x-ms-date").is_some());
+        }
+    }
+
+    #[tokio::test]
+    async fn requests_aad_tokens_for_the_custom_uri_without_trailing_slash() {
+        let credential = MockCredential::new(chrono::Duration::hours(1));
+        let endpoint = MockEndpoint::start(vec![MockResponse::new(
+            200,
+            r#"{"id":"db","_rid":"a","_ts":1,"_self":"b","_etag":"c","_colls":"colls/","_users":"users/"}"#,
+        )
+        .with_cosmos_headers()
+        .with_header("etag", "c")]);
+        let client = CosmosClient::new_custom_with_token_credential(
+            "mock".to_owned(),
+            credential.clone(),
+            format!("{}/", endpoint.url()),
+            CosmosOptions::default(),
+        );
+
+        client
+            .database_client("db")
+            .get_database()
+            .into_future()
+            .await
+            .unwrap();
+
+        assert_eq!(*credential.resources.lock().unwrap(), [endpoint.url()]);
+    }
+
+    #[tokio::test]
+    async fn refreshes_aad_tokens_about_to_expire() {
+        let credential = MockCredential::new(chrono::Duration::minutes(1));
+
+        let endpoint = get_database_twice(credential.clone()).await;
+
+        assert_eq!(credential.requests.load(Ordering::SeqCst), 2);
+        assert_eq!(
+            endpoint.requests()[1].header("authorization"),
+            Some("type%3Daad%26ver%3D1.0%26sig%3Dtoken-2")
+        );
+    }
 
     #[test]
     fn string_to_sign_00() {
@@ -327,4 +550,10 @@ mon, 01 jan 1900 01:00:00 gmt
         );
         assert_eq!(generate_resource_link("dbs/test_db/colls"), "dbs/test_db");
     }
+
+    #[test]
+    fn generate_offer_resource_link_00() {
+        assert_eq!(generate_offer_resource_link("offers"), "");
+        assert_eq!(generate_offer_resource_link("offers/HDRB"), "hdrb");
+    }
 }
@@ -0,0 +1,192 @@
+use crate::prelude::*;
+use azure_core::error::{Error, ErrorKind, Result};
+use azure_core::prelude::*;
+use futures::StreamExt;
+use http::StatusCode;
+use std::time::Duration;
+
+/// A lease on the change feed of a partition key range.
+///
+/// Leases are stored as documents in the lease container, which must be partitioned by `/id`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Lease {
+    pub id: String,
+    /// The id of the partition key range the lease covers
+    pub lease_token: String,
+    /// The host processing the range, `None` if the lease is free
+    #[serde(default)]
+    pub owner: Option<String>,
+    /// The etag of the last change processed, `None` if nothing was processed yet
+    #[serde(default)]
+    pub continuation_token: Option<String>,
+    /// When the owner last renewed the lease, in seconds since the epoch
+    #[serde(default)]
+    pub timestamp: i64,
+    #[serde(rename = "_etag", default, skip_serializing)]
+    pub etag: String,
+}
+
+impl Lease {
+    /// Whether the lease can be acquired without taking it from a live host.
+    pub fn is_expired(&self, now: i64, expiration: Duration) -> bool {
+        self.owner.is_none() || now - self.timestamp >= expiration.as_secs() as i64
+    }
+
+    pub fn is_owned_by(&self, host_name: &str) -> bool {
+        self.owner.as_deref() == Some(host_name)
+    }
+}
+
+impl CosmosEntity for Lease {
+    type Entity = String;
+
+    fn partition_key(&self) -> Self::Entity {
+        self.id.clone()
+    }
+}
+
+/// The leases of a change feed processor stored in a Cosmos collection.
+///
+/// Every update is conditioned on the etag of the lease so that hosts racing for the same
+/// lease can not overwrite each other.
+#[derive(Debug, Clone)]
+pub(crate) struct LeaseStore {
+    client: CollectionClient,
+    prefix: String,
+}
+
+impl LeaseStore {
+    pub fn new(client: CollectionClient, prefix: String) -> Self {
+        Self { client, prefix }
+    }
+
+    fn lease_id(&self, partition_key_range_id: &str) -> String {
+        format!("{}..{}", self.prefix, partition_key_range_id)
+    }
+
+    /// Lists the leases of this processor.
+    pub async fn list(&self) -> Result<Vec<Lease>> {
+        let query = Query::with_params(
+            "SELECT * FROM c WHERE STARTSWITH(c.id, @prefix)".to_owned(),
+            vec![Param::new(
+                "@prefix".to_owned(),
+                format!("{}..", self.prefix),
+            )],
+        );
+        let mut stream = self
+            .client
+            .query_documents(query)
+            .query_cross_partition(true)
+            .into_stream::<Lease>();
+
+        let mut leases = Vec::new();
+        while let Some(page) = stream.next().await {
+            let page = page?.into_documents()?;
+            leases.extend(page.results.into_iter().map(|result| result.result));
+        }
+        Ok(leases)
+    }
+
+    /// Creates the lease of a partition key range.
+    ///
+    /// Returns `None` if another host created it first.
+    pub async fn create(
+        &self,
+        partition_key_range_id: &str,
+        continuation_token: Option<String>,
+        owner: Option<String>,
+    ) -> Result<Option<Lease>> {
+        let mut lease = Lease {
+            id: self.lease_id(partition_key_range_id),
+            lease_token: partition_key_range_id.to_owned(),
+            owner,
+            continuation_token,
+            timestamp: chrono::Utc::now().timestamp(),
+            etag: String::new(),
+        };
+        match self
+            .client
+            .create_document(lease.clone())
+            .into_future()
+            .await
+        {
+            Ok(response) => {
+                lease.etag = response.etag;
+                Ok(Some(lease))
+            }
+            Err(error) if has_status(&error, StatusCode::CONFLICT) => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Replaces a lease unless it was changed since it was read.
+    ///
+    /// Returns the updated lease, or `None` if another host changed or deleted it.
+    pub async fn replace(&self, lease: &Lease) -> Result<Option<Lease>> {
+        let response = self
+            .client
+            .document_client(lease.id.clone(), &lease.id)?
+            .replace_document(lease.clone())
+            .if_match_condition(IfMatchCondition::Match(lease.etag.clone()))
+            .into_future()
+            .await;
+        match response {
+            Ok(response) => Ok(Some(Lease {
+                etag: response.document_attributes.etag().to_owned(),
+                ..lease.clone()
+            })),
+            Err(error) if is_lost(&error) => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Deletes a lease unless it was changed since it was read.
+    pub async fn delete(&self, lease: &Lease) -> Result<()> {
+        let response = self
+            .client
+            .document_client(lease.id.clone(), &lease.id)?
+            .delete_document()
+            .if_match_condition(IfMatchCondition::Match(lease.etag.clone()))
+            .into_future()
+            .await;
+        match response {
+            Ok(_) => Ok(()),
+            Err(error) if is_lost(&error) => Ok(()),
+            Err(error) => Err(error),
+        }
+    }
+}
+
+/// Whether a lease update failed because another host changed or deleted the lease
+fn is_lost(error: &Error) -> bool {
+    has_status(error, StatusCode::PRECONDITION_FAILED) || has_status(error, StatusCode::NOT_FOUND)
+}
+
+fn has_status(error: &Error, expected: StatusCode) -> bool {
+    matches!(error.kind(), ErrorKind::HttpResponse { status, .. } if *status == expected.as_u16())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lease_serialization() {
+        let lease: Lease = serde_json::from_str(
+            r#"{"id":"orders..0","leaseToken":"0","owner":"host-1","continuationToken":"\"10\"","timestamp":100,
+                "_rid":"a","_self":"b","_etag":"\"l1\"","_attachments":"attachments/","_ts":100}"#,
+        )
+        .unwrap();
+        assert_eq!(lease.etag, "\"l1\"");
+        assert!(lease.is_owned_by("host-1"));
+        assert!(!lease.is_expired(159, Duration::from_secs(60)));
+        assert!(lease.is_expired(160, Duration::from_secs(60)));
+
+        let serialized = serde_json::to_string(&lease).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"id":"orders..0","leaseToken":"0","owner":"host-1","continuationToken":"\"10\"","timestamp":100}"#
+        );
+    }
+}
@@ -0,0 +1,125 @@
+use super::lease::Lease;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Picks the leases a host should acquire so that every live host owns about the same number of leases.
+///
+/// Free and expired leases are taken first. A host only steals from another host when no such
+/// lease is left and the other host owns more than its share, and then only one lease at a
+/// time so that hosts joining at the same time do not take turns stealing everything.
+pub(crate) fn leases_to_acquire<'a>(
+    leases: &'a [Lease],
+    host_name: &str,
+    now: i64,
+    expiration: Duration,
+) -> Vec<&'a Lease> {
+    if leases.is_empty() {
+        return Vec::new();
+    }
+
+    let mut owned: HashMap<&str, Vec<&Lease>> = HashMap::new();
+    owned.insert(host_name, Vec::new());
+    let mut available = Vec::new();
+    for lease in leases {
+        match &lease.owner {
+            Some(owner) if !lease.is_expired(now, expiration) => {
+                owned.entry(owner.as_str()).or_default().push(lease)
+            }
+            _ => available.push(lease),
+        }
+    }
+
+    let hosts = owned.len();
+    let target = leases.len().div_ceil(hosts);
+    let mine = owned[host_name].len();
+    if mine >= target {
+        return Vec::new();
+    }
+
+    if !available.is_empty() {
+        available.truncate(target - mine);
+        return available;
+    }
+
+    owned
+        .into_iter()
+        .filter(|(owner, leases)| *owner != host_name && leases.len() > target)
+        .max_by_key(|(_, leases)| leases.len())
+        .and_then(|(_, leases)| leases.first().copied())
+        .into_iter()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPIRATION: Duration = Duration::from_secs(60);
+    const NOW: i64 = 1000;
+
+    fn lease(range: &str, owner: Option<&str>, timestamp: i64) -> Lease {
+        Lease {
+            id: format!("test..{}", range),
+            lease_token: range.to_owned(),
+            owner: owner.map(ToOwned::to_owned),
+            continuation_token: None,
+            timestamp,
+            etag: String::new(),
+        }
+    }
+
+    fn tokens(leases: Vec<&Lease>) -> Vec<&str> {
+        leases
+            .into_iter()
+            .map(|lease| lease.lease_token.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn single_host_takes_every_free_lease() {
+        let leases = vec![lease("0", None, 0), lease("1", None, 0)];
+        assert_eq!(
+            tokens(leases_to_acquire(&leases, "a", NOW, EXPIRATION)),
+            ["0", "1"]
+        );
+    }
+
+    #[test]
+    fn takes_expired_leases_up_to_its_share() {
+        let leases = vec![
+            lease("0", Some("b"), NOW),
+            lease("1", Some("b"), NOW - 120),
+            lease("2", Some("b"), NOW - 120),
+            lease("3", None, 0),
+        ];
+        assert_eq!(
+            tokens(leases_to_acquire(&leases, "a", NOW, EXPIRATION)),
+            ["1", "2"]
+        );
+    }
+
+    #[test]
+    fn steals_one_lease_from_an_overloaded_host() {
+        let leases = vec![
+            lease("0", Some("b"), NOW),
+            lease("1", Some("b"), NOW),
+            lease("2", Some("b"), NOW),
+            lease("3", Some("c"), NOW),
+        ];
+        assert_eq!(
+            tokens(leases_to_acquire(&leases, "a", NOW, EXPIRATION)),
+            ["0"]
+        );
+    }
+
+    #[test]
+    fn keeps_a_balanced_distribution() {
+        let leases = vec![
+            lease("0", Some("a"), NOW),
+            lease("1", Some("b"), NOW),
+            lease("2", Some("b"), NOW),
+        ];
+        assert!(leases_to_acquire(&leases, "a", NOW, EXPIRATION).is_empty());
+        assert!(leases_to_acquire(&leases, "b", NOW, EXPIRATION).is_empty());
+    }
+}
@@ -0,0 +1,705 @@
+//! Processes the change feed of a collection across multiple hosts.
+//!
+//! Every host running a [`ChangeFeedProcessor`] with the same lease container and lease prefix
+//! takes a share of the partition key ranges of the monitored collection. The processor keeps a
+//! lease document per range in the lease container, recording the host owning the range and the
+//! last change processed. Hosts renew their leases while they run; the leases of hosts that stop
+//! or crash expire and are taken over by the remaining hosts, and new hosts steal leases until
+//! every host owns about the same number of ranges.
+//!
+//! Changes are delivered at least once: the position is only checkpointed after the handler
+//! succeeded, so a batch may be delivered again if a host fails before checkpointing it.
+
+mod lease;
+mod load_balancing;
+
+use crate::operations::{ChangeFeedContinuation, ChangeFeedRange, ChangeFeedStartFrom};
+use crate::prelude::*;
+use azure_core::error::Result;
+use futures::{Future, StreamExt};
+use lease::{Lease, LeaseStore};
+use serde::de::DeserializeOwned;
+use std::collections::HashSet;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// Information about a batch of changes passed to a [`ChangeFeedHandler`].
+#[derive(Debug, Clone)]
+pub struct ChangeFeedProcessorContext {
+    /// The partition key range the changes were read from
+    pub partition_key_range_id: String,
+    /// The request units consumed to read the changes
+    pub charge: f64,
+    /// The session token of the read
+    pub session_token: Option<String>,
+}
+
+/// Handles the changes read by a [`ChangeFeedProcessor`].
+///
+/// The changes of a partition key range are passed in order. Returning an error leaves the
+/// batch unprocessed, and it is passed again in a later cycle.
+///
+/// This is implemented for async closures taking a [`ChangeFeedProcessorContext`] and the
+/// changed documents.
+#[async_trait::async_trait]
+pub trait ChangeFeedHandler<T>: Send + Sync {
+    /// Handles a batch of changed documents.
+    async fn handle_changes(
+        &self,
+        context: ChangeFeedProcessorContext,
+        changes: Vec<Document<T>>,
+    ) -> Result<()>;
+}
+
+#[async_trait::async_trait]
+impl<T, F, Fut> ChangeFeedHandler<T> for F
+where
+    F: Fn(ChangeFeedProcessorContext, Vec<Document<T>>) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<()>> + Send,
+    T: Send + 'static,
+{
+    async fn handle_changes(
+        &self,
+        context: ChangeFeedProcessorContext,
+        changes: Vec<Document<T>>,
+    ) -> Result<()> {
+        self(context, changes).await
+    }
+}
+
+/// Builds a [`ChangeFeedProcessor`].
+///
+/// Created with [`CollectionClient::change_feed_processor`].
+#[derive(Debug, Clone)]
+pub struct ChangeFeedProcessorBuilder<D, H> {
+    client: CollectionClient,
+    lease_client: CollectionClient,
+    host_name: String,
+    handler: H,
+    lease_prefix: String,
+    start_from: ChangeFeedStartFrom,
+    max_item_count: i32,
+    poll_interval: Duration,
+    lease_expiration: Duration,
+    lease_renew_interval: Duration,
+    document_type: PhantomData<fn() -> D>,
+}
+
+impl<D, H> ChangeFeedProcessorBuilder<D, H>
+where
+    D: DeserializeOwned + Send + Sync + 'static,
+    H: ChangeFeedHandler<D>,
+{
+    pub(crate) fn new(
+        client: CollectionClient,
+        lease_client: CollectionClient,
+        host_name: String,
+        handler: H,
+    ) -> Self {
+        let lease_prefix = client.collection_name().to_owned();
+        Self {
+            client,
+            lease_client,
+            host_name,
+            handler,
+            lease_prefix,
+            start_from: ChangeFeedStartFrom::Beginning,
+            max_item_count: 100,
+            poll_interval: Duration::from_secs(5),
+            lease_expiration: Duration::from_secs(60),
+            lease_renew_interval: Duration::from_secs(17),
+            document_type: PhantomData,
+        }
+    }
+
+    /// Sets the prefix of the lease ids, which defaults to the name of the monitored collection.
+    ///
+    /// Processors sharing a lease collection must use different prefixes.
+    pub fn lease_prefix(self, lease_prefix: impl Into<String>) -> Self {
+        Self {
+            lease_prefix: lease_prefix.into(),
+            ..self
+        }
+    }
+
+    /// Sets where ranges without a checkpoint start reading. The default is the beginning.
+    pub fn start_from(self, start_from: ChangeFeedStartFrom) -> Self {
+        Self { start_from, ..self }
+    }
+
+    /// Sets the maximum number of changes passed to the handler at once. The default is 100.
+    pub fn max_item_count(self, max_item_count: i32) -> Self {
+        Self {
+            max_item_count,
+            ..self
+        }
+    }
+
+    /// Sets how long to wait between cycles. The default is 5 seconds.
+    pub fn poll_interval(self, poll_interval: Duration) -> Self {
+        Self {
+            poll_interval,
+            ..self
+        }
+    }
+
+    /// Sets how long a lease stays with a host that stopped renewing it. The default is 60 seconds.
+    pub fn lease_expiration(self, lease_expiration: Duration) -> Self {
+        Self {
+            lease_expiration,
+            ..self
+        }
+    }
+
+    /// Sets how often the leases of this host are renewed. The default is 17 seconds.
+    pub fn lease_renew_interval(self, lease_renew_interval: Duration) -> Self {
+        Self {
+            lease_renew_interval,
+            ..self
+        }
+    }
+
+    /// Creates the processor.
+    pub fn build(self) -> ChangeFeedProcessor<D, H> {
+        ChangeFeedProcessor {
+            client: self.client,
+            leases: LeaseStore::new(self.lease_client, self.lease_prefix),
+            host_name: self.host_name,
+            handler: self.handler,
+            start_from: self.start_from,
+            max_item_count: self.max_item_count,
+            poll_interval: self.poll_interval,
+            lease_expiration: self.lease_expiration,
+            lease_renew_interval: self.lease_renew_interval,
+            stopped: AtomicBool::new(false),
+            document_type: PhantomData,
+        }
+    }
+}
+
+/// Distributes the change feed of a collection across hosts and passes the changes to a handler.
+///
+/// See the [module documentation](self) for how the work is shared.
+#[derive(Debug)]
+pub struct ChangeFeedProcessor<D, H> {
+    client: CollectionClient,
+    leases: LeaseStore,
+    host_name: String,
+    handler: H,
+    start_from: ChangeFeedStartFrom,
+    max_item_count: i32,
+    poll_interval: Duration,
+    lease_expiration: Duration,
+    lease_renew_interval: Duration,
+    stopped: AtomicBool,
+    document_type: PhantomData<fn() -> D>,
+}
+
+impl<D, H> ChangeFeedProcessor<D, H>
+where
+    D: DeserializeOwned + Send + Sync + 'static,
+    H: ChangeFeedHandler<D>,
+{
+    /// The name identifying this host in the leases
+    pub fn host_name(&self) -> &str {
+        &self.host_name
+    }
+
+    /// Processes changes until [`stop`](Self::stop) is called, then releases the leases of this host.
+    ///
+    /// Failures are logged and retried in the next cycle.
+    pub async fn run(&self) -> Result<()> {
+        while !self.stopped.load(Ordering::SeqCst) {
+            if let Err(error) = self.run_once().await {
+                warn!(
+                    "change feed processor {} failed to process leases: {}",
+                    self.host_name, error
+                );
+            }
+            if self.stopped.load(Ordering::SeqCst) {
+                break;
+            }
+            azure_core::sleep(self.poll_interval).await;
+        }
+        self.release_leases().await
+    }
+
+    /// Makes [`run`](Self::run) return after the current cycle.
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::SeqCst);
+    }
+
+    /// Runs a single cycle: creates the leases of new partition key ranges, renews and acquires
+    /// leases, and processes the pending changes of the leases owned by this host.
+    ///
+    /// Returns the number of changes passed to the handler.
+    pub async fn run_once(&self) -> Result<usize> {
+        let mut leases = self.leases.list().await?;
+        leases.extend(self.create_missing_leases(&leases).await?);
+
+        let now = chrono::Utc::now().timestamp();
+        let mut owned = Vec::new();
+        for lease in &leases {
+            if !lease.is_owned_by(&self.host_name) || lease.is_expired(now, self.lease_expiration) {
+                continue;
+            }
+            if now - lease.timestamp < self.lease_renew_interval.as_secs() as i64 {
+                owned.push(lease.clone());
+            } else if let Some(lease) = self.update_lease(lease, Some(&self.host_name)).await? {
+                owned.push(lease);
+            } else {
+                info!(
+                    "{} lost the lease of range {}",
+                    self.host_name, lease.lease_token
+                );
+            }
+        }
+
+        let to_acquire =
+            load_balancing::leases_to_acquire(&leases, &self.host_name, now, self.lease_expiration);
+        for lease in to_acquire {
+            if let Some(lease) = self.update_lease(lease, Some(&self.host_name)).await? {
+                info!(
+                    "{} acquired the lease of range {}",
+                    self.host_name, lease.lease_token
+                );
+                owned.push(lease);
+            }
+        }
+
+        let processed =
+            futures::future::join_all(owned.into_iter().map(|lease| self.process_lease(lease)))
+                .await;
+        let mut count = 0;
+        for result in processed {
+            match result {
+                Ok(processed) => count += processed,
+                Err(error) => warn!(
+                    "change feed processor {} failed to process changes: {}",
+                    self.host_name, error
+                ),
+            }
+        }
+        Ok(count)
+    }
+
+    /// Creates the leases of the partition key ranges that have none.
+    ///
+    /// Ranges split from a range that still has a lease are skipped: the host owning the lease
+    /// of the parent creates them when it notices the split, so they continue from where the
+    /// parent stopped.
+    async fn create_missing_leases(&self, leases: &[Lease]) -> Result<Vec<Lease>> {
+        let leased: HashSet<&str> = leases
+            .iter()
+            .map(|lease| lease.lease_token.as_str())
+            .collect();
+        let ranges = self
+            .client
+            .get_partition_key_ranges()
+            .into_future()
+            .await?
+            .partition_key_ranges;
+
+        let mut created = Vec::new();
+        for range in ranges {
+            if leased.contains(range.id.as_str())
+                || range
+                    .parents
+                    .iter()
+                    .any(|parent| leased.contains(parent.as_str()))
+            {
+                continue;
+            }
+            let continuation_token = self.initial_etag(&range.id);
+            if let Some(lease) = self
+                .leases
+                .create(&range.id, continuation_token, None)
+                .await?
+            {
+                created.push(lease);
+            }
+        }
+        Ok(created)
+    }
+
+    /// The etag new leases start reading from
+    fn initial_etag(&self, partition_key_range_id: &str) -> Option<String> {
+        match &self.start_from {
+            ChangeFeedStartFrom::Now => Some("*".to_owned()),
+            ChangeFeedStartFrom::Continuation(continuation) => continuation
+                .ranges()
+                .iter()
+                .find(|range| range.partition_key_range_id == partition_key_range_id)
+                .and_then(|range| range.etag.clone()),
+            ChangeFeedStartFrom::Beginning | ChangeFeedStartFrom::PointInTime(_) => None,
+        }
+    }
+
+    fn start_time(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        match &self.start_from {
+            ChangeFeedStartFrom::PointInTime(start_time) => Some(*start_time),
+            ChangeFeedStartFrom::Continuation(continuation) => continuation.start_time(),
+            ChangeFeedStartFrom::Beginning | ChangeFeedStartFrom::Now => None,
+        }
+    }
+
+    /// Sets the owner of a lease and renews it, returning `None` if another host changed it.
+    async fn update_lease(&self, lease: &Lease, owner: Option<&str>) -> Result<Option<Lease>> {
+        let lease = Lease {
+            owner: owner.map(ToOwned::to_owned),
+            timestamp: chrono::Utc::now().timestamp(),
+            ..lease.clone()
+        };
+        self.leases.replace(&lease).await
+    }
+
+    /// Reads the pending changes of a lease, passes them to the handler and checkpoints them.
+    async fn process_lease(&self, mut lease: Lease) -> Result<usize> {
+        let continuation = ChangeFeedContinuation::new(
+            vec![ChangeFeedRange {
+                partition_key_range_id: lease.lease_token.clone(),
+                etag: lease.continuation_token.clone(),
+            }],
+            self.start_time(),
+        );
+        let mut stream = self
+            .client
+            .read_change_feed()
+            .start_from(ChangeFeedStartFrom::Continuation(continuation))
+            .max_item_count(self.max_item_count)
+            .into_stream::<D>();
+
+        let mut count = 0;
+        let mut last_continuation = None;
+        while let Some(page) = stream.next().await {
+            let page = page?;
+            if !page.documents.is_empty() {
+                let context = ChangeFeedProcessorContext {
+                    partition_key_range_id: page
+                        .partition_key_range_id
+                        .clone()
+                        .unwrap_or_else(|| lease.lease_token.clone()),
+                    charge: page.charge,
+                    session_token: page.session_token.clone(),
+                };
+                count += page.documents.len();
+                self.handler.handle_changes(context, page.documents).await?;
+            }
+
+            // checkpoint while the lease still covers a single range, splits are
+            // checkpointed in the leases of the new ranges
+            if let [range] = page.continuation.ranges() {
+                if range.partition_key_range_id == lease.lease_token
+                    && range.etag.is_some()
+                    && range.etag != lease.continuation_token
+                {
+                    lease.continuation_token = range.etag.clone();
+                    lease = match self.update_lease(&lease, Some(&self.host_name)).await? {
+                        Some(lease) => lease,
+                        None => {
+                            info!(
+                                "{} lost the lease of range {}",
+                                self.host_name, lease.lease_token
+                            );
+                            return Ok(count);
+                        }
+                    };
+                }
+            }
+            last_continuation = Some(page.continuation);
+        }
+
+        if let Some(continuation) = last_continuation {
+            if continuation.ranges().len() != 1
+                || continuation.ranges()[0].partition_key_range_id != lease.lease_token
+            {
+                self.split_lease(&lease, &continuation).await?;
+            }
+        }
+        Ok(count)
+    }
+
+    /// Replaces the lease of a split range with leases for the ranges it was split into.
+    async fn split_lease(
+        &self,
+        lease: &Lease,
+        continuation: &ChangeFeedContinuation,
+    ) -> Result<()> {
+        for range in continuation.ranges() {
+            self.leases
+                .create(
+                    &range.partition_key_range_id,
+                    range.etag.clone(),
+                    Some(self.host_name.clone()),
+                )
+                .await?;
+        }
+        self.leases.delete(lease).await?;
+        info!(
+            "{} split the lease of range {} into {:?}",
+            self.host_name,
+            lease.lease_token,
+            continuation
+                .ranges()
+                .iter()
+                .map(|range| range.partition_key_range_id.as_str())
+                .collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+
+    /// Frees the leases owned by this host so that other hosts can take them over right away.
+    async fn release_leases(&self) -> Result<()> {
+        for lease in self.leases.list().await? {
+            if lease.is_owned_by(&self.host_name) {
+                self.update_lease(&lease, None).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_endpoint::{MockEndpoint, MockResponse};
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    struct MyDocument {
+        id: String,
+    }
+
+    const ATTRIBUTES: &str = r#""_rid":"a","_self":"b","_attachments":"attachments/","_ts":1"#;
+
+    fn lease_document(range: &str, owner: Option<&str>, timestamp: i64, etag: &str) -> String {
+        format!(
+            r#"{{"id":"orders..{range}","leaseToken":"{range}","owner":{},"continuationToken":null,"timestamp":{timestamp},"_etag":"{etag}",{ATTRIBUTES}}}"#,
+            owner.map_or("null".to_owned(), |owner| format!("\"{}\"", owner))
+        )
+    }
+
+    /// The responses to listing the leases: the query plan, the lease ranges, and the leases
+    fn leases_responses(documents: &[String]) -> Vec<MockResponse> {
+        vec![
+            MockResponse::new(
+                200,
+                r#"{"queryInfo":{},"queryRanges":[{"min":"","max":"FF"}]}"#,
+            )
+            .with_cosmos_headers(),
+            ranges_response(&[("0", &[])]),
+            MockResponse::new(
+                200,
+                format!(
+                    r#"{{"_rid":"x","Documents":[{}],"_count":{}}}"#,
+                    documents.join(","),
+                    documents.len()
+                ),
+            )
+            .with_cosmos_headers(),
+        ]
+    }
+
+    fn ranges_response(ranges: &[(&str, &[&str])]) -> MockResponse {
+        let ranges: Vec<_> = ranges
+            .iter()
+            .map(|(id, parents)| {
+                format!(
+                    r#"{{"_rid":"r{id}","id":"{id}","_etag":"e","minInclusive":"","maxExclusive":"FF","ridPrefix":0,"_self":"s","throughputFraction":1,"status":"online","parents":{:?},"_ts":1}}"#,
+                    parents
+                )
+            })
+            .collect();
+        MockResponse::new(
+            200,
+            format!(
+                r#"{{"_rid":"x","PartitionKeyRanges":[{}],"_count":{}}}"#,
+                ranges.join(","),
+                ranges.len()
+            ),
+        )
+        .with_cosmos_headers()
+    }
+
+    fn lease_written(status: u16, etag: &str) -> MockResponse {
+        MockResponse::new(
+            status,
+            format!(
+                r#"{{"id":"x","_etag":{},{ATTRIBUTES}}}"#,
+                serde_json::to_string(etag).unwrap()
+            ),
+        )
+        .with_cosmos_headers()
+        .with_header("etag", etag)
+    }
+
+    fn changes(etag: &str, ids: &[&str]) -> MockResponse {
+        let documents: Vec<_> = ids
+            .iter()
+            .map(|id| format!(r#"{{"id":"{id}","_etag":"d",{ATTRIBUTES}}}"#))
+            .collect();
+        MockResponse::new(
+            200,
+            format!(
+                r#"{{"_rid":"x","Documents":[{}],"_count":{}}}"#,
+                documents.join(","),
+                ids.len()
+            ),
+        )
+        .with_header("etag", etag)
+        .with_header("x-ms-request-charge", "2")
+    }
+
+    fn not_modified(etag: &str) -> MockResponse {
+        MockResponse::new(304, "").with_header("etag", etag)
+    }
+
+    fn processor(
+        endpoint: &MockEndpoint,
+        handled: Arc<Mutex<Vec<String>>>,
+    ) -> ChangeFeedProcessor<MyDocument, impl ChangeFeedHandler<MyDocument>> {
+        let database = endpoint.cosmos_client().database_client("db");
+        database
+            .collection_client("orders")
+            .change_feed_processor(
+                database.collection_client("leases"),
+                "host-1",
+                move |_context: ChangeFeedProcessorContext, changes: Vec<Document<MyDocument>>| {
+                    let handled = handled.clone();
+                    async move {
+                        handled
+                            .lock()
+                            .unwrap()
+                            .extend(changes.into_iter().map(|change| change.document.id));
+                        Ok(())
+                    }
+                },
+            )
+            .build()
+    }
+
+    type Handler = fn(
+        ChangeFeedProcessorContext,
+        Vec<Document<MyDocument>>,
+    ) -> futures::future::Ready<Result<()>>;
+
+    #[allow(dead_code)]
+    fn ensure_send(processor: ChangeFeedProcessor<MyDocument, Handler>) {
+        fn is_send<F: Send>(_: F) {}
+        is_send(processor.run());
+    }
+
+    #[tokio::test]
+    async fn acquires_free_lease_and_checkpoints() {
+        let mut responses = leases_responses(&[lease_document("0", None, 0, "\\\"l1\\\"")]);
+        responses.extend([
+            ranges_response(&[("0", &[])]),
+            lease_written(200, "\"l2\""),
+            changes("\"10\"", &["a", "b"]),
+            lease_written(200, "\"l3\""),
+            not_modified("\"10\""),
+        ]);
+        let endpoint = MockEndpoint::start(responses);
+        let handled = Arc::new(Mutex::new(Vec::new()));
+
+        let count = processor(&endpoint, handled.clone())
+            .run_once()
+            .await
+            .unwrap();
+
+        assert_eq!(count, 2);
+        assert_eq!(*handled.lock().unwrap(), ["a", "b"]);
+        let requests = endpoint.requests();
+        assert_eq!(requests[0].path, "/dbs/db/colls/leases/docs");
+        assert_eq!(
+            requests[0].header("x-ms-cosmos-is-query-plan-request"),
+            Some("True")
+        );
+        assert_eq!(requests[1].path, "/dbs/db/colls/leases/pkranges");
+        assert!(requests[2].body.contains("STARTSWITH"));
+        let requests = &requests[2..];
+        assert_eq!(requests[1].path, "/dbs/db/colls/orders/pkranges");
+
+        let acquire = &requests[2];
+        assert_eq!(acquire.method, "PUT");
+        assert_eq!(acquire.path, "/dbs/db/colls/leases/docs/orders..0");
+        assert_eq!(acquire.header("if-match"), Some("\"l1\""));
+        assert!(acquire.body.contains(r#""owner":"host-1""#));
+
+        assert_eq!(requests[3].path, "/dbs/db/colls/orders/docs");
+        assert_eq!(requests[3].header("if-none-match"), None);
+        let checkpoint = &requests[4];
+        assert_eq!(checkpoint.header("if-match"), Some("\"l2\""));
+        assert!(checkpoint.body.contains(r#""continuationToken":"\"10\"""#));
+        assert_eq!(requests[5].header("if-none-match"), Some("\"10\""));
+    }
+
+    #[tokio::test]
+    async fn creates_leases_for_new_ranges() {
+        let mut responses = leases_responses(&[]);
+        responses.extend([
+            ranges_response(&[("0", &[])]),
+            lease_written(201, "\"l1\""),
+            lease_written(200, "\"l2\""),
+            not_modified("\"10\""),
+            lease_written(200, "\"l3\""),
+        ]);
+        let endpoint = MockEndpoint::start(responses);
+
+        let count = processor(&endpoint, Arc::default())
+            .run_once()
+            .await
+            .unwrap();
+
+        assert_eq!(count, 0);
+        let requests = &endpoint.requests()[2..];
+        let create = &requests[2];
+        assert_eq!(create.method, "POST");
+        assert_eq!(create.path, "/dbs/db/colls/leases/docs");
+        assert_eq!(
+            create.header("x-ms-documentdb-partitionkey"),
+            Some(r#"["orders..0"]"#)
+        );
+        assert!(create.body.contains(r#""leaseToken":"0""#));
+        assert_eq!(requests[3].header("if-match"), Some("\"l1\""));
+        assert!(requests[5].body.contains(r#""continuationToken":"\"10\"""#));
+    }
+
+    #[tokio::test]
+    async fn splits_leases_of_split_ranges() {
+        let now = chrono::Utc::now().timestamp();
+        let mut responses =
+            leases_responses(&[lease_document("0", Some("host-1"), now, "\\\"l1\\\"")]);
+        responses.extend([
+            ranges_response(&[("1", &["0"]), ("2", &["0"])]),
+            MockResponse::new(410, r#"{"code":"Gone","message":"gone"}"#),
+            ranges_response(&[("1", &["0"]), ("2", &["0"])]),
+            changes("\"12\"", &["c"]),
+            not_modified("\"12\""),
+            not_modified("\"13\""),
+            lease_written(201, "\"l4\""),
+            MockResponse::new(409, r#"{"code":"Conflict","message":"exists"}"#),
+            MockResponse::new(204, "").with_cosmos_headers(),
+        ]);
+        let endpoint = MockEndpoint::start(responses);
+        let handled = Arc::new(Mutex::new(Vec::new()));
+
+        let count = processor(&endpoint, handled.clone())
+            .run_once()
+            .await
+            .unwrap();
+
+        assert_eq!(count, 1);
+        assert_eq!(*handled.lock().unwrap(), ["c"]);
+        let requests = &endpoint.requests()[2..];
+        assert_eq!(requests.len(), 10);
+        assert!(requests[7].body.contains(r#""leaseToken":"1""#));
+        assert!(requests[7].body.contains(r#""continuationToken":"\"12\"""#));
+        assert!(requests[8].body.contains(r#""leaseToken":"2""#));
+        let delete = &requests[9];
+        assert_eq!(delete.method, "DELETE");
+        assert_eq!(delete.path, "/dbs/db/colls/leases/docs/orders..0");
+        assert_eq!(delete.header("if-match"), Some("\"l1\""));
+    }
+}
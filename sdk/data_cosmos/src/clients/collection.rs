@@ -1,11 +1,14 @@
 use super::{DatabaseClient, UserDefinedFunctionClient};
+use crate::change_feed_processor::{ChangeFeedHandler, ChangeFeedProcessorBuilder};
 use crate::clients::*;
 use crate::operations::*;
 use crate::resources::collection::PartitionKey;
 use crate::resources::document::Query;
+use crate::resources::Throughput;
 use crate::CosmosEntity;
 use crate::ReadonlyString;
 use azure_core::{Pipeline, Request};
+use serde::de::DeserializeOwned;
 use serde::Serialize;
 
 /// A client for Cosmos collection resources.
@@ -45,6 +48,15 @@ impl CollectionClient {
         DocumentClient::new(self.clone(), document_name, partition_key)
     }
 
+    /// convert into a [`ConflictClient`]
+    pub fn conflict_client<S: Into<String>, PK: Serialize>(
+        &self,
+        conflict_id: S,
+        partition_key: &PK,
+    ) -> azure_core::error::Result<ConflictClient> {
+        ConflictClient::new(self.clone(), conflict_id, partition_key)
+    }
+
     /// convert into a [`TriggerClient`]
     pub fn trigger_client<S: Into<ReadonlyString>>(&self, trigger_name: S) -> TriggerClient {
         TriggerClient::new(self.clone(), trigger_name)
@@ -102,6 +114,22 @@ impl CollectionClient {
         CreateDocumentBuilder::new(self.clone(), document)
     }
 
+    /// execute operations on the documents of a logical partition atomically
+    pub fn transactional_batch<PK: Serialize>(
+        &self,
+        partition_key: &PK,
+    ) -> azure_core::error::Result<TransactionalBatchBuilder> {
+        Ok(TransactionalBatchBuilder::new(
+            self.clone(),
+            crate::cosmos_entity::serialize_partition_key(partition_key)?,
+        ))
+    }
+
+    /// execute many independent operations on the documents in a collection
+    pub fn bulk(&self) -> BulkBuilder {
+        BulkBuilder::new(self.clone())
+    }
+
     /// query documents in a collection
     pub fn query_documents<Q: Into<Query>>(&self, query: Q) -> QueryDocumentsBuilder {
         QueryDocumentsBuilder::new(self.clone(), query.into())
@@ -122,6 +150,41 @@ impl CollectionClient {
         ListTriggersBuilder::new(self.clone())
     }
 
+    /// list the conflicts of a collection that were not resolved automatically
+    pub fn list_conflicts(&self) -> ListConflictsBuilder {
+        ListConflictsBuilder::new(self.clone())
+    }
+
+    /// read the changes made to the documents in a collection
+    pub fn read_change_feed(&self) -> ReadChangeFeedBuilder {
+        ReadChangeFeedBuilder::new(self.clone())
+    }
+
+    /// process the change feed of a collection, sharing the work with the other hosts using
+    /// the same lease collection
+    pub fn change_feed_processor<T, H>(
+        &self,
+        lease_client: CollectionClient,
+        host_name: impl Into<String>,
+        handler: H,
+    ) -> ChangeFeedProcessorBuilder<T, H>
+    where
+        T: DeserializeOwned + Send + Sync + 'static,
+        H: ChangeFeedHandler<T>,
+    {
+        ChangeFeedProcessorBuilder::new(self.clone(), lease_client, host_name.into(), handler)
+    }
+
+    /// Get the throughput offer of the collection
+    pub fn get_offer(&self) -> GetOfferBuilder {
+        GetOfferBuilder::new(OfferTarget::Collection(self.clone()))
+    }
+
+    /// Replace the throughput dedicated to the collection
+    pub fn replace_offer(&self, throughput: Throughput) -> ReplaceOfferBuilder {
+        ReplaceOfferBuilder::new(OfferTarget::Collection(self.clone()), throughput)
+    }
+
     /// list the partition key ranges in a collection
     pub fn get_partition_key_ranges(&self) -> GetPartitionKeyRangesBuilder {
         GetPartitionKeyRangesBuilder::new(self.clone())
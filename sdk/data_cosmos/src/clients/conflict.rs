@@ -0,0 +1,85 @@
+use super::{CollectionClient, CosmosClient, DatabaseClient};
+use crate::operations::*;
+use azure_core::{Pipeline, Request};
+use serde::Serialize;
+
+/// A client for Cosmos conflict resources.
+#[derive(Debug, Clone)]
+pub struct ConflictClient {
+    collection: CollectionClient,
+    conflict_id: String,
+    partition_key_serialized: String,
+}
+
+impl ConflictClient {
+    /// Create a new conflict client.
+    ///
+    /// A conflict is identified by its id and the partition key of the conflicting document.
+    pub(crate) fn new<S: Into<String>, PK: Serialize>(
+        collection: CollectionClient,
+        conflict_id: S,
+        partition_key: &PK,
+    ) -> azure_core::error::Result<Self> {
+        Ok(Self {
+            collection,
+            conflict_id: conflict_id.into(),
+            partition_key_serialized: crate::cosmos_entity::serialize_partition_key(partition_key)?,
+        })
+    }
+
+    /// Get a [`CosmosClient`]
+    pub fn cosmos_client(&self) -> &CosmosClient {
+        self.collection.cosmos_client()
+    }
+
+    /// Get a [`DatabaseClient`]
+    pub fn database_client(&self) -> &DatabaseClient {
+        self.collection.database_client()
+    }
+
+    /// Get a [`CollectionClient`]
+    pub fn collection_client(&self) -> &CollectionClient {
+        &self.collection
+    }
+
+    /// Get the conflict id
+    pub fn conflict_id(&self) -> &str {
+        &self.conflict_id
+    }
+
+    /// Get the partition key
+    pub fn partition_key_serialized(&self) -> &str {
+        &self.partition_key_serialized
+    }
+
+    /// Get the conflict
+    pub fn get_conflict(&self) -> GetConflictBuilder {
+        GetConflictBuilder::new(self.clone())
+    }
+
+    /// Delete the conflict, once the application resolved it
+    pub fn delete_conflict(&self) -> DeleteConflictBuilder {
+        DeleteConflictBuilder::new(self.clone())
+    }
+
+    pub(crate) fn prepare_pipeline_with_conflict_id(&self, method: http::Method) -> Request {
+        let mut request = self.cosmos_client().prepare_request_pipeline(
+            &format!(
+                "dbs/{}/colls/{}/conflicts/{}",
+                self.database_client().database_name(),
+                self.collection_client().collection_name(),
+                self.conflict_id()
+            ),
+            method,
+        );
+        crate::cosmos_entity::add_as_partition_key_header_serialized2(
+            self.partition_key_serialized(),
+            &mut request,
+        );
+        request
+    }
+
+    pub(crate) fn pipeline(&self) -> &Pipeline {
+        self.cosmos_client().pipeline()
+    }
+}
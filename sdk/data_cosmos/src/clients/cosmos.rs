@@ -1,13 +1,21 @@
-use super::DatabaseClient;
+use super::{DatabaseClient, OfferClient};
+use crate::authorization_policy::AuthorizationCredential;
+use crate::diagnostics::{CosmosDiagnostics, DiagnosticsPolicy};
+use crate::location_cache::LocationCache;
+use crate::location_routing_policy::LocationRoutingPolicy;
 use crate::operations::*;
 use crate::resources::permission::AuthorizationToken;
 use crate::resources::ResourceType;
-use crate::ReadonlyString;
+use crate::session_container::SessionContainer;
+use crate::session_policy::SessionPolicy;
+use crate::{LocationDiagnostics, ReadonlyString, RegionalEndpoint};
 
+use azure_core::auth::TokenCredential;
 use azure_core::{ClientOptions, Context, Pipeline, Request, Response};
 
 use std::fmt::Debug;
 use std::sync::Arc;
+use std::time::Duration;
 
 /// The well-known account key used by Azure Cosmos DB Emulator.
 /// https://docs.microsoft.com/azure/cosmos-db/local-emulator?tabs=ssl-netstd21#connect-with-emulator-apis
@@ -19,12 +27,27 @@ pub const EMULATOR_ACCOUNT_KEY: &str =
 pub struct CosmosClient {
     pipeline: Pipeline,
     cloud_location: CloudLocation,
+    location_cache: Option<Arc<LocationCache>>,
 }
 
 /// Options for specifying how a Cosmos client will behave
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone)]
 pub struct CosmosOptions {
     options: ClientOptions,
+    endpoint_discovery: bool,
+    preferred_regions: Vec<String>,
+    topology_refresh_interval: Duration,
+}
+
+impl Default for CosmosOptions {
+    fn default() -> Self {
+        Self {
+            options: ClientOptions::default(),
+            endpoint_discovery: false,
+            preferred_regions: Vec::new(),
+            topology_refresh_interval: Duration::from_secs(5 * 60),
+        }
+    }
 }
 
 impl CosmosOptions {
@@ -38,22 +61,67 @@ impl CosmosOptions {
     pub fn new_with_transaction_name(name: String) -> Self {
         Self {
             options: ClientOptions::new_with_transaction_name(name.into()),
+            ..Self::default()
         }
     }
+
+    /// Discover the regions of the account and fail over between them.
+    ///
+    /// Without endpoint discovery every request is sent to the account's global endpoint.
+    pub fn endpoint_discovery(mut self, endpoint_discovery: bool) -> Self {
+        self.endpoint_discovery = endpoint_discovery;
+        self
+    }
+
+    /// The regions reads are sent to, most preferred first, such as `["West US", "East US"]`.
+    ///
+    /// Setting preferred regions enables endpoint discovery.
+    pub fn preferred_regions<I, S>(mut self, preferred_regions: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.preferred_regions = preferred_regions.into_iter().map(Into::into).collect();
+        self.endpoint_discovery = true;
+        self
+    }
+
+    /// How often the regions of the account are read again, 5 minutes by default
+    pub fn topology_refresh_interval(mut self, topology_refresh_interval: Duration) -> Self {
+        self.topology_refresh_interval = topology_refresh_interval;
+        self
+    }
 }
 
 /// Create a Pipeline from CosmosOptions
 fn new_pipeline_from_options(
     options: CosmosOptions,
-    authorization_token: AuthorizationToken,
+    credential: AuthorizationCredential,
+    cloud_location: &CloudLocation,
+    location_cache: Option<Arc<LocationCache>>,
 ) -> Pipeline {
-    let auth_policy: Arc<dyn azure_core::Policy> =
-        Arc::new(crate::AuthorizationPolicy::new(authorization_token));
+    let auth_policy: Arc<dyn azure_core::Policy> = Arc::new(crate::AuthorizationPolicy::new(
+        credential,
+        cloud_location.aad_resource(),
+    ));
 
     // take care of adding the AuthorizationPolicy as **last** retry policy.
     // Policies can change the url and/or the headers and the AuthorizationPolicy
     // must be able to inspect them or the resulting token will be invalid.
-    let per_retry_policies = vec![auth_policy];
+    let mut per_retry_policies = Vec::new();
+    if let Some(location_cache) = location_cache.clone() {
+        per_retry_policies.push(
+            Arc::new(LocationRoutingPolicy::new(location_cache)) as Arc<dyn azure_core::Policy>
+        );
+    }
+    // the session tokens are shared by the clones of the client
+    per_retry_policies.push(Arc::new(SessionPolicy::new(
+        Arc::new(SessionContainer::default()),
+        location_cache.clone(),
+    )));
+    // sees every attempt, after the retries and the failovers
+    per_retry_policies.push(Arc::new(DiagnosticsPolicy::new(location_cache)));
+    per_retry_policies.push(auth_policy);
 
     Pipeline::new(
         option_env!("CARGO_PKG_NAME"),
@@ -65,16 +133,48 @@ fn new_pipeline_from_options(
 }
 
 impl CosmosClient {
-    /// Create a new `CosmosClient` which connects to the account's instance in the public Azure cloud.
-    pub fn new(account: String, auth_token: AuthorizationToken, options: CosmosOptions) -> Self {
-        let cloud_location = CloudLocation::Public(account);
-        let pipeline = new_pipeline_from_options(options, auth_token);
+    fn from_options(
+        cloud_location: CloudLocation,
+        credential: AuthorizationCredential,
+        options: CosmosOptions,
+    ) -> Self {
+        let location_cache = options.endpoint_discovery.then(|| {
+            Arc::new(LocationCache::new(
+                cloud_location.url(),
+                options.preferred_regions.clone(),
+                options.topology_refresh_interval,
+            ))
+        });
+        let pipeline =
+            new_pipeline_from_options(options, credential, &cloud_location, location_cache.clone());
         Self {
             pipeline,
             cloud_location,
+            location_cache,
         }
     }
 
+    /// Create a new `CosmosClient` which connects to the account's instance in the public Azure cloud.
+    pub fn new(account: String, auth_token: AuthorizationToken, options: CosmosOptions) -> Self {
+        let cloud_location = CloudLocation::Public(account);
+        Self::from_options(cloud_location, auth_token.into(), options)
+    }
+
+    /// Create a new `CosmosClient` which connects to the account's instance in the public Azure cloud
+    /// authenticating with Azure Active Directory tokens from the given credential.
+    pub fn new_with_token_credential(
+        account: String,
+        credential: Arc<dyn TokenCredential>,
+        options: CosmosOptions,
+    ) -> Self {
+        let cloud_location = CloudLocation::Public(account);
+        Self::from_options(
+            cloud_location,
+            AuthorizationCredential::TokenCredential(credential),
+            options,
+        )
+    }
+
     #[cfg(feature = "mock_transport_framework")]
     /// Create new options with a given transaction name
     pub fn new_with_transaction(
@@ -96,11 +196,7 @@ impl CosmosClient {
         options: CosmosOptions,
     ) -> Self {
         let cloud_location = CloudLocation::China(account);
-        let pipeline = new_pipeline_from_options(options, auth_token);
-        Self {
-            pipeline,
-            cloud_location,
-        }
+        Self::from_options(cloud_location, auth_token.into(), options)
     }
 
     /// Create a new `CosmosClient` which connects to the account's instance in custom Azure cloud.
@@ -111,11 +207,23 @@ impl CosmosClient {
         options: CosmosOptions,
     ) -> Self {
         let cloud_location = CloudLocation::Custom { account, uri };
-        let pipeline = new_pipeline_from_options(options, auth_token);
-        Self {
-            pipeline,
+        Self::from_options(cloud_location, auth_token.into(), options)
+    }
+
+    /// Create a new `CosmosClient` which connects to the account's instance in custom Azure cloud
+    /// authenticating with Azure Active Directory tokens from the given credential.
+    pub fn new_custom_with_token_credential(
+        account: String,
+        credential: Arc<dyn TokenCredential>,
+        uri: String,
+        options: CosmosOptions,
+    ) -> Self {
+        let cloud_location = CloudLocation::Custom { account, uri };
+        Self::from_options(
             cloud_location,
-        }
+            AuthorizationCredential::TokenCredential(credential),
+            options,
+        )
     }
 
     /// Create a new `CosmosClient` which connects to the account's instance in Azure emulator
@@ -126,24 +234,42 @@ impl CosmosClient {
             account: String::from("Custom"),
             uri,
         };
-        let pipeline = new_pipeline_from_options(options, auth_token);
-        Self {
-            pipeline,
-            cloud_location,
-        }
+        Self::from_options(cloud_location, auth_token.into(), options)
     }
 
     /// Set the auth token used
     pub fn auth_token(&mut self, auth_token: AuthorizationToken) {
         // we replace the AuthorizationPolicy. This is
         // the last-1 policy by construction.
-        let auth_policy: Arc<dyn azure_core::Policy> =
-            Arc::new(crate::AuthorizationPolicy::new(auth_token));
+        let auth_policy: Arc<dyn azure_core::Policy> = Arc::new(crate::AuthorizationPolicy::new(
+            auth_token.into(),
+            self.cloud_location.aad_resource(),
+        ));
 
         self.pipeline
             .replace_policy(auth_policy, self.pipeline.policies().len() - 2);
     }
 
+    /// Where requests are currently routed, and the regions avoided after failures
+    pub fn location_diagnostics(&self) -> LocationDiagnostics {
+        match &self.location_cache {
+            Some(location_cache) => location_cache.diagnostics(),
+            None => {
+                let endpoint = RegionalEndpoint {
+                    region: None,
+                    endpoint: self.cloud_location.url(),
+                };
+                LocationDiagnostics {
+                    endpoint_discovery: false,
+                    write_endpoints: vec![endpoint.clone()],
+                    read_endpoints: vec![endpoint],
+                    unavailable_regions: Vec::new(),
+                    last_refresh: None,
+                }
+            }
+        }
+    }
+
     /// Create a database
     pub fn create_database<S: AsRef<str>>(&self, database_name: S) -> CreateDatabaseBuilder {
         CreateDatabaseBuilder::new(self.clone(), database_name.as_ref().to_owned())
@@ -154,6 +280,16 @@ impl CosmosClient {
         ListDatabasesBuilder::new(self.clone())
     }
 
+    /// List the throughput offers of the databases and collections
+    pub fn list_offers(&self) -> ListOffersBuilder {
+        ListOffersBuilder::new(self.clone())
+    }
+
+    /// Create an [`OfferClient`].
+    pub fn offer_client<S: Into<ReadonlyString>>(&self, offer_id: S) -> OfferClient {
+        OfferClient::new(self.clone(), offer_id)
+    }
+
     /// Create a [`DatabaseClient`].
     pub fn database_client<S: Into<ReadonlyString>>(&self, database_name: S) -> DatabaseClient {
         DatabaseClient::new(self.clone(), database_name)
@@ -178,10 +314,8 @@ impl CosmosClient {
         mut request: Request,
         mut context: Context,
         resource_type: ResourceType,
-    ) -> azure_core::error::Result<Response> {
-        self.pipeline
-            .send(context.insert(resource_type), &mut request)
-            .await
+    ) -> azure_core::error::Result<(Response, CosmosDiagnostics)> {
+        crate::diagnostics::send(&self.pipeline, context.insert(resource_type), &mut request).await
     }
 
     pub(crate) fn pipeline(&self) -> &Pipeline {
@@ -214,4 +348,9 @@ impl CloudLocation {
             CloudLocation::Custom { uri, .. } => uri.clone(),
         }
     }
+
+    /// the resource AAD tokens are requested for: the base URL without a trailing slash
+    fn aad_resource(&self) -> String {
+        self.url().trim_end_matches('/').to_owned()
+    }
 }
@@ -1,6 +1,7 @@
 use super::*;
 use crate::operations::*;
 use crate::resources::collection::PartitionKey;
+use crate::resources::Throughput;
 use crate::ReadonlyString;
 use azure_core::Request;
 use http::Method;
@@ -53,6 +54,16 @@ impl DatabaseClient {
         DeleteDatabaseBuilder::new(self.clone())
     }
 
+    /// Get the throughput offer of the database
+    pub fn get_offer(&self) -> GetOfferBuilder {
+        GetOfferBuilder::new(OfferTarget::Database(self.clone()))
+    }
+
+    /// Replace the throughput shared by the collections of the database
+    pub fn replace_offer(&self, throughput: Throughput) -> ReplaceOfferBuilder {
+        ReplaceOfferBuilder::new(OfferTarget::Database(self.clone()), throughput)
+    }
+
     /// List collections in the database
     pub fn list_collections(&self) -> ListCollectionsBuilder {
         ListCollectionsBuilder::new(self.clone())
@@ -1,5 +1,6 @@
 use super::{AttachmentClient, CollectionClient, CosmosClient, DatabaseClient};
 use crate::operations::*;
+use crate::resources::document::PatchOperation;
 use crate::ReadonlyString;
 use azure_core::Request;
 use serde::Serialize;
@@ -74,6 +75,11 @@ impl DocumentClient {
         ReplaceDocumentBuilder::new(self.clone(), document)
     }
 
+    /// Apply a partial update to a document
+    pub fn patch_document(&self, operations: Vec<PatchOperation>) -> PatchDocumentBuilder {
+        PatchDocumentBuilder::new(self.clone(), operations)
+    }
+
     /// Delete a document
     pub fn delete_document(&self) -> DeleteDocumentBuilder {
         DeleteDocumentBuilder::new(self.clone())
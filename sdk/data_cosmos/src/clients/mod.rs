@@ -19,9 +19,11 @@
 
 mod attachment;
 mod collection;
+mod conflict;
 mod cosmos;
 mod database;
 mod document;
+mod offer;
 mod permission;
 mod stored_procedure;
 mod trigger;
@@ -30,9 +32,11 @@ mod user_defined_function;
 
 pub use attachment::AttachmentClient;
 pub use collection::CollectionClient;
-pub use cosmos::{CosmosClient, CosmosOptions};
+pub use conflict::ConflictClient;
+pub use cosmos::{CosmosClient, CosmosOptions, EMULATOR_ACCOUNT_KEY};
 pub use database::DatabaseClient;
 pub use document::DocumentClient;
+pub use offer::OfferClient;
 pub use permission::PermissionClient;
 pub use stored_procedure::StoredProcedureClient;
 pub use trigger::TriggerClient;
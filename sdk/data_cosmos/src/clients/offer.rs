@@ -0,0 +1,46 @@
+use crate::operations::*;
+use crate::prelude::*;
+use crate::ReadonlyString;
+
+/// A client for Cosmos offer resources.
+#[derive(Debug, Clone)]
+pub struct OfferClient {
+    client: CosmosClient,
+    offer_id: ReadonlyString,
+}
+
+impl OfferClient {
+    pub(crate) fn new<S: Into<ReadonlyString>>(client: CosmosClient, offer_id: S) -> Self {
+        Self {
+            client,
+            offer_id: offer_id.into(),
+        }
+    }
+
+    /// Get a [`CosmosClient`].
+    pub fn cosmos_client(&self) -> &CosmosClient {
+        &self.client
+    }
+
+    /// Get the offer's id
+    pub fn offer_id(&self) -> &str {
+        &self.offer_id
+    }
+
+    /// Get the offer
+    pub fn get_offer(&self) -> GetOfferBuilder {
+        GetOfferBuilder::new(self.target())
+    }
+
+    /// Replace the throughput of the offer
+    pub fn replace_offer(&self, throughput: Throughput) -> ReplaceOfferBuilder {
+        ReplaceOfferBuilder::new(self.target(), throughput)
+    }
+
+    fn target(&self) -> OfferTarget {
+        OfferTarget::Offer {
+            client: self.client.clone(),
+            offer_id: self.offer_id.to_string(),
+        }
+    }
+}
@@ -13,6 +13,9 @@ pub enum ConsistencyLevel {
     /// Reads are guaranteed to honor the consistent-prefix guarantee
     Bounded,
     /// Within a single client session reads are guaranteed to honor the consistent-prefix, monotonic reads, monotonic writes, read-your-writes, and write-follows-reads guarantees.
+    ///
+    /// The `CosmosClient` keeps the session tokens of its responses and sends them with its reads,
+    /// so the token only needs to be passed explicitly to share a session between clients.
     Session(String),
     /// Updates that are returned contain some prefix of all the updates, with no gaps.
     ConsistentPrefix,
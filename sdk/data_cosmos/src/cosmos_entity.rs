@@ -1,4 +1,5 @@
 use crate::headers;
+use crate::resources::collection::MAX_HIERARCHICAL_PARTITION_KEY_PATHS;
 use azure_core::Request as HttpRequest;
 use serde::Serialize;
 
@@ -8,6 +9,9 @@ pub trait CosmosEntity {
     type Entity: Serialize;
 
     /// Return partition key value as reference.
+    ///
+    /// Collections with a hierarchical partition key return a tuple with a value per path,
+    /// such as `(String, String)`.
     fn partition_key(&self) -> Self::Entity;
 }
 
@@ -19,11 +23,32 @@ impl CosmosEntity for serde_json::Value {
 }
 
 /// Serialize the partition key in the format CosmosDB expects.
+///
+/// Single values are wrapped in an array, while values serializing to an array, such as tuples,
+/// are the components of a hierarchical partition key.
 pub(crate) fn serialize_partition_key<PK: Serialize>(pk: &PK) -> azure_core::error::Result<String> {
-    use azure_core::error::ResultExt;
-    // this must be serialized as an array even tough CosmosDB supports only a sigle partition key.
-    serde_json::to_string(&[pk]).context(
-        azure_core::error::ErrorKind::DataConversion,
+    use azure_core::error::{Error, ErrorKind, ResultExt};
+    let value = serde_json::to_value(pk).context(
+        ErrorKind::DataConversion,
+        "could not convert partition_key into String",
+    )?;
+    let components = match value {
+        serde_json::Value::Array(components) => {
+            if components.is_empty() || components.len() > MAX_HIERARCHICAL_PARTITION_KEY_PATHS {
+                return Err(Error::with_message(ErrorKind::DataConversion, || {
+                    format!(
+                        "a hierarchical partition key has between 1 and {} components, got {}",
+                        MAX_HIERARCHICAL_PARTITION_KEY_PATHS,
+                        components.len()
+                    )
+                }));
+            }
+            components
+        }
+        value => vec![value],
+    };
+    serde_json::to_string(&components).context(
+        ErrorKind::DataConversion,
         "could not convert partition_key into String",
     )
 }
@@ -37,3 +62,24 @@ pub(crate) fn add_as_partition_key_header_serialized2(
         http::header::HeaderValue::from_str(partition_key_serialized).unwrap(),
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_hierarchical_partition_keys() {
+        assert_eq!(serialize_partition_key(&"pk").unwrap(), r#"["pk"]"#);
+        assert_eq!(serialize_partition_key(&42).unwrap(), "[42]");
+        assert_eq!(
+            serialize_partition_key(&("tenant", "user", 7)).unwrap(),
+            r#"["tenant","user",7]"#
+        );
+        assert_eq!(
+            serialize_partition_key(&vec!["tenant"]).unwrap(),
+            r#"["tenant"]"#
+        );
+        assert!(serialize_partition_key(&("a", "b", "c", "d")).is_err());
+        assert!(serialize_partition_key(&Vec::<String>::new()).is_err());
+    }
+}
@@ -0,0 +1,377 @@
+//! Records what happened while executing an operation: every attempt made, where it was sent,
+//! how long it took and what it cost.
+use crate::headers::{
+    HEADER_ACTIVITY_ID, HEADER_REQUEST_CHARGE, HEADER_RETRY_AFTER_MS, HEADER_SUB_STATUS,
+};
+use crate::location_cache::LocationCache;
+use azure_core::error::{Error, HttpError};
+use azure_core::{Context, Pipeline, Policy, PolicyResult, Request, Response};
+use http::HeaderMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// The diagnostics of an operation, available from its response and from its error.
+///
+/// An operation can take several attempts: the pipeline retries throttled and failed requests,
+/// and with endpoint discovery requests fail over to other regions.
+#[derive(Debug, Clone, Default, PartialEq, PartialOrd)]
+pub struct CosmosDiagnostics {
+    /// The requests sent, in order
+    pub attempts: Vec<AttemptDiagnostics>,
+    /// How long the operation took, retries included
+    pub elapsed: Duration,
+    /// How long the operation waited before retrying throttled requests
+    pub throttle_wait: Duration,
+}
+
+/// A request sent for an operation
+#[derive(Debug, Clone, Default, PartialEq, PartialOrd)]
+pub struct AttemptDiagnostics {
+    /// The status code, `None` when no response was received
+    pub status: Option<u16>,
+    /// The `x-ms-substatus` refining the status code, such as `3200` for a throttled request
+    pub sub_status: Option<u32>,
+    /// How long the request took
+    pub latency: Duration,
+    /// The region the request was sent to, `None` without endpoint discovery
+    pub region: Option<String>,
+    /// The url the request was sent to
+    pub uri: String,
+    /// The activity id the service assigned to the request
+    pub activity_id: Option<String>,
+    /// The request units charged
+    pub charge: f64,
+    /// How long the service asked to wait before retrying a throttled request
+    pub retry_after: Option<Duration>,
+}
+
+impl CosmosDiagnostics {
+    /// The request units charged across all attempts
+    pub fn total_charge(&self) -> f64 {
+        self.attempts.iter().map(|attempt| attempt.charge).sum()
+    }
+
+    /// The activity ids of the attempts, to give to support when investigating an operation
+    pub fn activity_ids(&self) -> Vec<&str> {
+        self.attempts
+            .iter()
+            .filter_map(|attempt| attempt.activity_id.as_deref())
+            .collect()
+    }
+
+    /// How many attempts were throttled
+    pub fn throttled_attempts(&self) -> usize {
+        self.attempts
+            .iter()
+            .filter(|attempt| attempt.status == Some(429))
+            .count()
+    }
+
+    /// The diagnostics of the operation that failed with the error, if it reached the service.
+    ///
+    /// When the service returned an error status, the error is the
+    /// [`HttpError`](azure_core::error::HttpError) of the last attempt, which carries the
+    /// diagnostics in its extensions. Other errors, such as connection failures, wrap the error
+    /// of the last attempt together with the diagnostics.
+    pub fn from_error(error: &Error) -> Option<&CosmosDiagnostics> {
+        match error.downcast_ref::<HttpError>() {
+            Some(http_error) => http_error.extensions().get(),
+            None => error
+                .downcast_ref::<DiagnosedError>()
+                .map(|diagnosed| &diagnosed.diagnostics),
+        }
+    }
+
+    /// Adds the attempts of another request sent after this one on behalf of the same
+    /// operation, such as reading another partition key range for the same page of a query
+    pub(crate) fn merge(&mut self, other: CosmosDiagnostics) {
+        self.attempts.extend(other.attempts);
+        self.elapsed += other.elapsed;
+        self.throttle_wait += other.throttle_wait;
+    }
+}
+
+/// The error of an operation that got no response, with its diagnostics
+#[derive(Debug)]
+struct DiagnosedError {
+    error: Error,
+    diagnostics: CosmosDiagnostics,
+}
+
+impl std::fmt::Display for DiagnosedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+impl std::error::Error for DiagnosedError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// Collects the attempts of an operation, shared through the context of its requests
+#[derive(Debug)]
+pub(crate) struct DiagnosticsRecorder {
+    started: Instant,
+    state: Mutex<RecorderState>,
+}
+
+#[derive(Debug, Default)]
+struct RecorderState {
+    attempts: Vec<AttemptDiagnostics>,
+    throttle_wait: Duration,
+    /// When the last attempt ended, if it was throttled
+    throttled_at: Option<Instant>,
+}
+
+impl DiagnosticsRecorder {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            state: Mutex::default(),
+        }
+    }
+
+    fn attempt_started(&self) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(throttled_at) = state.throttled_at.take() {
+            state.throttle_wait += throttled_at.elapsed();
+        }
+    }
+
+    fn attempt_ended(&self, attempt: AttemptDiagnostics) {
+        let mut state = self.state.lock().unwrap();
+        state.throttled_at = (attempt.status == Some(429)).then(Instant::now);
+        state.attempts.push(attempt);
+    }
+
+    pub fn diagnostics(&self) -> CosmosDiagnostics {
+        let state = self.state.lock().unwrap();
+        CosmosDiagnostics {
+            attempts: state.attempts.clone(),
+            elapsed: self.started.elapsed(),
+            throttle_wait: state.throttle_wait,
+        }
+    }
+}
+
+/// Sends the request of an operation through the pipeline, recording its diagnostics.
+///
+/// The error of a failed operation carries its diagnostics, see
+/// [`CosmosDiagnostics::from_error`].
+pub(crate) async fn send(
+    pipeline: &Pipeline,
+    ctx: &mut Context,
+    request: &mut Request,
+) -> azure_core::error::Result<(Response, CosmosDiagnostics)> {
+    ctx.insert(DiagnosticsRecorder::new());
+    let result = pipeline.send(ctx, request).await;
+    let diagnostics = ctx
+        .get::<DiagnosticsRecorder>()
+        .map(DiagnosticsRecorder::diagnostics)
+        .unwrap_or_default();
+    match result {
+        Ok(response) => Ok((response, diagnostics)),
+        Err(mut error) => match error.downcast_mut::<HttpError>() {
+            // callers keep finding the `HttpError` of the response
+            Some(http_error) => {
+                http_error.extensions_mut().insert(diagnostics);
+                Err(error)
+            }
+            None => Err(Error::new(
+                error.kind().clone(),
+                DiagnosedError { error, diagnostics },
+            )),
+        },
+    }
+}
+
+/// Copies the error of a request sent on behalf of several operations, such as the
+/// operations of a bulk request, keeping its `HttpError` and its diagnostics.
+pub(crate) fn copy_error(error: &Error) -> Error {
+    let kind = error.kind().clone();
+    let diagnostics = CosmosDiagnostics::from_error(error).cloned();
+    match error.downcast_ref::<HttpError>() {
+        Some(http_error) => {
+            let mut copy = http_error.duplicate();
+            if let Some(diagnostics) = diagnostics {
+                copy.extensions_mut().insert(diagnostics);
+            }
+            Error::new(kind, copy)
+        }
+        None => {
+            let copy = Error::with_message(kind.clone(), || error.to_string());
+            match diagnostics {
+                Some(diagnostics) => Error::new(
+                    kind,
+                    DiagnosedError {
+                        error: copy,
+                        diagnostics,
+                    },
+                ),
+                None => copy,
+            }
+        }
+    }
+}
+
+/// The `DiagnosticsPolicy` records every attempt of the operations sent with [`send`]: the
+/// status, the region, the latency, and the charge. It must be added after the policies
+/// retrying or rerouting requests, so that it sees every attempt.
+#[derive(Debug)]
+pub(crate) struct DiagnosticsPolicy {
+    /// Knows the regions when endpoint discovery is enabled
+    location_cache: Option<Arc<LocationCache>>,
+}
+
+impl DiagnosticsPolicy {
+    pub(crate) fn new(location_cache: Option<Arc<LocationCache>>) -> Self {
+        Self { location_cache }
+    }
+}
+
+#[async_trait::async_trait]
+impl Policy for DiagnosticsPolicy {
+    async fn send(
+        &self,
+        ctx: &Context,
+        request: &mut Request,
+        next: &[Arc<dyn Policy>],
+    ) -> PolicyResult {
+        assert!(
+            !next.is_empty(),
+            "DiagnosticsPolicy cannot be the last policy of a pipeline"
+        );
+
+        let recorder = match ctx.get::<DiagnosticsRecorder>() {
+            Some(recorder) => recorder,
+            None => return next[0].send(ctx, request, &next[1..]).await,
+        };
+        recorder.attempt_started();
+        let started = Instant::now();
+        let result = next[0].send(ctx, request, &next[1..]).await;
+
+        let uri = request.uri().to_string();
+        let mut attempt = AttemptDiagnostics {
+            latency: started.elapsed(),
+            region: self
+                .location_cache
+                .as_ref()
+                .and_then(|location_cache| location_cache.region(&uri)),
+            uri,
+            ..AttemptDiagnostics::default()
+        };
+        if let Ok(response) = &result {
+            let headers = response.headers();
+            attempt.status = Some(response.status().as_u16());
+            attempt.sub_status = parse_header(headers, HEADER_SUB_STATUS);
+            attempt.activity_id = header(headers, HEADER_ACTIVITY_ID).map(ToOwned::to_owned);
+            attempt.charge = parse_header(headers, HEADER_REQUEST_CHARGE).unwrap_or_default();
+            attempt.retry_after = parse_header::<f64>(headers, HEADER_RETRY_AFTER_MS)
+                .map(|ms| Duration::from_secs_f64(ms.max(0.0) / 1000.0));
+        }
+        recorder.attempt_ended(attempt);
+
+        result
+    }
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+fn parse_header<T: std::str::FromStr>(headers: &HeaderMap, name: &str) -> Option<T> {
+    header(headers, name).and_then(|value| value.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_endpoint::{MockEndpoint, MockResponse};
+    use azure_core::error::ErrorKind;
+
+    fn database() -> MockResponse {
+        MockResponse::new(
+            200,
+            r#"{"id":"db","_rid":"a","_ts":1,"_self":"b","_etag":"c","_colls":"colls/","_users":"users/"}"#,
+        )
+        .with_cosmos_headers()
+        .with_header("etag", "c")
+    }
+
+    fn throttled() -> MockResponse {
+        MockResponse::new(429, r#"{"code":"429","message":"Request rate is large"}"#)
+            .with_header("x-ms-substatus", "3200")
+            .with_header("x-ms-retry-after-ms", "5")
+            .with_header("x-ms-request-charge", "0.5")
+            .with_header("x-ms-activity-id", "throttled")
+    }
+
+    #[tokio::test]
+    async fn records_every_attempt_of_a_throttled_request() {
+        let endpoint = MockEndpoint::start(vec![throttled(), database()]);
+
+        let response = endpoint
+            .cosmos_client()
+            .database_client("db")
+            .get_database()
+            .into_future()
+            .await
+            .unwrap();
+
+        let diagnostics = response.diagnostics;
+        assert_eq!(diagnostics.attempts.len(), 2);
+        assert_eq!(diagnostics.throttled_attempts(), 1);
+        let throttled = &diagnostics.attempts[0];
+        assert_eq!(throttled.status, Some(429));
+        assert_eq!(throttled.sub_status, Some(3200));
+        assert_eq!(throttled.retry_after, Some(Duration::from_millis(5)));
+        assert!(throttled.uri.ends_with("/dbs/db"));
+        assert_eq!(throttled.region, None);
+        assert_eq!(diagnostics.attempts[1].status, Some(200));
+        assert_eq!(diagnostics.total_charge(), 1.5);
+        assert_eq!(
+            diagnostics.activity_ids(),
+            ["throttled", "a3a3bd5b-5b1c-4c32-9b5a-a5c0f5e2f3c4"]
+        );
+        assert!(diagnostics.throttle_wait > Duration::ZERO);
+        assert!(diagnostics.elapsed >= diagnostics.throttle_wait);
+    }
+
+    #[tokio::test]
+    async fn errors_carry_the_diagnostics_of_the_operation() {
+        let endpoint = MockEndpoint::start(vec![MockResponse::new(
+            404,
+            r#"{"code":"NotFound","message":"Resource Not Found"}"#,
+        )
+        .with_cosmos_headers()]);
+
+        let error = endpoint
+            .cosmos_client()
+            .database_client("db")
+            .get_database()
+            .into_future()
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            error.kind(),
+            ErrorKind::HttpResponse { status: 404, .. }
+        ));
+        let http_error = error.downcast_ref::<HttpError>().unwrap();
+        assert_eq!(http_error.status(), 404);
+        assert!(std::str::from_utf8(http_error.body())
+            .unwrap()
+            .contains("Resource Not Found"));
+        assert_eq!(
+            error.to_string(),
+            "server returned error status which will not be retried"
+        );
+        let diagnostics = CosmosDiagnostics::from_error(&error).unwrap();
+        assert_eq!(diagnostics.attempts.len(), 1);
+        assert_eq!(diagnostics.attempts[0].status, Some(404));
+        assert_eq!(diagnostics.total_charge(), 1.0);
+        assert!(CosmosDiagnostics::from_error(&Error::message(ErrorKind::Other, "")).is_none());
+    }
+}
@@ -1,7 +1,8 @@
 use crate::headers::*;
+use crate::query_metrics::{index_utilization_from_str, query_metrics_from_str};
 use crate::resource_quota::resource_quotas_from_str;
 use crate::resources::document::IndexingDirective;
-use crate::ResourceQuota;
+use crate::{IndexUtilization, QueryMetrics, ResourceQuota};
 
 use azure_core::error::{Error, ErrorKind, Result};
 use azure_core::headers::{self, parse_int};
@@ -42,6 +43,30 @@ pub(crate) fn resource_usage_from_headers(headers: &HeaderMap) -> Result<Vec<Res
     resource_quotas_from_str(s)
 }
 
+/// The query metrics of a response, which are only diagnostic so that a malformed header is
+/// logged rather than failing the query
+pub(crate) fn query_metrics_from_headers_optional(headers: &HeaderMap) -> Option<QueryMetrics> {
+    headers::get_option_str_from_headers(headers, HEADER_DOCUMENTDB_QUERY_METRICS)
+        .and_then(|metrics| metrics.map(query_metrics_from_str).transpose())
+        .unwrap_or_else(|error| {
+            warn!("ignoring malformed query metrics: {}", error);
+            None
+        })
+}
+
+/// The index utilization of a response, which is only diagnostic so that a malformed header is
+/// logged rather than failing the query
+pub(crate) fn index_utilization_from_headers_optional(
+    headers: &HeaderMap,
+) -> Option<IndexUtilization> {
+    headers::get_option_str_from_headers(headers, HEADER_INDEX_UTILIZATION)
+        .and_then(|utilization| utilization.map(index_utilization_from_str).transpose())
+        .unwrap_or_else(|error| {
+            warn!("ignoring malformed index utilization: {}", error);
+            None
+        })
+}
+
 pub(crate) fn quorum_acked_lsn_from_headers(headers: &HeaderMap) -> Result<u64> {
     headers::get_from_headers(headers, HEADER_QUORUM_ACKED_LSN)
 }
@@ -80,6 +105,14 @@ pub(crate) fn indexing_directive_from_headers_optional(
     headers::get_option_from_headers(headers, HEADER_INDEXING_DIRECTIVE)
 }
 
+pub(crate) fn offer_replace_pending_from_headers(headers: &HeaderMap) -> Result<bool> {
+    Ok(
+        headers::get_option_str_from_headers(headers, HEADER_OFFER_REPLACE_PENDING)?
+            .map(|s| s.eq_ignore_ascii_case("true"))
+            .unwrap_or_default(),
+    )
+}
+
 pub(crate) fn collection_service_index_from_headers(headers: &HeaderMap) -> Result<u64> {
     headers::get_from_headers(headers, HEADER_COLLECTION_SERVICE_INDEX)
 }
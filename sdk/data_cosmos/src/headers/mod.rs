@@ -11,17 +11,38 @@ pub(crate) const HEADER_A_IM: &str = "A-IM"; // Cow[str]
 pub(crate) const HEADER_ACTIVITY_ID: &str = "x-ms-activity-id"; // [String]
 pub(crate) const HEADER_DOCUMENTDB_PARTITIONRANGEID: &str = "x-ms-documentdb-partitionkeyrangeid"; // [String]
 pub(crate) const HEADER_DOCUMENTDB_PARTITIONKEY: &str = "x-ms-documentdb-partitionkey"; // [String]
+pub(crate) const HEADER_START_EPK: &str = "x-ms-start-epk"; // [String]
+pub(crate) const HEADER_END_EPK: &str = "x-ms-end-epk"; // [String]
 pub(crate) const HEADER_NUMBER_OF_READ_REGIONS: &str = "x-ms-number-of-read-regions";
 pub(crate) const HEADER_REQUEST_CHARGE: &str = "x-ms-request-charge"; // [f64]
 pub(crate) const HEADER_OFFER_THROUGHPUT: &str = "x-ms-offer-throughput"; // [u64]
 pub(crate) const HEADER_OFFER_TYPE: &str = "x-ms-offer-type"; // [&str]
+pub(crate) const HEADER_OFFER_AUTOPILOT_SETTINGS: &str = "x-ms-cosmos-offer-autopilot-settings"; // [String]
+pub(crate) const HEADER_OFFER_REPLACE_PENDING: &str = "x-ms-offer-replace-pending"; // [bool]
+pub(crate) const HEADER_MIGRATE_OFFER_TO_AUTOPILOT: &str = "x-ms-cosmos-migrate-offer-to-autopilot"; // [bool]
+pub(crate) const HEADER_MIGRATE_OFFER_TO_MANUAL_THROUGHPUT: &str =
+    "x-ms-cosmos-migrate-offer-to-manual-throughput"; // [bool]
 #[allow(dead_code)]
 pub(crate) const HEADER_DOCUMENTDB_ISQUERY: &str = "x-ms-documentdb-isquery"; // [bool]
+pub(crate) const HEADER_DOCUMENTDB_POPULATEQUERYMETRICS: &str =
+    "x-ms-documentdb-populatequerymetrics"; // [bool]
+pub(crate) const HEADER_DOCUMENTDB_QUERY_METRICS: &str = "x-ms-documentdb-query-metrics"; // [QueryMetrics]
+pub(crate) const HEADER_POPULATE_INDEX_METRICS: &str = "x-ms-cosmos-populateindexmetrics"; // [bool]
+pub(crate) const HEADER_INDEX_UTILIZATION: &str = "x-ms-cosmos-index-utilization"; // [IndexUtilization]
 pub(crate) const HEADER_DOCUMENTDB_QUERY_ENABLECROSSPARTITION: &str =
     "x-ms-documentdb-query-enablecrosspartition"; // [bool]
 pub(crate) const HEADER_DOCUMENTDB_QUERY_PARALLELIZECROSSPARTITIONQUERY: &str =
     "x-ms-documentdb-query-parallelizecrosspartitionquery"; // [bool]
 pub(crate) const HEADER_DOCUMENTDB_EXPIRY_SECONDS: &str = "x-ms-documentdb-expiry-seconds"; // [u64]
+pub(crate) const HEADER_IS_BATCH_REQUEST: &str = "x-ms-cosmos-is-batch-request"; // [bool]
+pub(crate) const HEADER_BATCH_ATOMIC: &str = "x-ms-cosmos-batch-atomic"; // [bool]
+pub(crate) const HEADER_BATCH_ORDERED: &str = "x-ms-cosmos-batch-ordered"; // [bool]
+pub(crate) const HEADER_BATCH_CONTINUE_ON_ERROR: &str = "x-ms-cosmos-batch-continue-on-error"; // [bool]
+pub(crate) const HEADER_SUB_STATUS: &str = "x-ms-substatus"; // [u32]
+pub(crate) const HEADER_RETRY_AFTER_MS: &str = "x-ms-retry-after-ms"; // [u64]
+pub(crate) const HEADER_IS_QUERY_PLAN_REQUEST: &str = "x-ms-cosmos-is-query-plan-request"; // [bool]
+pub(crate) const HEADER_SUPPORTED_QUERY_FEATURES: &str = "x-ms-cosmos-supported-query-features"; // [String]
+pub(crate) const HEADER_QUERY_VERSION: &str = "x-ms-cosmos-query-version"; // [String]
 pub(crate) const HEADER_CONTENT_PATH: &str = "x-ms-content-path"; // [String]
 pub(crate) const HEADER_ALT_CONTENT_PATH: &str = "x-ms-alt-content-path"; // [String]
 pub(crate) const HEADER_LAST_STATE_CHANGE_UTC: &str = "x-ms-last-state-change-utc"; // [DateTime<UTC>]
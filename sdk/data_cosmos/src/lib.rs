@@ -104,6 +104,7 @@ extern crate serde;
 #[macro_use]
 extern crate azure_core;
 
+pub mod change_feed_processor;
 pub mod clients;
 pub mod operations;
 pub mod prelude;
@@ -112,14 +113,29 @@ pub mod resources;
 mod authorization_policy;
 mod consistency_level;
 mod cosmos_entity;
+mod diagnostics;
 mod headers;
+mod location_cache;
+mod location_routing_policy;
+#[cfg(test)]
+mod mock_endpoint;
+mod partition_key_hash;
+mod query_metrics;
+mod query_pipeline;
 mod resource_quota;
+mod session_container;
+mod session_policy;
 mod time_nonce;
 mod to_json_vector;
 
 pub(crate) use authorization_policy::AuthorizationPolicy;
 pub use consistency_level::ConsistencyLevel;
 pub use cosmos_entity::CosmosEntity;
+pub use diagnostics::{AttemptDiagnostics, CosmosDiagnostics};
+pub use location_cache::{LocationDiagnostics, RegionalEndpoint};
+pub use query_metrics::{
+    CompositeIndexUtilization, IndexUtilization, QueryMetrics, SingleIndexUtilization,
+};
 pub use resource_quota::ResourceQuota;
 pub(crate) use time_nonce::TimeNonce;
 
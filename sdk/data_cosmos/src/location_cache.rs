@@ -0,0 +1,370 @@
+//! Tracks the regional endpoints of a Cosmos account and which of them to route requests to.
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+/// How long a regional endpoint is avoided after it failed
+const UNAVAILABLE_DURATION: Duration = Duration::from_secs(5 * 60);
+
+/// The regions and default consistency of the database account, as returned by `GET /`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DatabaseAccount {
+    #[serde(default)]
+    pub writable_locations: Vec<AccountRegion>,
+    #[serde(default)]
+    pub readable_locations: Vec<AccountRegion>,
+    #[serde(default)]
+    pub enable_multiple_write_locations: bool,
+    pub user_consistency_policy: Option<AccountConsistencyPolicy>,
+}
+
+impl DatabaseAccount {
+    /// Whether reads use session consistency unless requests ask for another level
+    pub fn uses_session_consistency(&self) -> bool {
+        // session is the default consistency of new accounts
+        self.user_consistency_policy.as_ref().is_none_or(|policy| {
+            policy
+                .default_consistency_level
+                .eq_ignore_ascii_case("Session")
+        })
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AccountConsistencyPolicy {
+    pub default_consistency_level: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AccountRegion {
+    pub name: String,
+    pub database_account_endpoint: String,
+}
+
+/// An endpoint requests can be routed to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegionalEndpoint {
+    /// The region name, `None` for the account's global endpoint
+    pub region: Option<String>,
+    /// The base url of the endpoint
+    pub endpoint: String,
+}
+
+/// The routing state of a client: where reads and writes currently go.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocationDiagnostics {
+    /// Whether the regions are discovered from the account, otherwise only the global endpoint is used
+    pub endpoint_discovery: bool,
+    /// The endpoints writes are sent to, in the order they are tried
+    pub write_endpoints: Vec<RegionalEndpoint>,
+    /// The endpoints reads are sent to, in the order they are tried
+    pub read_endpoints: Vec<RegionalEndpoint>,
+    /// The regions avoided after failures
+    pub unavailable_regions: Vec<String>,
+    /// How long ago the account topology was read, `None` if it never was
+    pub last_refresh: Option<Duration>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    account: Option<DatabaseAccount>,
+    refreshed_at: Option<Instant>,
+    force_refresh: bool,
+    /// Endpoints that failed, with when they can be tried again
+    unavailable: HashMap<String, Instant>,
+}
+
+/// The regional endpoints of an account, ordered by the preferred regions
+#[derive(Debug)]
+pub(crate) struct LocationCache {
+    default_endpoint: String,
+    preferred_regions: Vec<String>,
+    refresh_interval: Duration,
+    state: RwLock<State>,
+}
+
+impl LocationCache {
+    pub fn new(
+        default_endpoint: String,
+        preferred_regions: Vec<String>,
+        refresh_interval: Duration,
+    ) -> Self {
+        Self {
+            default_endpoint,
+            preferred_regions,
+            refresh_interval,
+            state: RwLock::new(State::default()),
+        }
+    }
+
+    pub fn default_endpoint(&self) -> &str {
+        &self.default_endpoint
+    }
+
+    /// The account as last read, `None` until its topology is read
+    pub fn account(&self) -> Option<DatabaseAccount> {
+        self.state.read().unwrap().account.clone()
+    }
+
+    /// Whether the account topology must be read again
+    pub fn needs_refresh(&self) -> bool {
+        let state = self.state.read().unwrap();
+        state.force_refresh
+            || state
+                .refreshed_at
+                .is_none_or(|refreshed_at| refreshed_at.elapsed() >= self.refresh_interval)
+    }
+
+    pub fn update(&self, account: DatabaseAccount) {
+        let mut state = self.state.write().unwrap();
+        state.account = Some(account);
+        state.refreshed_at = Some(Instant::now());
+        state.force_refresh = false;
+    }
+
+    /// Keeps routing with what is known until the next periodic refresh
+    pub fn refresh_failed(&self) {
+        let mut state = self.state.write().unwrap();
+        state.refreshed_at = Some(Instant::now());
+        state.force_refresh = false;
+    }
+
+    /// Avoids the endpoint for a while and reads the account topology again before the next request
+    pub fn mark_unavailable(&self, endpoint: &str) {
+        let mut state = self.state.write().unwrap();
+        state
+            .unavailable
+            .insert(endpoint.to_owned(), Instant::now() + UNAVAILABLE_DURATION);
+        state.force_refresh = true;
+    }
+
+    /// The endpoints to try for a request, the available ones first
+    pub fn endpoints(&self, read: bool) -> Vec<RegionalEndpoint> {
+        let state = self.state.read().unwrap();
+        let mut endpoints = match &state.account {
+            Some(account) => self.ordered_endpoints(account, read),
+            None => Vec::new(),
+        };
+        if endpoints.is_empty() {
+            endpoints.push(RegionalEndpoint {
+                region: None,
+                endpoint: self.default_endpoint.clone(),
+            });
+        }
+
+        let now = Instant::now();
+        // a stable sort keeps the preferred order within the available and unavailable endpoints
+        endpoints.sort_by_key(|endpoint| {
+            state
+                .unavailable
+                .get(&endpoint.endpoint)
+                .is_some_and(|until| *until > now)
+        });
+        endpoints
+    }
+
+    fn ordered_endpoints(&self, account: &DatabaseAccount, read: bool) -> Vec<RegionalEndpoint> {
+        // the preferred regions first, then the other regions in the order of the account
+        let order = |regions: &[AccountRegion]| {
+            let mut ordered = self
+                .preferred_regions
+                .iter()
+                .filter_map(|preferred| {
+                    regions
+                        .iter()
+                        .find(|region| same_region(&region.name, preferred))
+                })
+                .cloned()
+                .collect::<Vec<_>>();
+            for region in regions {
+                if !ordered.iter().any(|r| same_region(&r.name, &region.name)) {
+                    ordered.push(region.clone());
+                }
+            }
+            ordered
+        };
+
+        let regions = if read {
+            let mut regions = order(&account.readable_locations);
+            // without a readable region, reads go to the write region
+            if regions.is_empty() {
+                regions.extend(account.writable_locations.first().cloned());
+            }
+            regions
+        } else if account.enable_multiple_write_locations {
+            order(&account.writable_locations)
+        } else {
+            account.writable_locations.iter().take(1).cloned().collect()
+        };
+
+        regions
+            .into_iter()
+            .map(|region| RegionalEndpoint {
+                region: Some(region.name),
+                endpoint: region.database_account_endpoint,
+            })
+            .collect()
+    }
+
+    /// The region of the endpoint a request was sent to, such as `https://account-westus.documents.azure.com/dbs`
+    pub fn region(&self, uri: &str) -> Option<String> {
+        let state = self.state.read().unwrap();
+        let account = state.account.as_ref()?;
+        account
+            .writable_locations
+            .iter()
+            .chain(&account.readable_locations)
+            .find(|region| uri.starts_with(region.database_account_endpoint.trim_end_matches('/')))
+            .map(|region| region.name.clone())
+    }
+
+    pub fn diagnostics(&self) -> LocationDiagnostics {
+        let now = Instant::now();
+        let state = self.state.read().unwrap();
+        let region = |endpoint: &String| {
+            state
+                .account
+                .iter()
+                .flat_map(|account| {
+                    account
+                        .writable_locations
+                        .iter()
+                        .chain(&account.readable_locations)
+                })
+                .find(|region| &region.database_account_endpoint == endpoint)
+                .map_or_else(|| endpoint.clone(), |region| region.name.clone())
+        };
+        let mut unavailable_regions: Vec<_> = state
+            .unavailable
+            .iter()
+            .filter(|(_, until)| **until > now)
+            .map(|(endpoint, _)| region(endpoint))
+            .collect();
+        unavailable_regions.sort();
+        unavailable_regions.dedup();
+        let last_refresh = state
+            .refreshed_at
+            .map(|refreshed_at| refreshed_at.elapsed());
+        drop(state);
+
+        LocationDiagnostics {
+            endpoint_discovery: true,
+            write_endpoints: self.endpoints(false),
+            read_endpoints: self.endpoints(true),
+            unavailable_regions,
+            last_refresh,
+        }
+    }
+}
+
+/// Region names are compared ignoring case and spaces, so `West US` and `westus` are the same
+fn same_region(a: &str, b: &str) -> bool {
+    let normalize = |name: &str| {
+        name.chars()
+            .filter(|c| !c.is_whitespace())
+            .flat_map(char::to_lowercase)
+            .collect::<String>()
+    };
+    normalize(a) == normalize(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(multiple_write_locations: bool) -> DatabaseAccount {
+        serde_json::from_value(serde_json::json!({
+            "writableLocations": [
+                {"name": "West US", "databaseAccountEndpoint": "https://a-westus.documents.azure.com:443/"},
+                {"name": "East US", "databaseAccountEndpoint": "https://a-eastus.documents.azure.com:443/"}
+            ],
+            "readableLocations": [
+                {"name": "West US", "databaseAccountEndpoint": "https://a-westus.documents.azure.com:443/"},
+                {"name": "East US", "databaseAccountEndpoint": "https://a-eastus.documents.azure.com:443/"},
+                {"name": "North Europe", "databaseAccountEndpoint": "https://a-northeurope.documents.azure.com:443/"}
+            ],
+            "enableMultipleWriteLocations": multiple_write_locations
+        }))
+        .unwrap()
+    }
+
+    fn regions(endpoints: Vec<RegionalEndpoint>) -> Vec<String> {
+        endpoints
+            .into_iter()
+            .map(|endpoint| endpoint.region.unwrap_or_default())
+            .collect()
+    }
+
+    #[test]
+    fn orders_endpoints_by_preferred_regions() {
+        let cache = LocationCache::new(
+            "https://a.documents.azure.com".to_owned(),
+            vec!["northeurope".to_owned(), "East US".to_owned()],
+            Duration::from_secs(300),
+        );
+        assert!(cache.needs_refresh());
+        assert_eq!(
+            cache.endpoints(true),
+            [RegionalEndpoint {
+                region: None,
+                endpoint: "https://a.documents.azure.com".to_owned()
+            }]
+        );
+
+        cache.update(account(false));
+        assert!(!cache.needs_refresh());
+        assert_eq!(
+            regions(cache.endpoints(true)),
+            ["North Europe", "East US", "West US"]
+        );
+        assert_eq!(regions(cache.endpoints(false)), ["West US"]);
+
+        cache.update(account(true));
+        assert_eq!(regions(cache.endpoints(false)), ["East US", "West US"]);
+    }
+
+    #[test]
+    fn moves_unavailable_endpoints_last() {
+        let cache = LocationCache::new(
+            "https://a.documents.azure.com".to_owned(),
+            vec!["northeurope".to_owned()],
+            Duration::from_secs(300),
+        );
+        cache.update(account(false));
+
+        cache.mark_unavailable("https://a-northeurope.documents.azure.com:443/");
+
+        assert!(cache.needs_refresh());
+        assert_eq!(
+            regions(cache.endpoints(true)),
+            ["West US", "East US", "North Europe"]
+        );
+        assert_eq!(cache.diagnostics().unavailable_regions, ["North Europe"]);
+    }
+
+    #[test]
+    fn fails_over_reads_without_preferred_regions() {
+        let cache = LocationCache::new(
+            "https://a.documents.azure.com".to_owned(),
+            Vec::new(),
+            Duration::from_secs(300),
+        );
+        cache.update(account(false));
+        assert_eq!(
+            regions(cache.endpoints(true)),
+            ["West US", "East US", "North Europe"]
+        );
+
+        cache.mark_unavailable("https://a-westus.documents.azure.com:443/");
+
+        assert_eq!(
+            regions(cache.endpoints(true)),
+            ["East US", "North Europe", "West US"]
+        );
+        assert_eq!(regions(cache.endpoints(false)), ["West US"]);
+    }
+}
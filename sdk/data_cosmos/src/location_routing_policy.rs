@@ -0,0 +1,366 @@
+use crate::diagnostics::DiagnosticsRecorder;
+use crate::headers::{HEADER_DOCUMENTDB_ISQUERY, HEADER_SUB_STATUS};
+use crate::location_cache::{DatabaseAccount, LocationCache, RegionalEndpoint};
+use crate::resources::ResourceType;
+use azure_core::error::{ErrorKind, ResultExt};
+use azure_core::{collect_pinned_stream, Context, Policy, PolicyResult, Request};
+use futures::lock::Mutex;
+use http::StatusCode;
+use std::sync::Arc;
+
+/// The sub-status of a 403 returned by a region that no longer accepts writes
+const WRITE_FORBIDDEN: &str = "1008";
+
+/// The `LocationRoutingPolicy` sends every request to the regional endpoint of the account that
+/// should serve it. Reads go to the preferred regions in order, writes go to the write region.
+/// When a region is unavailable (503, network errors) or stopped accepting writes (403 with
+/// sub-status 1008), the request is sent to the next region and the account topology is read
+/// again. The topology is also refreshed periodically.
+/// The policy must be added before the `AuthorizationPolicy` since it replaces the request uri.
+#[derive(Debug)]
+pub(crate) struct LocationRoutingPolicy {
+    cache: Arc<LocationCache>,
+    /// Held while reading the account so that concurrent requests share a single refresh
+    refresh: Mutex<()>,
+}
+
+impl LocationRoutingPolicy {
+    pub(crate) fn new(cache: Arc<LocationCache>) -> Self {
+        Self {
+            cache,
+            refresh: Mutex::new(()),
+        }
+    }
+
+    /// Reads the account topology, trying the global endpoint and then the known regions.
+    ///
+    /// Failures are logged: requests keep being routed with what is known.
+    async fn refresh(&self, ctx: &Context, next: &[Arc<dyn Policy>]) {
+        let _refresh = self.refresh.lock().await;
+        if !self.cache.needs_refresh() {
+            return;
+        }
+
+        let mut ctx = ctx.clone();
+        ctx.insert(ResourceType::DatabaseAccount);
+        let mut endpoints = vec![self.cache.default_endpoint().to_owned()];
+        endpoints.extend(
+            self.cache
+                .endpoints(true)
+                .into_iter()
+                .map(|endpoint| endpoint.endpoint),
+        );
+        endpoints.dedup();
+
+        for endpoint in endpoints {
+            match read_account(&ctx, &endpoint, next).await {
+                Ok(account) => {
+                    debug!("read the regions of the account from {}", endpoint);
+                    self.cache.update(account);
+                    return;
+                }
+                Err(error) => {
+                    warn!(
+                        "could not read the regions of the account from {}: {}",
+                        endpoint, error
+                    )
+                }
+            }
+        }
+        self.cache.refresh_failed();
+    }
+}
+
+#[async_trait::async_trait]
+impl Policy for LocationRoutingPolicy {
+    async fn send(
+        &self,
+        ctx: &Context,
+        request: &mut Request,
+        next: &[Arc<dyn Policy>],
+    ) -> PolicyResult {
+        assert!(
+            !next.is_empty(),
+            "LocationRoutingPolicy cannot be the last policy of a pipeline"
+        );
+
+        if self.cache.needs_refresh() {
+            self.refresh(ctx, next).await;
+        }
+
+        let read = is_read(request);
+        // every endpoint can be tried once, plus once more after a write region change
+        let attempts = self.cache.endpoints(read).len() + 1;
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let endpoint = self.cache.endpoints(read).remove(0);
+            trace!(
+                "routing {} request to region {:?}",
+                if read { "read" } else { "write" },
+                endpoint.region
+            );
+            let mut routed = route(request, &endpoint)?;
+            let result = next[0].send(ctx, &mut routed, &next[1..]).await;
+
+            let write_forbidden = match &result {
+                Ok(response) => {
+                    response.status() == StatusCode::FORBIDDEN
+                        && response
+                            .headers()
+                            .get(HEADER_SUB_STATUS)
+                            .is_some_and(|sub_status| sub_status == WRITE_FORBIDDEN)
+                }
+                Err(_) => false,
+            };
+            let unavailable = match &result {
+                Ok(response) => response.status() == StatusCode::SERVICE_UNAVAILABLE,
+                Err(error) => error.kind() == &ErrorKind::Io,
+            };
+            if (!write_forbidden && !unavailable) || attempt >= attempts {
+                return result;
+            }
+
+            self.cache.mark_unavailable(&endpoint.endpoint);
+            if write_forbidden {
+                self.refresh(ctx, next).await;
+            } else if self.cache.endpoints(read)[0] == endpoint {
+                // there is no other region to fail over to
+                return result;
+            }
+            debug!(
+                "region {:?} failed, sending the request to the next region",
+                endpoint.region
+            );
+        }
+    }
+}
+
+/// Whether the request only reads, so that it can be served by any readable region
+pub(crate) fn is_read(request: &Request) -> bool {
+    match request.method() {
+        http::Method::GET | http::Method::HEAD => true,
+        http::Method::POST => request
+            .headers()
+            .get(&HEADER_DOCUMENTDB_ISQUERY.into())
+            .is_some(),
+        _ => false,
+    }
+}
+
+/// A copy of the request sent to the given endpoint
+fn route(request: &Request, endpoint: &RegionalEndpoint) -> azure_core::error::Result<Request> {
+    let path_and_query = request
+        .uri()
+        .path_and_query()
+        .map_or("/", |path_and_query| path_and_query.as_str());
+    let uri = format!(
+        "{}{}",
+        endpoint.endpoint.trim_end_matches('/'),
+        path_and_query
+    );
+    let mut routed = Request::new(
+        uri.parse().with_context(ErrorKind::DataConversion, || {
+            format!("invalid regional endpoint '{}'", endpoint.endpoint)
+        })?,
+        request.method(),
+    );
+    *routed.headers_mut() = request.headers().clone();
+    routed.set_body(request.body().clone());
+    Ok(routed)
+}
+
+/// Reads the account from the endpoint, the context must carry `ResourceType::DatabaseAccount`
+pub(crate) async fn read_account(
+    ctx: &Context,
+    endpoint: &str,
+    next: &[Arc<dyn Policy>],
+) -> azure_core::error::Result<DatabaseAccount> {
+    // the read is not an attempt of the operation that needed it
+    let mut ctx = ctx.clone();
+    ctx.remove::<DiagnosticsRecorder>();
+    let mut request = Request::new(
+        format!("{}/", endpoint.trim_end_matches('/'))
+            .parse()
+            .with_context(ErrorKind::DataConversion, || {
+                format!("invalid endpoint '{}'", endpoint)
+            })?,
+        http::Method::GET,
+    );
+    let response = next[0].send(&ctx, &mut request, &next[1..]).await?;
+    let (status, _headers, pinned_stream) = response.deconstruct();
+    let body = collect_pinned_stream(pinned_stream).await?;
+    if !status.is_success() {
+        return Err(ErrorKind::http_response_from_body(status.as_u16(), &body).into_error());
+    }
+    serde_json::from_slice(&body).context(
+        ErrorKind::DataConversion,
+        "could not parse the database account",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mock_endpoint::{MockEndpoint, MockResponse, RecordedRequest};
+    use crate::prelude::*;
+    use std::sync::{Arc, Mutex};
+
+    /// The account with its write region first
+    fn account(write: (&str, &str), read: (&str, &str)) -> MockResponse {
+        MockResponse::new(
+            200,
+            serde_json::json!({
+                "id": "mock",
+                "writableLocations": [{"name": write.0, "databaseAccountEndpoint": format!("{}/", write.1)}],
+                "readableLocations": [
+                    {"name": write.0, "databaseAccountEndpoint": format!("{}/", write.1)},
+                    {"name": read.0, "databaseAccountEndpoint": format!("{}/", read.1)}
+                ]
+            })
+            .to_string(),
+        )
+        .with_cosmos_headers()
+    }
+
+    fn database() -> MockResponse {
+        MockResponse::new(
+            200,
+            r#"{"id":"db","_rid":"a","_ts":1,"_self":"b","_etag":"c","_colls":"colls/","_users":"users/"}"#,
+        )
+        .with_cosmos_headers()
+        .with_header("etag", "c")
+    }
+
+    fn paths(endpoint: &MockEndpoint) -> Vec<String> {
+        endpoint
+            .requests()
+            .into_iter()
+            .map(|request| format!("{} {}", request.method, request.path))
+            .collect()
+    }
+
+    fn client(endpoint: &MockEndpoint, preferred_regions: &[&str]) -> CosmosClient {
+        CosmosClient::new_custom(
+            "mock".to_owned(),
+            AuthorizationToken::primary_from_base64(EMULATOR_ACCOUNT_KEY).unwrap(),
+            endpoint.url(),
+            CosmosOptions::new().preferred_regions(preferred_regions.iter().copied()),
+        )
+    }
+
+    #[tokio::test]
+    async fn reads_fail_over_to_the_next_preferred_region() {
+        let east = MockEndpoint::serve(|_: &RecordedRequest| Some(MockResponse::new(503, "")));
+        let west_url = Arc::new(Mutex::new(String::new()));
+        let west = MockEndpoint::serve({
+            let (west_url, east_url) = (west_url.clone(), east.url());
+            move |request: &RecordedRequest| {
+                Some(match request.path.as_str() {
+                    "/" => account(
+                        ("West US", &west_url.lock().unwrap()),
+                        ("East US", &east_url),
+                    ),
+                    _ => database(),
+                })
+            }
+        });
+        *west_url.lock().unwrap() = west.url();
+        let client = client(&west, &["East US", "West US"]);
+
+        let response = client
+            .database_client("db")
+            .get_database()
+            .into_future()
+            .await
+            .unwrap();
+
+        assert_eq!(paths(&west), ["GET /", "GET /dbs/db"]);
+        assert_eq!(paths(&east), ["GET /dbs/db"]);
+        let attempts: Vec<_> = response
+            .diagnostics
+            .attempts
+            .iter()
+            .map(|attempt| (attempt.status, attempt.region.as_deref()))
+            .collect();
+        assert_eq!(
+            attempts,
+            [(Some(503), Some("East US")), (Some(200), Some("West US"))]
+        );
+        let diagnostics = client.location_diagnostics();
+        assert!(diagnostics.endpoint_discovery);
+        assert_eq!(diagnostics.unavailable_regions, ["East US"]);
+        let read_regions: Vec<_> = diagnostics
+            .read_endpoints
+            .into_iter()
+            .map(|endpoint| endpoint.region.unwrap())
+            .collect();
+        assert_eq!(read_regions, ["West US", "East US"]);
+        let authorization = west.requests()[0]
+            .header("authorization")
+            .unwrap()
+            .to_owned();
+        assert!(authorization.starts_with("type%3Dmaster"));
+    }
+
+    #[tokio::test]
+    async fn writes_follow_the_write_region() {
+        let east = MockEndpoint::serve(|_: &RecordedRequest| {
+            Some(MockResponse::new(204, "").with_cosmos_headers())
+        });
+        let west_url = Arc::new(Mutex::new(String::new()));
+        let west = MockEndpoint::serve({
+            let (west_url, east_url) = (west_url.clone(), east.url());
+            let mut failed_over = false;
+            move |request: &RecordedRequest| {
+                let west_url = west_url.lock().unwrap().clone();
+                Some(match request.path.as_str() {
+                    "/" if failed_over => account(("East US", &east_url), ("West US", &west_url)),
+                    "/" => account(("West US", &west_url), ("East US", &east_url)),
+                    _ => {
+                        failed_over = true;
+                        MockResponse::new(
+                            403,
+                            r#"{"code":"Forbidden","message":"write forbidden"}"#,
+                        )
+                        .with_header("x-ms-substatus", "1008")
+                    }
+                })
+            }
+        });
+        *west_url.lock().unwrap() = west.url();
+        let client = client(&west, &[]);
+
+        client
+            .database_client("db")
+            .delete_database()
+            .into_future()
+            .await
+            .unwrap();
+
+        assert_eq!(paths(&west), ["GET /", "DELETE /dbs/db", "GET /"]);
+        assert_eq!(paths(&east), ["DELETE /dbs/db"]);
+        assert_eq!(
+            client.location_diagnostics().write_endpoints[0]
+                .region
+                .as_deref(),
+            Some("East US")
+        );
+    }
+
+    #[test]
+    fn routes_to_the_global_endpoint_without_discovery() {
+        let client = CosmosClient::new(
+            "mock".to_owned(),
+            AuthorizationToken::primary_from_base64(EMULATOR_ACCOUNT_KEY).unwrap(),
+            CosmosOptions::default(),
+        );
+
+        let diagnostics = client.location_diagnostics();
+
+        assert!(!diagnostics.endpoint_discovery);
+        assert_eq!(
+            diagnostics.read_endpoints[0].endpoint,
+            "https://mock.documents.azure.com"
+        );
+    }
+}
@@ -0,0 +1,240 @@
+//! A tiny HTTP server used by the unit tests to stand in for Azure endpoints
+//!
+//! Every accepted connection is answered with the next canned response and the
+//! request is recorded so tests can assert on what the client sent.
+use crate::clients::{CosmosClient, CosmosOptions, EMULATOR_ACCOUNT_KEY};
+use crate::resources::permission::AuthorizationToken;
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+
+/// A request received by the mock endpoint.
+#[derive(Debug, Clone)]
+pub(crate) struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl RecordedRequest {
+    /// Looks up a header by case-insensitive name.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn is_account_read(&self) -> bool {
+        self.method == "GET" && self.path == "/"
+    }
+}
+
+/// A canned response returned by the mock endpoint.
+#[derive(Debug, Clone)]
+pub(crate) struct MockResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+impl MockResponse {
+    pub fn new(status: u16, body: impl Into<String>) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: body.into(),
+        }
+    }
+
+    /// Sets a header, replacing any header of the same name.
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers
+            .retain(|(key, _)| !key.eq_ignore_ascii_case(name));
+        self.headers.push((name.to_owned(), value.to_owned()));
+        self
+    }
+
+    /// A database account with session consistency.
+    pub fn account() -> Self {
+        Self::new(
+            200,
+            r#"{"id":"mock","userConsistencyPolicy":{"defaultConsistencyLevel":"Session"}}"#,
+        )
+        .with_cosmos_headers()
+    }
+
+    /// Adds the headers Cosmos returns with every resource response.
+    pub fn with_cosmos_headers(self) -> Self {
+        const QUOTA: &str =
+            "documentSize=10240;documentsSize=10485760;documentsCount=-1;collectionSize=10485760;";
+        [
+            ("content-location", "https://mock/"),
+            ("server", "Microsoft-HTTPAPI/2.0"),
+            (
+                "x-ms-last-state-change-utc",
+                "Fri, 25 Mar 2022 08:05:10.000 GMT",
+            ),
+            ("x-ms-resource-quota", QUOTA),
+            ("x-ms-resource-usage", QUOTA),
+            ("lsn", "1"),
+            ("x-ms-item-count", "1"),
+            ("x-ms-schemaversion", "1.13"),
+            ("x-ms-alt-content-path", "dbs/db/colls/coll"),
+            ("x-ms-content-path", "3iNTAJKxVCk="),
+            ("x-ms-quorum-acked-lsn", "1"),
+            ("x-ms-current-write-quorum", "3"),
+            ("x-ms-current-replica-set-size", "4"),
+            ("x-ms-xp-role", "1"),
+            ("x-ms-global-committed-lsn", "1"),
+            ("x-ms-number-of-read-regions", "0"),
+            ("x-ms-item-lsn", "1"),
+            ("x-ms-transport-request-id", "1"),
+            ("x-ms-cosmos-llsn", "1"),
+            ("x-ms-cosmos-item-llsn", "1"),
+            ("x-ms-cosmos-quorum-acked-llsn", "1"),
+            ("x-ms-session-token", "0:-1#1"),
+            ("x-ms-request-charge", "1"),
+            ("x-ms-serviceversion", "version=2.14.0.0"),
+            ("x-ms-activity-id", "a3a3bd5b-5b1c-4c32-9b5a-a5c0f5e2f3c4"),
+            ("x-ms-gatewayversion", "version=2.14.0"),
+            ("date", "Fri, 25 Mar 2022 08:05:10 GMT"),
+        ]
+        .into_iter()
+        .fold(self, |response, (name, value)| {
+            response.with_header(name, value)
+        })
+    }
+}
+
+/// A mock endpoint listening on a random loopback port.
+pub(crate) struct MockEndpoint {
+    port: u16,
+    requests: Arc<Mutex<Vec<RecordedRequest>>>,
+}
+
+impl MockEndpoint {
+    /// Starts serving the given responses, one per connection, in order.
+    ///
+    /// Reads of the database account (`GET /`) are answered with [`MockResponse::account`]
+    /// and are neither taken from the responses nor recorded.
+    pub fn start(responses: Vec<MockResponse>) -> Self {
+        let mut responses: VecDeque<_> = responses.into();
+        Self::listen(move |_| responses.pop_front(), true)
+    }
+
+    /// Starts answering every request with the response the handler picks for it.
+    ///
+    /// The endpoint stops once the handler returns `None`.
+    pub fn serve<F>(handler: F) -> Self
+    where
+        F: FnMut(&RecordedRequest) -> Option<MockResponse> + Send + 'static,
+    {
+        Self::listen(handler, false)
+    }
+
+    fn listen<F>(mut handler: F, answer_account_reads: bool) -> Self
+    where
+        F: FnMut(&RecordedRequest) -> Option<MockResponse> + Send + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+
+        std::thread::spawn(move || loop {
+            let mut stream = match listener.accept() {
+                Ok((stream, _)) => stream,
+                Err(_) => return,
+            };
+            let request = match read_request(&mut stream) {
+                Some(request) => request,
+                None => continue,
+            };
+            let response = if answer_account_reads && request.is_account_read() {
+                Some(MockResponse::account())
+            } else {
+                let response = handler(&request);
+                recorded.lock().unwrap().push(request);
+                response
+            };
+            let response = match response {
+                Some(response) => response,
+                None => return,
+            };
+            let mut raw = format!(
+                "HTTP/1.1 {} Mock\r\ncontent-length: {}\r\nconnection: close\r\n",
+                response.status,
+                response.body.len()
+            );
+            for (name, value) in &response.headers {
+                raw.push_str(&format!("{}: {}\r\n", name, value));
+            }
+            raw.push_str("\r\n");
+            raw.push_str(&response.body);
+            let _ = stream.write_all(raw.as_bytes());
+        });
+
+        Self { port, requests }
+    }
+
+    /// The base url of the endpoint, without a trailing slash.
+    pub fn url(&self) -> String {
+        format!("http://127.0.0.1:{}", self.port)
+    }
+
+    /// A client for the emulator account served by this endpoint.
+    pub fn cosmos_client(&self) -> CosmosClient {
+        CosmosClient::new_custom(
+            "mock".to_owned(),
+            AuthorizationToken::primary_from_base64(EMULATOR_ACCOUNT_KEY).unwrap(),
+            self.url(),
+            CosmosOptions::default(),
+        )
+    }
+
+    /// The requests received so far.
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+fn read_request(stream: &mut std::net::TcpStream) -> Option<RecordedRequest> {
+    let mut reader = BufReader::new(stream);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).ok()?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next()?.to_owned();
+    let path = parts.next()?.to_owned();
+
+    let mut headers = Vec::new();
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).ok()?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_owned(), value.trim().to_owned()));
+        }
+    }
+
+    let content_length = headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.parse::<usize>().ok())
+        .unwrap_or(0);
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).ok()?;
+
+    Some(RecordedRequest {
+        method,
+        path,
+        headers,
+        body: String::from_utf8_lossy(&body).into_owned(),
+    })
+}
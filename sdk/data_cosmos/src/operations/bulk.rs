@@ -0,0 +1,744 @@
+use crate::cosmos_entity::serialize_partition_key;
+use crate::headers::HEADER_RETRY_AFTER_MS;
+use crate::partition_key_hash::{effective_partition_key, find_range};
+use crate::prelude::*;
+use crate::CosmosEntity;
+
+use azure_core::error::{Error, ErrorKind, HttpError};
+use azure_core::prelude::*;
+use futures::future::BoxFuture;
+use futures::stream::{BoxStream, FuturesUnordered, StreamExt};
+use http::StatusCode;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// How long to wait after a throttled request that did not say how long to back off
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(1);
+
+/// Executes many independent operations with as few requests as possible
+///
+/// Operations are grouped by partition key range and sent as non-atomic batches of up to
+/// [`MAX_BATCH_OPERATIONS`] operations sharing a partition key. Each range starts with a single
+/// request in flight and adds one more every time as many requests succeeded, up to
+/// `max_concurrency_per_range`; a throttled request halves it. Throttled operations are
+/// retried after the delay requested by the service. Operations on the same logical partition
+/// may be applied out of order.
+#[derive(Debug, Clone)]
+pub struct BulkBuilder {
+    client: CollectionClient,
+    operations: Vec<BulkOperation>,
+    max_concurrency_per_range: usize,
+    max_throttle_retries: u32,
+    max_request_units_per_second: Option<f64>,
+    consistency_level: Option<ConsistencyLevel>,
+    context: Context,
+}
+
+#[derive(Debug, Clone)]
+struct BulkOperation {
+    partition_key_serialized: String,
+    operation: TransactionalBatchOperation,
+}
+
+impl BulkBuilder {
+    pub(crate) fn new(client: CollectionClient) -> Self {
+        Self {
+            client,
+            operations: Vec::new(),
+            max_concurrency_per_range: 8,
+            max_throttle_retries: 10,
+            max_request_units_per_second: None,
+            consistency_level: None,
+            context: Context::new(),
+        }
+    }
+
+    setters! {
+        max_concurrency_per_range: usize => max_concurrency_per_range.max(1),
+        max_throttle_retries: u32,
+        max_request_units_per_second: f64 => Some(max_request_units_per_second),
+        consistency_level: ConsistencyLevel => Some(consistency_level),
+        context: Context => context,
+    }
+
+    /// Adds an operation on a document with the given partition key
+    pub fn operation<PK: Serialize>(
+        mut self,
+        partition_key: &PK,
+        operation: TransactionalBatchOperation,
+    ) -> azure_core::error::Result<Self> {
+        operation.validate()?;
+        self.operations.push(BulkOperation {
+            partition_key_serialized: serialize_partition_key(partition_key)?,
+            operation,
+        });
+        Ok(self)
+    }
+
+    /// Adds the creation of a document
+    pub fn create_document<D: Serialize + CosmosEntity>(
+        self,
+        document: &D,
+    ) -> azure_core::error::Result<Self> {
+        self.operation(
+            &document.partition_key(),
+            TransactionalBatchOperation::create(document)?,
+        )
+    }
+
+    /// Adds the upsert of a document
+    pub fn upsert_document<D: Serialize + CosmosEntity>(
+        self,
+        document: &D,
+    ) -> azure_core::error::Result<Self> {
+        self.operation(
+            &document.partition_key(),
+            TransactionalBatchOperation::upsert(document)?,
+        )
+    }
+
+    /// Adds the replacement of a document
+    pub fn replace_document<S: Into<String>, D: Serialize + CosmosEntity>(
+        self,
+        id: S,
+        document: &D,
+    ) -> azure_core::error::Result<Self> {
+        self.operation(
+            &document.partition_key(),
+            TransactionalBatchOperation::replace(id, document)?,
+        )
+    }
+
+    /// Adds the deletion of a document
+    pub fn delete_document<S: Into<String>, PK: Serialize>(
+        self,
+        id: S,
+        partition_key: &PK,
+    ) -> azure_core::error::Result<Self> {
+        self.operation(partition_key, TransactionalBatchOperation::delete(id))
+    }
+
+    /// Adds the read of a document
+    pub fn read_document<S: Into<String>, PK: Serialize>(
+        self,
+        id: S,
+        partition_key: &PK,
+    ) -> azure_core::error::Result<Self> {
+        self.operation(partition_key, TransactionalBatchOperation::read(id))
+    }
+
+    /// Adds a partial update of a document
+    pub fn patch_document<S: Into<String>, PK: Serialize>(
+        self,
+        id: S,
+        partition_key: &PK,
+        operations: Vec<PatchOperation>,
+    ) -> azure_core::error::Result<Self> {
+        self.operation(
+            partition_key,
+            TransactionalBatchOperation::patch(id, operations),
+        )
+    }
+
+    /// The number of operations added so far
+    pub fn len(&self) -> usize {
+        self.operations.len()
+    }
+
+    /// Whether no operation was added yet
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+
+    /// Convert into a stream yielding the result of every operation as it completes
+    pub fn into_stream(self) -> BulkStream {
+        futures::stream::unfold(Executor::new(self), |mut executor| async move {
+            executor.next().await.map(|result| (result, executor))
+        })
+        .boxed()
+    }
+
+    /// Convert into a future resolving to a summary of the execution once every operation completed
+    pub fn into_future(self) -> Bulk {
+        Box::pin(async move {
+            self.into_stream()
+                .fold(BulkSummary::default(), |mut summary, result| async move {
+                    summary.add(result);
+                    summary
+                })
+                .await
+        })
+    }
+}
+
+/// The stream returned by calling `into_stream` on the builder.
+pub type BulkStream = BoxStream<'static, BulkOperationResult>;
+
+/// The future returned by calling `into_future` on the builder.
+pub type Bulk = BoxFuture<'static, BulkSummary>;
+
+#[cfg(feature = "into_future")]
+impl std::future::IntoFuture for BulkBuilder {
+    type IntoFuture = Bulk;
+    type Output = <Bulk as std::future::Future>::Output;
+    fn into_future(self) -> Self::IntoFuture {
+        Self::into_future(self)
+    }
+}
+
+/// The outcome of an operation of a bulk execution
+#[derive(Debug)]
+pub struct BulkOperationResult {
+    /// The position of the operation in the order it was added
+    pub index: usize,
+    pub operation_type: BatchOperationType,
+    pub id: Option<String>,
+    pub partition_key_range_id: String,
+    /// The request units charged for the operation, including its throttled attempts
+    pub charge: f64,
+    /// How many times the operation was throttled and sent again
+    pub throttle_retries: u32,
+    /// The result reported by the service, or the error of the request that carried the operation
+    pub result: azure_core::error::Result<TransactionalBatchOperationResult>,
+}
+
+impl BulkOperationResult {
+    /// Whether the operation succeeded
+    pub fn is_success(&self) -> bool {
+        matches!(&self.result, Ok(result) if result.is_success())
+    }
+}
+
+/// A summary of a bulk execution
+#[derive(Debug, Default)]
+pub struct BulkSummary {
+    /// The request units charged for all the operations
+    pub total_charge: f64,
+    pub succeeded: usize,
+    /// How many times operations were throttled and sent again
+    pub throttle_retries: u64,
+    /// The operations that did not succeed, in the order they completed
+    pub failures: Vec<BulkOperationResult>,
+}
+
+impl BulkSummary {
+    fn add(&mut self, result: BulkOperationResult) {
+        self.total_charge += result.charge;
+        self.throttle_retries += u64::from(result.throttle_retries);
+        if result.is_success() {
+            self.succeeded += 1;
+        } else {
+            self.failures.push(result);
+        }
+    }
+
+    /// The number of operations that did not succeed
+    pub fn failed(&self) -> usize {
+        self.failures.len()
+    }
+}
+
+#[derive(Debug)]
+struct PendingOperation {
+    index: usize,
+    partition_key_serialized: String,
+    operation: TransactionalBatchOperation,
+    charge: f64,
+    retries: u32,
+}
+
+impl PendingOperation {
+    fn complete(
+        self,
+        partition_key_range_id: &str,
+        result: azure_core::error::Result<TransactionalBatchOperationResult>,
+    ) -> BulkOperationResult {
+        BulkOperationResult {
+            index: self.index,
+            operation_type: self.operation.operation_type(),
+            id: self.operation.id().map(ToOwned::to_owned),
+            partition_key_range_id: partition_key_range_id.to_owned(),
+            charge: self.charge,
+            throttle_retries: self.retries,
+            result,
+        }
+    }
+}
+
+/// The operations of a partition key range waiting to be sent, and how many requests may be in flight
+#[derive(Debug)]
+struct RangeState {
+    id: String,
+    queue: VecDeque<PendingOperation>,
+    concurrency: usize,
+    successes: usize,
+    in_flight: usize,
+    throttled_until: Option<Instant>,
+}
+
+impl RangeState {
+    fn new(id: String) -> Self {
+        Self {
+            id,
+            queue: VecDeque::new(),
+            concurrency: 1,
+            successes: 0,
+            in_flight: 0,
+            throttled_until: None,
+        }
+    }
+
+    /// Takes the next operations sharing a partition key, keeping the others in order
+    fn next_batch(&mut self) -> Vec<PendingOperation> {
+        let partition_key = match self.queue.front() {
+            Some(operation) => operation.partition_key_serialized.clone(),
+            None => return Vec::new(),
+        };
+        let mut batch = Vec::new();
+        let mut rest = VecDeque::with_capacity(self.queue.len());
+        for operation in self.queue.drain(..) {
+            if batch.len() < MAX_BATCH_OPERATIONS
+                && operation.partition_key_serialized == partition_key
+            {
+                batch.push(operation);
+            } else {
+                rest.push_back(operation);
+            }
+        }
+        self.queue = rest;
+        batch
+    }
+
+    fn on_success(&mut self, max_concurrency: usize) {
+        self.successes += 1;
+        if self.successes >= self.concurrency && self.concurrency < max_concurrency {
+            self.concurrency += 1;
+            self.successes = 0;
+        }
+    }
+
+    fn on_throttled(&mut self, retry_after: Option<Duration>) {
+        self.concurrency = (self.concurrency / 2).max(1);
+        self.successes = 0;
+        let until = Instant::now() + retry_after.unwrap_or(DEFAULT_RETRY_AFTER);
+        self.throttled_until = Some(self.throttled_until.map_or(until, |u| u.max(until)));
+    }
+}
+
+type BatchOutcome = (
+    usize,
+    Vec<PendingOperation>,
+    azure_core::error::Result<TransactionalBatchResponse>,
+);
+
+struct Executor {
+    builder: BulkBuilder,
+    unassigned: Option<Vec<PendingOperation>>,
+    ranges: Vec<RangeState>,
+    in_flight: FuturesUnordered<BoxFuture<'static, BatchOutcome>>,
+    completed: VecDeque<BulkOperationResult>,
+    started: Instant,
+    charge: f64,
+}
+
+impl Executor {
+    fn new(mut builder: BulkBuilder) -> Self {
+        let unassigned = std::mem::take(&mut builder.operations)
+            .into_iter()
+            .enumerate()
+            .map(|(index, operation)| PendingOperation {
+                index,
+                partition_key_serialized: operation.partition_key_serialized,
+                operation: operation.operation,
+                charge: 0.0,
+                retries: 0,
+            })
+            .collect();
+        Self {
+            builder,
+            unassigned: Some(unassigned),
+            ranges: Vec::new(),
+            in_flight: FuturesUnordered::new(),
+            completed: VecDeque::new(),
+            started: Instant::now(),
+            charge: 0.0,
+        }
+    }
+
+    async fn next(&mut self) -> Option<BulkOperationResult> {
+        loop {
+            if let Some(result) = self.completed.pop_front() {
+                return Some(result);
+            }
+            if let Some(operations) = self.unassigned.take() {
+                self.assign(operations).await;
+            }
+            self.dispatch();
+            let (range, batch, response) = self.in_flight.next().await?;
+            self.complete(range, batch, response);
+        }
+    }
+
+    /// Queues the operations on the partition key range holding their partition key
+    async fn assign(&mut self, operations: Vec<PendingOperation>) {
+        if operations.is_empty() {
+            return;
+        }
+        let ranges = self
+            .builder
+            .client
+            .get_partition_key_ranges()
+            .context(self.builder.context.clone())
+            .into_future()
+            .await
+            .map(|response| response.partition_key_ranges);
+        let ranges = match ranges {
+            Ok(ranges) => ranges,
+            Err(error) => {
+                // the ranges only spread the load, so carry on with a single queue
+                warn!(
+                    "could not read the partition key ranges, sending operations in sequence: {}",
+                    error
+                );
+                Vec::new()
+            }
+        };
+
+        self.ranges = ranges
+            .iter()
+            .map(|range| RangeState::new(range.id.clone()))
+            .collect();
+        if self.ranges.is_empty() {
+            self.ranges.push(RangeState::new(String::new()));
+        }
+        for operation in operations {
+            let range = effective_partition_key(&operation.partition_key_serialized)
+                .and_then(|epk| find_range(&ranges, &epk))
+                .unwrap_or_default();
+            self.ranges[range].queue.push_back(operation);
+        }
+    }
+
+    /// Sends batches for every range with room for more requests in flight
+    fn dispatch(&mut self) {
+        let budget_delay = self.budget_delay();
+        for (index, range) in self.ranges.iter_mut().enumerate() {
+            while range.in_flight < range.concurrency && !range.queue.is_empty() {
+                let batch = range.next_batch();
+                let delay = range
+                    .throttled_until
+                    .map(|until| until.saturating_duration_since(Instant::now()))
+                    .unwrap_or_default()
+                    .max(budget_delay);
+
+                let mut request = TransactionalBatchBuilder::new(
+                    self.builder.client.clone(),
+                    batch[0].partition_key_serialized.clone(),
+                )
+                .non_atomic()
+                .context(self.builder.context.clone());
+                if let Some(consistency_level) = &self.builder.consistency_level {
+                    request = request.consistency_level(consistency_level.clone());
+                }
+                for operation in &batch {
+                    request = request.operation(operation.operation.clone());
+                }
+
+                range.in_flight += 1;
+                self.in_flight.push(Box::pin(async move {
+                    if !delay.is_zero() {
+                        azure_core::sleep(delay).await;
+                    }
+                    (index, batch, request.into_future().await)
+                }));
+            }
+        }
+    }
+
+    /// How long to wait so that the request units charged so far stay within the budget
+    fn budget_delay(&self) -> Duration {
+        match self.builder.max_request_units_per_second {
+            Some(budget) if budget > 0.0 => {
+                Duration::from_secs_f64(self.charge / budget).saturating_sub(self.started.elapsed())
+            }
+            _ => Duration::ZERO,
+        }
+    }
+
+    fn complete(
+        &mut self,
+        range_index: usize,
+        batch: Vec<PendingOperation>,
+        response: azure_core::error::Result<TransactionalBatchResponse>,
+    ) {
+        let max_concurrency = self.builder.max_concurrency_per_range;
+        let max_retries = self.builder.max_throttle_retries;
+        let range = &mut self.ranges[range_index];
+        range.in_flight -= 1;
+
+        match response {
+            Ok(response) if response.results.len() == batch.len() => {
+                self.charge += response.charge;
+                let mut throttled = false;
+                let mut retry_after = None;
+                let mut retries = Vec::new();
+                for (mut operation, result) in batch.into_iter().zip(response.results) {
+                    operation.charge += result.request_charge;
+                    if result.status_code == StatusCode::TOO_MANY_REQUESTS.as_u16()
+                        && operation.retries < max_retries
+                    {
+                        throttled = true;
+                        retry_after = retry_after.max(result.retry_after_milliseconds);
+                        operation.retries += 1;
+                        retries.push(operation);
+                    } else {
+                        self.completed
+                            .push_back(operation.complete(&range.id, Ok(result)));
+                    }
+                }
+                for operation in retries.into_iter().rev() {
+                    range.queue.push_front(operation);
+                }
+                if throttled {
+                    range.on_throttled(retry_after.map(Duration::from_millis));
+                } else {
+                    range.on_success(max_concurrency);
+                }
+            }
+            Ok(response) => {
+                self.charge += response.charge;
+                let message = format!(
+                    "the bulk request returned {} results for {} operations",
+                    response.results.len(),
+                    batch.len()
+                );
+                for operation in batch {
+                    let error = Error::message(ErrorKind::DataConversion, message.clone());
+                    self.completed
+                        .push_back(operation.complete(&range.id, Err(error)));
+                }
+            }
+            Err(error) => {
+                let http_error = error.downcast_ref::<HttpError>();
+                let throttled = http_error.map(HttpError::status)
+                    == Some(StatusCode::TOO_MANY_REQUESTS.as_u16());
+                if throttled {
+                    let retry_after = http_error
+                        .and_then(|e| e.header(HEADER_RETRY_AFTER_MS))
+                        .and_then(|ms| ms.parse().ok())
+                        .map(Duration::from_millis);
+                    range.on_throttled(retry_after);
+                }
+                let mut retries = Vec::new();
+                for mut operation in batch {
+                    if throttled && operation.retries < max_retries {
+                        operation.retries += 1;
+                        retries.push(operation);
+                    } else {
+                        self.completed.push_back(
+                            operation
+                                .complete(&range.id, Err(crate::diagnostics::copy_error(&error))),
+                        );
+                    }
+                }
+                for operation in retries.into_iter().rev() {
+                    range.queue.push_front(operation);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_endpoint::{MockEndpoint, MockResponse};
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct MyDocument {
+        id: String,
+        pk: String,
+    }
+
+    impl CosmosEntity for MyDocument {
+        type Entity = String;
+
+        fn partition_key(&self) -> Self::Entity {
+            self.pk.clone()
+        }
+    }
+
+    fn document(id: &str, pk: &str) -> MyDocument {
+        MyDocument {
+            id: id.to_owned(),
+            pk: pk.to_owned(),
+        }
+    }
+
+    fn collection(endpoint: &MockEndpoint) -> CollectionClient {
+        endpoint
+            .cosmos_client()
+            .database_client("db")
+            .collection_client("coll")
+    }
+
+    fn partition_key_ranges() -> MockResponse {
+        MockResponse::new(
+            200,
+            r#"{"_rid":"coll","PartitionKeyRanges":[{"_rid":"r","id":"0","_etag":"\"0\"","minInclusive":"","maxExclusive":"FF","ridPrefix":0,"_self":"s","throughputFraction":1,"status":"online","parents":[],"_ts":1}],"_count":1}"#,
+        )
+        .with_cosmos_headers()
+    }
+
+    fn batch_response(body: &str, charge: &str) -> MockResponse {
+        MockResponse::new(207, body).with_header("x-ms-request-charge", charge)
+    }
+
+    #[tokio::test]
+    async fn groups_operations_by_partition_key() {
+        let endpoint = MockEndpoint::start(vec![
+            partition_key_ranges(),
+            batch_response(
+                r#"[{"statusCode":201,"requestCharge":5},{"statusCode":201,"requestCharge":5}]"#,
+                "10",
+            ),
+            batch_response(r#"[{"statusCode":204,"requestCharge":2}]"#, "2"),
+        ]);
+
+        let results: Vec<_> = collection(&endpoint)
+            .bulk()
+            .create_document(&document("a", "1"))
+            .unwrap()
+            .delete_document("b", &"2")
+            .unwrap()
+            .upsert_document(&document("c", "1"))
+            .unwrap()
+            .into_stream()
+            .collect()
+            .await;
+
+        let mut indices: Vec<_> = results.iter().map(|result| result.index).collect();
+        indices.sort_unstable();
+        assert_eq!(indices, [0, 1, 2]);
+        assert!(results.iter().all(BulkOperationResult::is_success));
+        assert!(results.iter().all(|r| r.partition_key_range_id == "0"));
+        let delete = results.iter().find(|result| result.index == 1).unwrap();
+        assert_eq!(delete.operation_type, BatchOperationType::Delete);
+        assert_eq!(delete.id.as_deref(), Some("b"));
+        assert_eq!(delete.charge, 2.0);
+
+        let requests = endpoint.requests();
+        assert_eq!(requests[0].path, "/dbs/db/colls/coll/pkranges");
+        assert_eq!(
+            requests[1].header("x-ms-cosmos-batch-atomic"),
+            Some("False")
+        );
+        assert_eq!(
+            requests[1].header("x-ms-cosmos-batch-continue-on-error"),
+            Some("True")
+        );
+        assert_eq!(
+            requests[1].header("x-ms-documentdb-partitionkey"),
+            Some(r#"["1"]"#)
+        );
+        assert_eq!(
+            requests[1].body,
+            r#"[{"operationType":"Create","resourceBody":{"id":"a","pk":"1"}},{"operationType":"Upsert","resourceBody":{"id":"c","pk":"1"}}]"#
+        );
+        assert_eq!(
+            requests[2].header("x-ms-documentdb-partitionkey"),
+            Some(r#"["2"]"#)
+        );
+    }
+
+    #[tokio::test]
+    async fn retries_throttled_operations() {
+        let endpoint = MockEndpoint::start(vec![
+            partition_key_ranges(),
+            batch_response(
+                r#"[{"statusCode":201,"requestCharge":5},{"statusCode":429,"requestCharge":0.5,"retryAfterMilliseconds":1}]"#,
+                "5.5",
+            ),
+            batch_response(r#"[{"statusCode":201,"requestCharge":5}]"#, "5"),
+        ]);
+
+        let summary = collection(&endpoint)
+            .bulk()
+            .create_document(&document("a", "1"))
+            .unwrap()
+            .create_document(&document("b", "1"))
+            .unwrap()
+            .into_future()
+            .await;
+
+        assert_eq!(summary.succeeded, 2);
+        assert_eq!(summary.failed(), 0);
+        assert_eq!(summary.throttle_retries, 1);
+        assert_eq!(summary.total_charge, 10.5);
+
+        let requests = endpoint.requests();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(
+            requests[2].body,
+            r#"[{"operationType":"Create","resourceBody":{"id":"b","pk":"1"}}]"#
+        );
+    }
+
+    #[tokio::test]
+    async fn reports_failures() {
+        let endpoint = MockEndpoint::start(vec![
+            partition_key_ranges(),
+            batch_response(
+                r#"[{"statusCode":409,"requestCharge":1},{"statusCode":429,"requestCharge":0}]"#,
+                "1",
+            ),
+            MockResponse::new(400, r#"{"code":"BadRequest","message":"bad"}"#),
+        ]);
+
+        let summary = collection(&endpoint)
+            .bulk()
+            .max_throttle_retries(0u32)
+            .create_document(&document("a", "1"))
+            .unwrap()
+            .read_document("b", &"1")
+            .unwrap()
+            .replace_document("c", &document("c", "2"))
+            .unwrap()
+            .into_future()
+            .await;
+
+        assert_eq!(summary.succeeded, 0);
+        assert_eq!(summary.failed(), 3);
+        let status = |index: usize| {
+            let failure = summary.failures.iter().find(|f| f.index == index).unwrap();
+            failure.result.as_ref().map(|result| result.status_code)
+        };
+        assert_eq!(status(0).unwrap(), 409);
+        assert_eq!(status(1).unwrap(), 429);
+        assert!(status(2).is_err());
+
+        let failure = summary.failures.iter().find(|f| f.index == 2).unwrap();
+        let error = failure.result.as_ref().unwrap_err();
+        assert_eq!(error.downcast_ref::<HttpError>().unwrap().status(), 400);
+        let diagnostics = crate::CosmosDiagnostics::from_error(error).unwrap();
+        assert_eq!(diagnostics.attempts.len(), 1);
+        assert_eq!(diagnostics.attempts[0].status, Some(400));
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_operations() {
+        let endpoint = MockEndpoint::start(vec![]);
+        assert!(collection(&endpoint)
+            .bulk()
+            .patch_document("a", &"1", vec![])
+            .is_err());
+        assert!(collection(&endpoint)
+            .bulk()
+            .into_future()
+            .await
+            .failures
+            .is_empty());
+        assert!(endpoint.requests().is_empty());
+    }
+}
@@ -1,6 +1,9 @@
 use crate::headers::from_headers::*;
 use crate::prelude::*;
-use crate::resources::collection::{IndexingPolicy, PartitionKey};
+use crate::resources::collection::{
+    ChangeFeedPolicy, ConflictResolutionPolicy, IndexingPolicy, PartitionKey, UniqueKeyPolicy,
+};
+use crate::CosmosDiagnostics;
 use azure_core::headers::{etag_from_headers, session_token_from_headers};
 use azure_core::{collect_pinned_stream, Context, Response as HttpResponse};
 use chrono::{DateTime, Utc};
@@ -11,6 +14,11 @@ pub struct CreateCollectionBuilder {
     partition_key: PartitionKey,
     consistency_level: Option<ConsistencyLevel>,
     indexing_policy: Option<IndexingPolicy>,
+    conflict_resolution_policy: Option<ConflictResolutionPolicy>,
+    unique_key_policy: Option<UniqueKeyPolicy>,
+    default_ttl: Option<i64>,
+    analytical_storage_ttl: Option<i64>,
+    change_feed_policy: Option<ChangeFeedPolicy>,
     collection_name: String,
     offer: Option<Offer>,
     context: Context,
@@ -28,6 +36,11 @@ impl CreateCollectionBuilder {
             partition_key,
             consistency_level: None,
             indexing_policy: None,
+            conflict_resolution_policy: None,
+            unique_key_policy: None,
+            default_ttl: None,
+            analytical_storage_ttl: None,
+            change_feed_policy: None,
             offer: None,
             context: Context::new(),
         }
@@ -36,12 +49,18 @@ impl CreateCollectionBuilder {
     setters! {
         consistency_level: ConsistencyLevel => Some(consistency_level),
         indexing_policy: IndexingPolicy => Some(indexing_policy),
+        conflict_resolution_policy: ConflictResolutionPolicy => Some(conflict_resolution_policy),
+        unique_key_policy: UniqueKeyPolicy => Some(unique_key_policy),
+        default_ttl: i64 => Some(default_ttl),
+        analytical_storage_ttl: i64 => Some(analytical_storage_ttl),
+        change_feed_policy: ChangeFeedPolicy => Some(change_feed_policy),
         offer: Offer => Some(offer),
         context: Context => context,
     }
 
     pub fn into_future(self) -> CreateCollection {
         Box::pin(async move {
+            self.partition_key.validate()?;
             let mut request = self.client.prepare_collections_pipeline(http::Method::POST);
             request.insert_headers(&self.offer);
             if let Some(cl) = &self.consistency_level {
@@ -51,18 +70,23 @@ impl CreateCollectionBuilder {
             let collection = CreateCollectionBody {
                 id: &self.collection_name,
                 indexing_policy: &self.indexing_policy,
+                conflict_resolution_policy: &self.conflict_resolution_policy,
+                unique_key_policy: &self.unique_key_policy,
+                default_ttl: self.default_ttl,
+                analytical_storage_ttl: self.analytical_storage_ttl,
+                change_feed_policy: &self.change_feed_policy,
                 partition_key: &self.partition_key,
             };
 
             request.set_body(serde_json::to_vec(&collection)?);
 
-            let response = self
+            let (response, diagnostics) = self
                 .client
                 .cosmos_client()
                 .send(request, self.context.clone(), ResourceType::Collections)
                 .await?;
 
-            CreateCollectionResponse::try_from(response).await
+            CreateCollectionResponse::try_from_with_diagnostics(response, diagnostics).await
         })
     }
 }
@@ -86,6 +110,22 @@ struct CreateCollectionBody<'a> {
     pub id: &'a str,
     #[serde(rename = "indexingPolicy", skip_serializing_if = "Option::is_none")]
     pub indexing_policy: &'a Option<IndexingPolicy>,
+    #[serde(
+        rename = "conflictResolutionPolicy",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub conflict_resolution_policy: &'a Option<ConflictResolutionPolicy>,
+    #[serde(rename = "uniqueKeyPolicy", skip_serializing_if = "Option::is_none")]
+    pub unique_key_policy: &'a Option<UniqueKeyPolicy>,
+    #[serde(rename = "defaultTtl", skip_serializing_if = "Option::is_none")]
+    pub default_ttl: Option<i64>,
+    #[serde(
+        rename = "analyticalStorageTtl",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub analytical_storage_ttl: Option<i64>,
+    #[serde(rename = "changeFeedPolicy", skip_serializing_if = "Option::is_none")]
+    pub change_feed_policy: &'a Option<ChangeFeedPolicy>,
     #[serde(rename = "partitionKey")]
     pub partition_key: &'a PartitionKey,
 }
@@ -105,14 +145,23 @@ pub struct CreateCollectionResponse {
     pub quorum_acked_lsn: u64,
     pub current_write_quorum: u64,
     pub current_replica_set_size: u64,
+    pub diagnostics: CosmosDiagnostics,
 }
 
 impl CreateCollectionResponse {
     pub async fn try_from(response: HttpResponse) -> azure_core::error::Result<Self> {
+        Self::try_from_with_diagnostics(response, CosmosDiagnostics::default()).await
+    }
+
+    pub(crate) async fn try_from_with_diagnostics(
+        response: HttpResponse,
+        diagnostics: CosmosDiagnostics,
+    ) -> azure_core::error::Result<Self> {
         let (_status_code, headers, pinned_stream) = response.deconstruct();
         let body = collect_pinned_stream(pinned_stream).await?;
 
         Ok(Self {
+            diagnostics,
             collection: serde_json::from_slice(&body)?,
             charge: request_charge_from_headers(&headers)?,
             activity_id: activity_id_from_headers(&headers)?,
@@ -129,3 +178,123 @@ impl CreateCollectionResponse {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::mock_endpoint::{MockEndpoint, MockResponse};
+    use crate::prelude::*;
+    use crate::resources::collection::{ChangeFeedPolicy, PartitionKey, UniqueKeyPolicy};
+
+    #[tokio::test]
+    async fn creates_a_collection_with_a_conflict_resolution_policy() {
+        let endpoint = MockEndpoint::start(vec![MockResponse::new(
+            201,
+            r#"{"id":"coll","indexingPolicy":{"indexingMode":"consistent","automatic":true,"includedPaths":[],"excludedPaths":[]},"partitionKey":{"paths":["/pk"],"kind":"Hash"},"conflictResolutionPolicy":{"mode":"Custom","conflictResolutionPath":"","conflictResolutionProcedure":"dbs/db/colls/coll/sprocs/resolver"},"_rid":"a","_ts":1,"_self":"b","_etag":"c","_docs":"docs/","_sprocs":"sprocs/","_triggers":"triggers/","_udfs":"udfs/","_conflicts":"conflicts/"}"#,
+        )
+        .with_cosmos_headers()
+        .with_header("etag", "c")]);
+
+        let response = endpoint
+            .cosmos_client()
+            .database_client("db")
+            .create_collection("coll", "/pk")
+            .conflict_resolution_policy(ConflictResolutionPolicy::custom(
+                "dbs/db/colls/coll/sprocs/resolver",
+            ))
+            .into_future()
+            .await
+            .unwrap();
+
+        assert_eq!(
+            response.collection.conflict_resolution_policy,
+            Some(ConflictResolutionPolicy::custom(
+                "dbs/db/colls/coll/sprocs/resolver"
+            ))
+        );
+        let body: serde_json::Value = serde_json::from_str(&endpoint.requests()[0].body).unwrap();
+        assert_eq!(
+            body["conflictResolutionPolicy"],
+            serde_json::json!({
+                "mode": "Custom",
+                "conflictResolutionProcedure": "dbs/db/colls/coll/sprocs/resolver"
+            })
+        );
+    }
+
+    #[test]
+    fn serializes_last_writer_wins() {
+        assert_eq!(
+            serde_json::to_value(ConflictResolutionPolicy::last_writer_wins("/_ts")).unwrap(),
+            serde_json::json!({"mode": "LastWriterWins", "conflictResolutionPath": "/_ts"})
+        );
+        assert_eq!(
+            serde_json::to_value(ConflictResolutionPolicy::manual()).unwrap(),
+            serde_json::json!({"mode": "Custom"})
+        );
+    }
+
+    #[test]
+    fn serializes_hierarchical_partition_keys() {
+        assert_eq!(
+            serde_json::to_value(PartitionKey::hierarchical(["/tenantId", "/userId"])).unwrap(),
+            serde_json::json!({"paths": ["/tenantId", "/userId"], "kind": "MultiHash", "version": 2})
+        );
+        assert_eq!(
+            serde_json::to_value(PartitionKey::from("/pk")).unwrap(),
+            serde_json::json!({"paths": ["/pk"], "kind": "Hash"})
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_hierarchical_partition_keys_with_too_many_paths() {
+        let endpoint = MockEndpoint::start(vec![]);
+
+        let result = endpoint
+            .cosmos_client()
+            .database_client("db")
+            .create_collection("coll", PartitionKey::hierarchical(["/a", "/b", "/c", "/d"]))
+            .into_future()
+            .await;
+
+        assert!(result.is_err());
+        assert!(endpoint.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn sends_the_container_settings() {
+        let endpoint = MockEndpoint::start(vec![MockResponse::new(
+            201,
+            r#"{"id":"coll","indexingPolicy":{"indexingMode":"consistent","automatic":true},"partitionKey":{"paths":["/pk"],"kind":"Hash"},"uniqueKeyPolicy":{"uniqueKeys":[{"paths":["/email"]}]},"defaultTtl":3600,"analyticalStorageTtl":-1,"changeFeedPolicy":{"retentionDuration":5},"_rid":"a","_ts":1,"_self":"b","_etag":"c","_docs":"docs/","_sprocs":"sprocs/","_triggers":"triggers/","_udfs":"udfs/","_conflicts":"conflicts/"}"#,
+        )
+        .with_cosmos_headers()
+        .with_header("etag", "c")]);
+
+        let response = endpoint
+            .cosmos_client()
+            .database_client("db")
+            .create_collection("coll", "/pk")
+            .unique_key_policy(UniqueKeyPolicy::default().unique_key(["/email"]))
+            .default_ttl(3600)
+            .analytical_storage_ttl(-1)
+            .change_feed_policy(ChangeFeedPolicy {
+                retention_duration: 5,
+            })
+            .into_future()
+            .await
+            .unwrap();
+
+        assert_eq!(response.collection.default_ttl, Some(3600));
+        let body: serde_json::Value = serde_json::from_str(&endpoint.requests()[0].body).unwrap();
+        assert_eq!(
+            body["uniqueKeyPolicy"],
+            serde_json::json!({"uniqueKeys": [{"paths": ["/email"]}]})
+        );
+        assert_eq!(body["defaultTtl"], 3600);
+        assert_eq!(body["analyticalStorageTtl"], -1);
+        assert_eq!(
+            body["changeFeedPolicy"],
+            serde_json::json!({"retentionDuration": 5})
+        );
+        assert!(body.get("indexingPolicy").is_none());
+    }
+}
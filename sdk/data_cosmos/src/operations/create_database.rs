@@ -1,6 +1,7 @@
 use crate::headers::from_headers::*;
 use crate::prelude::*;
 use crate::resources::Database;
+use crate::CosmosDiagnostics;
 use crate::ResourceQuota;
 use azure_core::headers::{etag_from_headers, session_token_from_headers};
 use azure_core::{collect_pinned_stream, Context, Response as HttpResponse};
@@ -11,6 +12,7 @@ pub struct CreateDatabaseBuilder {
     client: CosmosClient,
     database_name: String,
     consistency_level: Option<ConsistencyLevel>,
+    offer: Option<Offer>,
     context: Context,
 }
 
@@ -20,12 +22,14 @@ impl CreateDatabaseBuilder {
             client,
             database_name,
             consistency_level: None,
+            offer: None,
             context: Context::new(),
         }
     }
 
     setters! {
         consistency_level: ConsistencyLevel => Some(consistency_level),
+        offer: Offer => Some(offer),
         context: Context => context,
     }
 
@@ -47,13 +51,14 @@ impl CreateDatabaseBuilder {
             if let Some(cl) = &self.consistency_level {
                 request.insert_headers(cl);
             }
+            request.insert_headers(&self.offer);
             request.set_body(serde_json::to_vec(&body)?);
 
-            let response = self
+            let (response, diagnostics) = self
                 .client
                 .send(request, self.context.clone(), ResourceType::Databases)
                 .await?;
-            CreateDatabaseResponse::try_from(response).await
+            CreateDatabaseResponse::try_from_with_diagnostics(response, diagnostics).await
         })
     }
 }
@@ -92,14 +97,23 @@ pub struct CreateDatabaseResponse {
     pub service_version: String,
     pub activity_id: uuid::Uuid,
     pub gateway_version: String,
+    pub diagnostics: CosmosDiagnostics,
 }
 
 impl CreateDatabaseResponse {
     pub async fn try_from(response: HttpResponse) -> azure_core::error::Result<Self> {
+        Self::try_from_with_diagnostics(response, CosmosDiagnostics::default()).await
+    }
+
+    pub(crate) async fn try_from_with_diagnostics(
+        response: HttpResponse,
+        diagnostics: CosmosDiagnostics,
+    ) -> azure_core::error::Result<Self> {
         let (_status_code, headers, pinned_stream) = response.deconstruct();
         let body: bytes::Bytes = collect_pinned_stream(pinned_stream).await?;
 
         Ok(Self {
+            diagnostics,
             database: serde_json::from_slice(&body)?,
             charge: request_charge_from_headers(&headers)?,
             etag: etag_from_headers(&headers)?,
@@ -2,6 +2,7 @@ use crate::cosmos_entity::{add_as_partition_key_header_serialized2, serialize_pa
 use crate::headers::from_headers::*;
 use crate::prelude::*;
 use crate::resources::document::DocumentAttributes;
+use crate::CosmosDiagnostics;
 use crate::ResourceQuota;
 use azure_core::headers::{etag_from_headers, session_token_from_headers};
 use azure_core::prelude::*;
@@ -81,16 +82,14 @@ impl<D: Serialize + CosmosEntity + Send + 'static> CreateDocumentBuilder<D> {
             request.insert_headers(&self.allow_tentative_writes);
 
             request.set_body(serialized);
-            let response = self
-                .client
-                .pipeline()
-                .send(
-                    self.context.clone().insert(ResourceType::Documents),
-                    &mut request,
-                )
-                .await?;
-
-            CreateDocumentResponse::try_from(response).await
+            let (response, diagnostics) = crate::diagnostics::send(
+                self.client.pipeline(),
+                self.context.clone().insert(ResourceType::Documents),
+                &mut request,
+            )
+            .await?;
+
+            CreateDocumentResponse::try_from_with_diagnostics(response, diagnostics).await
         })
     }
 }
@@ -137,14 +136,23 @@ pub struct CreateDocumentResponse {
     pub activity_id: uuid::Uuid,
     pub gateway_version: String,
     pub date: DateTime<Utc>,
+    pub diagnostics: CosmosDiagnostics,
 }
 
 impl CreateDocumentResponse {
     pub async fn try_from(response: HttpResponse) -> azure_core::error::Result<Self> {
+        Self::try_from_with_diagnostics(response, CosmosDiagnostics::default()).await
+    }
+
+    pub(crate) async fn try_from_with_diagnostics(
+        response: HttpResponse,
+        diagnostics: CosmosDiagnostics,
+    ) -> azure_core::error::Result<Self> {
         let (status_code, headers, pinned_stream) = response.deconstruct();
         let body = collect_pinned_stream(pinned_stream).await?;
 
         Ok(CreateDocumentResponse {
+            diagnostics,
             is_update: status_code == StatusCode::OK,
 
             last_state_change: last_state_change_from_headers(&headers)?,
@@ -1,6 +1,7 @@
 use crate::headers::from_headers::*;
 use crate::prelude::*;
 use crate::resources::Attachment;
+use crate::CosmosDiagnostics;
 use crate::ResourceQuota;
 
 use azure_core::collect_pinned_stream;
@@ -79,16 +80,15 @@ impl CreateOrReplaceSlugAttachmentBuilder {
             );
 
             request.set_body(body);
-            let response = self
-                .client
-                .pipeline()
-                .send(
-                    self.context.clone().insert(ResourceType::Attachments),
-                    &mut request,
-                )
-                .await?;
-
-            CreateOrReplaceSlugAttachmentResponse::try_from(response).await
+            let (response, diagnostics) = crate::diagnostics::send(
+                self.client.pipeline(),
+                self.context.clone().insert(ResourceType::Attachments),
+                &mut request,
+            )
+            .await?;
+
+            CreateOrReplaceSlugAttachmentResponse::try_from_with_diagnostics(response, diagnostics)
+                .await
         })
     }
 }
@@ -132,16 +132,25 @@ pub struct CreateOrReplaceSlugAttachmentResponse {
     pub activity_id: uuid::Uuid,
     pub gateway_version: String,
     pub date: DateTime<Utc>,
+    pub diagnostics: CosmosDiagnostics,
 }
 
 impl CreateOrReplaceSlugAttachmentResponse {
     pub async fn try_from(response: HttpResponse) -> azure_core::error::Result<Self> {
+        Self::try_from_with_diagnostics(response, CosmosDiagnostics::default()).await
+    }
+
+    pub(crate) async fn try_from_with_diagnostics(
+        response: HttpResponse,
+        diagnostics: CosmosDiagnostics,
+    ) -> azure_core::error::Result<Self> {
         let (_status_code, headers, pinned_stream) = response.deconstruct();
         let body = collect_pinned_stream(pinned_stream).await?;
 
         let attachment: Attachment = serde_json::from_slice(&body)?;
 
         Ok(Self {
+            diagnostics,
             attachment,
             max_media_storage_usage_mb: max_media_storage_usage_mb_from_headers(&headers)?,
             media_storage_usage_mb: media_storage_usage_mb_from_headers(&headers)?,
@@ -2,6 +2,7 @@ use crate::headers::from_headers::*;
 use crate::prelude::*;
 use crate::resources::trigger::*;
 use crate::resources::Trigger;
+use crate::CosmosDiagnostics;
 use crate::ResourceQuota;
 use azure_core::collect_pinned_stream;
 use azure_core::headers::{etag_from_headers, session_token_from_headers};
@@ -75,16 +76,14 @@ impl CreateOrReplaceTriggerBuilder {
             };
 
             request.set_body(serde_json::to_vec(&request_body)?);
-            let response = self
-                .client
-                .pipeline()
-                .send(
-                    self.context.clone().insert(ResourceType::Triggers),
-                    &mut request,
-                )
-                .await?;
+            let (response, diagnostics) = crate::diagnostics::send(
+                self.client.pipeline(),
+                self.context.clone().insert(ResourceType::Triggers),
+                &mut request,
+            )
+            .await?;
 
-            CreateOrReplaceTriggerResponse::try_from(response).await
+            CreateOrReplaceTriggerResponse::try_from_with_diagnostics(response, diagnostics).await
         })
     }
 }
@@ -128,14 +127,23 @@ pub struct CreateOrReplaceTriggerResponse {
     pub activity_id: uuid::Uuid,
     pub gateway_version: String,
     pub date: DateTime<Utc>,
+    pub diagnostics: CosmosDiagnostics,
 }
 
 impl CreateOrReplaceTriggerResponse {
     pub async fn try_from(response: HttpResponse) -> azure_core::error::Result<Self> {
+        Self::try_from_with_diagnostics(response, CosmosDiagnostics::default()).await
+    }
+
+    pub(crate) async fn try_from_with_diagnostics(
+        response: HttpResponse,
+        diagnostics: CosmosDiagnostics,
+    ) -> azure_core::error::Result<Self> {
         let (_status_code, headers, pinned_stream) = response.deconstruct();
         let body = collect_pinned_stream(pinned_stream).await?;
 
         Ok(Self {
+            diagnostics,
             trigger: serde_json::from_slice(&body)?,
             server: server_from_headers(&headers)?.to_owned(),
             last_state_change: last_state_change_from_headers(&headers)?,
@@ -1,6 +1,7 @@
 use crate::headers::from_headers::*;
 use crate::prelude::*;
 use crate::resources::StoredProcedure;
+use crate::CosmosDiagnostics;
 use crate::ResourceQuota;
 use azure_core::headers::{etag_from_headers, session_token_from_headers};
 use azure_core::{collect_pinned_stream, Context, Response as HttpResponse};
@@ -49,15 +50,13 @@ impl CreateStoredProcedureBuilder {
 
             req.set_body(serde_json::to_vec(&body)?);
 
-            let response = self
-                .client
-                .pipeline()
-                .send(
-                    self.context.clone().insert(ResourceType::StoredProcedures),
-                    &mut req,
-                )
-                .await?;
-            CreateStoredProcedureResponse::try_from(response).await
+            let (response, diagnostics) = crate::diagnostics::send(
+                self.client.pipeline(),
+                self.context.clone().insert(ResourceType::StoredProcedures),
+                &mut req,
+            )
+            .await?;
+            CreateStoredProcedureResponse::try_from_with_diagnostics(response, diagnostics).await
         })
     }
 }
@@ -89,14 +88,23 @@ pub struct CreateStoredProcedureResponse {
     pub quorum_acked_lsn: u64,
     pub current_write_quorum: u64,
     pub current_replica_set_size: u64,
+    pub diagnostics: CosmosDiagnostics,
 }
 
 impl CreateStoredProcedureResponse {
     pub async fn try_from(response: HttpResponse) -> azure_core::error::Result<Self> {
+        Self::try_from_with_diagnostics(response, CosmosDiagnostics::default()).await
+    }
+
+    pub(crate) async fn try_from_with_diagnostics(
+        response: HttpResponse,
+        diagnostics: CosmosDiagnostics,
+    ) -> azure_core::error::Result<Self> {
         let (_status_code, headers, pinned_stream) = response.deconstruct();
         let body = collect_pinned_stream(pinned_stream).await?;
 
         Ok(Self {
+            diagnostics,
             stored_procedure: serde_json::from_slice(&body)?,
             charge: request_charge_from_headers(&headers)?,
             activity_id: activity_id_from_headers(&headers)?,
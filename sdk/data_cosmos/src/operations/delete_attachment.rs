@@ -1,5 +1,6 @@
 use crate::headers::from_headers::*;
 use crate::prelude::*;
+use crate::CosmosDiagnostics;
 use crate::ResourceQuota;
 
 use azure_core::headers::session_token_from_headers;
@@ -48,16 +49,14 @@ impl DeleteAttachmentBuilder {
                 self.client.document_client().partition_key_serialized(),
                 &mut request,
             );
-            let response = self
-                .client
-                .pipeline()
-                .send(
-                    self.context.clone().insert(ResourceType::Attachments),
-                    &mut request,
-                )
-                .await?;
+            let (response, diagnostics) = crate::diagnostics::send(
+                self.client.pipeline(),
+                self.context.clone().insert(ResourceType::Attachments),
+                &mut request,
+            )
+            .await?;
 
-            DeleteAttachmentResponse::try_from(response).await
+            DeleteAttachmentResponse::try_from_with_diagnostics(response, diagnostics).await
         })
     }
 }
@@ -99,13 +98,22 @@ pub struct DeleteAttachmentResponse {
     pub activity_id: uuid::Uuid,
     pub gateway_version: String,
     pub date: DateTime<Utc>,
+    pub diagnostics: CosmosDiagnostics,
 }
 
 impl DeleteAttachmentResponse {
     pub async fn try_from(response: HttpResponse) -> azure_core::error::Result<Self> {
+        Self::try_from_with_diagnostics(response, CosmosDiagnostics::default()).await
+    }
+
+    pub(crate) async fn try_from_with_diagnostics(
+        response: HttpResponse,
+        diagnostics: CosmosDiagnostics,
+    ) -> azure_core::error::Result<Self> {
         let headers = response.headers();
 
         Ok(Self {
+            diagnostics,
             max_media_storage_usage_mb: max_media_storage_usage_mb_from_headers(headers)?,
             media_storage_usage_mb: media_storage_usage_mb_from_headers(headers)?,
             last_change: last_state_change_from_headers(headers)?,
@@ -1,4 +1,5 @@
 use crate::prelude::*;
+use crate::CosmosDiagnostics;
 use crate::{headers::from_headers::*, ResourceQuota};
 use azure_core::headers::{content_type_from_headers, session_token_from_headers};
 use azure_core::{Context, Response as HttpResponse};
@@ -35,16 +36,14 @@ impl DeleteCollectionBuilder {
                 request.insert_headers(cl);
             }
 
-            let response = self
-                .client
-                .pipeline()
-                .send(
-                    self.context.clone().insert(ResourceType::Collections),
-                    &mut request,
-                )
-                .await?;
+            let (response, diagnostics) = crate::diagnostics::send(
+                self.client.pipeline(),
+                self.context.clone().insert(ResourceType::Collections),
+                &mut request,
+            )
+            .await?;
 
-            DeleteCollectionResponse::try_from(response).await
+            DeleteCollectionResponse::try_from_with_diagnostics(response, diagnostics).await
         })
     }
 }
@@ -88,13 +87,22 @@ pub struct DeleteCollectionResponse {
     pub cosmos_quorum_acked_llsn: u64,
     pub content_location: String,
     pub content_type: String,
+    pub diagnostics: CosmosDiagnostics,
 }
 
 impl DeleteCollectionResponse {
     pub async fn try_from(response: HttpResponse) -> azure_core::error::Result<Self> {
+        Self::try_from_with_diagnostics(response, CosmosDiagnostics::default()).await
+    }
+
+    pub(crate) async fn try_from_with_diagnostics(
+        response: HttpResponse,
+        diagnostics: CosmosDiagnostics,
+    ) -> azure_core::error::Result<Self> {
         let (_status_code, headers, _pinned_stream) = response.deconstruct();
 
         Ok(Self {
+            diagnostics,
             last_state_change: last_state_change_from_headers(&headers)?,
             collection_partition_index: collection_partition_index_from_headers(&headers)?,
             collection_service_index: collection_service_index_from_headers(&headers)?,
@@ -0,0 +1,91 @@
+use crate::headers::from_headers::*;
+use crate::prelude::*;
+use crate::CosmosDiagnostics;
+
+use azure_core::headers::session_token_from_headers;
+use azure_core::prelude::*;
+use azure_core::Response as HttpResponse;
+
+#[derive(Debug, Clone)]
+pub struct DeleteConflictBuilder {
+    client: ConflictClient,
+    consistency_level: Option<ConsistencyLevel>,
+    context: Context,
+}
+
+impl DeleteConflictBuilder {
+    pub(crate) fn new(client: ConflictClient) -> Self {
+        Self {
+            client,
+            consistency_level: None,
+            context: Context::new(),
+        }
+    }
+
+    setters! {
+        consistency_level: ConsistencyLevel => Some(consistency_level),
+        context: Context => context,
+    }
+
+    pub fn into_future(self) -> DeleteConflict {
+        Box::pin(async move {
+            let mut request = self
+                .client
+                .prepare_pipeline_with_conflict_id(http::Method::DELETE);
+
+            if let Some(cl) = &self.consistency_level {
+                request.insert_headers(cl);
+            }
+
+            let (response, diagnostics) = crate::diagnostics::send(
+                self.client.pipeline(),
+                self.context.clone().insert(ResourceType::Conflicts),
+                &mut request,
+            )
+            .await?;
+
+            DeleteConflictResponse::try_from_with_diagnostics(response, diagnostics).await
+        })
+    }
+}
+
+/// The future returned by calling `into_future` on the builder.
+pub type DeleteConflict =
+    futures::future::BoxFuture<'static, azure_core::error::Result<DeleteConflictResponse>>;
+
+#[cfg(feature = "into_future")]
+impl std::future::IntoFuture for DeleteConflictBuilder {
+    type IntoFuture = DeleteConflict;
+    type Output = <DeleteConflict as std::future::Future>::Output;
+    fn into_future(self) -> Self::IntoFuture {
+        Self::into_future(self)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeleteConflictResponse {
+    pub session_token: String,
+    pub charge: f64,
+    pub activity_id: uuid::Uuid,
+    pub diagnostics: CosmosDiagnostics,
+}
+
+impl DeleteConflictResponse {
+    pub async fn try_from(response: HttpResponse) -> azure_core::error::Result<Self> {
+        Self::try_from_with_diagnostics(response, CosmosDiagnostics::default()).await
+    }
+
+    pub(crate) async fn try_from_with_diagnostics(
+        response: HttpResponse,
+        diagnostics: CosmosDiagnostics,
+    ) -> azure_core::error::Result<Self> {
+        let (_status_code, headers, _pinned_stream) = response.deconstruct();
+
+        Ok(Self {
+            diagnostics,
+            session_token: session_token_from_headers(&headers)?,
+            charge: request_charge_from_headers(&headers)?,
+            activity_id: activity_id_from_headers(&headers)?,
+        })
+    }
+}
@@ -1,5 +1,6 @@
 use crate::headers::from_headers::*;
 use crate::prelude::*;
+use crate::CosmosDiagnostics;
 use crate::ResourceQuota;
 use azure_core::headers::session_token_from_headers;
 use azure_core::Context;
@@ -33,12 +34,12 @@ impl DeleteDatabaseBuilder {
                 request.insert_headers(cl);
             }
 
-            let response = self
+            let (response, diagnostics) = self
                 .client
                 .cosmos_client()
                 .send(request, self.context.clone(), ResourceType::Databases)
                 .await?;
-            DeleteDatabaseResponse::try_from(response).await
+            DeleteDatabaseResponse::try_from_with_diagnostics(response, diagnostics).await
         })
     }
 }
@@ -63,16 +64,25 @@ pub struct DeleteDatabaseResponse {
     pub session_token: String,
     pub resource_quota: Vec<ResourceQuota>,
     pub resource_usage: Vec<ResourceQuota>,
+    pub diagnostics: CosmosDiagnostics,
 }
 
 impl DeleteDatabaseResponse {
     pub async fn try_from(response: HttpResponse) -> azure_core::error::Result<Self> {
+        Self::try_from_with_diagnostics(response, CosmosDiagnostics::default()).await
+    }
+
+    pub(crate) async fn try_from_with_diagnostics(
+        response: HttpResponse,
+        diagnostics: CosmosDiagnostics,
+    ) -> azure_core::error::Result<Self> {
         let headers = response.headers();
 
         let charge = request_charge_from_headers(headers)?;
         let activity_id = activity_id_from_headers(headers)?;
 
         Ok(Self {
+            diagnostics,
             charge,
             activity_id,
             session_token: session_token_from_headers(headers)?,
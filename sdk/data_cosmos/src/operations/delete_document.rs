@@ -1,5 +1,6 @@
 use crate::headers::from_headers::*;
 use crate::prelude::*;
+use crate::CosmosDiagnostics;
 
 use azure_core::headers::session_token_from_headers;
 use azure_core::prelude::*;
@@ -54,17 +55,14 @@ impl DeleteDocumentBuilder {
                 &mut request,
             );
 
-            let response = self
-                .client
-                .cosmos_client()
-                .pipeline()
-                .send(
-                    self.context.clone().insert(ResourceType::Documents),
-                    &mut request,
-                )
-                .await?;
-
-            DeleteDocumentResponse::try_from(response).await
+            let (response, diagnostics) = crate::diagnostics::send(
+                self.client.cosmos_client().pipeline(),
+                self.context.clone().insert(ResourceType::Documents),
+                &mut request,
+            )
+            .await?;
+
+            DeleteDocumentResponse::try_from_with_diagnostics(response, diagnostics).await
         })
     }
 }
@@ -87,10 +85,18 @@ pub struct DeleteDocumentResponse {
     pub charge: f64,
     pub activity_id: uuid::Uuid,
     pub session_token: String,
+    pub diagnostics: CosmosDiagnostics,
 }
 
 impl DeleteDocumentResponse {
     pub async fn try_from(response: HttpResponse) -> azure_core::error::Result<Self> {
+        Self::try_from_with_diagnostics(response, CosmosDiagnostics::default()).await
+    }
+
+    pub(crate) async fn try_from_with_diagnostics(
+        response: HttpResponse,
+        diagnostics: CosmosDiagnostics,
+    ) -> azure_core::error::Result<Self> {
         let (_status_code, headers, _pinned_stream) = response.deconstruct();
 
         let charge = request_charge_from_headers(&headers)?;
@@ -98,6 +104,7 @@ impl DeleteDocumentResponse {
         let session_token = session_token_from_headers(&headers)?;
 
         Ok(Self {
+            diagnostics,
             charge,
             activity_id,
             session_token,
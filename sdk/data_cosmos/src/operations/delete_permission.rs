@@ -1,5 +1,6 @@
 use crate::headers::from_headers::*;
 use crate::prelude::*;
+use crate::CosmosDiagnostics;
 
 use azure_core::headers::session_token_from_headers;
 use azure_core::Context;
@@ -36,16 +37,14 @@ impl DeletePermissionBuilder {
                 request.insert_headers(cl);
             }
 
-            let response = self
-                .client
-                .pipeline()
-                .send(
-                    self.context.clone().insert(ResourceType::Permissions),
-                    &mut request,
-                )
-                .await?;
+            let (response, diagnostics) = crate::diagnostics::send(
+                self.client.pipeline(),
+                self.context.clone().insert(ResourceType::Permissions),
+                &mut request,
+            )
+            .await?;
 
-            DeletePermissionResponse::try_from(response).await
+            DeletePermissionResponse::try_from_with_diagnostics(response, diagnostics).await
         })
     }
 }
@@ -70,13 +69,22 @@ pub struct DeletePermissionResponse {
     pub session_token: String,
     pub content_path: String,
     pub alt_content_path: String,
+    pub diagnostics: CosmosDiagnostics,
 }
 
 impl DeletePermissionResponse {
     pub async fn try_from(response: HttpResponse) -> azure_core::error::Result<Self> {
+        Self::try_from_with_diagnostics(response, CosmosDiagnostics::default()).await
+    }
+
+    pub(crate) async fn try_from_with_diagnostics(
+        response: HttpResponse,
+        diagnostics: CosmosDiagnostics,
+    ) -> azure_core::error::Result<Self> {
         let (_status_code, headers, _pinned_stream) = response.deconstruct();
 
         Ok(Self {
+            diagnostics,
             charge: request_charge_from_headers(&headers)?,
             activity_id: activity_id_from_headers(&headers)?,
             session_token: session_token_from_headers(&headers)?,
@@ -1,5 +1,6 @@
 use crate::headers::from_headers::*;
 use crate::prelude::*;
+use crate::CosmosDiagnostics;
 use crate::ResourceQuota;
 use azure_core::headers::session_token_from_headers;
 use azure_core::prelude::*;
@@ -36,16 +37,14 @@ impl DeleteStoredProcedureBuilder {
                 request.insert_headers(cl);
             }
 
-            let response = self
-                .client
-                .pipeline()
-                .send(
-                    self.context.clone().insert(ResourceType::Permissions),
-                    &mut request,
-                )
-                .await?;
+            let (response, diagnostics) = crate::diagnostics::send(
+                self.client.pipeline(),
+                self.context.clone().insert(ResourceType::Permissions),
+                &mut request,
+            )
+            .await?;
 
-            DeleteStoredProcedureResponse::try_from(response).await
+            DeleteStoredProcedureResponse::try_from_with_diagnostics(response, diagnostics).await
         })
     }
 }
@@ -71,13 +70,22 @@ pub struct DeleteStoredProcedureResponse {
     pub last_change: DateTime<Utc>,
     pub resource_quota: Vec<ResourceQuota>,
     pub resource_usage: Vec<ResourceQuota>,
+    pub diagnostics: CosmosDiagnostics,
 }
 
 impl DeleteStoredProcedureResponse {
     pub async fn try_from(response: HttpResponse) -> azure_core::error::Result<Self> {
+        Self::try_from_with_diagnostics(response, CosmosDiagnostics::default()).await
+    }
+
+    pub(crate) async fn try_from_with_diagnostics(
+        response: HttpResponse,
+        diagnostics: CosmosDiagnostics,
+    ) -> azure_core::error::Result<Self> {
         let headers = response.headers();
 
         Ok(Self {
+            diagnostics,
             charge: request_charge_from_headers(headers)?,
             activity_id: activity_id_from_headers(headers)?,
             session_token: session_token_from_headers(headers)?,
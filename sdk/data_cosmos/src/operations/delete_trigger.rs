@@ -1,5 +1,6 @@
 use crate::headers::from_headers::*;
 use crate::prelude::*;
+use crate::CosmosDiagnostics;
 use crate::ResourceQuota;
 
 use azure_core::headers::session_token_from_headers;
@@ -38,16 +39,14 @@ impl DeleteTriggerBuilder {
                 request.insert_headers(cl);
             }
 
-            let response = self
-                .client
-                .pipeline()
-                .send(
-                    self.context.clone().insert(ResourceType::Triggers),
-                    &mut request,
-                )
-                .await?;
+            let (response, diagnostics) = crate::diagnostics::send(
+                self.client.pipeline(),
+                self.context.clone().insert(ResourceType::Triggers),
+                &mut request,
+            )
+            .await?;
 
-            DeleteTriggerResponse::try_from(response).await
+            DeleteTriggerResponse::try_from_with_diagnostics(response, diagnostics).await
         })
     }
 }
@@ -91,12 +90,21 @@ pub struct DeleteTriggerResponse {
     pub activity_id: uuid::Uuid,
     pub gateway_version: String,
     pub date: DateTime<Utc>,
+    pub diagnostics: CosmosDiagnostics,
 }
 impl DeleteTriggerResponse {
     pub async fn try_from(response: HttpResponse) -> azure_core::error::Result<Self> {
+        Self::try_from_with_diagnostics(response, CosmosDiagnostics::default()).await
+    }
+
+    pub(crate) async fn try_from_with_diagnostics(
+        response: HttpResponse,
+        diagnostics: CosmosDiagnostics,
+    ) -> azure_core::error::Result<Self> {
         let (_status_code, headers, _pinned_stream) = response.deconstruct();
 
         Ok(Self {
+            diagnostics,
             content_location: content_location_from_headers(&headers)?.to_owned(),
             server: server_from_headers(&headers)?.to_owned(),
             last_state_change: last_state_change_from_headers(&headers)?,
@@ -1,5 +1,6 @@
 use crate::headers::from_headers::*;
 use crate::prelude::*;
+use crate::CosmosDiagnostics;
 use azure_core::{headers::session_token_from_headers, Context, Response as HttpResponse};
 
 #[derive(Debug, Clone)]
@@ -32,16 +33,14 @@ impl DeleteUserBuilder {
                 request.insert_headers(cl);
             }
             request.set_body(bytes::Bytes::new());
-            let response = self
-                .client
-                .pipeline()
-                .send(
-                    self.context.clone().insert(ResourceType::Users),
-                    &mut request,
-                )
-                .await?;
+            let (response, diagnostics) = crate::diagnostics::send(
+                self.client.pipeline(),
+                self.context.clone().insert(ResourceType::Users),
+                &mut request,
+            )
+            .await?;
 
-            DeleteUserResponse::try_from(response).await
+            DeleteUserResponse::try_from_with_diagnostics(response, diagnostics).await
         })
     }
 }
@@ -64,13 +63,22 @@ pub struct DeleteUserResponse {
     pub charge: f64,
     pub activity_id: uuid::Uuid,
     pub session_token: String,
+    pub diagnostics: CosmosDiagnostics,
 }
 
 impl DeleteUserResponse {
     pub async fn try_from(response: HttpResponse) -> azure_core::error::Result<Self> {
+        Self::try_from_with_diagnostics(response, CosmosDiagnostics::default()).await
+    }
+
+    pub(crate) async fn try_from_with_diagnostics(
+        response: HttpResponse,
+        diagnostics: CosmosDiagnostics,
+    ) -> azure_core::error::Result<Self> {
         let (_status_code, headers, _pinned_stream) = response.deconstruct();
 
         Ok(Self {
+            diagnostics,
             charge: request_charge_from_headers(&headers)?,
             activity_id: activity_id_from_headers(&headers)?,
             session_token: session_token_from_headers(&headers)?,
@@ -1,6 +1,7 @@
 use crate::headers::from_headers::*;
 use crate::prelude::*;
 use crate::resources::stored_procedure::Parameters;
+use crate::CosmosDiagnostics;
 use azure_core::collect_pinned_stream;
 use azure_core::headers::session_token_from_headers;
 use azure_core::prelude::*;
@@ -73,17 +74,14 @@ impl ExecuteStoredProcedureBuilder {
 
             request.set_body(body);
 
-            let response = self
-                .client
-                .cosmos_client()
-                .pipeline()
-                .send(
-                    self.context.clone().insert(ResourceType::StoredProcedures),
-                    &mut request,
-                )
-                .await?;
-
-            ExecuteStoredProcedureResponse::try_from(response).await
+            let (response, diagnostics) = crate::diagnostics::send(
+                self.client.cosmos_client().pipeline(),
+                self.context.clone().insert(ResourceType::StoredProcedures),
+                &mut request,
+            )
+            .await?;
+
+            ExecuteStoredProcedureResponse::try_from_with_diagnostics(response, diagnostics).await
         })
     }
 }
@@ -120,6 +118,7 @@ where
     pub activity_id: uuid::Uuid,
     pub gateway_version: String,
     pub date: DateTime<Utc>,
+    pub diagnostics: CosmosDiagnostics,
 }
 
 impl<T> ExecuteStoredProcedureResponse<T>
@@ -127,10 +126,18 @@ where
     T: DeserializeOwned,
 {
     pub async fn try_from(response: HttpResponse) -> azure_core::error::Result<Self> {
+        Self::try_from_with_diagnostics(response, CosmosDiagnostics::default()).await
+    }
+
+    pub(crate) async fn try_from_with_diagnostics(
+        response: HttpResponse,
+        diagnostics: CosmosDiagnostics,
+    ) -> azure_core::error::Result<Self> {
         let (_status_code, headers, pinned_stream) = response.deconstruct();
         let body = collect_pinned_stream(pinned_stream).await?;
 
         Ok(Self {
+            diagnostics,
             payload: serde_json::from_slice(&body)?,
             last_state_change: last_state_change_from_headers(&headers)?,
             schema_version: schema_version_from_headers(&headers)?.to_owned(),
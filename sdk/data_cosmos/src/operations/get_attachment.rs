@@ -2,6 +2,7 @@ use crate::headers::from_headers::*;
 use crate::prelude::*;
 use crate::resources::document::IndexingDirective;
 use crate::resources::Attachment;
+use crate::CosmosDiagnostics;
 use crate::ResourceQuota;
 use azure_core::headers::{
     content_type_from_headers, etag_from_headers, session_token_from_headers,
@@ -49,16 +50,14 @@ impl GetAttachmentBuilder {
                 self.client.document_client().partition_key_serialized(),
                 &mut request,
             );
-            let response = self
-                .client
-                .pipeline()
-                .send(
-                    self.context.clone().insert(ResourceType::Attachments),
-                    &mut request,
-                )
-                .await?;
+            let (response, diagnostics) = crate::diagnostics::send(
+                self.client.pipeline(),
+                self.context.clone().insert(ResourceType::Attachments),
+                &mut request,
+            )
+            .await?;
 
-            GetAttachmentResponse::try_from(response).await
+            GetAttachmentResponse::try_from_with_diagnostics(response, diagnostics).await
         })
     }
 }
@@ -102,14 +101,23 @@ pub struct GetAttachmentResponse {
     pub activity_id: uuid::Uuid,
     pub gateway_version: String,
     pub date: DateTime<Utc>,
+    pub diagnostics: CosmosDiagnostics,
 }
 
 impl GetAttachmentResponse {
     pub async fn try_from(response: HttpResponse) -> azure_core::error::Result<Self> {
+        Self::try_from_with_diagnostics(response, CosmosDiagnostics::default()).await
+    }
+
+    pub(crate) async fn try_from_with_diagnostics(
+        response: HttpResponse,
+        diagnostics: CosmosDiagnostics,
+    ) -> azure_core::error::Result<Self> {
         let (_status_code, headers, pinned_stream) = response.deconstruct();
         let body = collect_pinned_stream(pinned_stream).await?;
 
         Ok(Self {
+            diagnostics,
             attachment: serde_json::from_slice(&body)?,
             content_type: content_type_from_headers(&headers)?.to_owned(),
             content_location: content_location_from_headers(&headers)?.to_owned(),
@@ -1,4 +1,5 @@
 use crate::prelude::*;
+use crate::CosmosDiagnostics;
 
 use crate::headers::from_headers::*;
 use azure_core::headers::{
@@ -38,16 +39,14 @@ impl GetCollectionBuilder {
                 request.insert_headers(cl);
             }
 
-            let response = self
-                .client
-                .pipeline()
-                .send(
-                    self.context.clone().insert(ResourceType::Collections),
-                    &mut request,
-                )
-                .await?;
+            let (response, diagnostics) = crate::diagnostics::send(
+                self.client.pipeline(),
+                self.context.clone().insert(ResourceType::Collections),
+                &mut request,
+            )
+            .await?;
 
-            GetCollectionResponse::try_from(response).await
+            GetCollectionResponse::try_from_with_diagnostics(response, diagnostics).await
         })
     }
 }
@@ -92,14 +91,23 @@ pub struct GetCollectionResponse {
     pub content_type: String,
     pub content_location: String,
     pub date: DateTime<Utc>,
+    pub diagnostics: CosmosDiagnostics,
 }
 
 impl GetCollectionResponse {
     pub async fn try_from(response: HttpResponse) -> azure_core::error::Result<Self> {
+        Self::try_from_with_diagnostics(response, CosmosDiagnostics::default()).await
+    }
+
+    pub(crate) async fn try_from_with_diagnostics(
+        response: HttpResponse,
+        diagnostics: CosmosDiagnostics,
+    ) -> azure_core::error::Result<Self> {
         let (_status_code, headers, pinned_stream) = response.deconstruct();
         let body = collect_pinned_stream(pinned_stream).await?;
 
         Ok(Self {
+            diagnostics,
             collection: serde_json::from_slice(&body)?,
             last_state_change: last_state_change_from_headers(&headers)?,
             etag: etag_from_headers(&headers)?,
@@ -0,0 +1,137 @@
+use crate::headers::from_headers::*;
+use crate::prelude::*;
+use crate::resources::Conflict;
+use crate::CosmosDiagnostics;
+
+use azure_core::headers::session_token_from_headers;
+use azure_core::prelude::*;
+use azure_core::{collect_pinned_stream, Response as HttpResponse};
+
+#[derive(Debug, Clone)]
+pub struct GetConflictBuilder {
+    client: ConflictClient,
+    consistency_level: Option<ConsistencyLevel>,
+    context: Context,
+}
+
+impl GetConflictBuilder {
+    pub(crate) fn new(client: ConflictClient) -> Self {
+        Self {
+            client,
+            consistency_level: None,
+            context: Context::new(),
+        }
+    }
+
+    setters! {
+        consistency_level: ConsistencyLevel => Some(consistency_level),
+        context: Context => context,
+    }
+
+    pub fn into_future(self) -> GetConflict {
+        Box::pin(async move {
+            let mut request = self
+                .client
+                .prepare_pipeline_with_conflict_id(http::Method::GET);
+
+            if let Some(cl) = &self.consistency_level {
+                request.insert_headers(cl);
+            }
+
+            let (response, diagnostics) = crate::diagnostics::send(
+                self.client.pipeline(),
+                self.context.clone().insert(ResourceType::Conflicts),
+                &mut request,
+            )
+            .await?;
+
+            GetConflictResponse::try_from_with_diagnostics(response, diagnostics).await
+        })
+    }
+}
+
+/// The future returned by calling `into_future` on the builder.
+pub type GetConflict =
+    futures::future::BoxFuture<'static, azure_core::error::Result<GetConflictResponse>>;
+
+#[cfg(feature = "into_future")]
+impl std::future::IntoFuture for GetConflictBuilder {
+    type IntoFuture = GetConflict;
+    type Output = <GetConflict as std::future::Future>::Output;
+    fn into_future(self) -> Self::IntoFuture {
+        Self::into_future(self)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GetConflictResponse {
+    pub conflict: Conflict,
+    pub session_token: String,
+    pub charge: f64,
+    pub activity_id: uuid::Uuid,
+    pub diagnostics: CosmosDiagnostics,
+}
+
+impl GetConflictResponse {
+    pub async fn try_from(response: HttpResponse) -> azure_core::error::Result<Self> {
+        Self::try_from_with_diagnostics(response, CosmosDiagnostics::default()).await
+    }
+
+    pub(crate) async fn try_from_with_diagnostics(
+        response: HttpResponse,
+        diagnostics: CosmosDiagnostics,
+    ) -> azure_core::error::Result<Self> {
+        let (_status_code, headers, pinned_stream) = response.deconstruct();
+        let body = collect_pinned_stream(pinned_stream).await?;
+
+        Ok(Self {
+            diagnostics,
+            conflict: serde_json::from_slice(&body)?,
+            session_token: session_token_from_headers(&headers)?,
+            charge: request_charge_from_headers(&headers)?,
+            activity_id: activity_id_from_headers(&headers)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mock_endpoint::{MockEndpoint, MockResponse};
+    use crate::resources::conflict::ConflictOperation;
+
+    #[tokio::test]
+    async fn reads_and_deletes_a_conflict() {
+        let endpoint = MockEndpoint::start(vec![
+            MockResponse::new(
+                200,
+                r#"{"id":"c1","_rid":"c1","_self":"dbs/db/colls/coll/conflicts/c1/","_etag":"\"0\"","_ts":1,"resourceId":"doc","resourceType":"document","operationType":"delete","content":"{\"id\":\"doc\",\"pk\":\"pk\"}"}"#,
+            )
+            .with_cosmos_headers(),
+            MockResponse::new(204, "").with_cosmos_headers(),
+        ]);
+        let conflict_client = endpoint
+            .cosmos_client()
+            .database_client("db")
+            .collection_client("coll")
+            .conflict_client("c1", &"pk")
+            .unwrap();
+
+        let response = conflict_client.get_conflict().into_future().await.unwrap();
+        conflict_client
+            .delete_conflict()
+            .into_future()
+            .await
+            .unwrap();
+
+        assert_eq!(response.conflict.operation_type, ConflictOperation::Delete);
+        let requests = endpoint.requests();
+        for (request, method) in requests.iter().zip(["GET", "DELETE"]) {
+            assert_eq!(request.method, method);
+            assert_eq!(request.path, "/dbs/db/colls/coll/conflicts/c1");
+            assert_eq!(
+                request.header("x-ms-documentdb-partitionkey"),
+                Some(r#"["pk"]"#)
+            );
+        }
+    }
+}
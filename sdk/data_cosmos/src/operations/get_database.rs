@@ -1,5 +1,6 @@
 use crate::headers::from_headers::*;
 use crate::prelude::*;
+use crate::CosmosDiagnostics;
 use crate::ResourceQuota;
 
 use azure_core::headers::{etag_from_headers, session_token_from_headers};
@@ -35,12 +36,12 @@ impl GetDatabaseBuilder {
                 request.insert_headers(cl);
             }
 
-            let response = self
+            let (response, diagnostics) = self
                 .client
                 .cosmos_client()
                 .send(request, self.context.clone(), ResourceType::Databases)
                 .await?;
-            GetDatabaseResponse::try_from(response).await
+            GetDatabaseResponse::try_from_with_diagnostics(response, diagnostics).await
         })
     }
 }
@@ -71,14 +72,23 @@ pub struct GetDatabaseResponse {
     pub schema_version: String,
     pub service_version: String,
     pub gateway_version: String,
+    pub diagnostics: CosmosDiagnostics,
 }
 
 impl GetDatabaseResponse {
     pub async fn try_from(response: HttpResponse) -> azure_core::error::Result<Self> {
+        Self::try_from_with_diagnostics(response, CosmosDiagnostics::default()).await
+    }
+
+    pub(crate) async fn try_from_with_diagnostics(
+        response: HttpResponse,
+        diagnostics: CosmosDiagnostics,
+    ) -> azure_core::error::Result<Self> {
         let (_status_code, headers, pinned_stream) = response.deconstruct();
         let body = collect_pinned_stream(pinned_stream).await?;
 
         Ok(Self {
+            diagnostics,
             database: serde_json::from_slice(&body)?,
             charge: request_charge_from_headers(&headers)?,
             activity_id: activity_id_from_headers(&headers)?,
@@ -1,7 +1,7 @@
 use crate::headers::from_headers::*;
 use crate::prelude::*;
 use crate::resources::Document;
-use crate::ResourceQuota;
+use crate::{CosmosDiagnostics, ResourceQuota};
 use azure_core::headers::{etag_from_headers, session_token_from_headers};
 use azure_core::prelude::*;
 use azure_core::{collect_pinned_stream, Response as HttpResponse, SessionToken};
@@ -59,17 +59,14 @@ impl GetDocumentBuilder {
 
             request.set_body(azure_core::EMPTY_BODY);
 
-            let response = self
-                .client
-                .cosmos_client()
-                .pipeline()
-                .send(
-                    self.context.clone().insert(ResourceType::Documents),
-                    &mut request,
-                )
-                .await?;
-
-            GetDocumentResponse::try_from(response).await
+            let (response, diagnostics) = crate::diagnostics::send(
+                self.client.cosmos_client().pipeline(),
+                self.context.clone().insert(ResourceType::Documents),
+                &mut request,
+            )
+            .await?;
+
+            GetDocumentResponse::try_from_with_diagnostics(response, diagnostics).await
         })
     }
 }
@@ -93,6 +90,13 @@ where
     T: DeserializeOwned,
 {
     pub async fn try_from(response: HttpResponse) -> azure_core::error::Result<Self> {
+        Self::try_from_with_diagnostics(response, CosmosDiagnostics::default()).await
+    }
+
+    pub(crate) async fn try_from_with_diagnostics(
+        response: HttpResponse,
+        diagnostics: CosmosDiagnostics,
+    ) -> azure_core::error::Result<Self> {
         let (status_code, headers, pinned_stream) = response.deconstruct();
 
         let has_been_found =
@@ -102,11 +106,11 @@ where
 
         if has_been_found {
             Ok(GetDocumentResponse::Found(
-                FoundDocumentResponse::try_from(&headers, body).await?,
+                FoundDocumentResponse::try_from(&headers, body, diagnostics).await?,
             ))
         } else {
             Ok(GetDocumentResponse::NotFound(
-                NotFoundDocumentResponse::try_from(&headers).await?,
+                NotFoundDocumentResponse::try_from(&headers, diagnostics).await?,
             ))
         }
     }
@@ -137,13 +141,18 @@ pub struct FoundDocumentResponse<T> {
     pub activity_id: uuid::Uuid,
     pub gateway_version: String,
     pub date: DateTime<Utc>,
+    pub diagnostics: CosmosDiagnostics,
 }
 
 impl<T> FoundDocumentResponse<T>
 where
     T: DeserializeOwned,
 {
-    async fn try_from(headers: &HeaderMap, body: bytes::Bytes) -> azure_core::error::Result<Self> {
+    async fn try_from(
+        headers: &HeaderMap,
+        body: bytes::Bytes,
+        diagnostics: CosmosDiagnostics,
+    ) -> azure_core::error::Result<Self> {
         Ok(Self {
             document: serde_json::from_slice(&body)?,
 
@@ -169,6 +178,7 @@ where
             activity_id: activity_id_from_headers(headers)?,
             gateway_version: gateway_version_from_headers(headers)?.to_owned(),
             date: date_from_headers(headers)?,
+            diagnostics,
         })
     }
 }
@@ -193,10 +203,14 @@ pub struct NotFoundDocumentResponse {
     pub activity_id: uuid::Uuid,
     pub gateway_version: String,
     pub date: DateTime<Utc>,
+    pub diagnostics: CosmosDiagnostics,
 }
 
 impl NotFoundDocumentResponse {
-    async fn try_from(headers: &HeaderMap) -> azure_core::error::Result<Self> {
+    async fn try_from(
+        headers: &HeaderMap,
+        diagnostics: CosmosDiagnostics,
+    ) -> azure_core::error::Result<Self> {
         Ok(Self {
             content_location: content_location_from_headers(headers)?.to_owned(),
             last_state_change: last_state_change_from_headers(headers)?,
@@ -216,6 +230,7 @@ impl NotFoundDocumentResponse {
             activity_id: activity_id_from_headers(headers)?,
             gateway_version: gateway_version_from_headers(headers)?.to_owned(),
             date: date_from_headers(headers)?,
+            diagnostics,
         })
     }
 }
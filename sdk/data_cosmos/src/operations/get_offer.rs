@@ -0,0 +1,252 @@
+use crate::headers::from_headers::*;
+use crate::headers::HEADER_DOCUMENTDB_ISQUERY;
+use crate::prelude::*;
+use crate::resources::document::{Param, Query};
+use crate::resources::ThroughputOffer;
+use crate::CosmosDiagnostics;
+
+use azure_core::error::{Error, ErrorKind, ResultExt};
+use azure_core::{collect_pinned_stream, Context};
+use http::HeaderMap;
+
+#[derive(Debug, Clone)]
+pub struct GetOfferBuilder {
+    target: OfferTarget,
+    context: Context,
+}
+
+impl GetOfferBuilder {
+    pub(crate) fn new(target: OfferTarget) -> Self {
+        Self {
+            target,
+            context: Context::new(),
+        }
+    }
+
+    setters! {
+        context: Context => context,
+    }
+
+    pub fn into_future(self) -> GetOffer {
+        Box::pin(async move {
+            let (offer, headers, diagnostics) = self.target.read(&self.context).await?;
+            GetOfferResponse::try_from(offer, &headers, diagnostics)
+        })
+    }
+}
+
+/// The future returned by calling `into_future` on the builder.
+pub type GetOffer =
+    futures::future::BoxFuture<'static, azure_core::error::Result<GetOfferResponse>>;
+
+#[cfg(feature = "into_future")]
+impl std::future::IntoFuture for GetOfferBuilder {
+    type IntoFuture = GetOffer;
+    type Output = <GetOffer as std::future::Future>::Output;
+    fn into_future(self) -> Self::IntoFuture {
+        Self::into_future(self)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GetOfferResponse {
+    pub offer: ThroughputOffer,
+    /// Whether a throughput change is still being applied
+    pub replace_pending: bool,
+    pub charge: f64,
+    pub activity_id: uuid::Uuid,
+    pub diagnostics: CosmosDiagnostics,
+}
+
+impl GetOfferResponse {
+    fn try_from(
+        offer: ThroughputOffer,
+        headers: &HeaderMap,
+        diagnostics: CosmosDiagnostics,
+    ) -> azure_core::error::Result<Self> {
+        Ok(Self {
+            offer,
+            replace_pending: offer_replace_pending_from_headers(headers)?,
+            charge: request_charge_from_headers(headers)?,
+            activity_id: activity_id_from_headers(headers)?,
+            diagnostics,
+        })
+    }
+}
+
+/// The offer an operation applies to, either directly or through its database or collection
+#[derive(Debug, Clone)]
+pub(crate) enum OfferTarget {
+    Offer {
+        client: CosmosClient,
+        offer_id: String,
+    },
+    Database(DatabaseClient),
+    Collection(CollectionClient),
+}
+
+impl OfferTarget {
+    pub(crate) fn cosmos_client(&self) -> &CosmosClient {
+        match self {
+            OfferTarget::Offer { client, .. } => client,
+            OfferTarget::Database(client) => client.cosmos_client(),
+            OfferTarget::Collection(client) => client.cosmos_client(),
+        }
+    }
+
+    /// Reads the offer, looking it up by the resource id of its database or collection if needed
+    pub(crate) async fn read(
+        &self,
+        context: &Context,
+    ) -> azure_core::error::Result<(ThroughputOffer, HeaderMap, CosmosDiagnostics)> {
+        let (request, resource_type) = match self {
+            OfferTarget::Offer { client, offer_id } => {
+                let request = client
+                    .prepare_request_pipeline(&format!("offers/{}", offer_id), http::Method::GET);
+                let (headers, body, diagnostics) =
+                    self.send(request, ResourceType::Offers, context).await?;
+                let offer = serde_json::from_slice(&body)
+                    .context(ErrorKind::DataConversion, "could not parse the offer")?;
+                return Ok((offer, headers, diagnostics));
+            }
+            OfferTarget::Database(client) => (
+                client.prepare_pipeline(http::Method::GET),
+                ResourceType::Databases,
+            ),
+            OfferTarget::Collection(client) => (
+                client.prepare_request_with_collection_name(http::Method::GET),
+                ResourceType::Collections,
+            ),
+        };
+
+        #[derive(Deserialize)]
+        struct Resource {
+            #[serde(rename = "_rid")]
+            rid: String,
+        }
+
+        let (_, body, mut diagnostics) = self.send(request, resource_type, context).await?;
+        let resource: Resource = serde_json::from_slice(&body)?;
+        let (offer, headers, found) = self.find(resource.rid, context).await?;
+        diagnostics.merge(found);
+        Ok((offer, headers, diagnostics))
+    }
+
+    /// Queries the offer of the database or collection with the given resource id
+    async fn find(
+        &self,
+        resource_id: String,
+        context: &Context,
+    ) -> azure_core::error::Result<(ThroughputOffer, HeaderMap, CosmosDiagnostics)> {
+        let mut request = self
+            .cosmos_client()
+            .prepare_request_pipeline("offers", http::Method::POST);
+        request.headers_mut().insert(
+            HEADER_DOCUMENTDB_ISQUERY,
+            http::HeaderValue::from_static("True"),
+        );
+        request.headers_mut().insert(
+            http::header::CONTENT_TYPE,
+            http::HeaderValue::from_static("application/query+json"),
+        );
+        let query = Query::with_params(
+            "SELECT * FROM root WHERE root.offerResourceId = @resourceId".to_owned(),
+            vec![Param::new("@resourceId".to_owned(), resource_id.clone())],
+        );
+        request.set_body(azure_core::to_json(&query)?);
+        let (headers, body, diagnostics) =
+            self.send(request, ResourceType::Offers, context).await?;
+
+        #[derive(Deserialize)]
+        struct Offers {
+            #[serde(rename = "Offers")]
+            offers: Vec<ThroughputOffer>,
+        }
+
+        let offers: Offers = serde_json::from_slice(&body)
+            .context(ErrorKind::DataConversion, "could not parse the offers")?;
+        let offer = offers.offers.into_iter().next().ok_or_else(|| {
+            Error::message(
+                ErrorKind::Other,
+                format!(
+                    "no offer found for resource '{}': its throughput may be shared with its database",
+                    resource_id
+                ),
+            )
+        })?;
+        Ok((offer, headers, diagnostics))
+    }
+
+    async fn send(
+        &self,
+        request: azure_core::Request,
+        resource_type: ResourceType,
+        context: &Context,
+    ) -> azure_core::error::Result<(HeaderMap, bytes::Bytes, CosmosDiagnostics)> {
+        let (response, diagnostics) = self
+            .cosmos_client()
+            .send(request, context.clone(), resource_type)
+            .await?;
+        let (_status_code, headers, pinned_stream) = response.deconstruct();
+        Ok((
+            headers,
+            collect_pinned_stream(pinned_stream).await?,
+            diagnostics,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mock_endpoint::{MockEndpoint, MockResponse};
+    use crate::prelude::*;
+
+    const OFFER: &str = r#"{"resource":"dbs/rgkVAA==/colls/rgkVAMHcJww=/","offerType":"Invalid","offerResourceId":"rgkVAMHcJww=","offerVersion":"V2","content":{"offerThroughput":400,"offerIsRUPerMinuteThroughputEnabled":false},"id":"HDRB","_rid":"HDRB","_self":"offers/HDRB/","_etag":"\"1\"","_ts":1518029673}"#;
+
+    #[tokio::test]
+    async fn gets_offer_by_id() {
+        let endpoint = MockEndpoint::start(vec![MockResponse::new(200, OFFER)
+            .with_cosmos_headers()
+            .with_header("x-ms-offer-replace-pending", "true")]);
+
+        let response = endpoint
+            .cosmos_client()
+            .offer_client("HDRB")
+            .get_offer()
+            .into_future()
+            .await
+            .unwrap();
+
+        assert_eq!(response.offer.throughput(), Throughput::Manual(400));
+        assert!(response.replace_pending);
+        let request = &endpoint.requests()[0];
+        assert_eq!(request.method, "GET");
+        assert_eq!(request.path, "/offers/HDRB");
+    }
+
+    #[tokio::test]
+    async fn fails_when_the_database_has_no_offer() {
+        let endpoint = MockEndpoint::start(vec![
+            MockResponse::new(200, r#"{"id":"db","_rid":"rgkVAA=="}"#).with_cosmos_headers(),
+            MockResponse::new(200, r#"{"_rid":"","Offers":[],"_count":0}"#).with_cosmos_headers(),
+        ]);
+
+        let error = endpoint
+            .cosmos_client()
+            .database_client("db")
+            .get_offer()
+            .into_future()
+            .await
+            .unwrap_err();
+
+        assert!(error.to_string().contains("rgkVAA=="));
+        let requests = endpoint.requests();
+        assert_eq!(requests[0].path, "/dbs/db");
+        assert_eq!(requests[1].method, "POST");
+        assert_eq!(requests[1].path, "/offers");
+        assert_eq!(
+            requests[1].body,
+            r#"{"query":"SELECT * FROM root WHERE root.offerResourceId = @resourceId","parameters":[{"name":"@resourceId","value":"rgkVAA=="}]}"#
+        );
+    }
+}
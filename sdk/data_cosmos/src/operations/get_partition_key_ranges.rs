@@ -1,6 +1,7 @@
 use crate::headers::from_headers::*;
 use crate::prelude::*;
 use crate::resources::ResourceType;
+use crate::CosmosDiagnostics;
 use azure_core::headers::{item_count_from_headers, session_token_from_headers};
 use azure_core::{collect_pinned_stream, prelude::*, Response as HttpResponse};
 use chrono::{DateTime, Utc};
@@ -49,18 +50,16 @@ impl GetPartitionKeyRangesBuilder {
                 request.insert_headers(cl);
             }
 
-            let response = self
-                .client
-                .pipeline()
-                .send(
-                    self.context
-                        .clone()
-                        .insert(ResourceType::PartitionKeyRanges),
-                    &mut request,
-                )
-                .await?;
-
-            GetPartitionKeyRangesResponse::try_from(response).await
+            let (response, diagnostics) = crate::diagnostics::send(
+                self.client.pipeline(),
+                self.context
+                    .clone()
+                    .insert(ResourceType::PartitionKeyRanges),
+                &mut request,
+            )
+            .await?;
+
+            GetPartitionKeyRangesResponse::try_from_with_diagnostics(response, diagnostics).await
         })
     }
 }
@@ -100,10 +99,18 @@ pub struct GetPartitionKeyRangesResponse {
     pub gateway_version: String,
     pub date: DateTime<Utc>,
     pub partition_key_ranges: Vec<PartitionKeyRange>,
+    pub diagnostics: CosmosDiagnostics,
 }
 
 impl GetPartitionKeyRangesResponse {
     pub async fn try_from(response: HttpResponse) -> azure_core::error::Result<Self> {
+        Self::try_from_with_diagnostics(response, CosmosDiagnostics::default()).await
+    }
+
+    pub(crate) async fn try_from_with_diagnostics(
+        response: HttpResponse,
+        diagnostics: CosmosDiagnostics,
+    ) -> azure_core::error::Result<Self> {
         let (_status_code, headers, pinned_stream) = response.deconstruct();
         let body = collect_pinned_stream(pinned_stream).await?;
 
@@ -118,6 +125,7 @@ impl GetPartitionKeyRangesResponse {
         let r: Response = serde_json::from_slice(&body)?;
 
         Ok(Self {
+            diagnostics,
             rid: r.rid,
             content_location: content_location_from_headers(&headers)?.to_owned(),
             server: server_from_headers(&headers)?.to_owned(),
@@ -159,7 +167,9 @@ pub struct PartitionKeyRange {
     #[serde(rename = "throughputFraction")]
     pub throughput_fraction: u64,
     pub status: String,
-    // TODO: parents
+    /// The ids of the ranges this range was split from
+    #[serde(default)]
+    pub parents: Vec<String>,
     #[serde(rename = "_ts")]
     pub ts: u64,
 }
@@ -33,16 +33,14 @@ impl GetPermissionBuilder {
                 request.insert_headers(cl);
             }
 
-            let response = self
-                .client
-                .pipeline()
-                .send(
-                    self.context.clone().insert(ResourceType::Permissions),
-                    &mut request,
-                )
-                .await?;
-
-            PermissionResponse::try_from(response).await
+            let (response, diagnostics) = crate::diagnostics::send(
+                self.client.pipeline(),
+                self.context.clone().insert(ResourceType::Permissions),
+                &mut request,
+            )
+            .await?;
+
+            PermissionResponse::try_from_with_diagnostics(response, diagnostics).await
         })
     }
 }
@@ -32,16 +32,14 @@ impl GetUserBuilder {
                 request.insert_headers(cl);
             }
             request.set_body(bytes::Bytes::new());
-            let response = self
-                .client
-                .pipeline()
-                .send(
-                    self.context.clone().insert(ResourceType::Users),
-                    &mut request,
-                )
-                .await?;
-
-            UserResponse::try_from(response).await
+            let (response, diagnostics) = crate::diagnostics::send(
+                self.client.pipeline(),
+                self.context.clone().insert(ResourceType::Users),
+                &mut request,
+            )
+            .await?;
+
+            UserResponse::try_from_with_diagnostics(response, diagnostics).await
         })
     }
 }
@@ -2,6 +2,7 @@ use crate::headers::from_headers::*;
 use crate::prelude::*;
 use crate::resources::Attachment;
 use crate::resources::ResourceType;
+use crate::CosmosDiagnostics;
 use crate::ResourceQuota;
 use azure_core::collect_pinned_stream;
 use azure_core::headers::{
@@ -69,13 +70,13 @@ impl ListAttachmentsBuilder {
 
                 request.insert_headers(&continuation);
 
-                let response = this
-                    .client
-                    .cosmos_client()
-                    .pipeline()
-                    .send(ctx.clone().insert(ResourceType::Attachments), &mut request)
-                    .await?;
-                ListAttachmentsResponse::try_from(response).await
+                let (response, diagnostics) = crate::diagnostics::send(
+                    this.client.cosmos_client().pipeline(),
+                    ctx.clone().insert(ResourceType::Attachments),
+                    &mut request,
+                )
+                .await?;
+                ListAttachmentsResponse::try_from_with_diagnostics(response, diagnostics).await
             }
         };
 
@@ -121,16 +122,25 @@ pub struct ListAttachmentsResponse {
     pub gateway_version: String,
     pub date: DateTime<Utc>,
     pub continuation_token: Option<String>,
+    pub diagnostics: CosmosDiagnostics,
 }
 
 impl ListAttachmentsResponse {
     pub async fn try_from(response: HttpResponse) -> azure_core::error::Result<Self> {
+        Self::try_from_with_diagnostics(response, CosmosDiagnostics::default()).await
+    }
+
+    pub(crate) async fn try_from_with_diagnostics(
+        response: HttpResponse,
+        diagnostics: CosmosDiagnostics,
+    ) -> azure_core::error::Result<Self> {
         let (_status_code, headers, pinned_stream) = response.deconstruct();
         let body = collect_pinned_stream(pinned_stream).await?;
 
         let json: JsonListAttachmentResponse = serde_json::from_slice(&body)?;
 
         Ok(Self {
+            diagnostics,
             rid: json.rid,
             count: json.count,
             attachments: json.attachments,
@@ -1,6 +1,7 @@
 use crate::headers::from_headers::*;
 use crate::prelude::*;
 use crate::resources::Collection;
+use crate::CosmosDiagnostics;
 use crate::ResourceQuota;
 use azure_core::headers::{continuation_token_from_headers_optional, session_token_from_headers};
 use azure_core::prelude::*;
@@ -45,12 +46,12 @@ impl ListCollectionsBuilder {
 
                 request.insert_headers(&continuation);
 
-                let response = this
+                let (response, diagnostics) = this
                     .client
                     .cosmos_client()
                     .send(request, ctx.clone(), ResourceType::Collections)
                     .await?;
-                ListCollectionsResponse::try_from(response).await
+                ListCollectionsResponse::try_from_with_diagnostics(response, diagnostics).await
             }
         };
 
@@ -77,10 +78,18 @@ pub struct ListCollectionsResponse {
     pub session_token: String,
     pub gateway_version: String,
     pub continuation_token: Option<String>,
+    pub diagnostics: CosmosDiagnostics,
 }
 
 impl ListCollectionsResponse {
     pub async fn try_from(response: HttpResponse) -> azure_core::error::Result<Self> {
+        Self::try_from_with_diagnostics(response, CosmosDiagnostics::default()).await
+    }
+
+    pub(crate) async fn try_from_with_diagnostics(
+        response: HttpResponse,
+        diagnostics: CosmosDiagnostics,
+    ) -> azure_core::error::Result<Self> {
         let (_status_code, headers, pinned_stream) = response.deconstruct();
         let body = collect_pinned_stream(pinned_stream).await?;
 
@@ -96,6 +105,7 @@ impl ListCollectionsResponse {
         let response: Response = serde_json::from_slice(&*body)?;
 
         Ok(Self {
+            diagnostics,
             rid: response._rid,
             collections: response.collections,
             count: response.count,
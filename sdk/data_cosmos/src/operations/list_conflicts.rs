@@ -0,0 +1,185 @@
+use crate::headers::from_headers::*;
+use crate::prelude::*;
+use crate::resources::Conflict;
+use crate::CosmosDiagnostics;
+
+use azure_core::headers::{continuation_token_from_headers_optional, session_token_from_headers};
+use azure_core::prelude::*;
+use azure_core::{collect_pinned_stream, Pageable, Response as HttpResponse};
+
+#[derive(Debug, Clone)]
+pub struct ListConflictsBuilder {
+    client: CollectionClient,
+    consistency_level: Option<ConsistencyLevel>,
+    max_item_count: MaxItemCount,
+    context: Context,
+}
+
+impl ListConflictsBuilder {
+    pub(crate) fn new(client: CollectionClient) -> Self {
+        Self {
+            client,
+            consistency_level: None,
+            max_item_count: MaxItemCount::new(-1),
+            context: Context::new(),
+        }
+    }
+
+    setters! {
+        consistency_level: ConsistencyLevel => Some(consistency_level),
+        max_item_count: i32 => MaxItemCount::new(max_item_count),
+        context: Context => context,
+    }
+
+    pub fn into_stream(self) -> ListConflicts {
+        let make_request = move |continuation: Option<Continuation>| {
+            let this = self.clone();
+            let ctx = self.context.clone();
+            async move {
+                let mut request = this.client.cosmos_client().prepare_request_pipeline(
+                    &format!(
+                        "dbs/{}/colls/{}/conflicts",
+                        this.client.database_client().database_name(),
+                        this.client.collection_name()
+                    ),
+                    http::Method::GET,
+                );
+
+                if let Some(cl) = &this.consistency_level {
+                    request.insert_headers(cl);
+                }
+                request.insert_headers(&this.max_item_count);
+                request.insert_headers(&continuation);
+
+                let (response, diagnostics) = crate::diagnostics::send(
+                    this.client.pipeline(),
+                    ctx.clone().insert(ResourceType::Conflicts),
+                    &mut request,
+                )
+                .await?;
+                ListConflictsResponse::try_from_with_diagnostics(response, diagnostics).await
+            }
+        };
+
+        Pageable::new(make_request)
+    }
+}
+
+pub type ListConflicts = Pageable<ListConflictsResponse, azure_core::error::Error>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListConflictsResponse {
+    pub rid: String,
+    pub conflicts: Vec<Conflict>,
+    pub count: u32,
+    pub session_token: String,
+    pub charge: f64,
+    pub activity_id: uuid::Uuid,
+    pub continuation_token: Option<String>,
+    pub diagnostics: CosmosDiagnostics,
+}
+
+impl ListConflictsResponse {
+    pub async fn try_from(response: HttpResponse) -> azure_core::error::Result<Self> {
+        Self::try_from_with_diagnostics(response, CosmosDiagnostics::default()).await
+    }
+
+    pub(crate) async fn try_from_with_diagnostics(
+        response: HttpResponse,
+        diagnostics: CosmosDiagnostics,
+    ) -> azure_core::error::Result<Self> {
+        let (_status_code, headers, pinned_stream) = response.deconstruct();
+        let body = collect_pinned_stream(pinned_stream).await?;
+
+        #[derive(Debug, Deserialize)]
+        struct Response {
+            #[serde(rename = "_rid")]
+            rid: String,
+            #[serde(rename = "Conflicts")]
+            conflicts: Vec<Conflict>,
+            #[serde(rename = "_count")]
+            count: u32,
+        }
+        let response: Response = serde_json::from_slice(&body)?;
+
+        Ok(Self {
+            diagnostics,
+            rid: response.rid,
+            conflicts: response.conflicts,
+            count: response.count,
+            session_token: session_token_from_headers(&headers)?,
+            charge: request_charge_from_headers(&headers)?,
+            activity_id: activity_id_from_headers(&headers)?,
+            continuation_token: continuation_token_from_headers_optional(&headers)?,
+        })
+    }
+}
+
+impl Continuable for ListConflictsResponse {
+    fn continuation(&self) -> Option<String> {
+        self.continuation_token.clone()
+    }
+}
+
+impl IntoIterator for ListConflictsResponse {
+    type Item = Conflict;
+
+    type IntoIter = std::vec::IntoIter<Self::Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.conflicts.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mock_endpoint::{MockEndpoint, MockResponse};
+    use futures::StreamExt;
+
+    fn conflicts(ids: &[&str]) -> String {
+        let conflicts: Vec<_> = ids
+            .iter()
+            .map(|id| {
+                serde_json::json!({
+                    "id": id,
+                    "_rid": id,
+                    "_self": format!("dbs/db/colls/coll/conflicts/{}/", id),
+                    "_etag": "\"0\"",
+                    "_ts": 1,
+                    "resourceId": "doc",
+                    "resourceType": "document",
+                    "operationType": "create",
+                    "content": "{\"id\":\"doc\"}"
+                })
+            })
+            .collect();
+        serde_json::json!({"_rid": "coll", "Conflicts": conflicts, "_count": ids.len()}).to_string()
+    }
+
+    #[tokio::test]
+    async fn lists_every_page_of_conflicts() {
+        let endpoint = MockEndpoint::start(vec![
+            MockResponse::new(200, conflicts(&["a", "b"]))
+                .with_cosmos_headers()
+                .with_header("x-ms-continuation", "next"),
+            MockResponse::new(200, conflicts(&["c"])).with_cosmos_headers(),
+        ]);
+
+        let ids: Vec<_> = endpoint
+            .cosmos_client()
+            .database_client("db")
+            .collection_client("coll")
+            .list_conflicts()
+            .into_stream()
+            .map(|page| page.unwrap())
+            .flat_map(|page| futures::stream::iter(page.conflicts))
+            .map(|conflict| conflict.id)
+            .collect()
+            .await;
+
+        assert_eq!(ids, ["a", "b", "c"]);
+        let requests = endpoint.requests();
+        assert_eq!(requests[0].path, "/dbs/db/colls/coll/conflicts");
+        assert_eq!(requests[1].header("x-ms-continuation"), Some("next"));
+    }
+}
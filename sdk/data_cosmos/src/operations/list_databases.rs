@@ -1,6 +1,7 @@
 use crate::headers::from_headers::*;
 use crate::prelude::*;
 use crate::resources::Database;
+use crate::CosmosDiagnostics;
 use crate::ResourceQuota;
 
 use azure_core::headers::{continuation_token_from_headers_optional, session_token_from_headers};
@@ -45,13 +46,14 @@ impl ListDatabasesBuilder {
                 request.insert_headers(&this.max_item_count);
                 request.insert_headers(&continuation);
 
-                let response = this
-                    .client
-                    .pipeline()
-                    .send(ctx.clone().insert(ResourceType::Databases), &mut request)
-                    .await?;
+                let (response, diagnostics) = crate::diagnostics::send(
+                    this.client.pipeline(),
+                    ctx.clone().insert(ResourceType::Databases),
+                    &mut request,
+                )
+                .await?;
 
-                ListDatabasesResponse::try_from(response).await
+                ListDatabasesResponse::try_from(response, diagnostics).await
             }
         };
 
@@ -76,10 +78,14 @@ pub struct ListDatabasesResponse {
     pub service_version: String,
     pub continuation_token: Option<String>,
     pub gateway_version: String,
+    pub diagnostics: CosmosDiagnostics,
 }
 
 impl ListDatabasesResponse {
-    pub(crate) async fn try_from(response: Response) -> azure_core::error::Result<Self> {
+    pub(crate) async fn try_from(
+        response: Response,
+        diagnostics: CosmosDiagnostics,
+    ) -> azure_core::error::Result<Self> {
         let (_status_code, headers, pinned_stream) = response.deconstruct();
         let body: bytes::Bytes = collect_pinned_stream(pinned_stream).await?;
 
@@ -96,6 +102,7 @@ impl ListDatabasesResponse {
         let response: Response = serde_json::from_slice(&body)?;
 
         Ok(Self {
+            diagnostics,
             rid: response.rid,
             databases: response.databases,
             count: response.count,
@@ -2,6 +2,7 @@ use crate::headers::from_headers::*;
 use crate::prelude::*;
 use crate::resources::document::{Document, DocumentAttributes};
 use crate::resources::ResourceType;
+use crate::CosmosDiagnostics;
 use crate::ResourceQuota;
 use azure_core::headers::{
     continuation_token_from_headers_optional, item_count_from_headers, session_token_from_headers,
@@ -69,13 +70,14 @@ impl ListDocumentsBuilder {
                 req.insert_headers(&this.partition_range_id);
                 req.insert_headers(&continuation);
 
-                let response = this
-                    .client
-                    .pipeline()
-                    .send(ctx.clone().insert(ResourceType::Documents), &mut req)
-                    .await?;
+                let (response, diagnostics) = crate::diagnostics::send(
+                    this.client.pipeline(),
+                    ctx.clone().insert(ResourceType::Documents),
+                    &mut req,
+                )
+                .await?;
 
-                ListDocumentsResponse::try_from(response).await
+                ListDocumentsResponse::try_from(response, diagnostics).await
             }
         };
 
@@ -122,6 +124,7 @@ pub struct ListDocumentsResponse<T> {
     pub gateway_version: String,
     pub date: DateTime<Utc>,
     pub continuation_token: Option<String>,
+    pub diagnostics: CosmosDiagnostics,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -136,7 +139,10 @@ impl<T> ListDocumentsResponse<T>
 where
     T: DeserializeOwned,
 {
-    pub(crate) async fn try_from(response: Response) -> azure_core::error::Result<Self> {
+    pub(crate) async fn try_from(
+        response: Response,
+        diagnostics: CosmosDiagnostics,
+    ) -> azure_core::error::Result<Self> {
         let (_status_code, headers, pinned_stream) = response.deconstruct();
         let body: bytes::Bytes = collect_pinned_stream(pinned_stream).await?;
         let headers = &headers;
@@ -160,6 +166,7 @@ where
             .collect();
 
         Ok(ListDocumentsResponse {
+            diagnostics,
             rid: document_attributes.rid,
             documents,
             content_location: content_location_from_headers(headers)?.to_owned(),
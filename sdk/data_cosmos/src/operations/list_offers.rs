@@ -0,0 +1,115 @@
+use crate::headers::from_headers::*;
+use crate::prelude::*;
+use crate::resources::ThroughputOffer;
+use crate::CosmosDiagnostics;
+
+use azure_core::headers::continuation_token_from_headers_optional;
+use azure_core::{collect_pinned_stream, prelude::*, Pageable, Response};
+
+#[derive(Debug, Clone)]
+pub struct ListOffersBuilder {
+    client: CosmosClient,
+    max_item_count: MaxItemCount,
+    context: Context,
+}
+
+impl ListOffersBuilder {
+    pub(crate) fn new(client: CosmosClient) -> Self {
+        Self {
+            client,
+            max_item_count: MaxItemCount::new(-1),
+            context: Context::new(),
+        }
+    }
+
+    setters! {
+        max_item_count: i32 => MaxItemCount::new(max_item_count),
+        context: Context => context,
+    }
+
+    pub fn into_stream(self) -> ListOffers {
+        let make_request = move |continuation: Option<Continuation>| {
+            let this = self.clone();
+            let ctx = self.context.clone();
+            async move {
+                let mut request = this
+                    .client
+                    .prepare_request_pipeline("offers", http::Method::GET);
+                request.insert_headers(&this.max_item_count);
+                request.insert_headers(&continuation);
+
+                let (response, diagnostics) = crate::diagnostics::send(
+                    this.client.pipeline(),
+                    ctx.clone().insert(ResourceType::Offers),
+                    &mut request,
+                )
+                .await?;
+
+                ListOffersResponse::try_from(response, diagnostics).await
+            }
+        };
+
+        Pageable::new(make_request)
+    }
+}
+
+pub type ListOffers = Pageable<ListOffersResponse, azure_core::error::Error>;
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct ListOffersResponse {
+    pub rid: String,
+    pub offers: Vec<ThroughputOffer>,
+    pub count: u32,
+    pub activity_id: uuid::Uuid,
+    pub charge: f64,
+    pub continuation_token: Option<String>,
+    pub diagnostics: CosmosDiagnostics,
+}
+
+impl ListOffersResponse {
+    pub(crate) async fn try_from(
+        response: Response,
+        diagnostics: CosmosDiagnostics,
+    ) -> azure_core::error::Result<Self> {
+        let (_status_code, headers, pinned_stream) = response.deconstruct();
+        let body: bytes::Bytes = collect_pinned_stream(pinned_stream).await?;
+
+        #[derive(Deserialize, Debug)]
+        pub struct Response {
+            #[serde(rename = "_rid")]
+            rid: String,
+            #[serde(rename = "Offers")]
+            pub offers: Vec<ThroughputOffer>,
+            #[serde(rename = "_count")]
+            pub count: u32,
+        }
+
+        let response: Response = serde_json::from_slice(&body)?;
+
+        Ok(Self {
+            diagnostics,
+            rid: response.rid,
+            offers: response.offers,
+            count: response.count,
+            charge: request_charge_from_headers(&headers)?,
+            activity_id: activity_id_from_headers(&headers)?,
+            continuation_token: continuation_token_from_headers_optional(&headers)?,
+        })
+    }
+}
+
+impl Continuable for ListOffersResponse {
+    fn continuation(&self) -> Option<String> {
+        self.continuation_token.clone()
+    }
+}
+
+impl IntoIterator for ListOffersResponse {
+    type Item = ThroughputOffer;
+
+    type IntoIter = std::vec::IntoIter<Self::Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.offers.into_iter()
+    }
+}
@@ -2,6 +2,7 @@ use crate::headers::from_headers::*;
 use crate::prelude::*;
 use crate::resources::Permission;
 use crate::resources::ResourceType;
+use crate::CosmosDiagnostics;
 use azure_core::collect_pinned_stream;
 use azure_core::headers::{continuation_token_from_headers_optional, session_token_from_headers};
 use azure_core::prelude::*;
@@ -52,12 +53,13 @@ impl ListPermissionsBuilder {
 
                 request.insert_headers(&continuation);
 
-                let response = this
-                    .client
-                    .pipeline()
-                    .send(ctx.clone().insert(ResourceType::Permissions), &mut request)
-                    .await?;
-                ListPermissionsResponse::try_from(response).await
+                let (response, diagnostics) = crate::diagnostics::send(
+                    this.client.pipeline(),
+                    ctx.clone().insert(ResourceType::Permissions),
+                    &mut request,
+                )
+                .await?;
+                ListPermissionsResponse::try_from_with_diagnostics(response, diagnostics).await
             }
         };
 
@@ -76,10 +78,18 @@ pub struct ListPermissionsResponse {
     pub content_path: String,
     pub alt_content_path: String,
     pub continuation_token: Option<String>,
+    pub diagnostics: CosmosDiagnostics,
 }
 
 impl ListPermissionsResponse {
     pub async fn try_from(response: HttpResponse) -> azure_core::error::Result<Self> {
+        Self::try_from_with_diagnostics(response, CosmosDiagnostics::default()).await
+    }
+
+    pub(crate) async fn try_from_with_diagnostics(
+        response: HttpResponse,
+        diagnostics: CosmosDiagnostics,
+    ) -> azure_core::error::Result<Self> {
         let (_status_code, headers, pinned_stream) = response.deconstruct();
         let body = collect_pinned_stream(pinned_stream).await?;
 
@@ -95,6 +105,7 @@ impl ListPermissionsResponse {
         let permissions = response.permissions;
 
         Ok(Self {
+            diagnostics,
             permissions,
             charge: request_charge_from_headers(&headers)?,
             activity_id: activity_id_from_headers(&headers)?,
@@ -2,6 +2,7 @@ use crate::headers::from_headers::*;
 use crate::prelude::*;
 use crate::resources::ResourceType;
 use crate::resources::StoredProcedure;
+use crate::CosmosDiagnostics;
 use crate::ResourceQuota;
 use azure_core::collect_pinned_stream;
 use azure_core::headers::{continuation_token_from_headers_optional, session_token_from_headers};
@@ -54,15 +55,13 @@ impl ListStoredProceduresBuilder {
 
                 request.insert_headers(&continuation);
 
-                let response = this
-                    .client
-                    .pipeline()
-                    .send(
-                        ctx.clone().insert(ResourceType::StoredProcedures),
-                        &mut request,
-                    )
-                    .await?;
-                ListStoredProceduresResponse::try_from(response).await
+                let (response, diagnostics) = crate::diagnostics::send(
+                    this.client.pipeline(),
+                    ctx.clone().insert(ResourceType::StoredProcedures),
+                    &mut request,
+                )
+                .await?;
+                ListStoredProceduresResponse::try_from_with_diagnostics(response, diagnostics).await
             }
         };
 
@@ -83,10 +82,18 @@ pub struct ListStoredProceduresResponse {
     pub resource_usage: Vec<ResourceQuota>,
     pub gateway_version: String,
     pub continuation_token: Option<String>,
+    pub diagnostics: CosmosDiagnostics,
 }
 
 impl ListStoredProceduresResponse {
     pub async fn try_from(response: HttpResponse) -> azure_core::error::Result<Self> {
+        Self::try_from_with_diagnostics(response, CosmosDiagnostics::default()).await
+    }
+
+    pub(crate) async fn try_from_with_diagnostics(
+        response: HttpResponse,
+        diagnostics: CosmosDiagnostics,
+    ) -> azure_core::error::Result<Self> {
         let (_status_code, headers, pinned_stream) = response.deconstruct();
         let body = collect_pinned_stream(pinned_stream).await?;
 
@@ -99,6 +106,7 @@ impl ListStoredProceduresResponse {
         }
 
         Ok(Self {
+            diagnostics,
             stored_procedures: serde_json::from_slice::<Response>(&body)?.stored_procedures,
             charge: request_charge_from_headers(&headers)?,
             activity_id: activity_id_from_headers(&headers)?,
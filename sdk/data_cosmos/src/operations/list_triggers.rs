@@ -1,6 +1,7 @@
 use crate::headers::from_headers::*;
 use crate::prelude::*;
 use crate::resources::ResourceType;
+use crate::CosmosDiagnostics;
 use crate::ResourceQuota;
 use azure_core::collect_pinned_stream;
 use azure_core::headers::item_count_from_headers;
@@ -58,12 +59,13 @@ impl ListTriggersBuilder {
 
                 request.insert_headers(&continuation);
 
-                let response = this
-                    .client
-                    .pipeline()
-                    .send(ctx.clone().insert(ResourceType::Triggers), &mut request)
-                    .await?;
-                ListTriggersResponse::try_from(response).await
+                let (response, diagnostics) = crate::diagnostics::send(
+                    this.client.pipeline(),
+                    ctx.clone().insert(ResourceType::Triggers),
+                    &mut request,
+                )
+                .await?;
+                ListTriggersResponse::try_from_with_diagnostics(response, diagnostics).await
             }
         };
 
@@ -100,10 +102,18 @@ pub struct ListTriggersResponse {
     pub activity_id: uuid::Uuid,
     pub gateway_version: String,
     pub date: DateTime<Utc>,
+    pub diagnostics: CosmosDiagnostics,
 }
 
 impl ListTriggersResponse {
     pub async fn try_from(response: HttpResponse) -> azure_core::error::Result<Self> {
+        Self::try_from_with_diagnostics(response, CosmosDiagnostics::default()).await
+    }
+
+    pub(crate) async fn try_from_with_diagnostics(
+        response: HttpResponse,
+        diagnostics: CosmosDiagnostics,
+    ) -> azure_core::error::Result<Self> {
         let (_status_code, headers, pinned_stream) = response.deconstruct();
         let body = collect_pinned_stream(pinned_stream).await?;
 
@@ -120,6 +130,7 @@ impl ListTriggersResponse {
         let response: Response = serde_json::from_slice(&body)?;
 
         Ok(Self {
+            diagnostics,
             rid: response.rid.to_owned(),
             triggers: response.triggers,
             content_location: content_location_from_headers(&headers)?.to_owned(),
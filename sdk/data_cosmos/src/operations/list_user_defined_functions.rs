@@ -2,6 +2,7 @@ use crate::headers::from_headers::*;
 use crate::prelude::*;
 use crate::resources::ResourceType;
 use crate::resources::UserDefinedFunction;
+use crate::CosmosDiagnostics;
 use crate::ResourceQuota;
 use azure_core::collect_pinned_stream;
 use azure_core::headers::{
@@ -59,15 +60,14 @@ impl ListUserDefinedFunctionsBuilder {
 
                 request.insert_headers(&continuation);
 
-                let response = this
-                    .client
-                    .pipeline()
-                    .send(
-                        ctx.clone().insert(ResourceType::UserDefinedFunctions),
-                        &mut request,
-                    )
-                    .await?;
-                ListUserDefinedFunctionsResponse::try_from(response).await
+                let (response, diagnostics) = crate::diagnostics::send(
+                    this.client.pipeline(),
+                    ctx.clone().insert(ResourceType::UserDefinedFunctions),
+                    &mut request,
+                )
+                .await?;
+                ListUserDefinedFunctionsResponse::try_from_with_diagnostics(response, diagnostics)
+                    .await
             }
         };
 
@@ -104,10 +104,18 @@ pub struct ListUserDefinedFunctionsResponse {
     pub activity_id: uuid::Uuid,
     pub gateway_version: String,
     pub date: DateTime<Utc>,
+    pub diagnostics: CosmosDiagnostics,
 }
 
 impl ListUserDefinedFunctionsResponse {
     pub async fn try_from(response: HttpResponse) -> azure_core::error::Result<Self> {
+        Self::try_from_with_diagnostics(response, CosmosDiagnostics::default()).await
+    }
+
+    pub(crate) async fn try_from_with_diagnostics(
+        response: HttpResponse,
+        diagnostics: CosmosDiagnostics,
+    ) -> azure_core::error::Result<Self> {
         let (_status_code, headers, pinned_stream) = response.deconstruct();
         let body = collect_pinned_stream(pinned_stream).await?;
 
@@ -124,6 +132,7 @@ impl ListUserDefinedFunctionsResponse {
         let response: Response = serde_json::from_slice(&body)?;
 
         Ok(Self {
+            diagnostics,
             rid: response.rid.to_owned(),
             user_defined_functions: response.user_defined_functions,
             content_location: content_location_from_headers(&headers)?.to_owned(),
@@ -1,6 +1,7 @@
 use crate::headers::from_headers::{activity_id_from_headers, request_charge_from_headers};
 use crate::prelude::*;
 use crate::resources::User;
+use crate::CosmosDiagnostics;
 use azure_core::prelude::Continuation;
 use azure_core::{
     collect_pinned_stream,
@@ -53,12 +54,12 @@ impl ListUsersBuilder {
                     request.insert_headers(c);
                 }
 
-                let response = this
+                let (response, diagnostics) = this
                     .client
                     .cosmos_client()
                     .send(request, ctx.clone(), ResourceType::Users)
                     .await?;
-                ListUsersResponse::try_from(response).await
+                ListUsersResponse::try_from_with_diagnostics(response, diagnostics).await
             }
         };
 
@@ -77,10 +78,18 @@ pub struct ListUsersResponse {
     pub activity_id: uuid::Uuid,
     pub session_token: SessionToken,
     pub continuation_token: Option<String>,
+    pub diagnostics: CosmosDiagnostics,
 }
 
 impl ListUsersResponse {
     pub async fn try_from(response: HttpResponse) -> azure_core::error::Result<Self> {
+        Self::try_from_with_diagnostics(response, CosmosDiagnostics::default()).await
+    }
+
+    pub(crate) async fn try_from_with_diagnostics(
+        response: HttpResponse,
+        diagnostics: CosmosDiagnostics,
+    ) -> azure_core::error::Result<Self> {
         let (_status_code, headers, pinned_stream) = response.deconstruct();
         let body = collect_pinned_stream(pinned_stream).await?;
 
@@ -97,6 +106,7 @@ impl ListUsersResponse {
         let response: Response = serde_json::from_slice(&body)?;
 
         Ok(Self {
+            diagnostics,
             users: response.users,
             rid: response.rid,
             count: response.count,
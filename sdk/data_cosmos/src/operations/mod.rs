@@ -2,6 +2,7 @@
 
 #![allow(missing_docs)]
 
+mod bulk;
 mod create_collection;
 mod create_database;
 mod create_document;
@@ -14,6 +15,7 @@ mod create_stored_procedure;
 mod create_user;
 mod delete_attachment;
 mod delete_collection;
+mod delete_conflict;
 mod delete_database;
 mod delete_document;
 mod delete_permission;
@@ -24,27 +26,36 @@ mod delete_user_defined_function;
 mod execute_stored_procedure;
 mod get_attachment;
 mod get_collection;
+mod get_conflict;
 mod get_database;
 mod get_document;
+mod get_offer;
 mod get_partition_key_ranges;
 mod get_permission;
 mod get_user;
 mod list_attachments;
 mod list_collections;
+mod list_conflicts;
 mod list_databases;
 mod list_documents;
+mod list_offers;
 mod list_permissions;
 mod list_stored_procedures;
 mod list_triggers;
 mod list_user_defined_functions;
 mod list_users;
+mod patch_document;
 mod query_documents;
+mod read_change_feed;
 mod replace_collection;
 mod replace_document;
+mod replace_offer;
 mod replace_permission;
 mod replace_stored_procedure;
 mod replace_user;
+mod transactional_batch;
 
+pub use bulk::*;
 pub use create_collection::*;
 pub use create_database::*;
 pub use create_document::*;
@@ -57,6 +68,7 @@ pub use create_stored_procedure::*;
 pub use create_user::*;
 pub use delete_attachment::*;
 pub use delete_collection::*;
+pub use delete_conflict::*;
 pub use delete_database::*;
 pub use delete_document::*;
 pub use delete_permission::*;
@@ -67,23 +79,31 @@ pub use delete_user_defined_function::*;
 pub use execute_stored_procedure::*;
 pub use get_attachment::*;
 pub use get_collection::*;
+pub use get_conflict::*;
 pub use get_database::*;
 pub use get_document::*;
+pub use get_offer::*;
 pub use get_partition_key_ranges::*;
 pub use get_permission::*;
 pub use get_user::*;
 pub use list_attachments::*;
 pub use list_collections::*;
+pub use list_conflicts::*;
 pub use list_databases::*;
 pub use list_documents::*;
+pub use list_offers::*;
 pub use list_permissions::*;
 pub use list_stored_procedures::*;
 pub use list_triggers::*;
 pub use list_user_defined_functions::*;
 pub use list_users::*;
+pub use patch_document::*;
 pub use query_documents::*;
+pub use read_change_feed::*;
 pub use replace_collection::*;
 pub use replace_document::*;
+pub use replace_offer::*;
 pub use replace_permission::*;
 pub use replace_stored_procedure::*;
 pub use replace_user::*;
+pub use transactional_batch::*;
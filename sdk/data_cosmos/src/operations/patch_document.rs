@@ -0,0 +1,259 @@
+use crate::headers::from_headers::*;
+use crate::prelude::*;
+use crate::resources::document::validate_patch_operations;
+use crate::CosmosDiagnostics;
+use crate::ResourceQuota;
+
+use azure_core::headers::{etag_from_headers, session_token_from_headers};
+use azure_core::prelude::*;
+use azure_core::SessionToken;
+use azure_core::{collect_pinned_stream, Response as HttpResponse};
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+
+#[derive(Debug, Clone)]
+pub struct PatchDocumentBuilder {
+    client: DocumentClient,
+    operations: Vec<PatchOperation>,
+    filter_predicate: Option<String>,
+    if_match_condition: Option<IfMatchCondition>,
+    consistency_level: Option<ConsistencyLevel>,
+    allow_tentative_writes: TentativeWritesAllowance,
+    context: Context,
+}
+
+/// The body of a patch request
+#[derive(Serialize)]
+struct PatchRequest<'a> {
+    #[serde(rename = "condition", skip_serializing_if = "Option::is_none")]
+    filter_predicate: Option<&'a str>,
+    operations: &'a [PatchOperation],
+}
+
+impl PatchDocumentBuilder {
+    pub(crate) fn new(client: DocumentClient, operations: Vec<PatchOperation>) -> Self {
+        Self {
+            client,
+            operations,
+            filter_predicate: None,
+            if_match_condition: None,
+            consistency_level: None,
+            allow_tentative_writes: TentativeWritesAllowance::Deny,
+            context: Context::new(),
+        }
+    }
+
+    setters! {
+        filter_predicate: String => Some(filter_predicate),
+        consistency_level: ConsistencyLevel => Some(consistency_level),
+        if_match_condition: IfMatchCondition => Some(if_match_condition),
+        allow_tentative_writes: TentativeWritesAllowance,
+        context: Context => context,
+    }
+
+    /// Adds an operation to the patch
+    pub fn operation(mut self, operation: PatchOperation) -> Self {
+        self.operations.push(operation);
+        self
+    }
+
+    /// Convert into a future
+    ///
+    /// The operations are validated before the request is sent. The patch is applied
+    /// atomically: if an operation or the filter predicate fails, the document is left
+    /// unchanged and the service returns an error.
+    pub fn into_future<T: DeserializeOwned>(self) -> PatchDocument<T> {
+        Box::pin(async move {
+            validate_patch_operations(&self.operations)?;
+
+            let mut request = self
+                .client
+                .prepare_request_pipeline_with_document_name(http::Method::PATCH);
+
+            crate::cosmos_entity::add_as_partition_key_header_serialized2(
+                self.client.partition_key_serialized(),
+                &mut request,
+            );
+            request.insert_headers(&self.if_match_condition);
+            if let Some(cl) = &self.consistency_level {
+                request.insert_headers(cl);
+            }
+            request.insert_headers(&self.allow_tentative_writes);
+            request.headers_mut().insert(
+                http::header::CONTENT_TYPE,
+                http::HeaderValue::from_static("application/json_patch+json"),
+            );
+
+            let body = PatchRequest {
+                filter_predicate: self.filter_predicate.as_deref(),
+                operations: &self.operations,
+            };
+            request.set_body(azure_core::to_json(&body)?);
+
+            let (response, diagnostics) = crate::diagnostics::send(
+                self.client.cosmos_client().pipeline(),
+                self.context.clone().insert(ResourceType::Documents),
+                &mut request,
+            )
+            .await?;
+
+            PatchDocumentResponse::try_from_with_diagnostics(response, diagnostics).await
+        })
+    }
+}
+
+/// The future returned by calling `into_future` on the builder.
+pub type PatchDocument<T> =
+    futures::future::BoxFuture<'static, azure_core::error::Result<PatchDocumentResponse<T>>>;
+
+#[derive(Debug, Clone)]
+pub struct PatchDocumentResponse<T> {
+    /// The document after the patch was applied
+    pub document: Document<T>,
+    pub etag: String,
+    pub content_location: String,
+    pub last_state_change: DateTime<Utc>,
+    pub resource_quota: Vec<ResourceQuota>,
+    pub resource_usage: Vec<ResourceQuota>,
+    pub lsn: u64,
+    pub schema_version: String,
+    pub alt_content_path: String,
+    pub content_path: String,
+    pub quorum_acked_lsn: Option<u64>,
+    pub current_write_quorum: Option<u64>,
+    pub current_replica_set_size: Option<u64>,
+    pub role: u32,
+    pub global_committed_lsn: u64,
+    pub number_of_read_regions: u32,
+    pub transport_request_id: u64,
+    pub cosmos_llsn: u64,
+    pub cosmos_quorum_acked_llsn: Option<u64>,
+    pub session_token: SessionToken,
+    pub charge: f64,
+    pub service_version: String,
+    pub activity_id: uuid::Uuid,
+    pub gateway_version: String,
+    pub date: DateTime<Utc>,
+    pub diagnostics: CosmosDiagnostics,
+}
+
+impl<T> PatchDocumentResponse<T>
+where
+    T: DeserializeOwned,
+{
+    pub async fn try_from(response: HttpResponse) -> azure_core::error::Result<Self> {
+        Self::try_from_with_diagnostics(response, CosmosDiagnostics::default()).await
+    }
+
+    pub(crate) async fn try_from_with_diagnostics(
+        response: HttpResponse,
+        diagnostics: CosmosDiagnostics,
+    ) -> azure_core::error::Result<Self> {
+        let (_status_code, headers, pinned_stream) = response.deconstruct();
+        let body = collect_pinned_stream(pinned_stream).await?;
+
+        Ok(Self {
+            diagnostics,
+            document: serde_json::from_slice(&body)?,
+            etag: etag_from_headers(&headers)?,
+            content_location: content_location_from_headers(&headers)?.to_owned(),
+            last_state_change: last_state_change_from_headers(&headers)?,
+            resource_quota: resource_quota_from_headers(&headers)?,
+            resource_usage: resource_usage_from_headers(&headers)?,
+            lsn: lsn_from_headers(&headers)?,
+            schema_version: schema_version_from_headers(&headers)?.to_owned(),
+            alt_content_path: alt_content_path_from_headers(&headers)?.to_owned(),
+            content_path: content_path_from_headers(&headers)?.to_owned(),
+            quorum_acked_lsn: quorum_acked_lsn_from_headers_optional(&headers)?,
+            current_write_quorum: current_write_quorum_from_headers_optional(&headers)?,
+            current_replica_set_size: current_replica_set_size_from_headers_optional(&headers)?,
+            role: role_from_headers(&headers)?,
+            global_committed_lsn: global_committed_lsn_from_headers(&headers)?,
+            number_of_read_regions: number_of_read_regions_from_headers(&headers)?,
+            transport_request_id: transport_request_id_from_headers(&headers)?,
+            cosmos_llsn: cosmos_llsn_from_headers(&headers)?,
+            cosmos_quorum_acked_llsn: cosmos_quorum_acked_llsn_from_headers_optional(&headers)?,
+            session_token: session_token_from_headers(&headers)?,
+            charge: request_charge_from_headers(&headers)?,
+            service_version: service_version_from_headers(&headers)?.to_owned(),
+            activity_id: activity_id_from_headers(&headers)?,
+            gateway_version: gateway_version_from_headers(&headers)?.to_owned(),
+            date: date_from_headers(&headers)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_endpoint::{MockEndpoint, MockResponse};
+
+    #[derive(Debug, Clone, Deserialize)]
+    struct MyDocument {
+        id: String,
+        count: i64,
+    }
+
+    fn document_client(endpoint: &MockEndpoint) -> DocumentClient {
+        endpoint
+            .cosmos_client()
+            .database_client("db")
+            .collection_client("coll")
+            .document_client("doc", &"pk")
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn patches_document() {
+        let endpoint = MockEndpoint::start(vec![MockResponse::new(
+            200,
+            r#"{"id":"doc","count":3,"_rid":"a","_self":"b","_etag":"\"2\"","_attachments":"attachments/","_ts":1}"#,
+        )
+        .with_cosmos_headers()
+        .with_header("etag", "\"2\"")]);
+
+        let response = document_client(&endpoint)
+            .patch_document(vec![PatchOperation::increment("/count", 1)])
+            .operation(PatchOperation::remove("/obsolete"))
+            .filter_predicate("from c where c.count = 2")
+            .if_match_condition(IfMatchCondition::Match("\"1\"".to_owned()))
+            .into_future::<MyDocument>()
+            .await
+            .unwrap();
+
+        assert_eq!(response.document.document.id, "doc");
+        assert_eq!(response.document.document.count, 3);
+        assert_eq!(response.etag, "\"2\"");
+
+        let request = &endpoint.requests()[0];
+        assert_eq!(request.method, "PATCH");
+        assert_eq!(request.path, "/dbs/db/colls/coll/docs/doc");
+        assert_eq!(request.header("if-match"), Some("\"1\""));
+        assert_eq!(
+            request.header("x-ms-documentdb-partitionkey"),
+            Some(r#"["pk"]"#)
+        );
+        assert_eq!(
+            request.header("content-type"),
+            Some("application/json_patch+json")
+        );
+        assert_eq!(
+            request.body,
+            r#"{"condition":"from c where c.count = 2","operations":[{"op":"incr","path":"/count","value":1},{"op":"remove","path":"/obsolete"}]}"#
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_patch_without_sending() {
+        let endpoint = MockEndpoint::start(vec![]);
+
+        let error = document_client(&endpoint)
+            .patch_document(vec![PatchOperation::set("/a", 1); 11])
+            .into_future::<MyDocument>()
+            .await
+            .unwrap_err();
+
+        assert!(error.to_string().contains("at most 10 operations"));
+        assert!(endpoint.requests().is_empty());
+    }
+}
@@ -1,9 +1,11 @@
 use crate::headers::from_headers::*;
+use crate::partition_key_hash::effective_partition_key_range;
 use crate::prelude::*;
+use crate::query_pipeline::{CrossPartitionQuery, QueryPage, QueryPipeline};
 use crate::resources::document::DocumentAttributes;
 use crate::resources::document::Query;
 use crate::resources::ResourceType;
-use crate::ResourceQuota;
+use crate::{CosmosDiagnostics, IndexUtilization, QueryMetrics, ResourceQuota};
 use azure_core::collect_pinned_stream;
 use azure_core::headers::{
     continuation_token_from_headers_optional, item_count_from_headers, session_token_from_headers,
@@ -14,9 +16,11 @@ use azure_core::Response as HttpResponse;
 use azure_core::SessionToken;
 use chrono::{DateTime, Utc};
 use http::response::Response;
+use http::HeaderMap;
 use serde::de::DeserializeOwned;
 use serde_json::Value;
 use std::convert::TryInto;
+use std::sync::{Arc, Mutex};
 
 #[derive(Debug, Clone)]
 pub struct QueryDocumentsBuilder {
@@ -25,11 +29,14 @@ pub struct QueryDocumentsBuilder {
     if_match_condition: Option<IfMatchCondition>,
     if_modified_since: Option<IfModifiedSince>,
     consistency_level: Option<ConsistencyLevel>,
-    max_item_count: MaxItemCount,
+    max_item_count: i32,
     partition_key_serialized: Option<String>,
+    partition_key_prefix_serialized: Option<String>,
     query_cross_partition: QueryCrossPartition,
-    #[allow(unused)]
     parallelize_cross_partition_query: ParallelizeCrossPartition,
+    populate_query_metrics: bool,
+    populate_index_metrics: bool,
+    continuation: Option<String>,
     context: Context,
 }
 
@@ -41,11 +48,14 @@ impl QueryDocumentsBuilder {
             if_match_condition: None,
             if_modified_since: None,
             consistency_level: None,
-            max_item_count: MaxItemCount::new(-1),
+            max_item_count: -1,
             partition_key_serialized: None,
+            partition_key_prefix_serialized: None,
             query_cross_partition: QueryCrossPartition::No,
-            // TODO: use this in request
             parallelize_cross_partition_query: ParallelizeCrossPartition::No,
+            populate_query_metrics: false,
+            populate_index_metrics: false,
+            continuation: None,
             context: Context::new(),
         }
     }
@@ -53,10 +63,13 @@ impl QueryDocumentsBuilder {
     setters! {
         consistency_level: ConsistencyLevel => Some(consistency_level),
         if_match_condition: IfMatchCondition => Some(if_match_condition),
-        max_item_count: i32 => MaxItemCount::new(max_item_count),
+        max_item_count: i32,
         if_modified_since: DateTime<Utc> => Some(IfModifiedSince::new(if_modified_since)),
         query_cross_partition: bool => if query_cross_partition { QueryCrossPartition::Yes } else { QueryCrossPartition::No },
         parallelize_cross_partition_query: bool => if parallelize_cross_partition_query { ParallelizeCrossPartition::Yes } else { ParallelizeCrossPartition::No },
+        populate_query_metrics: bool,
+        populate_index_metrics: bool,
+        continuation: String => Some(continuation),
         context: Context => context,
     }
 
@@ -67,10 +80,46 @@ impl QueryDocumentsBuilder {
         })
     }
 
+    /// Restricts the query to the documents whose hierarchical partition key starts with the
+    /// prefix, such as `("tenant",)` for a collection partitioned on `/tenantId` and `/userId`.
+    ///
+    /// The query reads only the partition key ranges holding the prefix, across partitions.
+    pub fn partition_key_prefix<PK: serde::Serialize>(
+        self,
+        prefix: &PK,
+    ) -> azure_core::error::Result<Self> {
+        Ok(Self {
+            partition_key_prefix_serialized: Some(crate::cosmos_entity::serialize_partition_key(
+                prefix,
+            )?),
+            ..self
+        })
+    }
+
+    /// Convert into a stream of result pages
+    ///
+    /// A query across partitions, enabled with `query_cross_partition` when no partition key
+    /// is set, is executed by a client-side pipeline: the service plans the query, every
+    /// partition key range it selects is read, and the results are merged so that `ORDER BY`,
+    /// `DISTINCT`, `OFFSET`, `LIMIT`, `TOP`, and `SELECT VALUE` aggregates apply to the whole
+    /// collection. With `parallelize_cross_partition_query` the ranges are read in parallel
+    /// even when the results do not need to be merge sorted. `GROUP BY` is not supported
+    /// across partitions.
+    ///
+    /// The continuation token of a page can be passed to `continuation` to resume the query
+    /// later. A `DISTINCT` query on unordered results can only be resumed within its first
+    /// 10,000 results, the results returned so far being part of the continuation.
     pub fn into_stream<T>(self) -> QueryDocuments<T>
     where
         T: DeserializeOwned + Send + Sync,
     {
+        if self.partition_key_serialized.is_none()
+            && (self.query_cross_partition == QueryCrossPartition::Yes
+                || self.partition_key_prefix_serialized.is_some())
+        {
+            return self.into_cross_partition_stream();
+        }
+
         let make_request = move |continuation: Option<Continuation>| {
             let this = self.clone();
             let ctx = self.context.clone();
@@ -99,8 +148,20 @@ impl QueryDocumentsBuilder {
                 if let Some(cl) = &this.consistency_level {
                     request.insert_headers(cl);
                 }
-                request.insert_headers(&this.max_item_count);
+                request.insert_headers(&MaxItemCount::new(this.max_item_count));
                 request.insert_headers(&this.query_cross_partition);
+                if this.populate_query_metrics {
+                    request.headers_mut().insert(
+                        crate::headers::HEADER_DOCUMENTDB_POPULATEQUERYMETRICS,
+                        http::HeaderValue::from_static("true"),
+                    );
+                }
+                if this.populate_index_metrics {
+                    request.headers_mut().insert(
+                        crate::headers::HEADER_POPULATE_INDEX_METRICS,
+                        http::HeaderValue::from_static("true"),
+                    );
+                }
 
                 request.set_body(serde_json::to_vec(&this.query)?);
                 if let Some(partition_key_serialized) = this.partition_key_serialized.as_ref() {
@@ -110,21 +171,83 @@ impl QueryDocumentsBuilder {
                     );
                 }
 
+                let continuation =
+                    continuation.or_else(|| this.continuation.clone().map(Continuation::new));
                 if let Some(ref c) = continuation {
                     request.insert_headers(c);
                 }
 
-                let response = this
-                    .client
-                    .pipeline()
-                    .send(ctx.clone().insert(ResourceType::Documents), &mut request)
-                    .await?;
-                QueryDocumentsResponse::try_from(response).await
+                let (response, diagnostics) = crate::diagnostics::send(
+                    this.client.pipeline(),
+                    ctx.clone().insert(ResourceType::Documents),
+                    &mut request,
+                )
+                .await?;
+                QueryDocumentsResponse::try_from_with_diagnostics(response, diagnostics).await
             }
         };
 
         Pageable::new(make_request)
     }
+
+    fn into_cross_partition_stream<T>(self) -> QueryDocuments<T>
+    where
+        T: DeserializeOwned + Send + Sync,
+    {
+        // the pipeline of the previous page, reused while the stream asks for its continuation
+        let previous: Arc<Mutex<Option<QueryPipeline>>> = Arc::default();
+        let make_request = move |continuation: Option<Continuation>| {
+            let this = self.clone();
+            let previous = previous.clone();
+            async move {
+                let continuation = continuation
+                    .map(Continuation::into_raw)
+                    .or_else(|| this.continuation.clone());
+                let reusable = previous
+                    .lock()
+                    .unwrap()
+                    .take()
+                    .filter(|pipeline| pipeline.continuation() == continuation.as_deref());
+                let mut pipeline = match reusable {
+                    Some(pipeline) => pipeline,
+                    None => {
+                        QueryPipeline::start(this.cross_partition_query()?, continuation).await?
+                    }
+                };
+
+                let page = pipeline.next_page().await?;
+                *previous.lock().unwrap() = Some(pipeline);
+                QueryDocumentsResponse::from_page(page)
+            }
+        };
+
+        Pageable::new(make_request)
+    }
+
+    fn cross_partition_query(&self) -> azure_core::error::Result<CrossPartitionQuery> {
+        let epk_range = match &self.partition_key_prefix_serialized {
+            Some(prefix) => Some(effective_partition_key_range(prefix).ok_or_else(|| {
+                azure_core::error::Error::with_message(
+                    azure_core::error::ErrorKind::DataConversion,
+                    || format!("{} cannot be a partition key prefix", prefix),
+                )
+            })?),
+            None => None,
+        };
+        Ok(CrossPartitionQuery {
+            client: self.client.clone(),
+            query: self.query.clone(),
+            if_match_condition: self.if_match_condition.clone(),
+            if_modified_since: self.if_modified_since,
+            consistency_level: self.consistency_level.clone(),
+            max_item_count: self.max_item_count,
+            parallelize: self.parallelize_cross_partition_query == ParallelizeCrossPartition::Yes,
+            epk_range,
+            populate_query_metrics: self.populate_query_metrics,
+            populate_index_metrics: self.populate_index_metrics,
+            context: self.context.clone(),
+        })
+    }
 }
 
 pub type QueryDocuments<T> = Pageable<QueryDocumentsResponse<T>, azure_core::error::Error>;
@@ -199,6 +322,12 @@ pub struct QueryDocumentsResponse<T> {
     pub gateway_version: String,
     pub date: DateTime<Utc>,
     pub continuation_token: Option<String>,
+    /// How the query executed, when asked for with `populate_query_metrics`
+    pub query_metrics: Option<QueryMetrics>,
+    /// The indexes the query used and could use, when asked for with `populate_index_metrics`
+    pub index_utilization: Option<IndexUtilization>,
+    /// The attempts made for the page, across partition key ranges for a cross-partition query
+    pub diagnostics: CosmosDiagnostics,
 }
 
 impl<T> QueryDocumentsResponse<T> {
@@ -216,63 +345,112 @@ where
     T: DeserializeOwned,
 {
     pub async fn try_from(response: HttpResponse) -> azure_core::error::Result<Self> {
+        Self::try_from_with_diagnostics(response, CosmosDiagnostics::default()).await
+    }
+
+    pub(crate) async fn try_from_with_diagnostics(
+        response: HttpResponse,
+        diagnostics: CosmosDiagnostics,
+    ) -> azure_core::error::Result<Self> {
         let (_status_code, headers, pinned_stream) = response.deconstruct();
         let body = collect_pinned_stream(pinned_stream).await?;
 
         let inner: Value = serde_json::from_slice(&body)?;
+        let documents = match &inner["Documents"] {
+            Value::Array(documents) => documents.clone(),
+            _ => Vec::new(),
+        };
+
+        Self::from_documents(
+            &headers,
+            documents,
+            serde_json::from_slice(&body)?,
+            diagnostics,
+        )
+    }
+
+    /// Builds a response out of a page of the cross-partition query pipeline
+    fn from_page(page: QueryPage) -> azure_core::error::Result<Self> {
+        let count = page.documents.len();
+        let query_response_meta = QueryResponseMeta {
+            rid: page.rid,
+            count: count as u64,
+        };
+        Ok(Self {
+            item_count: count as u32,
+            charge: page.charge,
+            continuation_token: page.continuation,
+            query_metrics: page.query_metrics,
+            index_utilization: page.index_utilization,
+            ..Self::from_documents(
+                &page.headers,
+                page.documents,
+                query_response_meta,
+                page.diagnostics,
+            )?
+        })
+    }
+
+    fn from_documents(
+        headers: &HeaderMap,
+        documents: Vec<Value>,
+        query_response_meta: QueryResponseMeta,
+        diagnostics: CosmosDiagnostics,
+    ) -> azure_core::error::Result<Self> {
         let mut results = Vec::new();
-        if let Value::Array(documents) = &inner["Documents"] {
-            for doc in documents {
-                let result: T = serde_json::from_value(doc.to_owned())?;
-                // If we have all the necessary fields to construct a
-                // DocumentQueryResult we use it, otherwise we just add a raw
-                // struct.
-                // If I can ascertain that we receive *either* QueryResults
-                // or a raw documents - but not a mix of the two -
-                // we might want to avoid a discriminated union
-                // to be handled at runtime.
-                match serde_json::from_value(doc.to_owned()) {
-                    Ok(document_attributes) => {
-                        results.push(QueryResult::Document(DocumentQueryResult {
-                            document_attributes,
-                            result,
-                        }))
-                    }
-                    Err(error) => {
-                        warn!("{:#?}", error);
-                        results.push(QueryResult::Raw(result));
-                    }
+        for doc in documents {
+            let result: T = serde_json::from_value(doc.to_owned())?;
+            // If we have all the necessary fields to construct a
+            // DocumentQueryResult we use it, otherwise we just add a raw
+            // struct.
+            // If I can ascertain that we receive *either* QueryResults
+            // or a raw documents - but not a mix of the two -
+            // we might want to avoid a discriminated union
+            // to be handled at runtime.
+            match serde_json::from_value(doc) {
+                Ok(document_attributes) => {
+                    results.push(QueryResult::Document(DocumentQueryResult {
+                        document_attributes,
+                        result,
+                    }))
+                }
+                Err(error) => {
+                    warn!("{:#?}", error);
+                    results.push(QueryResult::Raw(result));
                 }
             }
         }
 
         Ok(QueryDocumentsResponse {
             results,
-            last_state_change: last_state_change_from_headers(&headers)?,
-            resource_quota: resource_quota_from_headers(&headers)?,
-            resource_usage: resource_usage_from_headers(&headers)?,
-            lsn: lsn_from_headers(&headers)?,
-            item_count: item_count_from_headers(&headers)?,
-            schema_version: schema_version_from_headers(&headers)?.to_owned(),
-            alt_content_path: alt_content_path_from_headers(&headers)?.to_owned(),
-            content_path: content_path_from_headers(&headers)?.to_owned(),
-            quorum_acked_lsn: quorum_acked_lsn_from_headers_optional(&headers)?,
-            current_write_quorum: current_write_quorum_from_headers_optional(&headers)?,
-            current_replica_set_size: current_replica_set_size_from_headers_optional(&headers)?,
-            role: role_from_headers(&headers)?,
-            global_committed_lsn: global_committed_lsn_from_headers(&headers)?,
-            number_of_read_regions: number_of_read_regions_from_headers(&headers)?,
-            transport_request_id: transport_request_id_from_headers(&headers)?,
-            cosmos_llsn: cosmos_llsn_from_headers(&headers)?,
-            cosmos_quorum_acked_llsn: cosmos_quorum_acked_llsn_from_headers_optional(&headers)?,
-            session_token: session_token_from_headers(&headers)?,
-            charge: request_charge_from_headers(&headers)?,
-            service_version: service_version_from_headers(&headers)?.to_owned(),
-            activity_id: activity_id_from_headers(&headers)?,
-            gateway_version: gateway_version_from_headers(&headers)?.to_owned(),
-            continuation_token: continuation_token_from_headers_optional(&headers)?,
-            date: date_from_headers(&headers)?,
-            query_response_meta: serde_json::from_slice(&body)?,
+            last_state_change: last_state_change_from_headers(headers)?,
+            resource_quota: resource_quota_from_headers(headers)?,
+            resource_usage: resource_usage_from_headers(headers)?,
+            lsn: lsn_from_headers(headers)?,
+            item_count: item_count_from_headers(headers)?,
+            schema_version: schema_version_from_headers(headers)?.to_owned(),
+            alt_content_path: alt_content_path_from_headers(headers)?.to_owned(),
+            content_path: content_path_from_headers(headers)?.to_owned(),
+            quorum_acked_lsn: quorum_acked_lsn_from_headers_optional(headers)?,
+            current_write_quorum: current_write_quorum_from_headers_optional(headers)?,
+            current_replica_set_size: current_replica_set_size_from_headers_optional(headers)?,
+            role: role_from_headers(headers)?,
+            global_committed_lsn: global_committed_lsn_from_headers(headers)?,
+            number_of_read_regions: number_of_read_regions_from_headers(headers)?,
+            transport_request_id: transport_request_id_from_headers(headers)?,
+            cosmos_llsn: cosmos_llsn_from_headers(headers)?,
+            cosmos_quorum_acked_llsn: cosmos_quorum_acked_llsn_from_headers_optional(headers)?,
+            session_token: session_token_from_headers(headers)?,
+            charge: request_charge_from_headers(headers)?,
+            service_version: service_version_from_headers(headers)?.to_owned(),
+            activity_id: activity_id_from_headers(headers)?,
+            gateway_version: gateway_version_from_headers(headers)?.to_owned(),
+            continuation_token: continuation_token_from_headers_optional(headers)?,
+            date: date_from_headers(headers)?,
+            query_metrics: query_metrics_from_headers_optional(headers),
+            index_utilization: index_utilization_from_headers_optional(headers),
+            diagnostics,
+            query_response_meta,
         })
     }
 }
@@ -306,6 +484,12 @@ pub struct QueryDocumentsResponseRaw<T> {
     pub gateway_version: String,
     pub date: DateTime<Utc>,
     pub continuation_token: Option<String>,
+    /// How the query executed, when asked for with `populate_query_metrics`
+    pub query_metrics: Option<QueryMetrics>,
+    /// The indexes the query used and could use, when asked for with `populate_index_metrics`
+    pub index_utilization: Option<IndexUtilization>,
+    /// The attempts made for the page, across partition key ranges for a cross-partition query
+    pub diagnostics: CosmosDiagnostics,
 }
 
 impl<T> std::convert::From<QueryDocumentsResponse<T>> for QueryDocumentsResponseRaw<T> {
@@ -345,6 +529,9 @@ impl<T> std::convert::From<QueryDocumentsResponse<T>> for QueryDocumentsResponse
             gateway_version: q.gateway_version,
             continuation_token: q.continuation_token,
             date: q.date,
+            query_metrics: q.query_metrics,
+            index_utilization: q.index_utilization,
+            diagnostics: q.diagnostics,
         }
     }
 }
@@ -378,6 +565,12 @@ pub struct QueryDocumentsResponseDocuments<T> {
     pub gateway_version: String,
     pub date: DateTime<Utc>,
     pub continuation_token: Option<String>,
+    /// How the query executed, when asked for with `populate_query_metrics`
+    pub query_metrics: Option<QueryMetrics>,
+    /// The indexes the query used and could use, when asked for with `populate_index_metrics`
+    pub index_utilization: Option<IndexUtilization>,
+    /// The attempts made for the page, across partition key ranges for a cross-partition query
+    pub diagnostics: CosmosDiagnostics,
 }
 
 impl<T> std::convert::TryFrom<QueryDocumentsResponse<T>> for QueryDocumentsResponseDocuments<T> {
@@ -424,6 +617,9 @@ impl<T> std::convert::TryFrom<QueryDocumentsResponse<T>> for QueryDocumentsRespo
             gateway_version: q.gateway_version,
             continuation_token: q.continuation_token,
             date: q.date,
+            query_metrics: q.query_metrics,
+            index_utilization: q.index_utilization,
+            diagnostics: q.diagnostics,
         })
     }
 }
@@ -432,3 +628,503 @@ impl<T> Continuable for QueryDocumentsResponse<T> {
         self.continuation_token.clone()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_endpoint::{MockEndpoint, MockResponse, RecordedRequest};
+    use futures::StreamExt;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    #[derive(Debug, Clone, PartialEq, Deserialize)]
+    struct Item {
+        id: String,
+        n: u64,
+    }
+
+    fn ranges() -> MockResponse {
+        let range = |id: &str, min: &str, max: &str| {
+            format!(
+                r#"{{"_rid":"r","id":"{id}","_etag":"e","minInclusive":"{min}","maxExclusive":"{max}","ridPrefix":0,"_self":"s","throughputFraction":1,"status":"online","parents":[],"_ts":1}}"#
+            )
+        };
+        MockResponse::new(
+            200,
+            format!(
+                r#"{{"_rid":"x","PartitionKeyRanges":[{},{}],"_count":2}}"#,
+                range("1", "7F", "FF"),
+                range("0", "", "7F")
+            ),
+        )
+        .with_cosmos_headers()
+    }
+
+    fn page(documents: &[String], continuation: Option<&str>) -> MockResponse {
+        let response = MockResponse::new(
+            200,
+            format!(
+                r#"{{"_rid":"x","Documents":[{}],"_count":{}}}"#,
+                documents.join(","),
+                documents.len()
+            ),
+        )
+        .with_cosmos_headers();
+        match continuation {
+            Some(continuation) => response.with_header("x-ms-continuation", continuation),
+            None => response,
+        }
+    }
+
+    /// Serves the account, the query plan, the partition key ranges, and the pages of every range
+    fn serve(plan: &'static str, pages: fn(&str, Option<&str>) -> MockResponse) -> MockEndpoint {
+        MockEndpoint::serve(move |request: &RecordedRequest| {
+            Some(if request.path == "/" {
+                MockResponse::account()
+            } else if request.path.ends_with("/pkranges") {
+                ranges()
+            } else if request
+                .header("x-ms-cosmos-is-query-plan-request")
+                .is_some()
+            {
+                MockResponse::new(200, plan).with_cosmos_headers()
+            } else {
+                pages(
+                    request
+                        .header("x-ms-documentdb-partitionkeyrangeid")
+                        .unwrap(),
+                    request.header("x-ms-continuation"),
+                )
+            })
+        })
+    }
+
+    fn ordered(n: u64) -> String {
+        format!(
+            r#"{{"_rid":"d{n}","orderByItems":[{{"item":{n}}}],"payload":{{"id":"{n}","n":{n}}}}}"#
+        )
+    }
+
+    const ORDER_BY_PLAN: &str = r#"{"queryInfo":{"distinctType":"None","orderBy":["Ascending"],"aggregates":[],"rewrittenQuery":"SELECT c._rid, [{\"item\": c.n}] AS orderByItems, c AS payload FROM c WHERE ({documentdb-formattableorderbyquery-filter}) ORDER BY c.n","hasSelectValue":false},"queryRanges":[{"min":"","max":"FF","isMinInclusive":true,"isMaxInclusive":false}]}"#;
+
+    fn order_by_pages(range: &str, continuation: Option<&str>) -> MockResponse {
+        match (range, continuation) {
+            ("0", None) => page(&[ordered(1), ordered(4)], Some("0-2")),
+            ("0", Some("0-2")) => page(&[ordered(5)], None),
+            ("1", None) => page(&[ordered(2), ordered(3)], None),
+            _ => MockResponse::new(400, r#"{"code":"BadRequest","message":"unexpected"}"#),
+        }
+    }
+
+    async fn collect_pages(builder: QueryDocumentsBuilder) -> Vec<(Vec<u64>, Option<String>)> {
+        builder
+            .into_stream::<Item>()
+            .map(|page| {
+                let page = page.unwrap().into_raw();
+                let items = page.results.iter().map(|item| item.n).collect();
+                (items, page.continuation_token)
+            })
+            .collect()
+            .await
+    }
+
+    fn query(endpoint: &MockEndpoint, query: &'static str) -> QueryDocumentsBuilder {
+        endpoint
+            .cosmos_client()
+            .database_client("db")
+            .collection_client("coll")
+            .query_documents(query)
+            .query_cross_partition(true)
+    }
+
+    #[tokio::test]
+    async fn merge_sorts_ordered_results_across_ranges() {
+        let endpoint = serve(ORDER_BY_PLAN, order_by_pages);
+
+        let pages =
+            collect_pages(query(&endpoint, "SELECT * FROM c ORDER BY c.n").max_item_count(2)).await;
+
+        let items: Vec<_> = pages.iter().map(|(items, _)| items.clone()).collect();
+        assert_eq!(items, [vec![1, 2], vec![3, 4], vec![5]]);
+        assert!(pages[0].1.is_some());
+        assert!(pages[2].1.is_none());
+
+        let requests = endpoint.requests();
+        assert_eq!(
+            requests[0].header("x-ms-cosmos-supported-query-features"),
+            Some("Aggregate, Distinct, MultipleOrderBy, OffsetAndLimit, OrderBy, Top")
+        );
+        let range_query = requests
+            .iter()
+            .find(|request| {
+                request
+                    .header("x-ms-documentdb-partitionkeyrangeid")
+                    .is_some()
+            })
+            .unwrap();
+        assert!(range_query.body.contains("WHERE (true) ORDER BY c.n"));
+        assert_eq!(range_query.header("x-ms-max-item-count"), Some("2"));
+    }
+
+    #[tokio::test]
+    async fn resumes_from_a_continuation() {
+        let endpoint = serve(ORDER_BY_PLAN, order_by_pages);
+        let first =
+            collect_pages(query(&endpoint, "SELECT * FROM c ORDER BY c.n").max_item_count(2))
+                .await
+                .remove(0);
+
+        let resumed = collect_pages(
+            query(&endpoint, "SELECT * FROM c ORDER BY c.n")
+                .max_item_count(2)
+                .continuation(first.1.unwrap()),
+        )
+        .await;
+
+        let items: Vec<_> = resumed.into_iter().map(|(items, _)| items).collect();
+        assert_eq!(items, [vec![3, 4], vec![5]]);
+    }
+
+    #[tokio::test]
+    async fn resumes_after_a_partition_split() {
+        let split = Arc::new(AtomicBool::new(false));
+        let endpoint = MockEndpoint::serve({
+            let split = split.clone();
+            move |request: &RecordedRequest| {
+                let split = split.load(Ordering::SeqCst);
+                let range = request.header("x-ms-documentdb-partitionkeyrangeid");
+                let continuation = request.header("x-ms-continuation");
+                Some(if request.path.ends_with("/pkranges") && split {
+                    let range = |id: &str, min: &str, max: &str, parents: &str| {
+                        format!(
+                            r#"{{"_rid":"r","id":"{id}","_etag":"e","minInclusive":"{min}","maxExclusive":"{max}","ridPrefix":0,"_self":"s","throughputFraction":1,"status":"online","parents":[{parents}],"_ts":1}}"#
+                        )
+                    };
+                    MockResponse::new(
+                        200,
+                        format!(
+                            r#"{{"_rid":"x","PartitionKeyRanges":[{},{},{}],"_count":3}}"#,
+                            range("1", "7F", "FF", ""),
+                            range("2", "", "3F", r#""0""#),
+                            range("3", "3F", "7F", r#""0""#)
+                        ),
+                    )
+                    .with_cosmos_headers()
+                } else if request.path.ends_with("/pkranges") {
+                    ranges()
+                } else if request.path == "/" {
+                    MockResponse::account()
+                } else if request
+                    .header("x-ms-cosmos-is-query-plan-request")
+                    .is_some()
+                {
+                    MockResponse::new(200, ORDER_BY_PLAN).with_cosmos_headers()
+                } else {
+                    match (split, range.unwrap(), continuation) {
+                        (true, "0", _) => MockResponse::new(
+                            410,
+                            r#"{"code":"Gone","message":"partition key range is gone"}"#,
+                        )
+                        .with_header("x-ms-substatus", "1002"),
+                        // the children resume from the position in the page of their parent
+                        (true, "2", None) => page(&[ordered(1), ordered(4)], Some("2-2")),
+                        (true, "2", Some("2-2")) => page(&[ordered(5)], None),
+                        (true, "3", None) => page(&[], None),
+                        (_, range, continuation) => order_by_pages(range, continuation),
+                    }
+                })
+            }
+        });
+        let first =
+            collect_pages(query(&endpoint, "SELECT * FROM c ORDER BY c.n").max_item_count(2))
+                .await
+                .remove(0);
+        split.store(true, Ordering::SeqCst);
+
+        let resumed = collect_pages(
+            query(&endpoint, "SELECT * FROM c ORDER BY c.n")
+                .max_item_count(2)
+                .continuation(first.1.unwrap()),
+        )
+        .await;
+
+        let items: Vec<_> = resumed.into_iter().map(|(items, _)| items).collect();
+        assert_eq!(items, [vec![3, 4], vec![5]]);
+    }
+
+    #[tokio::test]
+    async fn sends_the_conditions_to_every_range() {
+        let endpoint = serve(ORDER_BY_PLAN, order_by_pages);
+
+        collect_pages(
+            query(&endpoint, "SELECT * FROM c ORDER BY c.n")
+                .if_match_condition(IfMatchCondition::NotMatch("etag".into()))
+                .if_modified_since(Utc::now()),
+        )
+        .await;
+
+        let requests = endpoint.requests();
+        let range_requests: Vec<_> = requests
+            .iter()
+            .filter(|request| {
+                request
+                    .header("x-ms-documentdb-partitionkeyrangeid")
+                    .is_some()
+            })
+            .collect();
+        assert_eq!(range_requests.len(), 3);
+        for request in range_requests {
+            assert_eq!(request.header("if-none-match"), Some("etag"));
+            assert!(request.header("if-modified-since").is_some());
+        }
+    }
+
+    #[tokio::test]
+    async fn combines_aggregates_across_ranges() {
+        let endpoint = serve(
+            r#"{"queryInfo":{"distinctType":"None","aggregates":["Count"],"rewrittenQuery":"SELECT VALUE [{\"item\": COUNT(1)}] FROM c","hasSelectValue":true},"queryRanges":[{"min":"","max":"FF"}]}"#,
+            |range, _| match range {
+                "0" => page(&[r#"[{"item":3}]"#.to_owned()], None),
+                _ => page(&[r#"[{"item":4}]"#.to_owned()], None),
+            },
+        );
+
+        let pages: Vec<_> = query(&endpoint, "SELECT VALUE COUNT(1) FROM c")
+            .into_stream::<u64>()
+            .map(|page| page.unwrap().into_raw())
+            .collect()
+            .await;
+
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0].results, [7]);
+        assert!(pages[0].continuation_token.is_none());
+    }
+
+    #[tokio::test]
+    async fn rejects_truncated_distinct_continuations() {
+        let endpoint = serve(
+            r#"{"queryInfo":{"distinctType":"Unordered","aggregates":[],"rewrittenQuery":"","hasSelectValue":true},"queryRanges":[{"min":"","max":"FF"}]}"#,
+            |_, _| page(&[], None),
+        );
+
+        let error = query(&endpoint, "SELECT DISTINCT VALUE c.n FROM c")
+            .continuation(r#"{"ranges":[{"id":"0"}],"seen_truncated":true}"#)
+            .into_stream::<Item>()
+            .next()
+            .await
+            .unwrap()
+            .unwrap_err();
+
+        assert!(error.to_string().contains("cannot be resumed"));
+        assert!(endpoint.requests().iter().all(|request| request
+            .header("x-ms-documentdb-partitionkeyrangeid")
+            .is_none()));
+    }
+
+    #[tokio::test]
+    async fn applies_distinct_offset_and_limit_to_merged_results() {
+        let endpoint = serve(
+            r#"{"queryInfo":{"distinctType":"Unordered","offset":1,"limit":2,"aggregates":[],"rewrittenQuery":"","hasSelectValue":true},"queryRanges":[{"min":"","max":"FF"}]}"#,
+            |range, _| {
+                let numbers: &[u64] = if range == "0" { &[1, 2] } else { &[2, 3, 4] };
+                let documents: Vec<_> = numbers
+                    .iter()
+                    .map(|n| format!(r#"{{"id":"{n}","n":{n}}}"#))
+                    .collect();
+                page(&documents, None)
+            },
+        );
+
+        let pages = collect_pages(
+            query(&endpoint, "SELECT DISTINCT VALUE c FROM c OFFSET 1 LIMIT 2")
+                .parallelize_cross_partition_query(true),
+        )
+        .await;
+
+        let items: Vec<_> = pages.into_iter().flat_map(|(items, _)| items).collect();
+        assert_eq!(items, [2, 3]);
+        let range_requests = endpoint
+            .requests()
+            .iter()
+            .filter(|request| {
+                request
+                    .header("x-ms-documentdb-partitionkeyrangeid")
+                    .is_some()
+            })
+            .count();
+        assert_eq!(range_requests, 2);
+    }
+
+    #[tokio::test]
+    async fn reads_only_the_ranges_of_a_partition_key_prefix() {
+        let endpoint = serve(
+            r#"{"queryInfo":{"distinctType":"None","aggregates":[],"rewrittenQuery":"","hasSelectValue":false},"queryRanges":[{"min":"","max":"FF"}]}"#,
+            |_, _| page(&[r#"{"id":"1","n":1}"#.to_owned()], None),
+        );
+
+        let builder = endpoint
+            .cosmos_client()
+            .database_client("db")
+            .collection_client("coll")
+            .query_documents("SELECT * FROM c")
+            .partition_key_prefix(&("tenant",))
+            .unwrap();
+        let items: Vec<_> = collect_pages(builder)
+            .await
+            .into_iter()
+            .flat_map(|(items, _)| items)
+            .collect();
+        assert_eq!(items, [1]);
+
+        let (start, end) = effective_partition_key_range(r#"["tenant"]"#).unwrap();
+        let range = if start.as_str() < "7F" { "0" } else { "1" };
+        let requests = endpoint.requests();
+        let range_requests: Vec<_> = requests
+            .iter()
+            .filter(|request| {
+                request
+                    .header("x-ms-documentdb-partitionkeyrangeid")
+                    .is_some()
+            })
+            .collect();
+        assert_eq!(range_requests.len(), 1);
+        let request = range_requests[0];
+        assert_eq!(
+            request.header("x-ms-documentdb-partitionkeyrangeid"),
+            Some(range)
+        );
+        assert_eq!(request.header("x-ms-start-epk"), Some(start.as_str()));
+        assert_eq!(request.header("x-ms-end-epk"), Some(end.as_str()));
+        assert_eq!(request.header("x-ms-documentdb-partitionkey"), None);
+    }
+
+    #[tokio::test]
+    async fn returns_query_metrics_and_index_utilization() {
+        let utilization = base64::encode(
+            r#"{"UtilizedSingleIndexes":[{"FilterExpression":"","IndexSpec":"/n/?","FilterPreciseSet":true,"IndexPreciseSet":true,"IndexImpactScore":"High"}],"PotentialSingleIndexes":[],"UtilizedCompositeIndexes":[],"PotentialCompositeIndexes":[]}"#,
+        );
+        let endpoint = MockEndpoint::start(vec![page(&[r#"{"id":"1","n":1}"#.to_owned()], None)
+            .with_header(
+                "x-ms-documentdb-query-metrics",
+                "totalExecutionTimeInMs=1.50;retrievedDocumentCount=1;indexUtilizationRatio=1.00",
+            )
+            .with_header("x-ms-cosmos-index-utilization", &utilization)]);
+
+        let pages = endpoint
+            .cosmos_client()
+            .database_client("db")
+            .collection_client("coll")
+            .query_documents("SELECT * FROM c WHERE c.n = 1")
+            .partition_key(&"pk")
+            .unwrap()
+            .populate_query_metrics(true)
+            .populate_index_metrics(true)
+            .into_stream::<Item>()
+            .collect::<Vec<_>>()
+            .await;
+        let page = pages.into_iter().next().unwrap().unwrap();
+
+        let metrics = page.query_metrics.unwrap();
+        assert_eq!(
+            metrics.total_execution_time,
+            std::time::Duration::from_micros(1500)
+        );
+        assert_eq!(metrics.retrieved_document_count, 1);
+        assert_eq!(
+            page.index_utilization.unwrap().utilized_single_indexes[0].index_spec,
+            "/n/?"
+        );
+        let request = &endpoint.requests()[0];
+        assert_eq!(
+            request.header("x-ms-documentdb-populatequerymetrics"),
+            Some("true")
+        );
+        assert_eq!(
+            request.header("x-ms-cosmos-populateindexmetrics"),
+            Some("true")
+        );
+    }
+
+    #[tokio::test]
+    async fn ignores_malformed_query_metrics_and_index_utilization() {
+        let malformed = |response: MockResponse| {
+            response
+                .with_header("x-ms-documentdb-query-metrics", "totalExecutionTimeInMs")
+                .with_header("x-ms-cosmos-index-utilization", "not base64!")
+        };
+        let endpoint = serve(ORDER_BY_PLAN, |range, continuation| {
+            order_by_pages(range, continuation)
+                .with_header("x-ms-documentdb-query-metrics", "totalExecutionTimeInMs")
+                .with_header("x-ms-cosmos-index-utilization", "not base64!")
+        });
+        let single = MockEndpoint::start(vec![malformed(page(
+            &[r#"{"id":"1","n":1}"#.to_owned()],
+            None,
+        ))]);
+
+        let pages = query(&endpoint, "SELECT * FROM c ORDER BY c.n")
+            .populate_query_metrics(true)
+            .populate_index_metrics(true)
+            .into_stream::<Item>()
+            .collect::<Vec<_>>()
+            .await;
+        let page = pages.into_iter().next().unwrap().unwrap();
+        assert!(page.query_metrics.is_none());
+        assert!(page.index_utilization.is_none());
+
+        let pages = single
+            .cosmos_client()
+            .database_client("db")
+            .collection_client("coll")
+            .query_documents("SELECT * FROM c WHERE c.n = 1")
+            .partition_key(&"pk")
+            .unwrap()
+            .populate_query_metrics(true)
+            .populate_index_metrics(true)
+            .into_stream::<Item>()
+            .collect::<Vec<_>>()
+            .await;
+        let page = pages.into_iter().next().unwrap().unwrap();
+        assert_eq!(page.results.len(), 1);
+        assert!(page.query_metrics.is_none());
+        assert!(page.index_utilization.is_none());
+    }
+
+    #[tokio::test]
+    async fn adds_the_query_metrics_of_every_range() {
+        let endpoint = serve(ORDER_BY_PLAN, |range, continuation| {
+            order_by_pages(range, continuation).with_header(
+                "x-ms-documentdb-query-metrics",
+                "retrievedDocumentCount=2;outputDocumentCount=2",
+            )
+        });
+
+        let pages: Vec<_> = query(&endpoint, "SELECT * FROM c ORDER BY c.n")
+            .populate_query_metrics(true)
+            .into_stream::<Item>()
+            .map(|page| page.unwrap())
+            .collect()
+            .await;
+
+        // both ranges are read for the first page, and range 0 again for the second
+        let counts: Vec<_> = pages
+            .iter()
+            .map(|page| {
+                page.query_metrics
+                    .as_ref()
+                    .unwrap()
+                    .retrieved_document_count
+            })
+            .collect();
+        assert_eq!(counts, [4, 2]);
+        assert!(pages.iter().all(|page| page.index_utilization.is_none()));
+        assert!(endpoint
+            .requests()
+            .iter()
+            .filter(|request| request
+                .header("x-ms-documentdb-partitionkeyrangeid")
+                .is_some())
+            .all(|request| request
+                .header("x-ms-documentdb-populatequerymetrics")
+                .is_some()));
+    }
+}
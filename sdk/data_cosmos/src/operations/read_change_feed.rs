@@ -0,0 +1,620 @@
+use crate::headers::from_headers::*;
+use crate::operations::{ListDocumentsResponseAttributes, ListDocumentsResponseEntities};
+use crate::prelude::*;
+use crate::resources::ResourceType;
+use crate::CosmosDiagnostics;
+use azure_core::error::{Error, ErrorKind, ResultExt};
+use azure_core::headers::etag_from_headers_optional;
+use azure_core::{collect_pinned_stream, prelude::*, Pageable, Response};
+use chrono::{DateTime, TimeZone, Utc};
+use http::StatusCode;
+use serde::de::DeserializeOwned;
+
+/// Where a change feed starts reading.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChangeFeedStartFrom {
+    /// Read every change since the container was created.
+    Beginning,
+    /// Only read the changes made from now on.
+    Now,
+    /// Read the changes made since the given time.
+    PointInTime(DateTime<Utc>),
+    /// Continue where a previous read stopped.
+    Continuation(ChangeFeedContinuation),
+}
+
+/// The position of a change feed reader in a partition key range.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChangeFeedRange {
+    /// The id of the partition key range.
+    pub partition_key_range_id: String,
+    /// The etag of the last change read, `None` if nothing was read from the range yet.
+    pub etag: Option<String>,
+}
+
+/// The position of a change feed reader in every partition key range it reads.
+///
+/// Persist it with [`ToString`] and restore it with [`std::str::FromStr`] to resume reading
+/// where a previous reader stopped.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChangeFeedContinuation {
+    ranges: Vec<ChangeFeedRange>,
+    /// The start time, in seconds since the epoch, of the ranges nothing was read from yet
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    start_time: Option<i64>,
+}
+
+impl ChangeFeedContinuation {
+    /// The position in each partition key range.
+    pub fn ranges(&self) -> &[ChangeFeedRange] {
+        &self.ranges
+    }
+
+    /// The time the ranges nothing was read from yet start reading at.
+    pub fn start_time(&self) -> Option<DateTime<Utc>> {
+        self.start_time
+            .and_then(|start_time| Utc.timestamp_opt(start_time, 0).single())
+    }
+
+    pub(crate) fn new(ranges: Vec<ChangeFeedRange>, start_time: Option<DateTime<Utc>>) -> Self {
+        Self {
+            ranges,
+            start_time: start_time.map(|start_time| start_time.timestamp()),
+        }
+    }
+
+    /// Replaces a range that was split with the ranges it was split into.
+    ///
+    /// The new ranges continue from the etag of the split range.
+    pub(crate) fn split(&mut self, index: usize, children: Vec<String>) {
+        let etag = self.ranges[index].etag.clone();
+        self.ranges.splice(
+            index..=index,
+            children
+                .into_iter()
+                .map(|partition_key_range_id| ChangeFeedRange {
+                    partition_key_range_id,
+                    etag: etag.clone(),
+                }),
+        );
+    }
+}
+
+impl std::fmt::Display for ChangeFeedContinuation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&serde_json::to_string(self).map_err(|_| std::fmt::Error)?)
+    }
+}
+
+impl std::str::FromStr for ChangeFeedContinuation {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s).with_context(ErrorKind::DataConversion, || {
+            format!("invalid change feed continuation: {}", s)
+        })
+    }
+}
+
+/// The state carried between the pages of a change feed stream.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PageState {
+    continuation: ChangeFeedContinuation,
+    /// The index of the range to read next in this pass over the ranges
+    next: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReadChangeFeedBuilder {
+    client: CollectionClient,
+    start_from: ChangeFeedStartFrom,
+    partition_key_range_id: Option<String>,
+    consistency_level: Option<ConsistencyLevel>,
+    max_item_count: MaxItemCount,
+    context: Context,
+}
+
+impl ReadChangeFeedBuilder {
+    pub(crate) fn new(client: CollectionClient) -> Self {
+        Self {
+            client,
+            start_from: ChangeFeedStartFrom::Beginning,
+            partition_key_range_id: None,
+            consistency_level: None,
+            max_item_count: MaxItemCount::new(-1),
+            context: Context::new(),
+        }
+    }
+
+    setters! {
+        start_from: ChangeFeedStartFrom => start_from,
+        partition_key_range_id: String => Some(partition_key_range_id),
+        consistency_level: ConsistencyLevel => Some(consistency_level),
+        max_item_count: i32 => MaxItemCount::new(max_item_count),
+        context: Context => context,
+    }
+
+    /// Reads the changes of every partition key range, or only of the range set with
+    /// `partition_key_range_id`, until all of them are caught up.
+    ///
+    /// Every page holds the changes of a single range. The stream ends once every range
+    /// reported that it has no more changes; the continuation of the last page can then be
+    /// used to read the changes made later.
+    pub fn into_stream<T>(self) -> ReadChangeFeed<T>
+    where
+        T: DeserializeOwned + Send + Sync,
+    {
+        let make_request = move |continuation: Option<Continuation>| {
+            let this = self.clone();
+            async move {
+                let state = match continuation {
+                    Some(continuation) => serde_json::from_str(&continuation.into_raw())?,
+                    None => this.initial_state().await?,
+                };
+                this.read_page(state).await
+            }
+        };
+
+        Pageable::new(make_request)
+    }
+
+    async fn initial_state(&self) -> azure_core::error::Result<PageState> {
+        let (etag, start_time) = match &self.start_from {
+            ChangeFeedStartFrom::Continuation(continuation) => {
+                return Ok(PageState {
+                    continuation: continuation.clone(),
+                    next: 0,
+                })
+            }
+            ChangeFeedStartFrom::Beginning => (None, None),
+            ChangeFeedStartFrom::Now => (Some("*".to_owned()), None),
+            ChangeFeedStartFrom::PointInTime(start_time) => (None, Some(*start_time)),
+        };
+
+        let range_ids = match &self.partition_key_range_id {
+            Some(partition_key_range_id) => vec![partition_key_range_id.clone()],
+            None => self
+                .client
+                .get_partition_key_ranges()
+                .context(self.context.clone())
+                .into_future()
+                .await?
+                .partition_key_ranges
+                .into_iter()
+                .map(|range| range.id)
+                .collect(),
+        };
+        let ranges = range_ids
+            .into_iter()
+            .map(|partition_key_range_id| ChangeFeedRange {
+                partition_key_range_id,
+                etag: etag.clone(),
+            })
+            .collect();
+
+        Ok(PageState {
+            continuation: ChangeFeedContinuation::new(ranges, start_time),
+            next: 0,
+        })
+    }
+
+    async fn read_page<T>(
+        &self,
+        mut state: PageState,
+    ) -> azure_core::error::Result<ReadChangeFeedResponse<T>>
+    where
+        T: DeserializeOwned,
+    {
+        let range = match state.continuation.ranges.get(state.next) {
+            Some(range) => range.clone(),
+            None => {
+                return Ok(ReadChangeFeedResponse::empty(
+                    state,
+                    None,
+                    CosmosDiagnostics::default(),
+                ))
+            }
+        };
+
+        let mut request = self.client.prepare_doc_request_pipeline(http::Method::GET);
+        request.insert_headers(&ChangeFeed::Incremental);
+        request.insert_headers(&PartitionRangeId::new(range.partition_key_range_id.clone()));
+        request.insert_headers(&self.max_item_count);
+        if let Some(cl) = &self.consistency_level {
+            request.insert_headers(cl);
+        }
+        match (&range.etag, state.continuation.start_time()) {
+            (Some(etag), _) => request.insert_headers(&IfMatchCondition::NotMatch(etag.clone())),
+            (None, Some(start_time)) => request.insert_headers(&IfModifiedSince::new(start_time)),
+            (None, None) => {}
+        }
+
+        let response = crate::diagnostics::send(
+            self.client.pipeline(),
+            self.context.clone().insert(ResourceType::Documents),
+            &mut request,
+        )
+        .await;
+
+        match response {
+            Ok((response, diagnostics)) => {
+                ReadChangeFeedResponse::try_from(response, state, diagnostics).await
+            }
+            Err(error) if is_gone(&error) => {
+                // the range was split, continue with the ranges it was split into
+                let ranges = self
+                    .client
+                    .get_partition_key_ranges()
+                    .context(self.context.clone())
+                    .into_future()
+                    .await?;
+                let mut diagnostics = CosmosDiagnostics::from_error(&error)
+                    .cloned()
+                    .unwrap_or_default();
+                diagnostics.merge(ranges.diagnostics);
+                let children: Vec<String> = ranges
+                    .partition_key_ranges
+                    .into_iter()
+                    .filter(|child| child.parents.contains(&range.partition_key_range_id))
+                    .map(|child| child.id)
+                    .collect();
+                if children.is_empty() {
+                    return Err(error);
+                }
+                debug!(
+                    "partition key range {} was split into {:?}",
+                    range.partition_key_range_id, children
+                );
+                state.continuation.split(state.next, children);
+                let range_id = range.partition_key_range_id;
+                Ok(ReadChangeFeedResponse::empty(
+                    state,
+                    Some(range_id),
+                    diagnostics,
+                ))
+            }
+            Err(error) => Err(error),
+        }
+    }
+}
+
+fn is_gone(error: &Error) -> bool {
+    matches!(error.kind(), ErrorKind::HttpResponse { status, .. } if *status == StatusCode::GONE.as_u16())
+}
+
+pub type ReadChangeFeed<T> = Pageable<ReadChangeFeedResponse<T>, azure_core::error::Error>;
+
+#[derive(Debug, Clone)]
+pub struct ReadChangeFeedResponse<T> {
+    /// The changed documents, in the order they were changed
+    pub documents: Vec<Document<T>>,
+    /// The partition key range the documents were read from
+    pub partition_key_range_id: Option<String>,
+    pub charge: f64,
+    pub activity_id: Option<uuid::Uuid>,
+    pub session_token: Option<String>,
+    /// The position of the reader after this page
+    pub continuation: ChangeFeedContinuation,
+    pub diagnostics: CosmosDiagnostics,
+    next_page: Option<PageState>,
+}
+
+impl<T> ReadChangeFeedResponse<T>
+where
+    T: DeserializeOwned,
+{
+    async fn try_from(
+        response: Response,
+        mut state: PageState,
+        diagnostics: CosmosDiagnostics,
+    ) -> azure_core::error::Result<Self> {
+        let (status_code, headers, pinned_stream) = response.deconstruct();
+        let body = collect_pinned_stream(pinned_stream).await?;
+        let headers = &headers;
+
+        let documents = if status_code == StatusCode::NOT_MODIFIED || body.is_empty() {
+            Vec::new()
+        } else {
+            let attributes: ListDocumentsResponseAttributes = serde_json::from_slice(&body)?;
+            let entries: ListDocumentsResponseEntities<T> = serde_json::from_slice(&body)?;
+            attributes
+                .documents
+                .into_iter()
+                .zip(entries.entities)
+                .map(|(document_attributes, document)| Document {
+                    document_attributes,
+                    document,
+                })
+                .collect()
+        };
+
+        let range = &mut state.continuation.ranges[state.next];
+        let partition_key_range_id = range.partition_key_range_id.clone();
+        if let Some(etag) = etag_from_headers_optional(headers)? {
+            range.etag = Some(etag);
+        }
+        // a range is caught up once it reports that nothing changed
+        if status_code == StatusCode::NOT_MODIFIED {
+            state.next += 1;
+        }
+
+        Ok(Self {
+            documents,
+            partition_key_range_id: Some(partition_key_range_id),
+            charge: request_charge_from_headers(headers).unwrap_or_default(),
+            activity_id: activity_id_from_headers(headers).ok(),
+            session_token: azure_core::headers::session_token_from_headers(headers).ok(),
+            continuation: state.continuation.clone(),
+            diagnostics,
+            next_page: (state.next < state.continuation.ranges.len()).then_some(state),
+        })
+    }
+}
+
+impl<T> ReadChangeFeedResponse<T> {
+    fn empty(
+        state: PageState,
+        partition_key_range_id: Option<String>,
+        diagnostics: CosmosDiagnostics,
+    ) -> Self {
+        Self {
+            documents: Vec::new(),
+            partition_key_range_id,
+            charge: 0.0,
+            activity_id: None,
+            session_token: None,
+            continuation: state.continuation.clone(),
+            diagnostics,
+            next_page: (state.next < state.continuation.ranges.len()).then_some(state),
+        }
+    }
+}
+
+impl<T> Continuable for ReadChangeFeedResponse<T> {
+    fn continuation(&self) -> Option<String> {
+        self.next_page
+            .as_ref()
+            .and_then(|state| serde_json::to_string(state).ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_endpoint::{MockEndpoint, MockResponse};
+    use futures::StreamExt;
+
+    #[allow(dead_code)]
+    fn ensure_send() {
+        fn is_send<T: Send>() {}
+        is_send::<ReadChangeFeed<()>>();
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct MyDocument {
+        id: String,
+    }
+
+    const RANGES: &str = r#"{"_rid":"3iNTAJKxVCk=","PartitionKeyRanges":[
+        {"_rid":"3iNTAJKxVCkCAAAAAAAAUA==","id":"0","_etag":"\"0\"","minInclusive":"","maxExclusive":"80","ridPrefix":0,"_self":"a","throughputFraction":1,"status":"online","parents":[],"_ts":1},
+        {"_rid":"3iNTAJKxVCkCAAAAAAAAUB==","id":"1","_etag":"\"1\"","minInclusive":"80","maxExclusive":"FF","ridPrefix":1,"_self":"b","throughputFraction":1,"status":"online","parents":[],"_ts":1}],"_count":2}"#;
+
+    const SPLIT_RANGES: &str = r#"{"_rid":"3iNTAJKxVCk=","PartitionKeyRanges":[
+        {"_rid":"3iNTAJKxVCkCAAAAAAAAUC==","id":"2","_etag":"\"2\"","minInclusive":"","maxExclusive":"40","ridPrefix":2,"_self":"c","throughputFraction":1,"status":"online","parents":["0"],"_ts":2},
+        {"_rid":"3iNTAJKxVCkCAAAAAAAAUD==","id":"3","_etag":"\"3\"","minInclusive":"40","maxExclusive":"80","ridPrefix":3,"_self":"d","throughputFraction":1,"status":"online","parents":["0"],"_ts":2},
+        {"_rid":"3iNTAJKxVCkCAAAAAAAAUB==","id":"1","_etag":"\"1\"","minInclusive":"80","maxExclusive":"FF","ridPrefix":1,"_self":"b","throughputFraction":1,"status":"online","parents":[],"_ts":1}],"_count":3}"#;
+
+    fn ranges_response(body: &str) -> MockResponse {
+        MockResponse::new(200, body).with_cosmos_headers()
+    }
+
+    fn changes(etag: &str, ids: &[&str]) -> MockResponse {
+        let documents: Vec<_> = ids
+            .iter()
+            .map(|id| {
+                format!(
+                    r#"{{"id":"{id}","_rid":"r{id}","_self":"s{id}","_etag":"e{id}","_attachments":"attachments/","_ts":1}}"#
+                )
+            })
+            .collect();
+        MockResponse::new(
+            200,
+            format!(
+                r#"{{"_rid":"3iNTAJKxVCk=","Documents":[{}],"_count":{}}}"#,
+                documents.join(","),
+                ids.len()
+            ),
+        )
+        .with_header("etag", etag)
+        .with_header("x-ms-request-charge", "2.5")
+    }
+
+    fn not_modified(etag: &str) -> MockResponse {
+        MockResponse::new(304, "")
+            .with_header("etag", etag)
+            .with_header("x-ms-request-charge", "1")
+    }
+
+    fn ids(pages: &[ReadChangeFeedResponse<MyDocument>]) -> Vec<String> {
+        pages
+            .iter()
+            .flat_map(|page| page.documents.iter().map(|d| d.document.id.clone()))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn reads_every_range_until_caught_up() {
+        let endpoint = MockEndpoint::start(vec![
+            ranges_response(RANGES),
+            changes("\"10\"", &["a", "b"]),
+            not_modified("\"10\""),
+            changes("\"20\"", &["c"]),
+            not_modified("\"20\""),
+        ]);
+        let collection = endpoint
+            .cosmos_client()
+            .database_client("db")
+            .collection_client("coll");
+
+        let pages: Vec<_> = collection
+            .read_change_feed()
+            .into_stream::<MyDocument>()
+            .map(|page| page.unwrap())
+            .collect()
+            .await;
+
+        assert_eq!(ids(&pages), ["a", "b", "c"]);
+        let continuation = &pages.last().unwrap().continuation;
+        assert_eq!(
+            continuation.ranges(),
+            [
+                ChangeFeedRange {
+                    partition_key_range_id: "0".to_owned(),
+                    etag: Some("\"10\"".to_owned())
+                },
+                ChangeFeedRange {
+                    partition_key_range_id: "1".to_owned(),
+                    etag: Some("\"20\"".to_owned())
+                }
+            ]
+        );
+        assert_eq!(
+            continuation
+                .to_string()
+                .parse::<ChangeFeedContinuation>()
+                .unwrap(),
+            *continuation
+        );
+
+        let requests = endpoint.requests();
+        assert_eq!(requests[0].path, "/dbs/db/colls/coll/pkranges");
+        assert_eq!(requests[1].path, "/dbs/db/colls/coll/docs");
+        assert_eq!(requests[1].header("a-im"), Some("Incremental feed"));
+        assert_eq!(
+            requests[1].header("x-ms-documentdb-partitionkeyrangeid"),
+            Some("0")
+        );
+        assert_eq!(requests[1].header("if-none-match"), None);
+        assert_eq!(requests[2].header("if-none-match"), Some("\"10\""));
+        assert_eq!(
+            requests[3].header("x-ms-documentdb-partitionkeyrangeid"),
+            Some("1")
+        );
+    }
+
+    #[tokio::test]
+    async fn resumes_from_continuation() {
+        let endpoint = MockEndpoint::start(vec![changes("\"11\"", &["d"]), not_modified("\"11\"")]);
+        let collection = endpoint
+            .cosmos_client()
+            .database_client("db")
+            .collection_client("coll");
+        let continuation = ChangeFeedContinuation::new(
+            vec![ChangeFeedRange {
+                partition_key_range_id: "0".to_owned(),
+                etag: Some("\"10\"".to_owned()),
+            }],
+            None,
+        );
+
+        let pages: Vec<_> = collection
+            .read_change_feed()
+            .start_from(ChangeFeedStartFrom::Continuation(continuation))
+            .into_stream::<MyDocument>()
+            .map(|page| page.unwrap())
+            .collect()
+            .await;
+
+        assert_eq!(ids(&pages), ["d"]);
+        assert_eq!(
+            endpoint.requests()[0].header("if-none-match"),
+            Some("\"10\"")
+        );
+    }
+
+    #[tokio::test]
+    async fn starts_from_now_or_point_in_time() {
+        let endpoint = MockEndpoint::start(vec![not_modified("\"30\""), not_modified("\"5\"")]);
+        let collection = endpoint
+            .cosmos_client()
+            .database_client("db")
+            .collection_client("coll");
+
+        let pages: Vec<_> = collection
+            .read_change_feed()
+            .partition_key_range_id("0")
+            .start_from(ChangeFeedStartFrom::Now)
+            .into_stream::<MyDocument>()
+            .map(|page| page.unwrap())
+            .collect()
+            .await;
+        assert_eq!(
+            pages[0].continuation.ranges()[0].etag.as_deref(),
+            Some("\"30\"")
+        );
+
+        let start_time = Utc.with_ymd_and_hms(2022, 3, 25, 8, 0, 0).unwrap();
+        let _: Vec<_> = collection
+            .read_change_feed()
+            .partition_key_range_id("0")
+            .start_from(ChangeFeedStartFrom::PointInTime(start_time))
+            .into_stream::<MyDocument>()
+            .collect()
+            .await;
+
+        let requests = endpoint.requests();
+        assert_eq!(requests[0].header("if-none-match"), Some("*"));
+        assert_eq!(
+            requests[1].header("if-modified-since"),
+            Some(start_time.to_rfc2822().as_str())
+        );
+    }
+
+    #[tokio::test]
+    async fn follows_partition_splits() {
+        let endpoint = MockEndpoint::start(vec![
+            MockResponse::new(
+                410,
+                r#"{"code":"Gone","message":"partition key range is gone"}"#,
+            )
+            .with_header("x-ms-substatus", "1002"),
+            ranges_response(SPLIT_RANGES),
+            changes("\"12\"", &["e"]),
+            not_modified("\"12\""),
+            not_modified("\"10\""),
+        ]);
+        let collection = endpoint
+            .cosmos_client()
+            .database_client("db")
+            .collection_client("coll");
+        let continuation = ChangeFeedContinuation::new(
+            vec![ChangeFeedRange {
+                partition_key_range_id: "0".to_owned(),
+                etag: Some("\"10\"".to_owned()),
+            }],
+            None,
+        );
+
+        let pages: Vec<_> = collection
+            .read_change_feed()
+            .start_from(ChangeFeedStartFrom::Continuation(continuation))
+            .into_stream::<MyDocument>()
+            .map(|page| page.unwrap())
+            .collect()
+            .await;
+
+        assert_eq!(ids(&pages), ["e"]);
+        let ranges = pages.last().unwrap().continuation.ranges().to_vec();
+        assert_eq!(ranges.len(), 2);
+        assert_eq!(ranges[0].partition_key_range_id, "2");
+        assert_eq!(ranges[1].partition_key_range_id, "3");
+        assert_eq!(ranges[1].etag.as_deref(), Some("\"10\""));
+
+        let requests = endpoint.requests();
+        assert_eq!(requests[2].header("if-none-match"), Some("\"10\""));
+        assert_eq!(
+            requests[4].header("x-ms-documentdb-partitionkeyrangeid"),
+            Some("3")
+        );
+    }
+}
@@ -1,6 +1,9 @@
 use crate::headers::from_headers::*;
 use crate::prelude::*;
-use crate::resources::collection::{IndexingPolicy, PartitionKey};
+use crate::resources::collection::{
+    ChangeFeedPolicy, ConflictResolutionPolicy, IndexingPolicy, PartitionKey, UniqueKeyPolicy,
+};
+use crate::CosmosDiagnostics;
 use azure_core::headers::{
     content_type_from_headers, etag_from_headers, session_token_from_headers,
 };
@@ -13,6 +16,11 @@ pub struct ReplaceCollectionBuilder {
     partition_key: PartitionKey,
     consistency_level: Option<ConsistencyLevel>,
     indexing_policy: Option<IndexingPolicy>,
+    conflict_resolution_policy: Option<ConflictResolutionPolicy>,
+    unique_key_policy: Option<UniqueKeyPolicy>,
+    default_ttl: Option<i64>,
+    analytical_storage_ttl: Option<i64>,
+    change_feed_policy: Option<ChangeFeedPolicy>,
     context: Context,
 }
 
@@ -23,6 +31,11 @@ impl ReplaceCollectionBuilder {
             partition_key,
             consistency_level: None,
             indexing_policy: None,
+            conflict_resolution_policy: None,
+            unique_key_policy: None,
+            default_ttl: None,
+            analytical_storage_ttl: None,
+            change_feed_policy: None,
             context: Context::new(),
         }
     }
@@ -30,11 +43,17 @@ impl ReplaceCollectionBuilder {
     setters! {
         consistency_level: ConsistencyLevel => Some(consistency_level),
         indexing_policy: IndexingPolicy => Some(indexing_policy),
+        conflict_resolution_policy: ConflictResolutionPolicy => Some(conflict_resolution_policy),
+        unique_key_policy: UniqueKeyPolicy => Some(unique_key_policy),
+        default_ttl: i64 => Some(default_ttl),
+        analytical_storage_ttl: i64 => Some(analytical_storage_ttl),
+        change_feed_policy: ChangeFeedPolicy => Some(change_feed_policy),
         context: Context => context,
     }
 
     pub fn into_future(self) -> ReplaceCollection {
         Box::pin(async move {
+            self.partition_key.validate()?;
             let mut request = self
                 .client
                 .prepare_request_with_collection_name(http::Method::PUT);
@@ -46,21 +65,24 @@ impl ReplaceCollectionBuilder {
             let collection = ReplaceCollectionBody {
                 id: self.client.collection_name(),
                 indexing_policy: &self.indexing_policy,
+                conflict_resolution_policy: &self.conflict_resolution_policy,
+                unique_key_policy: &self.unique_key_policy,
+                default_ttl: self.default_ttl,
+                analytical_storage_ttl: self.analytical_storage_ttl,
+                change_feed_policy: &self.change_feed_policy,
                 partition_key: &self.partition_key,
             };
 
             request.set_body(serde_json::to_vec(&collection)?);
 
-            let response = self
-                .client
-                .pipeline()
-                .send(
-                    self.context.clone().insert(ResourceType::Collections),
-                    &mut request,
-                )
-                .await?;
+            let (response, diagnostics) = crate::diagnostics::send(
+                self.client.pipeline(),
+                self.context.clone().insert(ResourceType::Collections),
+                &mut request,
+            )
+            .await?;
 
-            ReplaceCollectionResponse::try_from(response).await
+            ReplaceCollectionResponse::try_from_with_diagnostics(response, diagnostics).await
         })
     }
 }
@@ -83,6 +105,22 @@ struct ReplaceCollectionBody<'a> {
     pub id: &'a str,
     #[serde(rename = "indexingPolicy", skip_serializing_if = "Option::is_none")]
     pub indexing_policy: &'a Option<IndexingPolicy>,
+    #[serde(
+        rename = "conflictResolutionPolicy",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub conflict_resolution_policy: &'a Option<ConflictResolutionPolicy>,
+    #[serde(rename = "uniqueKeyPolicy", skip_serializing_if = "Option::is_none")]
+    pub unique_key_policy: &'a Option<UniqueKeyPolicy>,
+    #[serde(rename = "defaultTtl", skip_serializing_if = "Option::is_none")]
+    pub default_ttl: Option<i64>,
+    #[serde(
+        rename = "analyticalStorageTtl",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub analytical_storage_ttl: Option<i64>,
+    #[serde(rename = "changeFeedPolicy", skip_serializing_if = "Option::is_none")]
+    pub change_feed_policy: &'a Option<ChangeFeedPolicy>,
     #[serde(rename = "partitionKey")]
     pub partition_key: &'a PartitionKey,
 }
@@ -115,13 +153,22 @@ pub struct ReplaceCollectionResponse {
     pub content_location: String,
     pub activity_id: uuid::Uuid,
     pub schema_version: String,
+    pub diagnostics: CosmosDiagnostics,
 }
 
 impl ReplaceCollectionResponse {
     pub async fn try_from(response: HttpResponse) -> azure_core::error::Result<Self> {
+        Self::try_from_with_diagnostics(response, CosmosDiagnostics::default()).await
+    }
+
+    pub(crate) async fn try_from_with_diagnostics(
+        response: HttpResponse,
+        diagnostics: CosmosDiagnostics,
+    ) -> azure_core::error::Result<Self> {
         let (_status_code, headers, pinned_stream) = response.deconstruct();
         let body = collect_pinned_stream(pinned_stream).await?;
         Ok(Self {
+            diagnostics,
             collection: serde_json::from_slice(&body)?,
             last_state_change: last_state_change_from_headers(&headers)?,
             etag: etag_from_headers(&headers)?,
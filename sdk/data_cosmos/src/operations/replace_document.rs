@@ -2,6 +2,7 @@ use crate::cosmos_entity::{add_as_partition_key_header_serialized2, serialize_pa
 use crate::headers::from_headers::*;
 use crate::prelude::*;
 use crate::resources::document::DocumentAttributes;
+use crate::CosmosDiagnostics;
 use crate::ResourceQuota;
 
 use azure_core::headers::session_token_from_headers;
@@ -79,17 +80,14 @@ impl<D: Serialize + Send + 'static> ReplaceDocumentBuilder<D> {
             let serialized = azure_core::to_json(&self.document)?;
             request.set_body(serialized);
 
-            let response = self
-                .client
-                .cosmos_client()
-                .pipeline()
-                .send(
-                    self.context.clone().insert(ResourceType::Documents),
-                    &mut request,
-                )
-                .await?;
-
-            ReplaceDocumentResponse::try_from(response).await
+            let (response, diagnostics) = crate::diagnostics::send(
+                self.client.cosmos_client().pipeline(),
+                self.context.clone().insert(ResourceType::Documents),
+                &mut request,
+            )
+            .await?;
+
+            ReplaceDocumentResponse::try_from_with_diagnostics(response, diagnostics).await
         })
     }
 }
@@ -133,15 +131,24 @@ pub struct ReplaceDocumentResponse {
     pub activity_id: uuid::Uuid,
     pub gateway_version: String,
     pub date: DateTime<Utc>,
+    pub diagnostics: CosmosDiagnostics,
 }
 
 impl ReplaceDocumentResponse {
     pub async fn try_from(response: HttpResponse) -> azure_core::error::Result<Self> {
+        Self::try_from_with_diagnostics(response, CosmosDiagnostics::default()).await
+    }
+
+    pub(crate) async fn try_from_with_diagnostics(
+        response: HttpResponse,
+        diagnostics: CosmosDiagnostics,
+    ) -> azure_core::error::Result<Self> {
         let (_status_code, headers, pinned_stream) = response.deconstruct();
         let body = collect_pinned_stream(pinned_stream).await?;
         let document_attributes = serde_json::from_slice(&*body)?;
 
         Ok(Self {
+            diagnostics,
             content_location: content_location_from_headers(&headers)?.to_owned(),
             last_state_change: last_state_change_from_headers(&headers)?,
             resource_quota: resource_quota_from_headers(&headers)?,
@@ -0,0 +1,332 @@
+use super::get_offer::OfferTarget;
+use crate::headers::from_headers::*;
+use crate::headers::{
+    HEADER_MIGRATE_OFFER_TO_AUTOPILOT, HEADER_MIGRATE_OFFER_TO_MANUAL_THROUGHPUT,
+};
+use crate::prelude::*;
+use crate::resources::ThroughputOffer;
+use crate::CosmosDiagnostics;
+
+use azure_core::{collect_pinned_stream, Context};
+use std::time::{Duration, Instant};
+
+/// How long to wait for a pending throughput change by default
+const DEFAULT_COMPLETION_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+#[derive(Debug, Clone)]
+pub struct ReplaceOfferBuilder {
+    target: OfferTarget,
+    throughput: Throughput,
+    wait_for_completion: Option<Duration>,
+    completion_timeout: Duration,
+    context: Context,
+}
+
+impl ReplaceOfferBuilder {
+    pub(crate) fn new(target: OfferTarget, throughput: Throughput) -> Self {
+        Self {
+            target,
+            throughput,
+            wait_for_completion: None,
+            completion_timeout: DEFAULT_COMPLETION_TIMEOUT,
+            context: Context::new(),
+        }
+    }
+
+    setters! {
+        wait_for_completion: Duration => Some(wait_for_completion),
+        completion_timeout: Duration => completion_timeout,
+        context: Context => context,
+    }
+
+    /// Reads the current offer and replaces its throughput.
+    ///
+    /// Switching between manual and autoscale throughput first migrates the offer to the other
+    /// mode, then sets the requested throughput.
+    ///
+    /// Scaling beyond what the current partitions support completes asynchronously and the
+    /// response reports it as pending. When `wait_for_completion` is set, the offer is read again
+    /// at that interval until the change is applied or `completion_timeout` has passed, after
+    /// which the response is returned still pending.
+    pub fn into_future(self) -> ReplaceOffer {
+        Box::pin(async move {
+            let (mut offer, _, mut diagnostics) = self.target.read(&self.context).await?;
+            let mut charge = 0.0;
+
+            let migrate = match (offer.throughput(), self.throughput) {
+                (Throughput::Manual(_), Throughput::Autoscale { .. }) => {
+                    Some(HEADER_MIGRATE_OFFER_TO_AUTOPILOT)
+                }
+                (Throughput::Autoscale { .. }, Throughput::Manual(_)) => {
+                    Some(HEADER_MIGRATE_OFFER_TO_MANUAL_THROUGHPUT)
+                }
+                _ => None,
+            };
+            if let Some(migrate) = migrate {
+                let migrated = self.replace(&offer, Some(migrate)).await?;
+                offer = migrated.offer;
+                charge += migrated.charge;
+                diagnostics.merge(migrated.diagnostics);
+            }
+
+            offer.set_throughput(self.throughput);
+            let mut response = self.replace(&offer, None).await?;
+            response.charge += charge;
+            diagnostics.merge(response.diagnostics);
+            response.diagnostics = diagnostics;
+
+            if let Some(interval) = self.wait_for_completion {
+                let deadline = Instant::now() + self.completion_timeout;
+                let target = OfferTarget::Offer {
+                    client: self.target.cosmos_client().clone(),
+                    offer_id: response.offer.rid.clone(),
+                };
+                while response.replace_pending && Instant::now() < deadline {
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    if !interval.is_zero() {
+                        azure_core::sleep(interval.min(remaining)).await;
+                    }
+                    let (offer, headers, diagnostics) = target.read(&self.context).await?;
+                    response.offer = offer;
+                    response.replace_pending = offer_replace_pending_from_headers(&headers)?;
+                    response.charge += request_charge_from_headers(&headers)?;
+                    response.diagnostics.merge(diagnostics);
+                }
+            }
+
+            Ok(response)
+        })
+    }
+
+    /// Replaces the offer, migrating it to the other throughput mode when `migrate` is set
+    async fn replace(
+        &self,
+        offer: &ThroughputOffer,
+        migrate: Option<&'static str>,
+    ) -> azure_core::error::Result<ReplaceOfferResponse> {
+        let mut request = self
+            .target
+            .cosmos_client()
+            .prepare_request_pipeline(&format!("offers/{}", offer.rid), http::Method::PUT);
+        if let Some(migrate) = migrate {
+            request
+                .headers_mut()
+                .insert(migrate, http::HeaderValue::from_static("true"));
+        }
+        request.set_body(azure_core::to_json(offer)?);
+        let (response, diagnostics) = self
+            .target
+            .cosmos_client()
+            .send(request, self.context.clone(), ResourceType::Offers)
+            .await?;
+        ReplaceOfferResponse::try_from(response, diagnostics).await
+    }
+}
+
+/// The future returned by calling `into_future` on the builder.
+pub type ReplaceOffer =
+    futures::future::BoxFuture<'static, azure_core::error::Result<ReplaceOfferResponse>>;
+
+#[cfg(feature = "into_future")]
+impl std::future::IntoFuture for ReplaceOfferBuilder {
+    type IntoFuture = ReplaceOffer;
+    type Output = <ReplaceOffer as std::future::Future>::Output;
+    fn into_future(self) -> Self::IntoFuture {
+        Self::into_future(self)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplaceOfferResponse {
+    pub offer: ThroughputOffer,
+    /// Whether the throughput change is still being applied
+    pub replace_pending: bool,
+    pub charge: f64,
+    pub activity_id: uuid::Uuid,
+    pub diagnostics: CosmosDiagnostics,
+}
+
+impl ReplaceOfferResponse {
+    async fn try_from(
+        response: azure_core::Response,
+        diagnostics: CosmosDiagnostics,
+    ) -> azure_core::error::Result<Self> {
+        let (_status_code, headers, pinned_stream) = response.deconstruct();
+        let body = collect_pinned_stream(pinned_stream).await?;
+
+        Ok(Self {
+            offer: serde_json::from_slice(&body)?,
+            replace_pending: offer_replace_pending_from_headers(&headers)?,
+            charge: request_charge_from_headers(&headers)?,
+            activity_id: activity_id_from_headers(&headers)?,
+            diagnostics,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mock_endpoint::{MockEndpoint, MockResponse};
+    use crate::prelude::*;
+    use crate::resources::ThroughputOffer;
+    use std::time::Duration;
+
+    fn offer(throughput: Throughput) -> String {
+        let mut offer: ThroughputOffer = serde_json::from_str(
+            r#"{"resource":"dbs/rgkVAA==/colls/rgkVAMHcJww=/","offerType":"Invalid","offerResourceId":"rgkVAMHcJww=","offerVersion":"V2","content":{"offerThroughput":400,"offerIsRUPerMinuteThroughputEnabled":false},"id":"HDRB","_rid":"HDRB","_self":"offers/HDRB/","_etag":"\"1\"","_ts":1518029673}"#,
+        )
+        .unwrap();
+        offer.set_throughput(throughput);
+        serde_json::to_string(&offer).unwrap()
+    }
+
+    #[tokio::test]
+    async fn scales_collection_to_autoscale_and_waits_for_completion() {
+        let autoscale = Throughput::Autoscale {
+            max_throughput: 40000,
+        };
+        let pending = |body: String| {
+            MockResponse::new(200, body)
+                .with_cosmos_headers()
+                .with_header("x-ms-offer-replace-pending", "true")
+        };
+        let endpoint = MockEndpoint::start(vec![
+            MockResponse::new(200, r#"{"id":"coll","_rid":"rgkVAMHcJww="}"#).with_cosmos_headers(),
+            MockResponse::new(
+                200,
+                format!(
+                    r#"{{"_rid":"","Offers":[{}],"_count":1}}"#,
+                    offer(Throughput::Manual(400))
+                ),
+            )
+            .with_cosmos_headers(),
+            MockResponse::new(
+                200,
+                offer(Throughput::Autoscale {
+                    max_throughput: 4000,
+                }),
+            )
+            .with_cosmos_headers(),
+            pending(offer(autoscale)),
+            pending(offer(autoscale)),
+            MockResponse::new(200, offer(autoscale)).with_cosmos_headers(),
+        ]);
+
+        let response = endpoint
+            .cosmos_client()
+            .database_client("db")
+            .collection_client("coll")
+            .replace_offer(autoscale)
+            .wait_for_completion(Duration::ZERO)
+            .into_future()
+            .await
+            .unwrap();
+
+        assert!(!response.replace_pending);
+        assert_eq!(response.offer.throughput(), autoscale);
+        assert_eq!(response.charge, 4.0);
+
+        let requests = endpoint.requests();
+        assert_eq!(requests.len(), 6);
+        assert_eq!(requests[0].path, "/dbs/db/colls/coll");
+        assert_eq!(requests[2].method, "PUT");
+        assert_eq!(requests[2].path, "/offers/HDRB");
+        assert_eq!(
+            requests[2].header("x-ms-cosmos-migrate-offer-to-autopilot"),
+            Some("true")
+        );
+        let body: serde_json::Value = serde_json::from_str(&requests[2].body).unwrap();
+        assert_eq!(body["content"]["offerThroughput"], 400);
+        assert_eq!(requests[3].method, "PUT");
+        assert_eq!(
+            requests[3].header("x-ms-cosmos-migrate-offer-to-autopilot"),
+            None
+        );
+        let body: serde_json::Value = serde_json::from_str(&requests[3].body).unwrap();
+        assert_eq!(
+            body["content"],
+            serde_json::json!({
+                "offerAutopilotSettings": {"maxThroughput": 40000},
+                "offerIsRUPerMinuteThroughputEnabled": false
+            })
+        );
+        assert_eq!(requests[5].path, "/offers/HDRB");
+    }
+
+    #[tokio::test]
+    async fn migrates_autoscale_offers_to_manual_throughput() {
+        let autoscale = Throughput::Autoscale {
+            max_throughput: 4000,
+        };
+        let endpoint = MockEndpoint::start(vec![
+            MockResponse::new(200, offer(autoscale)).with_cosmos_headers(),
+            MockResponse::new(200, offer(Throughput::Manual(400))).with_cosmos_headers(),
+            MockResponse::new(200, offer(Throughput::Manual(1000))).with_cosmos_headers(),
+        ]);
+
+        let response = endpoint
+            .cosmos_client()
+            .offer_client("HDRB")
+            .replace_offer(Throughput::Manual(1000))
+            .into_future()
+            .await
+            .unwrap();
+
+        assert_eq!(response.offer.throughput(), Throughput::Manual(1000));
+        let requests = endpoint.requests();
+        assert_eq!(
+            requests[1].header("x-ms-cosmos-migrate-offer-to-manual-throughput"),
+            Some("true")
+        );
+        let body: serde_json::Value = serde_json::from_str(&requests[2].body).unwrap();
+        assert_eq!(body["content"]["offerThroughput"], 1000);
+    }
+
+    #[tokio::test]
+    async fn stops_waiting_after_the_completion_timeout() {
+        let pending = || {
+            MockResponse::new(200, offer(Throughput::Manual(20000)))
+                .with_cosmos_headers()
+                .with_header("x-ms-offer-replace-pending", "true")
+        };
+        let endpoint = MockEndpoint::start(vec![
+            MockResponse::new(200, offer(Throughput::Manual(400))).with_cosmos_headers(),
+            pending(),
+            pending(),
+        ]);
+
+        let response = endpoint
+            .cosmos_client()
+            .offer_client("HDRB")
+            .replace_offer(Throughput::Manual(20000))
+            .wait_for_completion(Duration::from_millis(50))
+            .completion_timeout(Duration::from_millis(10))
+            .into_future()
+            .await
+            .unwrap();
+
+        assert!(response.replace_pending);
+        assert_eq!(endpoint.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn returns_pending_replace_without_waiting() {
+        let endpoint = MockEndpoint::start(vec![
+            MockResponse::new(200, offer(Throughput::Manual(400))).with_cosmos_headers(),
+            MockResponse::new(200, offer(Throughput::Manual(20000)))
+                .with_cosmos_headers()
+                .with_header("x-ms-offer-replace-pending", "true"),
+        ]);
+
+        let response = endpoint
+            .cosmos_client()
+            .offer_client("HDRB")
+            .replace_offer(Throughput::Manual(20000))
+            .into_future()
+            .await
+            .unwrap();
+
+        assert!(response.replace_pending);
+        assert_eq!(endpoint.requests().len(), 2);
+    }
+}
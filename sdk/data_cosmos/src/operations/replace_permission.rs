@@ -55,16 +55,14 @@ impl ReplacePermissionBuilder {
             };
 
             request.set_body(serde_json::to_vec(&request_body)?);
-            let response = self
-                .client
-                .pipeline()
-                .send(
-                    self.context.clone().insert(ResourceType::Permissions),
-                    &mut request,
-                )
-                .await?;
+            let (response, diagnostics) = crate::diagnostics::send(
+                self.client.pipeline(),
+                self.context.clone().insert(ResourceType::Permissions),
+                &mut request,
+            )
+            .await?;
 
-            PermissionResponse::try_from(response).await
+            PermissionResponse::try_from_with_diagnostics(response, diagnostics).await
         })
     }
 }
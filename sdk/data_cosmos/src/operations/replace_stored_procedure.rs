@@ -48,15 +48,13 @@ impl ReplaceStoredProcedureBuilder {
 
             req.set_body(serde_json::to_vec(&body)?);
 
-            let response = self
-                .client
-                .pipeline()
-                .send(
-                    self.context.clone().insert(ResourceType::StoredProcedures),
-                    &mut req,
-                )
-                .await?;
-            ReplaceStoredProcedureResponse::try_from(response).await
+            let (response, diagnostics) = crate::diagnostics::send(
+                self.client.pipeline(),
+                self.context.clone().insert(ResourceType::StoredProcedures),
+                &mut req,
+            )
+            .await?;
+            ReplaceStoredProcedureResponse::try_from_with_diagnostics(response, diagnostics).await
         })
     }
 }
@@ -37,16 +37,14 @@ impl ReplaceUserBuilder {
                 id: &self.user_name,
             };
             request.set_body(serde_json::to_vec(&body)?);
-            let response = self
-                .client
-                .pipeline()
-                .send(
-                    self.context.clone().insert(ResourceType::Users),
-                    &mut request,
-                )
-                .await?;
+            let (response, diagnostics) = crate::diagnostics::send(
+                self.client.pipeline(),
+                self.context.clone().insert(ResourceType::Users),
+                &mut request,
+            )
+            .await?;
 
-            UserResponse::try_from(response).await
+            UserResponse::try_from_with_diagnostics(response, diagnostics).await
         })
     }
 }
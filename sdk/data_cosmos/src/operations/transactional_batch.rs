@@ -0,0 +1,605 @@
+use crate::headers::from_headers::*;
+use crate::headers::{
+    HEADER_BATCH_ATOMIC, HEADER_BATCH_CONTINUE_ON_ERROR, HEADER_BATCH_ORDERED,
+    HEADER_IS_BATCH_REQUEST,
+};
+use crate::prelude::*;
+use crate::resources::document::validate_patch_operations;
+use crate::resources::ResourceType;
+use crate::CosmosDiagnostics;
+
+use azure_core::error::{Error, ErrorKind, HttpError, ResultExt};
+use azure_core::headers::session_token_from_headers;
+use azure_core::prelude::*;
+use azure_core::{collect_pinned_stream, Response as HttpResponse};
+use http::StatusCode;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+/// The maximum number of operations in a transactional batch
+pub const MAX_BATCH_OPERATIONS: usize = 100;
+
+/// The kind of an operation in a transactional batch
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BatchOperationType {
+    Create,
+    Upsert,
+    Replace,
+    Delete,
+    Read,
+    Patch,
+}
+
+/// An operation of a transactional batch
+///
+/// Operations are usually added with the methods of [`TransactionalBatchBuilder`]. Build them
+/// directly to make them conditional on the etag of the document.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionalBatchOperation {
+    operation_type: BatchOperationType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    resource_body: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    if_match: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    if_none_match: Option<String>,
+    #[serde(skip)]
+    patch_operations: Vec<PatchOperation>,
+}
+
+impl TransactionalBatchOperation {
+    fn new(
+        operation_type: BatchOperationType,
+        id: Option<String>,
+        resource_body: Option<Value>,
+    ) -> Self {
+        Self {
+            operation_type,
+            id,
+            resource_body,
+            if_match: None,
+            if_none_match: None,
+            patch_operations: Vec::new(),
+        }
+    }
+
+    /// Creates a document
+    pub fn create<D: Serialize>(document: &D) -> azure_core::error::Result<Self> {
+        Ok(Self::new(
+            BatchOperationType::Create,
+            None,
+            Some(to_value(document)?),
+        ))
+    }
+
+    /// Creates a document or replaces it if it exists
+    pub fn upsert<D: Serialize>(document: &D) -> azure_core::error::Result<Self> {
+        Ok(Self::new(
+            BatchOperationType::Upsert,
+            None,
+            Some(to_value(document)?),
+        ))
+    }
+
+    /// Replaces the document with the given id
+    pub fn replace<S: Into<String>, D: Serialize>(
+        id: S,
+        document: &D,
+    ) -> azure_core::error::Result<Self> {
+        Ok(Self::new(
+            BatchOperationType::Replace,
+            Some(id.into()),
+            Some(to_value(document)?),
+        ))
+    }
+
+    /// Deletes the document with the given id
+    pub fn delete<S: Into<String>>(id: S) -> Self {
+        Self::new(BatchOperationType::Delete, Some(id.into()), None)
+    }
+
+    /// Reads the document with the given id
+    pub fn read<S: Into<String>>(id: S) -> Self {
+        Self::new(BatchOperationType::Read, Some(id.into()), None)
+    }
+
+    /// Applies a partial update to the document with the given id
+    pub fn patch<S: Into<String>>(id: S, operations: Vec<PatchOperation>) -> Self {
+        let resource_body = serde_json::json!({ "operations": operations });
+        Self {
+            patch_operations: operations,
+            ..Self::new(
+                BatchOperationType::Patch,
+                Some(id.into()),
+                Some(resource_body),
+            )
+        }
+    }
+
+    /// Only applies the operation if the etag of the document matches
+    pub fn if_match<S: Into<String>>(self, etag: S) -> Self {
+        Self {
+            if_match: Some(etag.into()),
+            ..self
+        }
+    }
+
+    /// Only applies the operation if the etag of the document does not match
+    pub fn if_none_match<S: Into<String>>(self, etag: S) -> Self {
+        Self {
+            if_none_match: Some(etag.into()),
+            ..self
+        }
+    }
+
+    /// The kind of the operation
+    pub fn operation_type(&self) -> BatchOperationType {
+        self.operation_type
+    }
+
+    /// The id of the document the operation applies to
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref().or_else(|| {
+            self.resource_body
+                .as_ref()
+                .and_then(|body| body.get("id"))
+                .and_then(Value::as_str)
+        })
+    }
+
+    /// Checks the operation before it is sent
+    pub(crate) fn validate(&self) -> azure_core::error::Result<()> {
+        match self.operation_type {
+            BatchOperationType::Patch => validate_patch_operations(&self.patch_operations),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn to_value<D: Serialize>(document: &D) -> azure_core::error::Result<Value> {
+    serde_json::to_value(document).context(
+        ErrorKind::DataConversion,
+        "could not serialize the document of a batch operation",
+    )
+}
+
+#[derive(Debug, Clone)]
+pub struct TransactionalBatchBuilder {
+    client: CollectionClient,
+    partition_key_serialized: String,
+    operations: Vec<TransactionalBatchOperation>,
+    atomic: bool,
+    consistency_level: Option<ConsistencyLevel>,
+    context: Context,
+}
+
+impl TransactionalBatchBuilder {
+    pub(crate) fn new(client: CollectionClient, partition_key_serialized: String) -> Self {
+        Self {
+            client,
+            partition_key_serialized,
+            operations: Vec::new(),
+            atomic: true,
+            consistency_level: None,
+            context: Context::new(),
+        }
+    }
+
+    /// Applies every operation on its own instead of all or none, as bulk execution does
+    pub(crate) fn non_atomic(self) -> Self {
+        Self {
+            atomic: false,
+            ..self
+        }
+    }
+
+    setters! {
+        consistency_level: ConsistencyLevel => Some(consistency_level),
+        context: Context => context,
+    }
+
+    /// Adds an operation to the batch
+    pub fn operation(mut self, operation: TransactionalBatchOperation) -> Self {
+        self.operations.push(operation);
+        self
+    }
+
+    /// Adds the creation of a document to the batch
+    pub fn create_document<D: Serialize>(self, document: &D) -> azure_core::error::Result<Self> {
+        Ok(self.operation(TransactionalBatchOperation::create(document)?))
+    }
+
+    /// Adds the upsert of a document to the batch
+    pub fn upsert_document<D: Serialize>(self, document: &D) -> azure_core::error::Result<Self> {
+        Ok(self.operation(TransactionalBatchOperation::upsert(document)?))
+    }
+
+    /// Adds the replacement of a document to the batch
+    pub fn replace_document<S: Into<String>, D: Serialize>(
+        self,
+        id: S,
+        document: &D,
+    ) -> azure_core::error::Result<Self> {
+        Ok(self.operation(TransactionalBatchOperation::replace(id, document)?))
+    }
+
+    /// Adds the deletion of a document to the batch
+    pub fn delete_document<S: Into<String>>(self, id: S) -> Self {
+        self.operation(TransactionalBatchOperation::delete(id))
+    }
+
+    /// Adds the read of a document to the batch
+    pub fn read_document<S: Into<String>>(self, id: S) -> Self {
+        self.operation(TransactionalBatchOperation::read(id))
+    }
+
+    /// Adds a partial update of a document to the batch
+    pub fn patch_document<S: Into<String>>(self, id: S, operations: Vec<PatchOperation>) -> Self {
+        self.operation(TransactionalBatchOperation::patch(id, operations))
+    }
+
+    /// The operations added so far
+    pub fn operations(&self) -> &[TransactionalBatchOperation] {
+        &self.operations
+    }
+
+    /// Convert into a future
+    ///
+    /// The operations are applied in order and atomically: either all of them succeed or none
+    /// is applied. A batch rejected by the service is not returned as an error; check
+    /// [`TransactionalBatchResponse::is_success`] or use
+    /// [`TransactionalBatchResponse::into_result`].
+    pub fn into_future(self) -> TransactionalBatch {
+        Box::pin(async move {
+            self.validate()?;
+
+            let mut request = self.client.prepare_doc_request_pipeline(http::Method::POST);
+            let atomic = if self.atomic { "True" } else { "False" };
+            for (header, value) in [
+                (HEADER_IS_BATCH_REQUEST, "True"),
+                (HEADER_BATCH_ATOMIC, atomic),
+                (HEADER_BATCH_ORDERED, "True"),
+            ] {
+                request
+                    .headers_mut()
+                    .insert(header, http::HeaderValue::from_static(value));
+            }
+            if !self.atomic {
+                request.headers_mut().insert(
+                    HEADER_BATCH_CONTINUE_ON_ERROR,
+                    http::HeaderValue::from_static("True"),
+                );
+            }
+            crate::cosmos_entity::add_as_partition_key_header_serialized2(
+                &self.partition_key_serialized,
+                &mut request,
+            );
+            if let Some(cl) = &self.consistency_level {
+                request.insert_headers(cl);
+            }
+            request.set_body(azure_core::to_json(&self.operations)?);
+
+            let response = crate::diagnostics::send(
+                self.client.pipeline(),
+                self.context.clone().insert(ResourceType::Documents),
+                &mut request,
+            )
+            .await;
+
+            match response {
+                Ok((response, diagnostics)) => {
+                    TransactionalBatchResponse::try_from(response, diagnostics).await
+                }
+                Err(error) => TransactionalBatchResponse::try_from_error(error),
+            }
+        })
+    }
+
+    fn validate(&self) -> azure_core::error::Result<()> {
+        if self.operations.is_empty() {
+            return Err(Error::message(
+                ErrorKind::Other,
+                "a transactional batch needs at least one operation",
+            ));
+        }
+        if self.operations.len() > MAX_BATCH_OPERATIONS {
+            return Err(Error::with_message(ErrorKind::Other, || {
+                format!(
+                    "a transactional batch supports at most {} operations, got {}",
+                    MAX_BATCH_OPERATIONS,
+                    self.operations.len()
+                )
+            }));
+        }
+        self.operations
+            .iter()
+            .try_for_each(TransactionalBatchOperation::validate)
+    }
+}
+
+/// The future returned by calling `into_future` on the builder.
+pub type TransactionalBatch =
+    futures::future::BoxFuture<'static, azure_core::error::Result<TransactionalBatchResponse>>;
+
+#[cfg(feature = "into_future")]
+impl std::future::IntoFuture for TransactionalBatchBuilder {
+    type IntoFuture = TransactionalBatch;
+    type Output = <TransactionalBatch as std::future::Future>::Output;
+    fn into_future(self) -> Self::IntoFuture {
+        Self::into_future(self)
+    }
+}
+
+/// The result of an operation of a transactional batch
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionalBatchOperationResult {
+    pub status_code: u16,
+    #[serde(default)]
+    pub sub_status_code: Option<u32>,
+    #[serde(default)]
+    pub request_charge: f64,
+    #[serde(rename = "eTag", default)]
+    pub etag: Option<String>,
+    #[serde(default)]
+    pub resource_body: Option<Value>,
+    #[serde(default)]
+    pub retry_after_milliseconds: Option<u64>,
+}
+
+impl TransactionalBatchOperationResult {
+    /// Whether the operation succeeded
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status_code)
+    }
+
+    /// The document returned by a create, upsert, replace, read, or patch operation
+    pub fn document<T: DeserializeOwned>(&self) -> azure_core::error::Result<Option<Document<T>>> {
+        self.resource_body
+            .clone()
+            .map(serde_json::from_value)
+            .transpose()
+            .context(
+                ErrorKind::DataConversion,
+                "could not deserialize the document of a batch operation",
+            )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TransactionalBatchResponse {
+    /// The status of the batch, which is the status of the first failing operation if it failed
+    pub status_code: StatusCode,
+    /// The results of the operations, in the order they were added
+    pub results: Vec<TransactionalBatchOperationResult>,
+    pub charge: f64,
+    pub activity_id: Option<uuid::Uuid>,
+    pub session_token: Option<String>,
+    pub diagnostics: CosmosDiagnostics,
+}
+
+impl TransactionalBatchResponse {
+    async fn try_from(
+        response: HttpResponse,
+        diagnostics: CosmosDiagnostics,
+    ) -> azure_core::error::Result<Self> {
+        let (status_code, headers, pinned_stream) = response.deconstruct();
+        let body = collect_pinned_stream(pinned_stream).await?;
+
+        Ok(Self {
+            status_code,
+            results: serde_json::from_slice(&body)?,
+            charge: request_charge_from_headers(&headers)?,
+            activity_id: activity_id_from_headers(&headers).ok(),
+            session_token: session_token_from_headers(&headers).ok(),
+            diagnostics,
+        })
+    }
+
+    /// Turns the error of a rejected batch back into a response holding the operation results
+    fn try_from_error(error: Error) -> azure_core::error::Result<Self> {
+        let parsed = error.downcast_ref::<HttpError>().and_then(|http_error| {
+            let results = serde_json::from_slice(http_error.body()).ok()?;
+            let header = |name: &str| http_error.header(name).map(ToOwned::to_owned);
+            Some(Self {
+                status_code: StatusCode::from_u16(http_error.status()).ok()?,
+                results,
+                charge: header(crate::headers::HEADER_REQUEST_CHARGE)
+                    .and_then(|charge| charge.parse().ok())
+                    .unwrap_or_default(),
+                activity_id: header(crate::headers::HEADER_ACTIVITY_ID)
+                    .and_then(|id| id.parse().ok()),
+                session_token: header(crate::headers::HEADER_SESSION_TOKEN),
+                diagnostics: CosmosDiagnostics::from_error(&error)
+                    .cloned()
+                    .unwrap_or_default(),
+            })
+        });
+        parsed.ok_or(error)
+    }
+
+    /// Whether every operation was applied
+    pub fn is_success(&self) -> bool {
+        self.status_code.is_success()
+    }
+
+    /// The index and the result of the operation that made the batch fail
+    ///
+    /// The other operations of a failed batch report a 424 (failed dependency) status.
+    pub fn first_failed_operation(&self) -> Option<(usize, &TransactionalBatchOperationResult)> {
+        if self.is_success() {
+            return None;
+        }
+        self.results.iter().enumerate().find(|(_, result)| {
+            !result.is_success() && result.status_code != StatusCode::FAILED_DEPENDENCY.as_u16()
+        })
+    }
+
+    /// Returns an error identifying the failing operation if the batch failed
+    pub fn into_result(self) -> azure_core::error::Result<Self> {
+        match self.first_failed_operation() {
+            None if self.is_success() => Ok(self),
+            None => Err(Error::with_message(
+                ErrorKind::http_response(self.status_code.as_u16(), None),
+                || {
+                    format!(
+                        "transactional batch failed with status {}",
+                        self.status_code
+                    )
+                },
+            )),
+            Some((index, result)) => Err(Error::with_message(
+                ErrorKind::http_response(result.status_code, None),
+                || {
+                    format!(
+                        "operation {} of the transactional batch failed with status {}",
+                        index, result.status_code
+                    )
+                },
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_endpoint::{MockEndpoint, MockResponse};
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct MyDocument {
+        id: String,
+        pk: String,
+    }
+
+    fn document(id: &str) -> MyDocument {
+        MyDocument {
+            id: id.to_owned(),
+            pk: "pk".to_owned(),
+        }
+    }
+
+    fn collection(endpoint: &MockEndpoint) -> CollectionClient {
+        endpoint
+            .cosmos_client()
+            .database_client("db")
+            .collection_client("coll")
+    }
+
+    #[test]
+    fn serialize_operations() {
+        let operations = vec![
+            TransactionalBatchOperation::create(&document("a")).unwrap(),
+            TransactionalBatchOperation::replace("b", &document("b"))
+                .unwrap()
+                .if_match("\"1\""),
+            TransactionalBatchOperation::delete("c"),
+            TransactionalBatchOperation::patch("d", vec![PatchOperation::increment("/n", 1)]),
+        ];
+        assert_eq!(
+            serde_json::to_string(&operations).unwrap(),
+            concat!(
+                r#"[{"operationType":"Create","resourceBody":{"id":"a","pk":"pk"}},"#,
+                r#"{"operationType":"Replace","id":"b","resourceBody":{"id":"b","pk":"pk"},"ifMatch":"\"1\""},"#,
+                r#"{"operationType":"Delete","id":"c"},"#,
+                r#"{"operationType":"Patch","id":"d","resourceBody":{"operations":[{"op":"incr","path":"/n","value":1}]}}]"#
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn executes_batch() {
+        let endpoint = MockEndpoint::start(vec![MockResponse::new(
+            200,
+            r#"[{"statusCode":201,"requestCharge":6.1,"eTag":"\"1\"","resourceBody":{"id":"a","pk":"pk","_rid":"r","_self":"s","_etag":"\"1\"","_attachments":"a/","_ts":1}},
+                {"statusCode":200,"requestCharge":1.0,"eTag":"\"2\"","resourceBody":{"id":"b","pk":"pk","_rid":"r","_self":"s","_etag":"\"2\"","_attachments":"a/","_ts":1}}]"#,
+        )
+        .with_cosmos_headers()
+        .with_header("x-ms-request-charge", "7.1")]);
+
+        let response = collection(&endpoint)
+            .transactional_batch(&"pk")
+            .unwrap()
+            .create_document(&document("a"))
+            .unwrap()
+            .read_document("b")
+            .into_future()
+            .await
+            .unwrap();
+
+        assert!(response.is_success());
+        assert_eq!(response.results.len(), 2);
+        assert_eq!(response.results[0].status_code, 201);
+        assert_eq!(response.results[0].request_charge, 6.1);
+        let read: Document<MyDocument> = response.results[1].document().unwrap().unwrap();
+        assert_eq!(read.document, document("b"));
+        assert_eq!(read.document_attributes.etag(), "\"2\"");
+        assert!(response.first_failed_operation().is_none());
+
+        let request = &endpoint.requests()[0];
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/dbs/db/colls/coll/docs");
+        assert_eq!(request.header("x-ms-cosmos-is-batch-request"), Some("True"));
+        assert_eq!(request.header("x-ms-cosmos-batch-atomic"), Some("True"));
+        assert_eq!(
+            request.header("x-ms-documentdb-partitionkey"),
+            Some(r#"["pk"]"#)
+        );
+        assert_eq!(
+            request.body,
+            r#"[{"operationType":"Create","resourceBody":{"id":"a","pk":"pk"}},{"operationType":"Read","id":"b"}]"#
+        );
+    }
+
+    #[tokio::test]
+    async fn identifies_failing_operation() {
+        let endpoint = MockEndpoint::start(vec![MockResponse::new(
+            409,
+            r#"[{"statusCode":424,"requestCharge":0},{"statusCode":409,"requestCharge":1.2},{"statusCode":424,"requestCharge":0}]"#,
+        )
+        .with_header("x-ms-request-charge", "1.2")]);
+
+        let response = collection(&endpoint)
+            .transactional_batch(&"pk")
+            .unwrap()
+            .upsert_document(&document("a"))
+            .unwrap()
+            .create_document(&document("b"))
+            .unwrap()
+            .delete_document("c")
+            .into_future()
+            .await
+            .unwrap();
+
+        assert!(!response.is_success());
+        assert_eq!(response.status_code, StatusCode::CONFLICT);
+        assert_eq!(response.charge, 1.2);
+        let (index, result) = response.first_failed_operation().unwrap();
+        assert_eq!(index, 1);
+        assert_eq!(result.status_code, 409);
+
+        let error = response.into_result().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "operation 1 of the transactional batch failed with status 409"
+        );
+    }
+
+    #[tokio::test]
+    async fn validates_batch() {
+        let endpoint = MockEndpoint::start(vec![]);
+        let batch = collection(&endpoint).transactional_batch(&"pk").unwrap();
+
+        assert!(batch.clone().into_future().await.is_err());
+        let too_large = (0..=MAX_BATCH_OPERATIONS)
+            .fold(batch.clone(), |batch, i| batch.read_document(i.to_string()));
+        assert!(too_large.into_future().await.is_err());
+        let invalid_patch = batch.patch_document("a", vec![]);
+        assert!(invalid_patch.into_future().await.is_err());
+        assert!(endpoint.requests().is_empty());
+    }
+}
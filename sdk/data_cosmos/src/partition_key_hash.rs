@@ -0,0 +1,193 @@
+//! Maps partition key values to the partition key range holding them
+//!
+//! Cosmos hashes the partition key into an effective partition key and assigns every
+//! partition key range a half-open interval of those. The hashing follows version 2 of the
+//! hash partitioning scheme: the components are encoded, hashed with MurmurHash3 (x64, 128
+//! bits), and the hash is hex encoded most significant byte first.
+use crate::operations::PartitionKeyRange;
+use serde_json::Value;
+
+const STRING_MARKER: u8 = 0x08;
+const NUMBER_MARKER: u8 = 0x05;
+const TRUE_MARKER: u8 = 0x03;
+const FALSE_MARKER: u8 = 0x02;
+const NULL_MARKER: u8 = 0x01;
+const STRING_TERMINATOR: u8 = 0xFF;
+
+/// The effective partition key of a serialized partition key such as `["a"]`
+///
+/// Every component of a hierarchical partition key, such as `["tenant", "user"]`, is hashed on
+/// its own and the hashes are concatenated, so the keys sharing a prefix are contiguous.
+///
+/// Returns `None` if the value cannot be a partition key.
+pub(crate) fn effective_partition_key(partition_key_serialized: &str) -> Option<String> {
+    let components: Vec<Value> = serde_json::from_str(partition_key_serialized).ok()?;
+    components
+        .iter()
+        .map(hash_component)
+        .collect::<Option<Vec<_>>>()
+        .map(|hashes| hashes.concat())
+}
+
+/// The range of effective partition keys of the documents whose hierarchical partition key
+/// starts with the serialized prefix, such as `["tenant"]`
+pub(crate) fn effective_partition_key_range(
+    partition_key_prefix_serialized: &str,
+) -> Option<(String, String)> {
+    let min = effective_partition_key(partition_key_prefix_serialized)?;
+    let max = format!("{}FF", min);
+    Some((min, max))
+}
+
+fn hash_component(component: &Value) -> Option<String> {
+    let mut encoded = Vec::new();
+    match component {
+        Value::String(s) => {
+            encoded.push(STRING_MARKER);
+            encoded.extend_from_slice(s.as_bytes());
+            encoded.push(STRING_TERMINATOR);
+        }
+        Value::Number(n) => {
+            encoded.push(NUMBER_MARKER);
+            encoded.extend_from_slice(&n.as_f64()?.to_le_bytes());
+        }
+        Value::Bool(true) => encoded.push(TRUE_MARKER),
+        Value::Bool(false) => encoded.push(FALSE_MARKER),
+        Value::Null => encoded.push(NULL_MARKER),
+        Value::Array(_) | Value::Object(_) => return None,
+    }
+
+    let (h1, h2) = murmurhash3_x64_128(&encoded, 0);
+    let mut bytes = [0; 16];
+    bytes[..8].copy_from_slice(&h1.to_le_bytes());
+    bytes[8..].copy_from_slice(&h2.to_le_bytes());
+    bytes.reverse();
+    // the top two bits are reserved
+    bytes[0] &= 0x3F;
+    Some(bytes.iter().map(|b| format!("{:02X}", b)).collect())
+}
+
+/// The index of the range holding the effective partition key
+pub(crate) fn find_range(
+    ranges: &[PartitionKeyRange],
+    effective_partition_key: &str,
+) -> Option<usize> {
+    ranges.iter().position(|range| {
+        range.min_exclusive.as_str() <= effective_partition_key
+            && effective_partition_key < range.max_exclusive.as_str()
+    })
+}
+
+pub(crate) fn murmurhash3_x64_128(data: &[u8], seed: u64) -> (u64, u64) {
+    const C1: u64 = 0x87c3_7b91_1142_53d5;
+    const C2: u64 = 0x4cf5_ad43_2745_937f;
+
+    let mut h1 = seed;
+    let mut h2 = seed;
+
+    let mut blocks = data.chunks_exact(16);
+    for block in &mut blocks {
+        let k1 = u64::from_le_bytes(block[..8].try_into().unwrap());
+        let k2 = u64::from_le_bytes(block[8..].try_into().unwrap());
+
+        h1 ^= k1.wrapping_mul(C1).rotate_left(31).wrapping_mul(C2);
+        h1 = h1
+            .rotate_left(27)
+            .wrapping_add(h2)
+            .wrapping_mul(5)
+            .wrapping_add(0x52dc_e729);
+
+        h2 ^= k2.wrapping_mul(C2).rotate_left(33).wrapping_mul(C1);
+        h2 = h2
+            .rotate_left(31)
+            .wrapping_add(h1)
+            .wrapping_mul(5)
+            .wrapping_add(0x3849_5ab5);
+    }
+
+    let tail = blocks.remainder();
+    let read = |bytes: &[u8]| {
+        bytes
+            .iter()
+            .rev()
+            .fold(0u64, |acc, &byte| (acc << 8) | u64::from(byte))
+    };
+    if tail.len() > 8 {
+        h2 ^= read(&tail[8..])
+            .wrapping_mul(C2)
+            .rotate_left(33)
+            .wrapping_mul(C1);
+    }
+    if !tail.is_empty() {
+        h1 ^= read(&tail[..tail.len().min(8)])
+            .wrapping_mul(C1)
+            .rotate_left(31)
+            .wrapping_mul(C2);
+    }
+
+    h1 ^= data.len() as u64;
+    h2 ^= data.len() as u64;
+    h1 = h1.wrapping_add(h2);
+    h2 = h2.wrapping_add(h1);
+    h1 = fmix64(h1);
+    h2 = fmix64(h2);
+    h1 = h1.wrapping_add(h2);
+    h2 = h2.wrapping_add(h1);
+    (h1, h2)
+}
+
+fn fmix64(mut k: u64) -> u64 {
+    k ^= k >> 33;
+    k = k.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    k ^= k >> 33;
+    k = k.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    k ^= k >> 33;
+    k
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn murmurhash3_reference_values() {
+        assert_eq!(murmurhash3_x64_128(b"", 0), (0, 0));
+        assert_eq!(
+            murmurhash3_x64_128(b"hello", 0),
+            (0xcbd8_a7b3_41bd_9b02, 0x5b1e_906a_48ae_1d19)
+        );
+        let (h1, h2) = murmurhash3_x64_128(b"The quick brown fox jumps over the lazy dog", 0);
+        let mut bytes = h1.to_le_bytes().to_vec();
+        bytes.extend_from_slice(&h2.to_le_bytes());
+        let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+        assert_eq!(hex, "6c1b07bc7bbc4be347939ac4a93c437a");
+    }
+
+    #[test]
+    fn effective_partition_keys() {
+        let epk = effective_partition_key(r#"["pk"]"#).unwrap();
+        assert_eq!(epk.len(), 32);
+        assert!(epk.as_str() < "40");
+        assert_eq!(effective_partition_key(r#"["pk"]"#), Some(epk.clone()));
+        assert_ne!(effective_partition_key(r#"["pl"]"#), Some(epk));
+        assert!(effective_partition_key("[1.5]").is_some());
+        assert!(effective_partition_key("[null]").is_some());
+        assert!(effective_partition_key(r#"[{"a":1}]"#).is_none());
+        assert!(effective_partition_key("pk").is_none());
+    }
+
+    #[test]
+    fn hierarchical_effective_partition_keys() {
+        let tenant = effective_partition_key(r#"["tenant"]"#).unwrap();
+        let user = effective_partition_key(r#"["user"]"#).unwrap();
+        let epk = effective_partition_key(r#"["tenant","user"]"#).unwrap();
+        assert_eq!(epk, format!("{}{}", tenant, user));
+        assert!(effective_partition_key(r#"["tenant",{"a":1}]"#).is_none());
+
+        let (min, max) = effective_partition_key_range(r#"["tenant"]"#).unwrap();
+        assert_eq!(min, tenant);
+        assert!(min.as_str() <= epk.as_str() && epk.as_str() < max.as_str());
+        let other = effective_partition_key(r#"["other","user"]"#).unwrap();
+        assert!(!(min.as_str() <= other.as_str() && other.as_str() < max.as_str()));
+    }
+}
@@ -0,0 +1,247 @@
+//! The metrics of query execution returned when asked for with `populate_query_metrics` and
+//! `populate_index_metrics`
+use azure_core::error::{Error, ErrorKind, Result, ResultExt};
+use std::time::Duration;
+
+/// How the service executed a query, parsed from the `x-ms-documentdb-query-metrics` header
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QueryMetrics {
+    /// The time spent executing the query
+    pub total_execution_time: Duration,
+    /// The time spent compiling the query
+    pub query_compile_time: Duration,
+    /// The time spent building the logical plan of the query
+    pub logical_plan_build_time: Duration,
+    /// The time spent building the physical plan of the query
+    pub physical_plan_build_time: Duration,
+    /// The time spent optimizing the query
+    pub query_optimization_time: Duration,
+    /// The time spent by the query runtime
+    pub vm_execution_time: Duration,
+    /// The time spent looking up the index
+    pub index_lookup_time: Duration,
+    /// The time spent loading documents
+    pub document_load_time: Duration,
+    /// The time spent writing the results
+    pub document_write_time: Duration,
+    /// The time spent in system functions
+    pub system_function_execution_time: Duration,
+    /// The time spent in user-defined functions
+    pub user_function_execution_time: Duration,
+    /// How many documents were loaded
+    pub retrieved_document_count: u64,
+    /// The size in bytes of the documents loaded
+    pub retrieved_document_size: u64,
+    /// How many documents were returned
+    pub output_document_count: u64,
+    /// The size in bytes of the documents returned
+    pub output_document_size: u64,
+    /// The share of the loaded documents that matched the filter, low values hint at a
+    /// missing index
+    pub index_hit_ratio: f64,
+}
+
+impl QueryMetrics {
+    /// Adds the metrics of another execution of the query, such as in another partition key
+    /// range
+    pub(crate) fn add(&mut self, other: &QueryMetrics) {
+        let retrieved = self.retrieved_document_count + other.retrieved_document_count;
+        // weighted by the documents each execution loaded
+        self.index_hit_ratio = if retrieved == 0 {
+            self.index_hit_ratio.max(other.index_hit_ratio)
+        } else {
+            (self.index_hit_ratio * self.retrieved_document_count as f64
+                + other.index_hit_ratio * other.retrieved_document_count as f64)
+                / retrieved as f64
+        };
+        self.total_execution_time += other.total_execution_time;
+        self.query_compile_time += other.query_compile_time;
+        self.logical_plan_build_time += other.logical_plan_build_time;
+        self.physical_plan_build_time += other.physical_plan_build_time;
+        self.query_optimization_time += other.query_optimization_time;
+        self.vm_execution_time += other.vm_execution_time;
+        self.index_lookup_time += other.index_lookup_time;
+        self.document_load_time += other.document_load_time;
+        self.document_write_time += other.document_write_time;
+        self.system_function_execution_time += other.system_function_execution_time;
+        self.user_function_execution_time += other.user_function_execution_time;
+        self.retrieved_document_count = retrieved;
+        self.retrieved_document_size += other.retrieved_document_size;
+        self.output_document_count += other.output_document_count;
+        self.output_document_size += other.output_document_size;
+    }
+}
+
+/// Parse [`QueryMetrics`] from a string such as
+/// `totalExecutionTimeInMs=33.67;retrievedDocumentCount=2000;indexUtilizationRatio=1.00`
+///
+/// Metrics the client does not know are ignored.
+pub(crate) fn query_metrics_from_str(full_string: &str) -> Result<QueryMetrics> {
+    let mut metrics = QueryMetrics::default();
+    for token in full_string
+        .split(';')
+        .map(str::trim)
+        .filter(|token| !token.is_empty())
+    {
+        let (name, value) = token.split_once('=').ok_or_else(|| {
+            Error::with_message(ErrorKind::DataConversion, || {
+                format!("malformed query metric '{}' in '{}'", token, full_string)
+            })
+        })?;
+        let number = || -> Result<f64> {
+            value.parse().with_context(ErrorKind::DataConversion, || {
+                format!("malformed query metric '{}' in '{}'", token, full_string)
+            })
+        };
+        let duration = || {
+            Duration::try_from_secs_f64(number()?.max(0.0) / 1000.0).with_context(
+                ErrorKind::DataConversion,
+                || {
+                    format!(
+                        "query metric '{}' is out of range in '{}'",
+                        token, full_string
+                    )
+                },
+            )
+        };
+        let count = || number().map(|count| count.max(0.0) as u64);
+        match name {
+            "totalExecutionTimeInMs" => metrics.total_execution_time = duration()?,
+            "queryCompileTimeInMs" => metrics.query_compile_time = duration()?,
+            "queryLogicalPlanBuildTimeInMs" => metrics.logical_plan_build_time = duration()?,
+            "queryPhysicalPlanBuildTimeInMs" => metrics.physical_plan_build_time = duration()?,
+            "queryOptimizationTimeInMs" => metrics.query_optimization_time = duration()?,
+            "VMExecutionTimeInMs" => metrics.vm_execution_time = duration()?,
+            "indexLookupTimeInMs" => metrics.index_lookup_time = duration()?,
+            "documentLoadTimeInMs" => metrics.document_load_time = duration()?,
+            "writeOutputTimeInMs" => metrics.document_write_time = duration()?,
+            "systemFunctionExecuteTimeInMs" => metrics.system_function_execution_time = duration()?,
+            "userFunctionExecuteTimeInMs" => metrics.user_function_execution_time = duration()?,
+            "retrievedDocumentCount" => metrics.retrieved_document_count = count()?,
+            "retrievedDocumentSize" => metrics.retrieved_document_size = count()?,
+            "outputDocumentCount" => metrics.output_document_count = count()?,
+            "outputDocumentSize" => metrics.output_document_size = count()?,
+            "indexUtilizationRatio" => metrics.index_hit_ratio = number()?,
+            _ => trace!("ignoring query metric {}", token),
+        }
+    }
+    Ok(metrics)
+}
+
+/// The indexes a query used and the ones that would have made it cheaper, decoded from the
+/// `x-ms-cosmos-index-utilization` header
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct IndexUtilization {
+    /// The single path indexes the query used
+    #[serde(rename = "UtilizedSingleIndexes", default)]
+    pub utilized_single_indexes: Vec<SingleIndexUtilization>,
+    /// The single path indexes that could help the query
+    #[serde(rename = "PotentialSingleIndexes", default)]
+    pub potential_single_indexes: Vec<SingleIndexUtilization>,
+    /// The composite indexes the query used
+    #[serde(rename = "UtilizedCompositeIndexes", default)]
+    pub utilized_composite_indexes: Vec<CompositeIndexUtilization>,
+    /// The composite indexes that could help the query
+    #[serde(rename = "PotentialCompositeIndexes", default)]
+    pub potential_composite_indexes: Vec<CompositeIndexUtilization>,
+}
+
+/// A single path index a query used or could use
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct SingleIndexUtilization {
+    /// The filter of the query the index serves
+    #[serde(rename = "FilterExpression", default)]
+    pub filter_expression: String,
+    /// The index, such as `/name/?`
+    #[serde(rename = "IndexSpec", default)]
+    pub index_spec: String,
+    /// Whether the filter is served precisely
+    #[serde(rename = "FilterPreciseSet", default)]
+    pub filter_precise_set: bool,
+    /// Whether the index is precise
+    #[serde(rename = "IndexPreciseSet", default)]
+    pub index_precise_set: bool,
+    /// How much the index helps, such as `High`
+    #[serde(rename = "IndexImpactScore", default)]
+    pub index_impact_score: Option<String>,
+}
+
+/// A composite index a query used or could use
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct CompositeIndexUtilization {
+    /// The paths of the index with their order, such as `/name ASC`
+    #[serde(rename = "IndexSpecs", default)]
+    pub index_specs: Vec<String>,
+    /// Whether the index is precise
+    #[serde(rename = "IndexPreciseSet", default)]
+    pub index_precise_set: bool,
+    /// How much the index helps, such as `High`
+    #[serde(rename = "IndexImpactScore", default)]
+    pub index_impact_score: Option<String>,
+}
+
+/// Decode [`IndexUtilization`] from its base64 encoded JSON
+pub(crate) fn index_utilization_from_str(encoded: &str) -> Result<IndexUtilization> {
+    let decoded = base64::decode(encoded.trim()).with_context(ErrorKind::DataConversion, || {
+        format!("could not decode the index utilization '{}'", encoded)
+    })?;
+    serde_json::from_slice(&decoded).context(
+        ErrorKind::DataConversion,
+        "could not parse the index utilization",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_query_metrics() {
+        let metrics = query_metrics_from_str("totalExecutionTimeInMs=33.67;queryCompileTimeInMs=0.06;queryLogicalPlanBuildTimeInMs=0.02;queryPhysicalPlanBuildTimeInMs=0.10;queryOptimizationTimeInMs=0.00;VMExecutionTimeInMs=32.56;indexLookupTimeInMs=0.99;documentLoadTimeInMs=9.58;systemFunctionExecuteTimeInMs=0.00;userFunctionExecuteTimeInMs=0.00;retrievedDocumentCount=2000;retrievedDocumentSize=1125600;outputDocumentCount=2000;outputDocumentSize=1125600;writeOutputTimeInMs=18.10;indexUtilizationRatio=1.00;someFutureMetric=1").unwrap();
+
+        assert_eq!(metrics.total_execution_time, Duration::from_micros(33_670));
+        assert_eq!(metrics.vm_execution_time, Duration::from_micros(32_560));
+        assert_eq!(metrics.index_lookup_time, Duration::from_micros(990));
+        assert_eq!(metrics.retrieved_document_count, 2000);
+        assert_eq!(metrics.output_document_size, 1_125_600);
+        assert_eq!(metrics.index_hit_ratio, 1.0);
+
+        assert!(query_metrics_from_str("retrievedDocumentCount=many").is_err());
+        assert!(query_metrics_from_str("totalExecutionTimeInMs=inf").is_err());
+        assert!(query_metrics_from_str("totalExecutionTimeInMs=1e400").is_err());
+        assert!(query_metrics_from_str("totalExecutionTimeInMs=1e300").is_err());
+    }
+
+    #[test]
+    fn adds_query_metrics_of_several_ranges() {
+        let mut metrics = query_metrics_from_str(
+            "totalExecutionTimeInMs=1.00;retrievedDocumentCount=30;indexUtilizationRatio=1.00",
+        )
+        .unwrap();
+        metrics.add(
+            &query_metrics_from_str(
+                "totalExecutionTimeInMs=2.00;retrievedDocumentCount=10;indexUtilizationRatio=0.20",
+            )
+            .unwrap(),
+        );
+
+        assert_eq!(metrics.total_execution_time, Duration::from_millis(3));
+        assert_eq!(metrics.retrieved_document_count, 40);
+        assert!((metrics.index_hit_ratio - 0.8).abs() < 1e-9);
+    }
+
+    #[test]
+    fn decodes_index_utilization() {
+        let encoded = base64::encode(
+            r#"{"UtilizedSingleIndexes":[{"FilterExpression":"","IndexSpec":"/name/?","FilterPreciseSet":true,"IndexPreciseSet":true,"IndexImpactScore":"High"}],"PotentialSingleIndexes":[],"UtilizedCompositeIndexes":[],"PotentialCompositeIndexes":[{"IndexSpecs":["/name ASC","/age ASC"],"IndexPreciseSet":false,"IndexImpactScore":"High"}]}"#,
+        );
+
+        let utilization = index_utilization_from_str(&encoded).unwrap();
+        assert_eq!(utilization.utilized_single_indexes[0].index_spec, "/name/?");
+        assert_eq!(
+            utilization.potential_composite_indexes[0].index_specs,
+            ["/name ASC", "/age ASC"]
+        );
+        assert!(index_utilization_from_str("not base64!").is_err());
+    }
+}
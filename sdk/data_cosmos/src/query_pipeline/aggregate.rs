@@ -0,0 +1,217 @@
+use super::plan::Aggregate;
+use serde_json::Value;
+use std::cmp::Ordering;
+
+/// Compares two values the way Cosmos orders them
+///
+/// Undefined (`None`) sorts first, followed by null, booleans, numbers, and strings.
+pub(crate) fn compare_values(a: Option<&Value>, b: Option<&Value>) -> Ordering {
+    fn rank(value: Option<&Value>) -> u8 {
+        match value {
+            None => 0,
+            Some(Value::Null) => 1,
+            Some(Value::Bool(_)) => 2,
+            Some(Value::Number(_)) => 3,
+            Some(Value::String(_)) => 4,
+            Some(Value::Array(_)) => 5,
+            Some(Value::Object(_)) => 6,
+        }
+    }
+
+    match (a, b) {
+        (Some(Value::Bool(a)), Some(Value::Bool(b))) => a.cmp(b),
+        (Some(Value::Number(a)), Some(Value::Number(b))) => a
+            .as_f64()
+            .partial_cmp(&b.as_f64())
+            .unwrap_or(Ordering::Equal),
+        (Some(Value::String(a)), Some(Value::String(b))) => a.cmp(b),
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+/// Combines the partial aggregates returned by every partition key range
+#[derive(Debug, Clone)]
+pub(crate) struct Aggregator {
+    aggregate: Aggregate,
+    /// `None` once a partial result could not be combined, which makes the result undefined
+    sum: Option<f64>,
+    count: u64,
+    extreme: Option<Value>,
+}
+
+impl Aggregator {
+    pub fn new(aggregate: Aggregate) -> Self {
+        Self {
+            aggregate,
+            sum: Some(0.0),
+            count: 0,
+            extreme: None,
+        }
+    }
+
+    /// Adds the partial result of a partition key range, such as `[{"item": 3}]`
+    pub fn add(&mut self, partial: &Value) {
+        let item = match partial.get(0).and_then(|partial| partial.get("item")) {
+            Some(item) => item,
+            // the range held no document the aggregate applies to
+            None => return,
+        };
+
+        match self.aggregate {
+            Aggregate::Count | Aggregate::Sum => {
+                self.sum = self.sum.zip(item.as_f64()).map(|(sum, item)| sum + item);
+            }
+            Aggregate::Average => {
+                let sum = item.get("sum").and_then(Value::as_f64);
+                let count = item.get("count").and_then(Value::as_u64);
+                match (sum, count) {
+                    (Some(sum), Some(count)) => {
+                        self.sum = self.sum.map(|total| total + sum);
+                        self.count += count;
+                    }
+                    _ => self.sum = None,
+                }
+            }
+            Aggregate::Min | Aggregate::Max => {
+                let key = if self.aggregate == Aggregate::Min {
+                    "min"
+                } else {
+                    "max"
+                };
+                let value = match item {
+                    Value::Object(partial) if partial.contains_key("count") => {
+                        if partial.get("count").and_then(Value::as_u64) == Some(0) {
+                            return;
+                        }
+                        match partial.get(key) {
+                            Some(value) => value,
+                            None => return,
+                        }
+                    }
+                    value => value,
+                };
+                let replace = match &self.extreme {
+                    None => true,
+                    Some(extreme) => {
+                        let ordering = compare_values(Some(value), Some(extreme));
+                        if self.aggregate == Aggregate::Min {
+                            ordering == Ordering::Less
+                        } else {
+                            ordering == Ordering::Greater
+                        }
+                    }
+                };
+                if replace {
+                    self.extreme = Some(value.clone());
+                }
+            }
+        }
+    }
+
+    /// The aggregated value, `None` when it is undefined
+    pub fn result(self) -> Option<Value> {
+        match self.aggregate {
+            Aggregate::Count | Aggregate::Sum => self.sum.map(number),
+            Aggregate::Average if self.count > 0 => {
+                self.sum.map(|sum| number(sum / self.count as f64))
+            }
+            Aggregate::Average => None,
+            Aggregate::Min | Aggregate::Max => self.extreme,
+        }
+    }
+}
+
+/// A JSON number, written as an integer when it has no fractional part
+fn number(value: f64) -> Value {
+    if value.fract() == 0.0 && value.abs() < i64::MAX as f64 {
+        Value::from(value as i64)
+    } else {
+        Value::from(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn aggregate(aggregate: Aggregate, partials: &[Value]) -> Option<Value> {
+        let mut aggregator = Aggregator::new(aggregate);
+        partials.iter().for_each(|partial| aggregator.add(partial));
+        aggregator.result()
+    }
+
+    #[test]
+    fn orders_values_like_cosmos() {
+        let values = [
+            None,
+            Some(json!(null)),
+            Some(json!(false)),
+            Some(json!(true)),
+            Some(json!(-1.5)),
+            Some(json!(2)),
+            Some(json!("a")),
+            Some(json!("b")),
+        ];
+        for (i, a) in values.iter().enumerate() {
+            for (j, b) in values.iter().enumerate() {
+                assert_eq!(compare_values(a.as_ref(), b.as_ref()), i.cmp(&j));
+            }
+        }
+    }
+
+    #[test]
+    fn combines_partial_aggregates() {
+        let counts = [json!([{"item": 3}]), json!([{"item": 4}]), json!([{}])];
+        assert_eq!(aggregate(Aggregate::Count, &counts), Some(json!(7)));
+        assert_eq!(
+            aggregate(
+                Aggregate::Sum,
+                &[json!([{"item": 1.5}]), json!([{"item": 2}])]
+            ),
+            Some(json!(3.5))
+        );
+        assert_eq!(
+            aggregate(
+                Aggregate::Sum,
+                &[json!([{"item": 1}]), json!([{"item": "a"}])]
+            ),
+            None
+        );
+        assert_eq!(
+            aggregate(
+                Aggregate::Average,
+                &[
+                    json!([{"item": {"sum": 6, "count": 2}}]),
+                    json!([{"item": {"sum": 4, "count": 3}}])
+                ]
+            ),
+            Some(json!(2))
+        );
+        assert_eq!(
+            aggregate(
+                Aggregate::Average,
+                &[json!([{"item": {"sum": 0, "count": 0}}])]
+            ),
+            None
+        );
+        assert_eq!(
+            aggregate(
+                Aggregate::Min,
+                &[
+                    json!([{"item": {"min": 4, "count": 2}}]),
+                    json!([{"item": {"min": null, "count": 0}}]),
+                    json!([{"item": {"min": 2, "count": 1}}])
+                ]
+            ),
+            Some(json!(2))
+        );
+        assert_eq!(
+            aggregate(
+                Aggregate::Max,
+                &[json!([{"item": "a"}]), json!([{"item": "c"}])]
+            ),
+            Some(json!("c"))
+        );
+    }
+}
@@ -0,0 +1,800 @@
+//! Executes queries across partition key ranges on the client
+//!
+//! The service executes a query in each partition key range on its own, so a query reading
+//! several ranges needs the client to put the partial results together. The pipeline asks
+//! the service for a query plan, reads the ranges the plan selects, and then merges the
+//! results: `ORDER BY` queries are merge sorted, aggregates are combined, and `DISTINCT`,
+//! `OFFSET`, `LIMIT`, and `TOP` are applied to the merged results.
+//!
+//! The continuation of a page records, for every range, the page being read and how much of
+//! it was returned, so a query can be resumed without holding anything in memory.
+mod aggregate;
+mod plan;
+
+use crate::headers::from_headers::{
+    index_utilization_from_headers_optional, query_metrics_from_headers_optional,
+    request_charge_from_headers,
+};
+use crate::headers::{
+    HEADER_DOCUMENTDB_ISQUERY, HEADER_DOCUMENTDB_POPULATEQUERYMETRICS, HEADER_END_EPK,
+    HEADER_POPULATE_INDEX_METRICS, HEADER_START_EPK,
+};
+use crate::partition_key_hash::murmurhash3_x64_128;
+use crate::prelude::*;
+use crate::resources::document::Query;
+use crate::resources::ResourceType;
+use crate::{CosmosDiagnostics, IndexUtilization, QueryMetrics};
+use aggregate::{compare_values, Aggregator};
+use azure_core::collect_pinned_stream;
+use azure_core::error::{Error, ErrorKind, ResultExt};
+use azure_core::headers::continuation_token_from_headers_optional;
+use azure_core::prelude::*;
+use http::HeaderMap;
+use plan::{get_query_plan, DistinctType, QueryPlan, SortOrder};
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::collections::{HashSet, VecDeque};
+use std::time::Instant;
+
+/// How many results of a `DISTINCT` query on unordered results a continuation remembers.
+///
+/// The continuation of a longer query cannot be resumed: the query must be read in a single
+/// stream, or ordered so that duplicates are adjacent.
+const MAX_CONTINUATION_DISTINCT_RESULTS: usize = 10_000;
+
+/// The options of a query executed across partitions
+#[derive(Debug, Clone)]
+pub(crate) struct CrossPartitionQuery {
+    pub client: CollectionClient,
+    pub query: Query,
+    pub if_match_condition: Option<IfMatchCondition>,
+    pub if_modified_since: Option<IfModifiedSince>,
+    pub consistency_level: Option<ConsistencyLevel>,
+    pub max_item_count: i32,
+    pub parallelize: bool,
+    /// The effective partition keys of a partition key prefix, only those are read
+    pub epk_range: Option<(String, String)>,
+    pub populate_query_metrics: bool,
+    pub populate_index_metrics: bool,
+    pub context: Context,
+}
+
+/// A page of the merged results of a query
+#[derive(Debug)]
+pub(crate) struct QueryPage {
+    pub rid: String,
+    pub documents: Vec<Value>,
+    /// The headers of the last response the page was built from
+    pub headers: HeaderMap,
+    /// The request units charged for the requests made for this page
+    pub charge: f64,
+    /// Where to resume the query, `None` once every result was returned
+    pub continuation: Option<String>,
+    /// The query metrics of the requests made for this page, added together
+    pub query_metrics: Option<QueryMetrics>,
+    /// The index utilization of the last request made for this page that returned one
+    pub index_utilization: Option<IndexUtilization>,
+    /// The attempts made for this page, including planning the query for the first page
+    pub diagnostics: CosmosDiagnostics,
+}
+
+/// The position of a query in a partition key range
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RangePosition {
+    id: String,
+    /// The continuation the page holding the next result is read with, `None` for the first page
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    token: Option<String>,
+    /// How many results of that page were already returned
+    #[serde(default)]
+    skip: usize,
+    #[serde(default)]
+    done: bool,
+    /// The effective partition keys of the range to read, when only a part of it is queried
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    epk: Option<(String, String)>,
+}
+
+/// The continuation of a cross-partition query
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct QueryContinuation {
+    ranges: Vec<RangePosition>,
+    /// How many results must still be skipped
+    #[serde(default)]
+    offset: u64,
+    /// How many results may still be returned
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    limit: Option<u64>,
+    /// The hash of the last result of a `DISTINCT` query on ordered results
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last: Option<String>,
+    /// The hashes of the results returned so far by a `DISTINCT` query on unordered results
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    seen: Vec<String>,
+    /// Whether `seen` was left out because there were too many results to remember
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    seen_truncated: bool,
+}
+
+#[derive(Debug)]
+struct RangeCursor {
+    position: RangePosition,
+    /// The unread results of the page at `position`
+    buffer: VecDeque<Value>,
+    /// Whether `buffer` holds the page at `position`
+    loaded: bool,
+    /// The continuation of the page after the buffered one
+    next_token: Option<String>,
+}
+
+impl RangeCursor {
+    fn new(position: RangePosition) -> Self {
+        Self {
+            position,
+            buffer: VecDeque::new(),
+            loaded: false,
+            next_token: None,
+        }
+    }
+
+    fn needs_page(&self) -> bool {
+        !self.position.done && !self.loaded
+    }
+
+    fn has_results(&self) -> bool {
+        !self.position.done && self.loaded
+    }
+
+    fn load(&mut self, documents: Vec<Value>, next_token: Option<String>) {
+        self.buffer = documents.into_iter().skip(self.position.skip).collect();
+        self.next_token = next_token;
+        self.loaded = true;
+        self.advance_if_drained();
+    }
+
+    fn pop(&mut self) -> Option<Value> {
+        let result = self.buffer.pop_front()?;
+        self.position.skip += 1;
+        self.advance_if_drained();
+        Some(result)
+    }
+
+    fn advance_if_drained(&mut self) {
+        if !self.buffer.is_empty() {
+            return;
+        }
+        match self.next_token.take() {
+            Some(token) => {
+                self.position.token = Some(token);
+                self.position.skip = 0;
+                self.loaded = false;
+            }
+            None => self.position.done = true,
+        }
+    }
+}
+
+/// A response of a partition key range
+struct RangePage {
+    rid: String,
+    documents: Vec<Value>,
+    headers: HeaderMap,
+    continuation: Option<String>,
+    charge: f64,
+    query_metrics: Option<QueryMetrics>,
+    index_utilization: Option<IndexUtilization>,
+    diagnostics: CosmosDiagnostics,
+}
+
+#[derive(Debug)]
+pub(crate) struct QueryPipeline {
+    query: CrossPartitionQuery,
+    plan: QueryPlan,
+    partition_query: Query,
+    cursors: Vec<RangeCursor>,
+    offset: u64,
+    limit: Option<u64>,
+    last: Option<String>,
+    seen: HashSet<String>,
+    rid: String,
+    headers: HeaderMap,
+    continuation: Option<String>,
+    finished: bool,
+    /// The query metrics of the requests made for the current page
+    query_metrics: Option<QueryMetrics>,
+    index_utilization: Option<IndexUtilization>,
+    /// The attempts made for the current page
+    diagnostics: CosmosDiagnostics,
+}
+
+impl QueryPipeline {
+    /// Plans the query and positions it at the start, or where the continuation says
+    pub async fn start(
+        query: CrossPartitionQuery,
+        continuation: Option<String>,
+    ) -> azure_core::error::Result<Self> {
+        let (plan, headers, mut diagnostics) =
+            get_query_plan(&query.client, &query.query, &query.context).await?;
+        let state = match &continuation {
+            Some(continuation) => serde_json::from_str(continuation)
+                .with_context(ErrorKind::DataConversion, || {
+                    format!("invalid query continuation: {}", continuation)
+                })?,
+            None => Self::initial_state(&query, &plan, &mut diagnostics).await?,
+        };
+        if state.seen_truncated {
+            return Err(Error::with_message(ErrorKind::DataConversion, || {
+                format!(
+                    "the query cannot be resumed: the continuation of a DISTINCT query on unordered results remembers at most {} results",
+                    MAX_CONTINUATION_DISTINCT_RESULTS
+                )
+            }));
+        }
+
+        Ok(Self {
+            partition_query: plan.partition_query(&query.query),
+            query,
+            plan,
+            cursors: state.ranges.into_iter().map(RangeCursor::new).collect(),
+            offset: state.offset,
+            limit: state.limit,
+            last: state.last,
+            seen: state.seen.into_iter().collect(),
+            rid: String::new(),
+            headers,
+            continuation,
+            finished: false,
+            query_metrics: None,
+            index_utilization: None,
+            diagnostics,
+        })
+    }
+
+    async fn initial_state(
+        query: &CrossPartitionQuery,
+        plan: &QueryPlan,
+        diagnostics: &mut CosmosDiagnostics,
+    ) -> azure_core::error::Result<QueryContinuation> {
+        let response = query
+            .client
+            .get_partition_key_ranges()
+            .context(query.context.clone())
+            .into_future()
+            .await?;
+        diagnostics.merge(response.diagnostics);
+        let mut ranges: Vec<_> = response
+            .partition_key_ranges
+            .into_iter()
+            .filter(|range| plan.reads(range))
+            .filter(|range| match &query.epk_range {
+                Some((min, max)) => {
+                    min.as_str() < range.max_exclusive.as_str()
+                        && max.as_str() > range.min_exclusive.as_str()
+                }
+                None => true,
+            })
+            .collect();
+        ranges.sort_by(|a, b| a.min_exclusive.cmp(&b.min_exclusive));
+
+        let info = &plan.query_info;
+        let limit = match (info.top, info.limit) {
+            (Some(top), Some(limit)) => Some(top.min(limit)),
+            (top, limit) => top.or(limit),
+        };
+        Ok(QueryContinuation {
+            ranges: ranges
+                .into_iter()
+                .map(|range| {
+                    // clipped to the bounds of the range
+                    let epk = query.epk_range.as_ref().map(|(min, max)| {
+                        (
+                            min.max(&range.min_exclusive).clone(),
+                            max.min(&range.max_exclusive).clone(),
+                        )
+                    });
+                    RangePosition {
+                        id: range.id,
+                        token: None,
+                        skip: 0,
+                        done: false,
+                        epk,
+                    }
+                })
+                .collect(),
+            offset: info.offset.unwrap_or_default(),
+            limit,
+            last: None,
+            seen: Vec::new(),
+            seen_truncated: false,
+        })
+    }
+
+    /// The continuation the next page is read from, `None` for the first page
+    pub fn continuation(&self) -> Option<&str> {
+        if self.finished {
+            return None;
+        }
+        self.continuation.as_deref()
+    }
+
+    /// Reads the next page of merged results
+    pub async fn next_page(&mut self) -> azure_core::error::Result<QueryPage> {
+        let started = Instant::now();
+        let mut charge = 0.0;
+        let documents = if self.plan.query_info.aggregates.is_empty() {
+            self.next_results(&mut charge).await?
+        } else {
+            self.aggregate(&mut charge).await?
+        };
+
+        self.continuation = if self.finished {
+            None
+        } else {
+            Some(self.serialize_state()?)
+        };
+        let mut diagnostics = std::mem::take(&mut self.diagnostics);
+        diagnostics.elapsed += started.elapsed();
+        Ok(QueryPage {
+            rid: self.rid.clone(),
+            documents,
+            headers: self.headers.clone(),
+            charge,
+            continuation: self.continuation.clone(),
+            query_metrics: self.query_metrics.take(),
+            index_utilization: self.index_utilization.take(),
+            diagnostics,
+        })
+    }
+
+    async fn next_results(&mut self, charge: &mut f64) -> azure_core::error::Result<Vec<Value>> {
+        let page_size = if self.query.max_item_count > 0 {
+            self.query.max_item_count as usize
+        } else {
+            usize::MAX
+        };
+        let ordered = !self.plan.query_info.order_by.is_empty();
+
+        let mut documents = Vec::new();
+        while documents.len() < page_size {
+            if self.limit == Some(0) {
+                self.finished = true;
+                break;
+            }
+            let needed = self.ranges_to_read();
+            if !needed.is_empty() {
+                // without a page size, return what was read so far before reading more
+                if !documents.is_empty() && page_size == usize::MAX {
+                    break;
+                }
+                *charge += self.read(needed).await?;
+                continue;
+            }
+
+            let index = match self.next_range() {
+                Some(index) => index,
+                None => {
+                    self.finished = true;
+                    break;
+                }
+            };
+            let result = match self.cursors[index].pop() {
+                Some(result) => result,
+                None => continue,
+            };
+            let result = match (ordered, result) {
+                (true, Value::Object(mut result)) => match result.remove("payload") {
+                    Some(payload) => payload,
+                    // the projection is undefined for this document
+                    None => continue,
+                },
+                (_, result) => result,
+            };
+            if !self.is_distinct(&result) {
+                continue;
+            }
+            if self.offset > 0 {
+                self.offset -= 1;
+                continue;
+            }
+            if let Some(limit) = &mut self.limit {
+                *limit -= 1;
+            }
+            documents.push(result);
+        }
+        Ok(documents)
+    }
+
+    /// Reads every range to the end and combines their partial aggregates
+    async fn aggregate(&mut self, charge: &mut f64) -> azure_core::error::Result<Vec<Value>> {
+        let mut aggregator = Aggregator::new(self.plan.query_info.aggregates[0]);
+        loop {
+            for cursor in &mut self.cursors {
+                while let Some(partial) = cursor.pop() {
+                    aggregator.add(&partial);
+                }
+            }
+            let needed: Vec<_> = (0..self.cursors.len())
+                .filter(|&index| self.cursors[index].needs_page())
+                .collect();
+            if needed.is_empty() {
+                break;
+            }
+            *charge += self.read(needed).await?;
+        }
+
+        self.finished = true;
+        let skip = self.offset.min(1) as usize;
+        let take = self.limit.unwrap_or(u64::MAX).min(1) as usize;
+        Ok(aggregator
+            .result()
+            .into_iter()
+            .skip(skip)
+            .take(take)
+            .collect())
+    }
+
+    /// The ranges that must be read before the next result can be picked
+    fn ranges_to_read(&self) -> Vec<usize> {
+        let needing_page =
+            (0..self.cursors.len()).filter(|&index| self.cursors[index].needs_page());
+        if !self.plan.query_info.order_by.is_empty() || self.query.parallelize {
+            // merging needs the next result of every range
+            return needing_page.collect();
+        }
+        // otherwise the ranges are read one after the other
+        match self.cursors.iter().position(|cursor| !cursor.position.done) {
+            Some(index) if self.cursors[index].needs_page() => vec![index],
+            _ => Vec::new(),
+        }
+    }
+
+    /// The range holding the next result
+    fn next_range(&self) -> Option<usize> {
+        let order_by = &self.plan.query_info.order_by;
+        if order_by.is_empty() {
+            return self.cursors.iter().position(RangeCursor::has_results);
+        }
+        (0..self.cursors.len())
+            .filter(|&index| self.cursors[index].has_results())
+            // the earliest range wins ties so that the order is stable
+            .min_by(|&a, &b| {
+                compare_order_by_items(
+                    order_by,
+                    self.cursors[a].buffer.front(),
+                    self.cursors[b].buffer.front(),
+                )
+                .then(a.cmp(&b))
+            })
+    }
+
+    fn is_distinct(&mut self, result: &Value) -> bool {
+        match self.plan.query_info.distinct_type {
+            DistinctType::None => true,
+            DistinctType::Ordered => {
+                let hash = hash(result);
+                if self.last.as_ref() == Some(&hash) {
+                    false
+                } else {
+                    self.last = Some(hash);
+                    true
+                }
+            }
+            DistinctType::Unordered => self.seen.insert(hash(result)),
+        }
+    }
+
+    /// Reads the next page of the given ranges in parallel and returns the request units charged
+    ///
+    /// A range that was split since the query started is replaced by the ranges it was split
+    /// into, which are read on the next call.
+    async fn read(&mut self, ranges: Vec<usize>) -> azure_core::error::Result<f64> {
+        let elapsed = self.diagnostics.elapsed;
+        let pages = futures::future::join_all(
+            ranges
+                .iter()
+                .map(|&index| self.read_range(&self.cursors[index].position)),
+        )
+        .await;
+
+        let mut charge = 0.0;
+        let mut split = Vec::new();
+        for (index, page) in ranges.into_iter().zip(pages) {
+            let page = match page {
+                Ok(page) => page,
+                Err(error) if is_gone(&error) => {
+                    split.push((index, error));
+                    continue;
+                }
+                Err(error) => return Err(error),
+            };
+            charge += page.charge;
+            if self.rid.is_empty() {
+                self.rid = page.rid;
+            }
+            self.headers = page.headers;
+            if let Some(query_metrics) = page.query_metrics {
+                match &mut self.query_metrics {
+                    Some(total) => total.add(&query_metrics),
+                    None => self.query_metrics = Some(query_metrics),
+                }
+            }
+            if page.index_utilization.is_some() {
+                self.index_utilization = page.index_utilization;
+            }
+            self.diagnostics.merge(page.diagnostics);
+            self.cursors[index].load(page.documents, page.continuation);
+        }
+        if !split.is_empty() {
+            self.split(split).await?;
+        }
+        // the ranges are read in parallel, the time taken is not the sum of their latencies
+        self.diagnostics.elapsed = elapsed;
+        Ok(charge)
+    }
+
+    /// Replaces the ranges that are gone with the ranges they were split into, which resume
+    /// from the same position
+    async fn split(&mut self, mut gone: Vec<(usize, Error)>) -> azure_core::error::Result<()> {
+        let response = self
+            .query
+            .client
+            .get_partition_key_ranges()
+            .context(self.query.context.clone())
+            .into_future()
+            .await?;
+        self.diagnostics.merge(response.diagnostics);
+        let mut ranges = response.partition_key_ranges;
+        ranges.sort_by(|a, b| a.min_exclusive.cmp(&b.min_exclusive));
+
+        // from the last so that the indices of the others stay valid
+        gone.sort_by_key(|(index, _)| std::cmp::Reverse(*index));
+        for (index, error) in gone {
+            if let Some(diagnostics) = CosmosDiagnostics::from_error(&error) {
+                self.diagnostics.merge(diagnostics.clone());
+            }
+            let parent = self.cursors[index].position.clone();
+            let children: Vec<_> = ranges
+                .iter()
+                .filter(|range| range.parents.contains(&parent.id))
+                .filter_map(|range| {
+                    let epk = match &parent.epk {
+                        Some((min, max)) => {
+                            let min = min.max(&range.min_exclusive).clone();
+                            let max = max.min(&range.max_exclusive).clone();
+                            if min >= max {
+                                return None;
+                            }
+                            Some((min, max))
+                        }
+                        None => None,
+                    };
+                    Some(RangeCursor::new(RangePosition {
+                        id: range.id.clone(),
+                        epk,
+                        ..parent.clone()
+                    }))
+                })
+                .collect();
+            if children.is_empty() {
+                return Err(error);
+            }
+            debug!(
+                "partition key range {} was split into {:?}",
+                parent.id,
+                children
+                    .iter()
+                    .map(|child| &child.position.id)
+                    .collect::<Vec<_>>()
+            );
+            self.cursors.splice(index..=index, children);
+        }
+        Ok(())
+    }
+
+    async fn read_range(&self, position: &RangePosition) -> azure_core::error::Result<RangePage> {
+        let client = &self.query.client;
+        let mut request = client.prepare_doc_request_pipeline(http::Method::POST);
+        request.headers_mut().insert(
+            HEADER_DOCUMENTDB_ISQUERY,
+            http::HeaderValue::from_static("True"),
+        );
+        request.headers_mut().insert(
+            http::header::CONTENT_TYPE,
+            http::HeaderValue::from_static("application/query+json"),
+        );
+        request.insert_headers(&QueryCrossPartition::Yes);
+        request.insert_headers(&PartitionRangeId::new(position.id.clone()));
+        request.insert_headers(&MaxItemCount::new(self.query.max_item_count));
+        request.insert_headers(&self.query.if_match_condition);
+        request.insert_headers(&self.query.if_modified_since);
+        if let Some(cl) = &self.query.consistency_level {
+            request.insert_headers(cl);
+        }
+        if let Some(token) = &position.token {
+            request.insert_headers(&Continuation::new(token.clone()));
+        }
+        if self.query.populate_query_metrics {
+            request.headers_mut().insert(
+                HEADER_DOCUMENTDB_POPULATEQUERYMETRICS,
+                http::HeaderValue::from_static("true"),
+            );
+        }
+        if self.query.populate_index_metrics {
+            request.headers_mut().insert(
+                HEADER_POPULATE_INDEX_METRICS,
+                http::HeaderValue::from_static("true"),
+            );
+        }
+        if let Some((start, end)) = &position.epk {
+            request
+                .headers_mut()
+                .insert(HEADER_START_EPK, http::HeaderValue::from_str(start)?);
+            request
+                .headers_mut()
+                .insert(HEADER_END_EPK, http::HeaderValue::from_str(end)?);
+        }
+        request.set_body(azure_core::to_json(&self.partition_query)?);
+
+        let (response, diagnostics) = crate::diagnostics::send(
+            client.pipeline(),
+            self.query.context.clone().insert(ResourceType::Documents),
+            &mut request,
+        )
+        .await?;
+        let (_status_code, headers, pinned_stream) = response.deconstruct();
+        let body = collect_pinned_stream(pinned_stream).await?;
+
+        #[derive(Deserialize)]
+        struct Response {
+            #[serde(rename = "_rid", default)]
+            rid: String,
+            #[serde(rename = "Documents")]
+            documents: Vec<Value>,
+        }
+        let response: Response = serde_json::from_slice(&body)?;
+
+        Ok(RangePage {
+            rid: response.rid,
+            documents: response.documents,
+            continuation: continuation_token_from_headers_optional(&headers)?,
+            charge: request_charge_from_headers(&headers).unwrap_or_default(),
+            query_metrics: query_metrics_from_headers_optional(&headers),
+            index_utilization: index_utilization_from_headers_optional(&headers),
+            headers,
+            diagnostics,
+        })
+    }
+
+    fn serialize_state(&self) -> azure_core::error::Result<String> {
+        let (seen, seen_truncated) = continuation_seen(&self.seen);
+        let state = QueryContinuation {
+            ranges: self
+                .cursors
+                .iter()
+                .map(|cursor| cursor.position.clone())
+                .collect(),
+            offset: self.offset,
+            limit: self.limit,
+            last: self.last.clone(),
+            seen,
+            seen_truncated,
+        };
+        serde_json::to_string(&state).context(
+            ErrorKind::DataConversion,
+            "could not serialize the query continuation",
+        )
+    }
+}
+
+/// The sorted hashes of the `DISTINCT` results a continuation remembers, and whether there
+/// were too many to remember
+fn continuation_seen(seen: &HashSet<String>) -> (Vec<String>, bool) {
+    if seen.len() > MAX_CONTINUATION_DISTINCT_RESULTS {
+        return (Vec::new(), true);
+    }
+    let mut seen: Vec<_> = seen.iter().cloned().collect();
+    seen.sort();
+    (seen, false)
+}
+
+/// Whether the request failed because its partition key range is gone, such as after a split
+fn is_gone(error: &Error) -> bool {
+    matches!(error.kind(), ErrorKind::HttpResponse { status, .. } if *status == http::StatusCode::GONE.as_u16())
+}
+
+/// Compares the sort keys of two `ORDER BY` results, such as `{"orderByItems":[{"item":1}]}`
+fn compare_order_by_items(
+    order_by: &[SortOrder],
+    a: Option<&Value>,
+    b: Option<&Value>,
+) -> Ordering {
+    let items = |result: Option<&Value>| {
+        result
+            .and_then(|result| result.get("orderByItems"))
+            .and_then(Value::as_array)
+            .cloned()
+            .unwrap_or_default()
+    };
+    let (a, b) = (items(a), items(b));
+    for (index, order) in order_by.iter().enumerate() {
+        let item = |items: &[Value]| items.get(index).and_then(|item| item.get("item")).cloned();
+        let ordering = compare_values(item(&a).as_ref(), item(&b).as_ref());
+        let ordering = match order {
+            SortOrder::Ascending => ordering,
+            SortOrder::Descending => ordering.reverse(),
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+/// A hash identifying a result of a `DISTINCT` query
+fn hash(value: &Value) -> String {
+    let (h1, h2) = murmurhash3_x64_128(value.to_string().as_bytes(), 0);
+    format!("{:016x}{:016x}", h1, h2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn resumes_from_a_position_inside_a_page() {
+        let mut cursor = RangeCursor::new(RangePosition {
+            id: "0".to_owned(),
+            token: Some("page2".to_owned()),
+            skip: 1,
+            done: false,
+            epk: None,
+        });
+        assert!(cursor.needs_page());
+
+        cursor.load(vec![json!(1), json!(2), json!(3)], Some("page3".to_owned()));
+        assert_eq!(cursor.pop(), Some(json!(2)));
+        assert_eq!(cursor.position.skip, 2);
+        assert_eq!(cursor.pop(), Some(json!(3)));
+        assert!(cursor.needs_page());
+        assert_eq!(cursor.position.token.as_deref(), Some("page3"));
+        assert_eq!(cursor.position.skip, 0);
+
+        cursor.load(vec![], None);
+        assert!(cursor.position.done);
+        assert!(!cursor.needs_page());
+    }
+
+    #[test]
+    fn truncates_the_distinct_results_of_continuations() {
+        let mut seen: HashSet<_> = (0..MAX_CONTINUATION_DISTINCT_RESULTS)
+            .map(|n| hash(&json!(n)))
+            .collect();
+        let (hashes, truncated) = continuation_seen(&seen);
+        assert_eq!(hashes.len(), MAX_CONTINUATION_DISTINCT_RESULTS);
+        assert!(!truncated);
+
+        seen.insert(hash(&json!("one more")));
+        assert_eq!(continuation_seen(&seen), (Vec::new(), true));
+    }
+
+    #[test]
+    fn compares_order_by_items() {
+        let a = json!({"orderByItems": [{"item": 1}, {"item": "b"}]});
+        let b = json!({"orderByItems": [{"item": 1}, {"item": "a"}]});
+        let c = json!({"orderByItems": [{}, {"item": "z"}]});
+        let orders = [SortOrder::Ascending, SortOrder::Descending];
+        assert_eq!(
+            compare_order_by_items(&orders, Some(&a), Some(&b)),
+            Ordering::Less
+        );
+        assert_eq!(
+            compare_order_by_items(&orders, Some(&c), Some(&a)),
+            Ordering::Less
+        );
+        assert_eq!(
+            compare_order_by_items(&orders[..1], Some(&a), Some(&b)),
+            Ordering::Equal
+        );
+    }
+}
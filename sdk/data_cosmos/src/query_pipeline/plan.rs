@@ -0,0 +1,215 @@
+use crate::headers::{
+    HEADER_DOCUMENTDB_ISQUERY, HEADER_IS_QUERY_PLAN_REQUEST, HEADER_QUERY_VERSION,
+    HEADER_SUPPORTED_QUERY_FEATURES,
+};
+use crate::operations::PartitionKeyRange;
+use crate::prelude::*;
+use crate::resources::document::Query;
+use crate::resources::ResourceType;
+use crate::CosmosDiagnostics;
+use azure_core::error::{Error, ErrorKind, ResultExt};
+use azure_core::{collect_pinned_stream, Context};
+use http::HeaderMap;
+
+/// The query features the pipeline can execute on the client.
+///
+/// The service refuses to plan queries needing anything else, such as `GROUP BY`.
+const SUPPORTED_QUERY_FEATURES: &str =
+    "Aggregate, Distinct, MultipleOrderBy, OffsetAndLimit, OrderBy, Top";
+const QUERY_VERSION: &str = "1.4";
+
+/// Where the service replaces the filter resuming an `ORDER BY` query
+const ORDER_BY_FILTER: &str = "{documentdb-formattableorderbyquery-filter}";
+
+/// How the service says a query must be executed across partitions
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct QueryPlan {
+    pub query_info: QueryInfo,
+    pub query_ranges: Vec<QueryRange>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct QueryInfo {
+    #[serde(default)]
+    pub distinct_type: DistinctType,
+    pub top: Option<u64>,
+    pub offset: Option<u64>,
+    pub limit: Option<u64>,
+    #[serde(default)]
+    pub order_by: Vec<SortOrder>,
+    #[serde(default)]
+    pub aggregates: Vec<Aggregate>,
+    #[serde(default)]
+    pub group_by_expressions: Vec<String>,
+    #[serde(default)]
+    pub rewritten_query: String,
+    #[serde(default)]
+    pub has_select_value: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+pub(crate) enum DistinctType {
+    #[default]
+    None,
+    Ordered,
+    Unordered,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub(crate) enum SortOrder {
+    Ascending,
+    Descending,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub(crate) enum Aggregate {
+    Average,
+    Count,
+    Max,
+    Min,
+    Sum,
+}
+
+/// A range of effective partition keys the query must read
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct QueryRange {
+    pub min: String,
+    pub max: String,
+    #[serde(default)]
+    pub is_max_inclusive: bool,
+}
+
+impl QueryRange {
+    /// Whether the range holds any effective partition key of the partition key range
+    pub fn overlaps(&self, range: &PartitionKeyRange) -> bool {
+        let starts_before_end = self.min.as_str() < range.max_exclusive.as_str();
+        let ends_after_start = if self.is_max_inclusive {
+            self.max.as_str() >= range.min_exclusive.as_str()
+        } else {
+            self.max.as_str() > range.min_exclusive.as_str()
+        };
+        starts_before_end && ends_after_start
+    }
+}
+
+impl QueryPlan {
+    /// Fails for the queries the pipeline cannot execute correctly
+    pub fn validate(&self) -> azure_core::error::Result<()> {
+        let info = &self.query_info;
+        if !info.group_by_expressions.is_empty() {
+            return Err(Error::message(
+                ErrorKind::Other,
+                "GROUP BY is not supported in cross-partition queries",
+            ));
+        }
+        if !info.aggregates.is_empty() && (!info.has_select_value || info.aggregates.len() > 1) {
+            return Err(Error::message(
+                ErrorKind::Other,
+                "cross-partition aggregates are only supported as a single SELECT VALUE",
+            ));
+        }
+        Ok(())
+    }
+
+    /// The query to send to every partition key range
+    pub fn partition_query(&self, query: &Query) -> Query {
+        if self.query_info.rewritten_query.is_empty() {
+            return query.clone();
+        }
+        Query::with_params(
+            self.query_info
+                .rewritten_query
+                .replace(ORDER_BY_FILTER, "true"),
+            query.params().to_vec(),
+        )
+    }
+
+    /// Whether the partition key range holds documents the query must read
+    pub fn reads(&self, range: &PartitionKeyRange) -> bool {
+        self.query_ranges
+            .iter()
+            .any(|query_range| query_range.overlaps(range))
+    }
+}
+
+/// Asks the service how to execute the query across partitions
+pub(crate) async fn get_query_plan(
+    client: &CollectionClient,
+    query: &Query,
+    context: &Context,
+) -> azure_core::error::Result<(QueryPlan, HeaderMap, CosmosDiagnostics)> {
+    let mut request = client.prepare_doc_request_pipeline(http::Method::POST);
+    for (header, value) in [
+        (HEADER_DOCUMENTDB_ISQUERY, "True"),
+        (HEADER_IS_QUERY_PLAN_REQUEST, "True"),
+        (HEADER_SUPPORTED_QUERY_FEATURES, SUPPORTED_QUERY_FEATURES),
+        (HEADER_QUERY_VERSION, QUERY_VERSION),
+    ] {
+        request
+            .headers_mut()
+            .insert(header, http::HeaderValue::from_static(value));
+    }
+    request.headers_mut().insert(
+        http::header::CONTENT_TYPE,
+        http::HeaderValue::from_static("application/query+json"),
+    );
+    request.insert_headers(&QueryCrossPartition::Yes);
+    request.set_body(azure_core::to_json(query)?);
+
+    let (response, diagnostics) = crate::diagnostics::send(
+        client.pipeline(),
+        context.clone().insert(ResourceType::Documents),
+        &mut request,
+    )
+    .await?;
+    let (_status_code, headers, pinned_stream) = response.deconstruct();
+    let body = collect_pinned_stream(pinned_stream).await?;
+    let plan: QueryPlan = serde_json::from_slice(&body)
+        .context(ErrorKind::DataConversion, "could not parse the query plan")?;
+    plan.validate()?;
+    Ok((plan, headers, diagnostics))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_query_plan() {
+        let plan: QueryPlan = serde_json::from_str(
+            r#"{"partitionedQueryExecutionInfoVersion":2,"queryInfo":{"distinctType":"None","top":null,"offset":2,"limit":5,"orderBy":["Descending"],"orderByExpressions":["c.n"],"groupByExpressions":[],"groupByAliases":[],"aggregates":[],"groupByAliasToAggregateType":{},"rewrittenQuery":"SELECT c._rid, [{\"item\": c.n}] AS orderByItems, c AS payload FROM c WHERE ({documentdb-formattableorderbyquery-filter}) ORDER BY c.n DESC","hasSelectValue":false},"queryRanges":[{"min":"","max":"FF","isMinInclusive":true,"isMaxInclusive":false}]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(plan.query_info.order_by, [SortOrder::Descending]);
+        assert_eq!(plan.query_info.offset, Some(2));
+        assert_eq!(plan.query_info.limit, Some(5));
+        assert!(plan.validate().is_ok());
+        let query = Query::with_params(
+            "SELECT * FROM c ORDER BY c.n DESC".to_owned(),
+            vec![crate::resources::document::Param::new("@a".to_owned(), 1)],
+        );
+        let partition_query = plan.partition_query(&query);
+        assert!(partition_query.query().contains("WHERE (true)"));
+        assert_eq!(partition_query.params().len(), 1);
+    }
+
+    #[test]
+    fn rejects_unsupported_plans() {
+        let mut plan = QueryPlan {
+            query_info: QueryInfo::default(),
+            query_ranges: Vec::new(),
+        };
+        plan.query_info.group_by_expressions = vec!["c.a".to_owned()];
+        assert!(plan.validate().is_err());
+
+        plan.query_info.group_by_expressions.clear();
+        plan.query_info.aggregates = vec![Aggregate::Count];
+        assert!(plan.validate().is_err());
+        plan.query_info.has_select_value = true;
+        assert!(plan.validate().is_ok());
+    }
+}
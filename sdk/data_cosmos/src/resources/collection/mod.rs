@@ -18,6 +18,43 @@ pub struct Collection {
     /// The partition key
     #[serde(rename = "partitionKey")]
     pub parition_key: PartitionKey,
+    /// How conflicting writes from multiple regions are resolved
+    #[serde(
+        rename = "conflictResolutionPolicy",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub conflict_resolution_policy: Option<ConflictResolutionPolicy>,
+    /// The sets of paths whose values must be unique within a logical partition
+    #[serde(
+        rename = "uniqueKeyPolicy",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub unique_key_policy: Option<UniqueKeyPolicy>,
+    /// How many seconds documents live after their last update.
+    ///
+    /// `None` never expires documents, `-1` expires only the documents with their own `ttl`.
+    #[serde(
+        rename = "defaultTtl",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub default_ttl: Option<i64>,
+    /// How many seconds documents live in the analytical store, `-1` to keep them forever
+    #[serde(
+        rename = "analyticalStorageTtl",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub analytical_storage_ttl: Option<i64>,
+    /// How long the change feed keeps the intermediate versions and deletes of documents
+    #[serde(
+        rename = "changeFeedPolicy",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub change_feed_policy: Option<ChangeFeedPolicy>,
     /// The resource id
     #[serde(rename = "_rid")]
     pub rid: String,
@@ -68,6 +105,8 @@ pub enum KeyKind {
     Range,
     /// useful for spatial queries
     Spatial,
+    /// hierarchical partitioning on up to three paths
+    MultiHash,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialOrd, PartialEq)]
@@ -127,6 +166,9 @@ impl From<String> for ExcludedPath {
     }
 }
 
+/// The most paths a hierarchical partition key can have
+pub const MAX_HIERARCHICAL_PARTITION_KEY_PATHS: usize = 3;
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialOrd, PartialEq)]
 /// The partitioning configuration settings for collection
 pub struct PartitionKey {
@@ -134,6 +176,45 @@ pub struct PartitionKey {
     pub paths: Vec<String>,
     /// The algorithm used for partitioning
     pub kind: KeyKind,
+    /// The version of the hashing, 2 for hierarchical partition keys
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<u8>,
+}
+
+impl PartitionKey {
+    /// A hierarchical partition key on up to three paths, such as `["/tenantId", "/userId"]`.
+    ///
+    /// Documents are identified by the values of all the paths, and queries can target a
+    /// prefix of them.
+    pub fn hierarchical<I, S>(paths: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            paths: paths.into_iter().map(Into::into).collect(),
+            kind: KeyKind::MultiHash,
+            version: Some(2),
+        }
+    }
+
+    pub(crate) fn validate(&self) -> azure_core::error::Result<()> {
+        if self.kind == KeyKind::MultiHash
+            && (self.paths.is_empty() || self.paths.len() > MAX_HIERARCHICAL_PARTITION_KEY_PATHS)
+        {
+            return Err(azure_core::error::Error::with_message(
+                azure_core::error::ErrorKind::Other,
+                || {
+                    format!(
+                        "a hierarchical partition key has between 1 and {} paths, got {}",
+                        MAX_HIERARCHICAL_PARTITION_KEY_PATHS,
+                        self.paths.len()
+                    )
+                },
+            ));
+        }
+        Ok(())
+    }
 }
 
 impl std::default::Default for PartitionKey {
@@ -141,6 +222,7 @@ impl std::default::Default for PartitionKey {
         Self {
             paths: vec![],
             kind: KeyKind::Hash,
+            version: None,
         }
     }
 }
@@ -153,6 +235,53 @@ where
         Self {
             paths: vec![t.as_ref().to_owned()],
             kind: KeyKind::Hash,
+            version: None,
+        }
+    }
+}
+
+/// How conflicting writes accepted by different regions are resolved.
+///
+/// Conflicts that are not resolved end up in the conflicts feed of the collection.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialOrd, PartialEq)]
+#[serde(tag = "mode")]
+pub enum ConflictResolutionPolicy {
+    /// The write with the highest value at the path wins
+    LastWriterWins {
+        /// A numeric path of the documents, `/_ts` when empty
+        #[serde(rename = "conflictResolutionPath", default)]
+        path: String,
+    },
+    /// A stored procedure resolves the conflicts
+    Custom {
+        /// The link of the stored procedure, such as `dbs/db/colls/coll/sprocs/resolver`.
+        /// When empty every conflict is added to the conflicts feed.
+        #[serde(
+            rename = "conflictResolutionProcedure",
+            default,
+            skip_serializing_if = "String::is_empty"
+        )]
+        procedure: String,
+    },
+}
+
+impl ConflictResolutionPolicy {
+    /// The write with the highest value at the path wins, such as `/_ts`
+    pub fn last_writer_wins<S: Into<String>>(path: S) -> Self {
+        Self::LastWriterWins { path: path.into() }
+    }
+
+    /// The stored procedure with the given link resolves the conflicts
+    pub fn custom<S: Into<String>>(procedure: S) -> Self {
+        Self::Custom {
+            procedure: procedure.into(),
+        }
+    }
+
+    /// Every conflict is added to the conflicts feed, to be resolved by the application
+    pub fn manual() -> Self {
+        Self::Custom {
+            procedure: String::new(),
         }
     }
 }
@@ -166,7 +295,179 @@ pub struct IndexingPolicy {
     /// The mode of indexing
     pub indexing_mode: IndexingMode,
     /// Array containing document paths to be indexed
+    #[serde(default)]
     pub included_paths: Vec<IncludedPath>,
     /// Array containing document paths to be excluded from indexing
+    #[serde(default)]
     pub excluded_paths: Vec<ExcludedPath>,
+    /// Indexes on several paths, used by queries ordering or filtering on all of them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub composite_indexes: Vec<Vec<CompositePath>>,
+    /// Indexes of the geospatial values of paths
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub spatial_indexes: Vec<SpatialIndex>,
+}
+
+/// A path of a composite index
+#[derive(Serialize, Deserialize, Clone, Debug, PartialOrd, PartialEq)]
+pub struct CompositePath {
+    /// The path, such as `/name`
+    pub path: String,
+    /// The order of the values of the path in the index
+    pub order: CompositePathOrder,
+}
+
+impl CompositePath {
+    /// A path of a composite index, such as `/name`
+    pub fn new<S: Into<String>>(path: S, order: CompositePathOrder) -> Self {
+        Self {
+            path: path.into(),
+            order,
+        }
+    }
+}
+
+/// The order of the values of a path in a composite index
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialOrd, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CompositePathOrder {
+    /// smallest first
+    Ascending,
+    /// largest first
+    Descending,
+}
+
+/// A spatial index of a path
+#[derive(Serialize, Deserialize, Clone, Debug, PartialOrd, PartialEq)]
+pub struct SpatialIndex {
+    /// The path, such as `/location/?`
+    pub path: String,
+    /// The types of the geospatial values indexed
+    pub types: Vec<SpatialType>,
+}
+
+impl SpatialIndex {
+    /// A spatial index of the values of the given types at the path, such as `/location/?`
+    pub fn new<S: Into<String>>(path: S, types: Vec<SpatialType>) -> Self {
+        Self {
+            path: path.into(),
+            types,
+        }
+    }
+}
+
+/// The type of a geospatial value
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialOrd, PartialEq, Eq)]
+#[allow(missing_docs)]
+pub enum SpatialType {
+    Point,
+    LineString,
+    Polygon,
+    MultiPolygon,
+}
+
+/// The sets of paths whose values must be unique within a logical partition.
+///
+/// The policy is set when the collection is created and cannot be changed afterwards.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialOrd, PartialEq)]
+pub struct UniqueKeyPolicy {
+    /// The unique keys
+    #[serde(rename = "uniqueKeys", default)]
+    pub unique_keys: Vec<UniqueKey>,
+}
+
+/// A set of paths whose combined values must be unique within a logical partition
+#[derive(Serialize, Deserialize, Clone, Debug, PartialOrd, PartialEq)]
+pub struct UniqueKey {
+    /// The paths, such as `/address/zipCode`
+    pub paths: Vec<String>,
+}
+
+impl UniqueKeyPolicy {
+    /// Adds a unique key on the given paths
+    pub fn unique_key<I, S>(mut self, paths: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.unique_keys.push(UniqueKey {
+            paths: paths.into_iter().map(Into::into).collect(),
+        });
+        self
+    }
+}
+
+/// How the change feed keeps the history of the documents
+#[derive(Serialize, Deserialize, Clone, Debug, PartialOrd, PartialEq)]
+pub struct ChangeFeedPolicy {
+    /// How many minutes the intermediate versions and deletes of documents are kept, `0`
+    /// to keep only the latest version
+    #[serde(rename = "retentionDuration")]
+    pub retention_duration: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_the_container_definition() {
+        let definition = serde_json::json!({
+            "id": "coll",
+            "indexingPolicy": {
+                "automatic": true,
+                "indexingMode": "consistent",
+                "includedPaths": [{"path": "/*"}],
+                "excludedPaths": [{"path": "/\"_etag\"/?"}],
+                "compositeIndexes": [[
+                    {"path": "/name", "order": "ascending"},
+                    {"path": "/age", "order": "descending"}
+                ]],
+                "spatialIndexes": [{"path": "/location/*", "types": ["Point", "Polygon"]}]
+            },
+            "partitionKey": {"paths": ["/pk"], "kind": "Hash"},
+            "uniqueKeyPolicy": {"uniqueKeys": [{"paths": ["/email"]}]},
+            "defaultTtl": -1,
+            "analyticalStorageTtl": 86400,
+            "changeFeedPolicy": {"retentionDuration": 10},
+            "_rid": "a",
+            "_ts": 1,
+            "_self": "b",
+            "_etag": "c",
+            "_docs": "docs/",
+            "_sprocs": "sprocs/",
+            "_triggers": "triggers/",
+            "_udfs": "udfs/",
+            "_conflicts": "conflicts/"
+        });
+
+        let collection: Collection = serde_json::from_value(definition.clone()).unwrap();
+        assert_eq!(
+            collection.indexing_policy.composite_indexes,
+            [vec![
+                CompositePath::new("/name", CompositePathOrder::Ascending),
+                CompositePath::new("/age", CompositePathOrder::Descending)
+            ]]
+        );
+        assert_eq!(
+            collection.indexing_policy.spatial_indexes,
+            [SpatialIndex::new(
+                "/location/*",
+                vec![SpatialType::Point, SpatialType::Polygon]
+            )]
+        );
+        assert_eq!(
+            collection.unique_key_policy,
+            Some(UniqueKeyPolicy::default().unique_key(["/email"]))
+        );
+        assert_eq!(collection.default_ttl, Some(-1));
+        assert_eq!(collection.analytical_storage_ttl, Some(86400));
+        assert_eq!(
+            collection.change_feed_policy,
+            Some(ChangeFeedPolicy {
+                retention_duration: 10
+            })
+        );
+        assert_eq!(serde_json::to_value(&collection).unwrap(), definition);
+    }
 }
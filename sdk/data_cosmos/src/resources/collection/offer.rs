@@ -1,13 +1,15 @@
 use crate::headers;
 use azure_core::Header;
 
-/// The collection performance level.
+/// The collection or database performance level.
 ///
-/// It can either be custom or fixed. You can find more details [here](https://docs.microsoft.com/rest/api/cosmos-db/create-a-collection).
+/// It can either be custom, autoscale, or fixed. You can find more details [here](https://docs.microsoft.com/rest/api/cosmos-db/create-a-collection).
 #[derive(Debug, Clone, PartialEq, Copy)]
 pub enum Offer {
     /// A Custom level of throughput
     Throughput(u64),
+    /// Throughput scaling automatically up to the given request units per second
+    Autoscale(u64),
     /// Legacy throughput level 1
     S1,
     /// Legacy throughput level 2
@@ -20,6 +22,7 @@ impl Header for Offer {
     fn name(&self) -> azure_core::headers::HeaderName {
         match self {
             Offer::Throughput(_) => headers::HEADER_OFFER_THROUGHPUT,
+            Offer::Autoscale(_) => headers::HEADER_OFFER_AUTOPILOT_SETTINGS,
             _ => headers::HEADER_OFFER_TYPE,
         }
         .into()
@@ -28,6 +31,9 @@ impl Header for Offer {
     fn value(&self) -> azure_core::headers::HeaderValue {
         match self {
             Offer::Throughput(throughput) => throughput.to_string(),
+            Offer::Autoscale(max_throughput) => {
+                format!(r#"{{"maxThroughput":{}}}"#, max_throughput)
+            }
             Offer::S1 => "S1".to_owned(),
             Offer::S2 => "S2".to_owned(),
             Offer::S3 => "S3".to_owned(),
@@ -0,0 +1,76 @@
+//! Utilities for interacting with [`Conflict`]s.
+
+use azure_core::error::{ErrorKind, ResultExt};
+use serde::de::DeserializeOwned;
+
+/// A write that conflicted with another write accepted by a different region and that the
+/// conflict resolution policy of the collection did not resolve.
+///
+/// You can learn more about conflicts [here](https://docs.microsoft.com/rest/api/cosmos-db/conflicts).
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Conflict {
+    /// The conflict id
+    pub id: String,
+    /// The resource id
+    #[serde(rename = "_rid")]
+    pub rid: String,
+    /// The last updated timestamp
+    #[serde(rename = "_ts")]
+    pub ts: u64,
+    /// The resource's uri
+    #[serde(rename = "_self")]
+    pub _self: String,
+    /// The resource's etag used for concurrency control
+    #[serde(rename = "_etag")]
+    pub etag: String,
+    /// The resource id of the conflicting resource
+    #[serde(rename = "resourceId")]
+    pub resource_id: String,
+    /// The type of the conflicting resource, such as `document`
+    #[serde(rename = "resourceType")]
+    pub resource_type: String,
+    /// The operation that conflicted
+    #[serde(rename = "operationType")]
+    pub operation_type: ConflictOperation,
+    /// The conflicting version of the resource, serialized as JSON
+    pub content: String,
+}
+
+impl Conflict {
+    /// Deserializes the conflicting version of the resource
+    pub fn content<T: DeserializeOwned>(&self) -> azure_core::error::Result<T> {
+        serde_json::from_str(&self.content).context(
+            ErrorKind::DataConversion,
+            "could not parse the content of the conflict",
+        )
+    }
+}
+
+/// The operation that conflicted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictOperation {
+    /// The resource was created
+    Create,
+    /// The resource was replaced
+    Replace,
+    /// The resource was deleted
+    Delete,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_conflict_content() {
+        let conflict: Conflict = serde_json::from_str(
+            r#"{"id":"K1YCAJOu3yY=","_rid":"K1YCAJOu3yYBAAAAAAAAAA==","_self":"dbs/K1YCAA==/colls/K1YCAJOu3yY=/conflicts/K1YCAJOu3yYBAAAAAAAAAA==/","_etag":"\"00000000-0000-0000-0000-000000000000\"","_ts":1637246022,"resourceId":"K1YCAJOu3yYBAAAAAAAAAA==","resourceType":"document","operationType":"replace","content":"{\"id\":\"doc\",\"value\":2}"}"#,
+        )
+        .unwrap();
+
+        assert_eq!(conflict.operation_type, ConflictOperation::Replace);
+        let content: serde_json::Value = conflict.content().unwrap();
+        assert_eq!(content["value"], 2);
+    }
+}
@@ -2,11 +2,16 @@
 
 mod document_attributes;
 mod indexing_directive;
+mod patch_operation;
 mod query;
+mod query_builder;
 
 pub use document_attributes::DocumentAttributes;
 pub use indexing_directive::IndexingDirective;
+pub(crate) use patch_operation::validate_patch_operations;
+pub use patch_operation::{PatchOperation, MAX_PATCH_OPERATIONS};
 pub use query::{Param, Query};
+pub use query_builder::{Condition, QueryBuilder, SortOrder};
 
 use super::Resource;
 use crate::headers;
@@ -0,0 +1,214 @@
+use azure_core::error::{Error, ErrorKind, Result};
+use serde_json::Value;
+
+/// The maximum number of operations in a single patch request
+pub const MAX_PATCH_OPERATIONS: usize = 10;
+
+/// An operation of a partial document update
+///
+/// Paths are JSON pointers into the document, such as `/address/city` or `/tags/0`.
+/// You can learn more about partial document updates [here](https://docs.microsoft.com/azure/cosmos-db/partial-document-update).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum PatchOperation {
+    /// Adds a property, or inserts an element into an array
+    Add {
+        /// Where to add the value
+        path: String,
+        /// The value to add
+        value: Value,
+    },
+    /// Sets a property, creating it if it does not exist
+    Set {
+        /// The property to set
+        path: String,
+        /// The new value
+        value: Value,
+    },
+    /// Replaces a property that must exist
+    Replace {
+        /// The property to replace
+        path: String,
+        /// The new value
+        value: Value,
+    },
+    /// Removes a property or an array element
+    Remove {
+        /// The property to remove
+        path: String,
+    },
+    /// Increments a numeric property, creating it if it does not exist
+    #[serde(rename = "incr")]
+    Increment {
+        /// The property to increment
+        path: String,
+        /// The number to add, which may be negative
+        value: Value,
+    },
+    /// Moves a property to another path
+    Move {
+        /// The property to move
+        from: String,
+        /// Where to move the property
+        path: String,
+    },
+}
+
+impl PatchOperation {
+    /// Adds a property, or inserts an element into an array
+    pub fn add<P: Into<String>, V: Into<Value>>(path: P, value: V) -> Self {
+        Self::Add {
+            path: path.into(),
+            value: value.into(),
+        }
+    }
+
+    /// Sets a property, creating it if it does not exist
+    pub fn set<P: Into<String>, V: Into<Value>>(path: P, value: V) -> Self {
+        Self::Set {
+            path: path.into(),
+            value: value.into(),
+        }
+    }
+
+    /// Replaces a property that must exist
+    pub fn replace<P: Into<String>, V: Into<Value>>(path: P, value: V) -> Self {
+        Self::Replace {
+            path: path.into(),
+            value: value.into(),
+        }
+    }
+
+    /// Removes a property or an array element
+    pub fn remove<P: Into<String>>(path: P) -> Self {
+        Self::Remove { path: path.into() }
+    }
+
+    /// Increments a numeric property, creating it if it does not exist
+    pub fn increment<P: Into<String>, V: Into<Value>>(path: P, value: V) -> Self {
+        Self::Increment {
+            path: path.into(),
+            value: value.into(),
+        }
+    }
+
+    /// Moves a property to another path
+    pub fn move_from<F: Into<String>, P: Into<String>>(from: F, path: P) -> Self {
+        Self::Move {
+            from: from.into(),
+            path: path.into(),
+        }
+    }
+
+    /// The path the operation changes
+    pub fn path(&self) -> &str {
+        match self {
+            Self::Add { path, .. }
+            | Self::Set { path, .. }
+            | Self::Replace { path, .. }
+            | Self::Remove { path }
+            | Self::Increment { path, .. }
+            | Self::Move { path, .. } => path,
+        }
+    }
+
+    /// Checks the operation before it is sent
+    pub(crate) fn validate(&self) -> Result<()> {
+        validate_path(self.path())?;
+        match self {
+            Self::Increment { path, value } if !value.is_number() => {
+                Err(Error::with_message(ErrorKind::Other, || {
+                    format!("cannot increment {} by {}: not a number", path, value)
+                }))
+            }
+            Self::Move { from, path } => {
+                validate_path(from)?;
+                if from == path {
+                    return Err(Error::with_message(ErrorKind::Other, || {
+                        format!("cannot move {} onto itself", path)
+                    }));
+                }
+                if path.starts_with(&format!("{}/", from)) {
+                    return Err(Error::with_message(ErrorKind::Other, || {
+                        format!("cannot move {} into its own child {}", from, path)
+                    }));
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+fn validate_path(path: &str) -> Result<()> {
+    if path.starts_with('/') && path.len() > 1 {
+        Ok(())
+    } else {
+        Err(Error::with_message(ErrorKind::Other, || {
+            format!("invalid patch path {:?}: must start with '/'", path)
+        }))
+    }
+}
+
+/// Checks the operations of a patch request before it is sent
+pub(crate) fn validate_patch_operations(operations: &[PatchOperation]) -> Result<()> {
+    if operations.is_empty() {
+        return Err(Error::message(
+            ErrorKind::Other,
+            "a patch request needs at least one operation",
+        ));
+    }
+    if operations.len() > MAX_PATCH_OPERATIONS {
+        return Err(Error::with_message(ErrorKind::Other, || {
+            format!(
+                "a patch request supports at most {} operations, got {}",
+                MAX_PATCH_OPERATIONS,
+                operations.len()
+            )
+        }));
+    }
+    operations.iter().try_for_each(PatchOperation::validate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serialize_operations() {
+        let operations = vec![
+            PatchOperation::add("/tags/-", "new"),
+            PatchOperation::set("/name", "pollo"),
+            PatchOperation::replace("/address/city", "Bianco"),
+            PatchOperation::remove("/obsolete"),
+            PatchOperation::increment("/count", -2),
+            PatchOperation::move_from("/old", "/new"),
+        ];
+        assert_eq!(
+            serde_json::to_string(&operations).unwrap(),
+            concat!(
+                r#"[{"op":"add","path":"/tags/-","value":"new"},"#,
+                r#"{"op":"set","path":"/name","value":"pollo"},"#,
+                r#"{"op":"replace","path":"/address/city","value":"Bianco"},"#,
+                r#"{"op":"remove","path":"/obsolete"},"#,
+                r#"{"op":"incr","path":"/count","value":-2},"#,
+                r#"{"op":"move","from":"/old","path":"/new"}]"#
+            )
+        );
+        assert!(validate_patch_operations(&operations).is_ok());
+    }
+
+    #[test]
+    fn validate_operations() {
+        assert!(validate_patch_operations(&[]).is_err());
+        assert!(validate_patch_operations(&vec![PatchOperation::remove("/a"); 11]).is_err());
+        assert!(validate_patch_operations(&vec![PatchOperation::remove("/a"); 10]).is_ok());
+        assert!(PatchOperation::set("name", 1).validate().is_err());
+        assert!(PatchOperation::remove("/").validate().is_err());
+        assert!(PatchOperation::increment("/count", "1").validate().is_err());
+        assert!(PatchOperation::increment("/count", 1.5).validate().is_ok());
+        assert!(PatchOperation::move_from("/a", "/a").validate().is_err());
+        assert!(PatchOperation::move_from("/a", "/a/b").validate().is_err());
+        assert!(PatchOperation::move_from("/a", "/ab").validate().is_ok());
+    }
+}
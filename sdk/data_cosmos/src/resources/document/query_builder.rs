@@ -0,0 +1,559 @@
+use super::{Param, Query};
+use azure_core::error::{Error, ErrorKind};
+use serde::Serialize;
+use serde_json::Value;
+
+/// Builds a parameterized [`Query`] from typed clauses.
+///
+/// Values are never written into the query text: each one is bound to a parameter named
+/// `@p0`, `@p1`, ... in the order it appears. Property paths, such as `c.address.city` or
+/// `c.tags[0]`, are checked so that they cannot change the meaning of the query.
+///
+/// ```
+/// use azure_data_cosmos::prelude::*;
+///
+/// let query = QueryBuilder::new()
+///     .select(["c.id", "c.name"])
+///     .filter(Condition::eq("c.city", "Seattle").and(Condition::array_contains("c.tags", "rust")))
+///     .order_by("c.name", SortOrder::Ascending)
+///     .offset_limit(0, 10)
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(
+///     query.query(),
+///     "SELECT c.id, c.name FROM c WHERE (c.city = @p0 AND ARRAY_CONTAINS(c.tags, @p1)) ORDER BY c.name ASC OFFSET 0 LIMIT 10"
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct QueryBuilder {
+    alias: String,
+    distinct: bool,
+    top: Option<u64>,
+    projection: Projection,
+    filter: Option<Condition>,
+    order_by: Vec<(String, SortOrder)>,
+    offset_limit: Option<(u64, u64)>,
+}
+
+#[derive(Debug, Clone)]
+enum Projection {
+    All,
+    Fields(Vec<String>),
+    Value(String),
+}
+
+/// The direction of an `ORDER BY` clause
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    /// Smallest values first
+    Ascending,
+    /// Largest values first
+    Descending,
+}
+
+impl Default for QueryBuilder {
+    fn default() -> Self {
+        Self {
+            alias: "c".to_owned(),
+            distinct: false,
+            top: None,
+            projection: Projection::All,
+            filter: None,
+            order_by: Vec::new(),
+            offset_limit: None,
+        }
+    }
+}
+
+impl QueryBuilder {
+    /// A query selecting whole documents, `SELECT * FROM c`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The name the documents are referred to by, `c` by default
+    pub fn from<S: Into<String>>(mut self, alias: S) -> Self {
+        self.alias = alias.into();
+        self
+    }
+
+    /// Selects the given properties instead of whole documents
+    pub fn select<I, S>(mut self, paths: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.projection = Projection::Fields(paths.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Selects the value of a single property, `SELECT VALUE c.id`
+    pub fn select_value<S: Into<String>>(mut self, path: S) -> Self {
+        self.projection = Projection::Value(path.into());
+        self
+    }
+
+    /// Removes duplicate results
+    pub fn distinct(mut self) -> Self {
+        self.distinct = true;
+        self
+    }
+
+    /// Returns at most `top` results
+    pub fn top(mut self, top: u64) -> Self {
+        self.top = Some(top);
+        self
+    }
+
+    /// Keeps the documents matching the condition.
+    ///
+    /// Calling `filter` again requires both conditions to match.
+    pub fn filter(mut self, condition: Condition) -> Self {
+        self.filter = Some(match self.filter.take() {
+            Some(filter) => filter.and(condition),
+            None => condition,
+        });
+        self
+    }
+
+    /// Sorts the results by the property, after the previous `order_by` properties
+    pub fn order_by<S: Into<String>>(mut self, path: S, order: SortOrder) -> Self {
+        self.order_by.push((path.into(), order));
+        self
+    }
+
+    /// Skips `offset` results and returns at most `limit` of the others
+    pub fn offset_limit(mut self, offset: u64, limit: u64) -> Self {
+        self.offset_limit = Some((offset, limit));
+        self
+    }
+
+    /// Renders the query and binds its parameters.
+    ///
+    /// Fails if a property path is not valid or a value cannot be serialized.
+    pub fn build(self) -> azure_core::error::Result<Query> {
+        check_identifier(&self.alias)?;
+        let mut text = String::from("SELECT ");
+        if self.distinct {
+            text.push_str("DISTINCT ");
+        }
+        if let Some(top) = self.top {
+            text.push_str(&format!("TOP {} ", top));
+        }
+        match &self.projection {
+            Projection::All => text.push('*'),
+            Projection::Fields(paths) if paths.is_empty() => text.push('*'),
+            Projection::Fields(paths) => {
+                for path in paths {
+                    check_path(path)?;
+                }
+                text.push_str(&paths.join(", "));
+            }
+            Projection::Value(path) => {
+                check_path(path)?;
+                text.push_str("VALUE ");
+                text.push_str(path);
+            }
+        }
+        text.push_str(" FROM ");
+        text.push_str(&self.alias);
+
+        let mut params = Vec::new();
+        if let Some(filter) = &self.filter {
+            text.push_str(" WHERE ");
+            filter.render(&mut text, &mut params)?;
+        }
+        if !self.order_by.is_empty() {
+            let clauses = self
+                .order_by
+                .iter()
+                .map(|(path, order)| {
+                    check_path(path)?;
+                    Ok(match order {
+                        SortOrder::Ascending => format!("{} ASC", path),
+                        SortOrder::Descending => format!("{} DESC", path),
+                    })
+                })
+                .collect::<azure_core::error::Result<Vec<_>>>()?;
+            text.push_str(" ORDER BY ");
+            text.push_str(&clauses.join(", "));
+        }
+        if let Some((offset, limit)) = self.offset_limit {
+            text.push_str(&format!(" OFFSET {} LIMIT {}", offset, limit));
+        }
+
+        Ok(Query::with_params(text, params))
+    }
+}
+
+/// A condition of a `WHERE` clause.
+///
+/// The values compared with are bound as parameters when the query is built.
+#[derive(Debug, Clone)]
+pub struct Condition(Expression);
+
+#[derive(Debug, Clone)]
+enum Expression {
+    Compare(String, &'static str, Operand),
+    In(String, Vec<Operand>),
+    Function(&'static str, String, Vec<Operand>),
+    IsDefined(String),
+    And(Vec<Condition>),
+    Or(Vec<Condition>),
+    Not(Box<Condition>),
+}
+
+/// A value bound as a parameter, or why it could not be serialized
+#[derive(Debug, Clone)]
+struct Operand(Result<Value, String>);
+
+impl Operand {
+    fn new<T: Serialize>(value: T) -> Self {
+        Self(serde_json::to_value(value).map_err(|error| error.to_string()))
+    }
+
+    fn bind(&self, params: &mut Vec<Param>) -> azure_core::error::Result<String> {
+        let value = self.0.clone().map_err(|error| {
+            Error::with_message(ErrorKind::DataConversion, || {
+                format!("could not serialize query parameter: {}", error)
+            })
+        })?;
+        let name = format!("@p{}", params.len());
+        params.push(Param::new(name.clone(), value));
+        Ok(name)
+    }
+}
+
+impl Condition {
+    fn compare<S: Into<String>, T: Serialize>(path: S, operator: &'static str, value: T) -> Self {
+        Self(Expression::Compare(
+            path.into(),
+            operator,
+            Operand::new(value),
+        ))
+    }
+
+    /// The property equals the value
+    pub fn eq<S: Into<String>, T: Serialize>(path: S, value: T) -> Self {
+        Self::compare(path, "=", value)
+    }
+
+    /// The property differs from the value
+    pub fn ne<S: Into<String>, T: Serialize>(path: S, value: T) -> Self {
+        Self::compare(path, "!=", value)
+    }
+
+    /// The property is less than the value
+    pub fn lt<S: Into<String>, T: Serialize>(path: S, value: T) -> Self {
+        Self::compare(path, "<", value)
+    }
+
+    /// The property is less than or equal to the value
+    pub fn le<S: Into<String>, T: Serialize>(path: S, value: T) -> Self {
+        Self::compare(path, "<=", value)
+    }
+
+    /// The property is greater than the value
+    pub fn gt<S: Into<String>, T: Serialize>(path: S, value: T) -> Self {
+        Self::compare(path, ">", value)
+    }
+
+    /// The property is greater than or equal to the value
+    pub fn ge<S: Into<String>, T: Serialize>(path: S, value: T) -> Self {
+        Self::compare(path, ">=", value)
+    }
+
+    /// The property equals one of the values, `c.status IN (@p0, @p1)`
+    pub fn is_in<S, I, T>(path: S, values: I) -> Self
+    where
+        S: Into<String>,
+        I: IntoIterator<Item = T>,
+        T: Serialize,
+    {
+        Self(Expression::In(
+            path.into(),
+            values.into_iter().map(Operand::new).collect(),
+        ))
+    }
+
+    /// The array property contains the value, `ARRAY_CONTAINS(c.tags, @p0)`
+    pub fn array_contains<S: Into<String>, T: Serialize>(path: S, value: T) -> Self {
+        Self(Expression::Function(
+            "ARRAY_CONTAINS",
+            path.into(),
+            vec![Operand::new(value)],
+        ))
+    }
+
+    /// The array property contains an object with all the properties of the value,
+    /// `ARRAY_CONTAINS(c.items, @p0, true)`
+    pub fn array_contains_partial<S: Into<String>, T: Serialize>(path: S, value: T) -> Self {
+        Self(Expression::Function(
+            "ARRAY_CONTAINS",
+            path.into(),
+            vec![Operand::new(value), Operand::new(true)],
+        ))
+    }
+
+    /// The string property starts with the prefix, `STARTSWITH(c.name, @p0)`
+    pub fn starts_with<S: Into<String>, T: Serialize>(path: S, prefix: T) -> Self {
+        Self(Expression::Function(
+            "STARTSWITH",
+            path.into(),
+            vec![Operand::new(prefix)],
+        ))
+    }
+
+    /// The string property contains the value, `CONTAINS(c.name, @p0)`
+    pub fn contains<S: Into<String>, T: Serialize>(path: S, value: T) -> Self {
+        Self(Expression::Function(
+            "CONTAINS",
+            path.into(),
+            vec![Operand::new(value)],
+        ))
+    }
+
+    /// The property is present, `IS_DEFINED(c.email)`
+    pub fn is_defined<S: Into<String>>(path: S) -> Self {
+        Self(Expression::IsDefined(path.into()))
+    }
+
+    /// Both conditions match
+    pub fn and(self, other: Condition) -> Self {
+        match self.0 {
+            Expression::And(mut conditions) => {
+                conditions.push(other);
+                Self(Expression::And(conditions))
+            }
+            _ => Self(Expression::And(vec![self, other])),
+        }
+    }
+
+    /// Either condition matches
+    pub fn or(self, other: Condition) -> Self {
+        match self.0 {
+            Expression::Or(mut conditions) => {
+                conditions.push(other);
+                Self(Expression::Or(conditions))
+            }
+            _ => Self(Expression::Or(vec![self, other])),
+        }
+    }
+
+    /// The condition does not match
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(self) -> Self {
+        Self(Expression::Not(Box::new(self)))
+    }
+
+    fn render(&self, text: &mut String, params: &mut Vec<Param>) -> azure_core::error::Result<()> {
+        match &self.0 {
+            Expression::Compare(path, operator, value) => {
+                check_path(path)?;
+                let name = value.bind(params)?;
+                text.push_str(&format!("{} {} {}", path, operator, name));
+            }
+            Expression::In(path, values) => {
+                check_path(path)?;
+                let names = values
+                    .iter()
+                    .map(|value| value.bind(params))
+                    .collect::<azure_core::error::Result<Vec<_>>>()?;
+                text.push_str(&format!("{} IN ({})", path, names.join(", ")));
+            }
+            Expression::Function(function, path, arguments) => {
+                check_path(path)?;
+                text.push_str(function);
+                text.push('(');
+                text.push_str(path);
+                for argument in arguments {
+                    let name = argument.bind(params)?;
+                    text.push_str(", ");
+                    text.push_str(&name);
+                }
+                text.push(')');
+            }
+            Expression::IsDefined(path) => {
+                check_path(path)?;
+                text.push_str(&format!("IS_DEFINED({})", path));
+            }
+            Expression::And(conditions) | Expression::Or(conditions) => {
+                let separator = match &self.0 {
+                    Expression::And(_) => " AND ",
+                    _ => " OR ",
+                };
+                text.push('(');
+                for (index, condition) in conditions.iter().enumerate() {
+                    if index > 0 {
+                        text.push_str(separator);
+                    }
+                    condition.render(text, params)?;
+                }
+                text.push(')');
+            }
+            Expression::Not(condition) => {
+                text.push_str("NOT ");
+                let compound = matches!(condition.0, Expression::And(_) | Expression::Or(_));
+                if !compound {
+                    text.push('(');
+                }
+                condition.render(text, params)?;
+                if !compound {
+                    text.push(')');
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn check_identifier(identifier: &str) -> azure_core::error::Result<()> {
+    let mut chars = identifier.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::with_message(ErrorKind::DataConversion, || {
+            format!("'{}' is not a valid query identifier", identifier)
+        }))
+    }
+}
+
+/// Checks that the path is made of identifiers, such as `c.address.city`, array indexes, such
+/// as `c.tags[0]`, and quoted property names, such as `c["first name"]`
+fn check_path(path: &str) -> azure_core::error::Result<()> {
+    let invalid = || {
+        Error::with_message(ErrorKind::DataConversion, || {
+            format!("'{}' is not a valid property path", path)
+        })
+    };
+
+    let mut rest = path;
+    let mut first = true;
+    while !rest.is_empty() {
+        if let Some(quoted) = rest.strip_prefix("[\"") {
+            let end = quoted.find("\"]").ok_or_else(invalid)?;
+            let name = &quoted[..end];
+            if name.contains(['"', '\\']) {
+                return Err(invalid());
+            }
+            rest = &quoted[end + 2..];
+        } else if let Some(indexed) = rest.strip_prefix('[') {
+            let end = indexed.find(']').ok_or_else(invalid)?;
+            let index = &indexed[..end];
+            if index.is_empty() || !index.chars().all(|c| c.is_ascii_digit()) {
+                return Err(invalid());
+            }
+            rest = &indexed[end + 1..];
+        } else {
+            let segment = if first {
+                rest
+            } else {
+                rest.strip_prefix('.').ok_or_else(invalid)?
+            };
+            let end = segment.find(['.', '[']).unwrap_or(segment.len());
+            check_identifier(&segment[..end]).map_err(|_| invalid())?;
+            rest = &segment[end..];
+        }
+        first = false;
+    }
+    if first {
+        Err(invalid())
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(query: &Query) -> Vec<(&str, &Value)> {
+        query
+            .params()
+            .iter()
+            .map(|param| (param.name(), param.value()))
+            .collect()
+    }
+
+    #[test]
+    fn binds_values_as_numbered_parameters() {
+        #[derive(Serialize)]
+        struct Address {
+            city: &'static str,
+        }
+
+        let query = QueryBuilder::new()
+            .from("doc")
+            .distinct()
+            .top(5)
+            .select(["doc.id", r#"doc["first name"]"#])
+            .filter(Condition::gt("doc.age", 18))
+            .filter(
+                Condition::is_in("doc.status", ["active", "pending"])
+                    .or(Condition::array_contains_partial(
+                        "doc.addresses",
+                        Address { city: "Seattle" },
+                    ))
+                    .or(Condition::is_defined("doc.tags[0]").not()),
+            )
+            .order_by("doc.age", SortOrder::Descending)
+            .order_by("doc.id", SortOrder::Ascending)
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            query.query(),
+            r#"SELECT DISTINCT TOP 5 doc.id, doc["first name"] FROM doc WHERE (doc.age > @p0 AND (doc.status IN (@p1, @p2) OR ARRAY_CONTAINS(doc.addresses, @p3, @p4) OR NOT (IS_DEFINED(doc.tags[0])))) ORDER BY doc.age DESC, doc.id ASC"#
+        );
+        assert_eq!(
+            params(&query),
+            [
+                ("@p0", &Value::from(18)),
+                ("@p1", &Value::from("active")),
+                ("@p2", &Value::from("pending")),
+                ("@p3", &serde_json::json!({"city": "Seattle"})),
+                ("@p4", &Value::from(true)),
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_values_out_of_the_query_text() {
+        let query = QueryBuilder::new()
+            .select_value("c.id")
+            .filter(Condition::eq("c.name", "x' OR 1=1 --"))
+            .build()
+            .unwrap();
+
+        assert_eq!(query.query(), "SELECT VALUE c.id FROM c WHERE c.name = @p0");
+        assert_eq!(params(&query), [("@p0", &Value::from("x' OR 1=1 --"))]);
+    }
+
+    #[test]
+    fn rejects_invalid_paths() {
+        for path in [
+            "",
+            "c.name = 1 OR c.id",
+            "c..name",
+            "c.tags[x]",
+            r#"c["a"] OR 1"#,
+            r#"c["a"b"]"#,
+            "1c",
+        ] {
+            assert!(
+                QueryBuilder::new()
+                    .filter(Condition::is_defined(path))
+                    .build()
+                    .is_err(),
+                "{}",
+                path
+            );
+        }
+        assert!(QueryBuilder::new().from("c c").build().is_err());
+    }
+}
@@ -3,7 +3,9 @@
 //! You can learn about the Cosmos DB resource model [here](https://docs.microsoft.com/azure/cosmos-db/account-databases-containers-items).
 
 pub mod collection;
+pub mod conflict;
 pub mod document;
+pub mod offer;
 pub mod permission;
 pub mod stored_procedure;
 pub mod trigger;
@@ -18,10 +20,14 @@ pub use attachment::Attachment;
 #[doc(inline)]
 pub use collection::Collection;
 #[doc(inline)]
+pub use conflict::Conflict;
+#[doc(inline)]
 pub use database::Database;
 #[doc(inline)]
 pub use document::Document;
 #[doc(inline)]
+pub use offer::{Throughput, ThroughputOffer};
+#[doc(inline)]
 pub use permission::Permission;
 #[doc(inline)]
 pub use stored_procedure::StoredProcedure;
@@ -62,4 +68,7 @@ pub(crate) enum ResourceType {
     PartitionKeyRanges,
     UserDefinedFunctions,
     Triggers,
+    Conflicts,
+    Offers,
+    DatabaseAccount,
 }
@@ -0,0 +1,147 @@
+//! Throughput offers of databases and collections.
+//!
+//! You can learn more about offers [here](https://docs.microsoft.com/rest/api/cosmos-db/offers).
+
+use serde_json::{Map, Value};
+
+/// The throughput provisioned for a database or a collection.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThroughputOffer {
+    /// The offer id
+    pub id: String,
+    /// The resource id
+    #[serde(rename = "_rid")]
+    pub rid: String,
+    /// The resource's uri
+    #[serde(rename = "_self")]
+    pub _self: String,
+    /// The resource's etag used for concurrency control
+    #[serde(rename = "_etag")]
+    pub etag: String,
+    /// The last updated timestamp
+    #[serde(rename = "_ts")]
+    pub ts: u64,
+    /// The self link of the database or collection the offer applies to
+    pub resource: String,
+    /// The resource id of the database or collection the offer applies to
+    pub offer_resource_id: String,
+    /// The offer version, `V2` for offers with custom throughput
+    pub offer_version: String,
+    /// The legacy performance level, `Invalid` for offers with custom throughput
+    pub offer_type: String,
+    /// The throughput settings
+    pub content: OfferContent,
+}
+
+impl ThroughputOffer {
+    /// The provisioned throughput
+    pub fn throughput(&self) -> Throughput {
+        match &self.content.offer_autopilot_settings {
+            Some(settings) => Throughput::Autoscale {
+                max_throughput: settings.max_throughput,
+            },
+            None => Throughput::Manual(self.content.offer_throughput.unwrap_or_default()),
+        }
+    }
+
+    /// Sets the provisioned throughput, keeping the other settings of the offer
+    ///
+    /// The service only switches an offer between manual and autoscale throughput through a
+    /// migration, which [`ReplaceOfferBuilder`](crate::operations::ReplaceOfferBuilder) makes
+    /// before replacing the offer.
+    pub fn set_throughput(&mut self, throughput: Throughput) {
+        match throughput {
+            Throughput::Manual(throughput) => {
+                self.content.offer_throughput = Some(throughput);
+                self.content.offer_autopilot_settings = None;
+            }
+            Throughput::Autoscale { max_throughput } => {
+                self.content.offer_throughput = None;
+                match &mut self.content.offer_autopilot_settings {
+                    Some(settings) => settings.max_throughput = max_throughput,
+                    None => {
+                        self.content.offer_autopilot_settings = Some(AutoscaleSettings {
+                            max_throughput,
+                            other: Map::new(),
+                        })
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The throughput settings of an offer.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OfferContent {
+    /// The request units per second currently provisioned
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offer_throughput: Option<u64>,
+    /// The autoscale settings, if the throughput scales automatically
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offer_autopilot_settings: Option<AutoscaleSettings>,
+    /// Other settings, such as the minimum throughput the offer can be scaled to
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// The settings of an offer scaling automatically.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AutoscaleSettings {
+    /// The request units per second the throughput scales up to
+    pub max_throughput: u64,
+    /// Other settings, such as the autoscale policy
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// How much throughput to provision.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Throughput {
+    /// A fixed number of request units per second
+    Manual(u64),
+    /// Throughput scaling between a tenth of the maximum and the maximum request units per second
+    Autoscale {
+        /// The request units per second the throughput scales up to
+        max_throughput: u64,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn switches_between_manual_and_autoscale() {
+        let mut offer: ThroughputOffer = serde_json::from_str(
+            r#"{"resource":"dbs/rgkVAA==/colls/rgkVAMHcJww=/","offerType":"Invalid","offerResourceId":"rgkVAMHcJww=","offerVersion":"V2","content":{"offerThroughput":400,"offerIsRUPerMinuteThroughputEnabled":false,"offerMinimumThroughputParameters":{"maxThroughputEverProvisioned":400}},"id":"HDRB","_rid":"HDRB","_self":"offers/HDRB/","_etag":"\"00000200-0000-0000-0000-5a7b4b690000\"","_ts":1518029673}"#,
+        )
+        .unwrap();
+        assert_eq!(offer.throughput(), Throughput::Manual(400));
+
+        offer.set_throughput(Throughput::Autoscale {
+            max_throughput: 4000,
+        });
+        assert_eq!(
+            offer.throughput(),
+            Throughput::Autoscale {
+                max_throughput: 4000
+            }
+        );
+        assert_eq!(
+            serde_json::to_value(&offer.content).unwrap(),
+            serde_json::json!({
+                "offerAutopilotSettings": {"maxThroughput": 4000},
+                "offerIsRUPerMinuteThroughputEnabled": false,
+                "offerMinimumThroughputParameters": {"maxThroughputEverProvisioned": 400}
+            })
+        );
+
+        offer.set_throughput(Throughput::Manual(1000));
+        assert_eq!(offer.throughput(), Throughput::Manual(1000));
+        assert!(offer.content.offer_autopilot_settings.is_none());
+    }
+}
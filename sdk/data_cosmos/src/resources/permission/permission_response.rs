@@ -1,4 +1,5 @@
 use crate::headers::from_headers::*;
+use crate::CosmosDiagnostics;
 
 use azure_core::headers::{etag_from_headers, session_token_from_headers};
 use azure_core::{collect_pinned_stream, Response as HttpResponse};
@@ -14,10 +15,18 @@ pub struct PermissionResponse {
     pub session_token: String,
     pub content_path: String,
     pub alt_content_path: String,
+    pub diagnostics: CosmosDiagnostics,
 }
 
 impl PermissionResponse {
     pub async fn try_from(response: HttpResponse) -> azure_core::error::Result<PermissionResponse> {
+        Self::try_from_with_diagnostics(response, CosmosDiagnostics::default()).await
+    }
+
+    pub(crate) async fn try_from_with_diagnostics(
+        response: HttpResponse,
+        diagnostics: CosmosDiagnostics,
+    ) -> azure_core::error::Result<PermissionResponse> {
         let (_status_code, headers, pinned_stream) = response.deconstruct();
         let body = collect_pinned_stream(pinned_stream).await?;
 
@@ -29,6 +38,7 @@ impl PermissionResponse {
             etag: etag_from_headers(&headers)?,
             content_path: String::from(content_path_from_headers(&headers)?),
             alt_content_path: String::from(alt_content_path_from_headers(&headers)?),
+            diagnostics,
         })
     }
 }
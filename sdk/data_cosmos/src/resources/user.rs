@@ -2,6 +2,7 @@
 
 use super::Resource;
 use crate::headers::from_headers::*;
+use crate::CosmosDiagnostics;
 use azure_core::{
     collect_pinned_stream,
     headers::{etag_from_headers, session_token_from_headers},
@@ -62,11 +63,20 @@ pub struct UserResponse {
     pub etag: String,
     /// The session token for the request
     pub session_token: String,
+    /// The attempts made for the request
+    pub diagnostics: CosmosDiagnostics,
 }
 
 impl UserResponse {
     /// Creates a UserResponse from an HttpResponse
     pub async fn try_from(response: HttpResponse) -> azure_core::error::Result<Self> {
+        Self::try_from_with_diagnostics(response, CosmosDiagnostics::default()).await
+    }
+
+    pub(crate) async fn try_from_with_diagnostics(
+        response: HttpResponse,
+        diagnostics: CosmosDiagnostics,
+    ) -> azure_core::error::Result<Self> {
         let (_status_code, headers, pinned_stream) = response.deconstruct();
         let body = collect_pinned_stream(pinned_stream).await?;
 
@@ -76,6 +86,7 @@ impl UserResponse {
             activity_id: activity_id_from_headers(&headers)?,
             session_token: session_token_from_headers(&headers)?,
             etag: etag_from_headers(&headers)?,
+            diagnostics,
         })
     }
 }
@@ -0,0 +1,156 @@
+//! Keeps the latest session token of every partition key range the client talked to.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::RwLock;
+
+/// The session token of a partition key range, such as `-1#12` in `0:-1#12`.
+///
+/// Tokens are ordered by their version and then by their global logical sequence number.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SessionToken {
+    raw: String,
+    version: i64,
+    global_lsn: i64,
+}
+
+impl SessionToken {
+    fn parse(raw: &str) -> Option<Self> {
+        let mut parts = raw.split('#');
+        let first = parts.next()?.parse().ok()?;
+        // simple tokens only carry the sequence number
+        let (version, global_lsn) = match parts.next() {
+            Some(global_lsn) => (first, global_lsn.parse().ok()?),
+            None => (0, first),
+        };
+        Some(Self {
+            raw: raw.to_owned(),
+            version,
+            global_lsn,
+        })
+    }
+
+    fn is_newer_than(&self, other: &Self) -> bool {
+        (self.version, self.global_lsn) > (other.version, other.global_lsn)
+    }
+}
+
+/// The session tokens learned from responses, per collection and partition key range.
+///
+/// Collections are identified by their link, such as `dbs/db/colls/coll`.
+#[derive(Debug, Default)]
+pub(crate) struct SessionContainer {
+    collections: RwLock<HashMap<String, BTreeMap<String, SessionToken>>>,
+}
+
+impl SessionContainer {
+    /// Records the tokens of a `x-ms-session-token` header, such as `0:-1#12,1:-1#5`,
+    /// keeping the newest token of every range.
+    pub fn update(&self, collection: &str, session_token: &str) {
+        let mut collections = self.collections.write().unwrap();
+        let ranges = collections.entry(collection.to_owned()).or_default();
+        for (range, token) in session_token
+            .split(',')
+            .filter_map(|token| token.trim().split_once(':'))
+        {
+            let token = match SessionToken::parse(token) {
+                Some(token) => token,
+                None => {
+                    warn!("ignoring malformed session token '{}'", token);
+                    continue;
+                }
+            };
+            match ranges.get(range) {
+                Some(known) if !token.is_newer_than(known) => {}
+                _ => {
+                    ranges.insert(range.to_owned(), token);
+                }
+            }
+        }
+    }
+
+    /// The session token to send with a read of the collection.
+    ///
+    /// When the partition key range is known only its token is sent, otherwise the tokens of
+    /// all the ranges are combined and the service picks the one of the range it reads.
+    pub fn session_token(&self, collection: &str, range: Option<&str>) -> Option<String> {
+        let collections = self.collections.read().unwrap();
+        let ranges = collections.get(collection)?;
+        if let Some((range, token)) = range.and_then(|range| ranges.get_key_value(range)) {
+            return Some(format!("{}:{}", range, token.raw));
+        }
+        if ranges.is_empty() {
+            return None;
+        }
+        Some(
+            ranges
+                .iter()
+                .map(|(range, token)| format!("{}:{}", range, token.raw))
+                .collect::<Vec<_>>()
+                .join(","),
+        )
+    }
+
+    /// Forgets the tokens of the collections under the link, such as after deleting a
+    /// database or collection: a new one with the same name starts a new session.
+    pub fn clear(&self, link: &str) {
+        let prefix = format!("{}/", link);
+        self.collections
+            .write()
+            .unwrap()
+            .retain(|collection, _| collection != link && !collection.starts_with(&prefix));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_newest_token_of_every_range() {
+        let container = SessionContainer::default();
+        container.update("dbs/db/colls/coll", "0:-1#12,1:-1#5");
+        container.update("dbs/db/colls/coll", "0:-1#10");
+        container.update("dbs/db/colls/coll", "1:-1#7#1=6");
+        container.update("dbs/db/colls/other", "0:-1#1");
+
+        assert_eq!(
+            container
+                .session_token("dbs/db/colls/coll", None)
+                .as_deref(),
+            Some("0:-1#12,1:-1#7#1=6")
+        );
+        assert_eq!(
+            container
+                .session_token("dbs/db/colls/coll", Some("1"))
+                .as_deref(),
+            Some("1:-1#7#1=6")
+        );
+        // a range without a token yet, such as the child of a split, gets all of them
+        assert_eq!(
+            container
+                .session_token("dbs/db/colls/coll", Some("2"))
+                .as_deref(),
+            Some("0:-1#12,1:-1#7#1=6")
+        );
+        assert_eq!(container.session_token("dbs/db/colls/none", None), None);
+
+        container.clear("dbs/db");
+        assert_eq!(container.session_token("dbs/db/colls/coll", None), None);
+    }
+
+    #[test]
+    fn orders_tokens_by_version_first() {
+        let container = SessionContainer::default();
+        container.update("dbs/db/colls/coll", "0:1#12");
+        container.update("dbs/db/colls/coll", "0:2#3");
+        container.update("dbs/db/colls/coll", "0:1#20");
+        container.update("dbs/db/colls/coll", "0:garbage");
+
+        assert_eq!(
+            container
+                .session_token("dbs/db/colls/coll", None)
+                .as_deref(),
+            Some("0:2#3")
+        );
+    }
+}
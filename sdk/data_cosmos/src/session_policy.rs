@@ -0,0 +1,330 @@
+use crate::headers::{
+    HEADER_CONSISTENCY_LEVEL, HEADER_DOCUMENTDB_PARTITIONRANGEID, HEADER_SESSION_TOKEN,
+};
+use crate::location_cache::LocationCache;
+use crate::location_routing_policy::{is_read, read_account};
+use crate::resources::ResourceType;
+use crate::session_container::SessionContainer;
+use azure_core::{Context, Policy, PolicyResult, Request};
+use futures::lock::Mutex;
+use std::sync::Arc;
+
+/// The `SessionPolicy` gives read-your-writes across the client without threading session
+/// tokens by hand. It records the `x-ms-session-token` of every response of a collection and
+/// sends the latest token with the following reads of that collection, when they use session
+/// consistency: either the request asks for it or it is the default consistency of the account.
+/// Requests that already carry a session token are sent as they are.
+/// The policy must be added before the `AuthorizationPolicy`.
+#[derive(Debug)]
+pub(crate) struct SessionPolicy {
+    container: Arc<SessionContainer>,
+    /// Knows the account when endpoint discovery is enabled
+    location_cache: Option<Arc<LocationCache>>,
+    /// Whether the account defaults to session consistency, read once when first needed
+    account_session: Mutex<Option<bool>>,
+}
+
+impl SessionPolicy {
+    pub(crate) fn new(
+        container: Arc<SessionContainer>,
+        location_cache: Option<Arc<LocationCache>>,
+    ) -> Self {
+        Self {
+            container,
+            location_cache,
+            account_session: Mutex::new(None),
+        }
+    }
+
+    /// Whether the read uses session consistency
+    async fn uses_session(
+        &self,
+        ctx: &Context,
+        request: &Request,
+        next: &[Arc<dyn Policy>],
+    ) -> bool {
+        if let Some(consistency_level) = request.headers().get(&HEADER_CONSISTENCY_LEVEL.into()) {
+            return consistency_level.as_str() == "Session";
+        }
+        if let Some(account) = self
+            .location_cache
+            .as_ref()
+            .and_then(|location_cache| location_cache.account())
+        {
+            return account.uses_session_consistency();
+        }
+
+        let mut account_session = self.account_session.lock().await;
+        if let Some(account_session) = *account_session {
+            return account_session;
+        }
+        let mut ctx = ctx.clone();
+        ctx.insert(ResourceType::DatabaseAccount);
+        let uri = request.uri();
+        let endpoint = format!(
+            "{}://{}",
+            uri.scheme_str().unwrap_or("https"),
+            uri.authority().map_or("", |authority| authority.as_str())
+        );
+        match read_account(&ctx, &endpoint, next).await {
+            Ok(account) => {
+                let session = account.uses_session_consistency();
+                *account_session = Some(session);
+                session
+            }
+            Err(error) => {
+                // accounts with other consistency levels ignore session tokens, the account is
+                // read again by the next request
+                warn!(
+                    "could not read the consistency of the account, assuming session: {}",
+                    error
+                );
+                true
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Policy for SessionPolicy {
+    async fn send(
+        &self,
+        ctx: &Context,
+        request: &mut Request,
+        next: &[Arc<dyn Policy>],
+    ) -> PolicyResult {
+        assert!(
+            !next.is_empty(),
+            "SessionPolicy cannot be the last policy of a pipeline"
+        );
+
+        let link = ResourceLink::parse(request.uri().path());
+        if let Some(collection) = link.collection.as_deref() {
+            let has_token = request
+                .headers()
+                .get(&HEADER_SESSION_TOKEN.into())
+                .is_some_and(|session_token| !session_token.as_str().is_empty());
+            if link.in_collection && is_read(request) && !has_token {
+                let range = request
+                    .headers()
+                    .get(&HEADER_DOCUMENTDB_PARTITIONRANGEID.into())
+                    .map(|range| range.as_str().to_owned());
+                if let Some(session_token) =
+                    self.container.session_token(collection, range.as_deref())
+                {
+                    if self.uses_session(ctx, request, next).await {
+                        trace!("sending session token {} to {}", session_token, collection);
+                        request
+                            .headers_mut()
+                            .insert(HEADER_SESSION_TOKEN, session_token);
+                    }
+                }
+            }
+        }
+
+        let response = next[0].send(ctx, request, &next[1..]).await?;
+
+        if let Some(collection) = link.collection.as_deref() {
+            if let Some(session_token) = response
+                .headers()
+                .get(HEADER_SESSION_TOKEN)
+                .and_then(|session_token| session_token.to_str().ok())
+            {
+                self.container.update(collection, session_token);
+            }
+        }
+        // a database or collection created again with the same name starts a new session
+        if request.method() == http::Method::DELETE
+            && response.status().is_success()
+            && !link.in_collection
+        {
+            if let Some(deleted) = link.collection.as_deref().or(link.database.as_deref()) {
+                self.container.clear(deleted);
+            }
+        }
+
+        Ok(response)
+    }
+}
+
+/// The database and collection a request path is about
+#[derive(Debug, Default, PartialEq, Eq)]
+struct ResourceLink {
+    /// Such as `dbs/db`
+    database: Option<String>,
+    /// Such as `dbs/db/colls/coll`
+    collection: Option<String>,
+    /// Whether the path is about a resource in the collection, such as a document
+    in_collection: bool,
+}
+
+impl ResourceLink {
+    fn parse(path: &str) -> Self {
+        let segments: Vec<_> = path
+            .trim_matches('/')
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .collect();
+        match segments.as_slice() {
+            ["dbs", database, "colls", collection, rest @ ..] => Self {
+                database: Some(format!("dbs/{}", database)),
+                collection: Some(format!("dbs/{}/colls/{}", database, collection)),
+                in_collection: !rest.is_empty(),
+            },
+            ["dbs", database, ..] => Self {
+                database: Some(format!("dbs/{}", database)),
+                ..Self::default()
+            },
+            _ => Self::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ResourceLink;
+    use crate::mock_endpoint::{MockEndpoint, MockResponse, RecordedRequest};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn parses_resource_links() {
+        assert_eq!(
+            ResourceLink::parse("/dbs/db/colls/coll/docs/id"),
+            ResourceLink {
+                database: Some("dbs/db".to_owned()),
+                collection: Some("dbs/db/colls/coll".to_owned()),
+                in_collection: true,
+            }
+        );
+        assert_eq!(
+            ResourceLink::parse("/dbs/db/colls/coll"),
+            ResourceLink {
+                database: Some("dbs/db".to_owned()),
+                collection: Some("dbs/db/colls/coll".to_owned()),
+                in_collection: false,
+            }
+        );
+        assert_eq!(ResourceLink::parse("/offers"), ResourceLink::default());
+    }
+
+    fn document(session_token: &str) -> MockResponse {
+        MockResponse::new(
+            200,
+            r#"{"id":"doc","_rid":"a","_ts":1,"_self":"b","_etag":"c","_attachments":"attachments/"}"#,
+        )
+        .with_cosmos_headers()
+        .with_header("x-ms-session-token", session_token)
+        .with_header("etag", "c")
+        .with_header("last-modified", "Thu, 01 Jan 1970 00:00:01 GMT")
+    }
+
+    fn account(consistency: &str) -> MockResponse {
+        MockResponse::new(
+            200,
+            serde_json::json!({
+                "id": "mock",
+                "userConsistencyPolicy": {"defaultConsistencyLevel": consistency}
+            })
+            .to_string(),
+        )
+        .with_cosmos_headers()
+    }
+
+    async fn write_then_read(endpoint: &MockEndpoint) {
+        let client = endpoint
+            .cosmos_client()
+            .database_client("db")
+            .collection_client("coll");
+        client
+            .create_document(serde_json::json!({"id": "doc", "pk": "pk"}))
+            .partition_key(&"pk")
+            .unwrap()
+            .into_future()
+            .await
+            .unwrap();
+        client
+            .document_client("doc", &"pk")
+            .unwrap()
+            .get_document()
+            .into_future::<serde_json::Value>()
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn reads_your_writes_with_session_accounts() {
+        let endpoint = MockEndpoint::serve(|request: &RecordedRequest| {
+            Some(match request.path.as_str() {
+                "/" => account("Session"),
+                _ => document("0:-1#12"),
+            })
+        });
+
+        write_then_read(&endpoint).await;
+
+        let requests = endpoint.requests();
+        let paths: Vec<_> = requests
+            .iter()
+            .map(|request| format!("{} {}", request.method, request.path))
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                "POST /dbs/db/colls/coll/docs",
+                "GET /",
+                "GET /dbs/db/colls/coll/docs/doc"
+            ]
+        );
+        assert_eq!(requests[0].header("x-ms-session-token"), None);
+        assert_eq!(requests[2].header("x-ms-session-token"), Some("0:-1#12"));
+    }
+
+    #[tokio::test]
+    async fn sends_no_token_with_other_consistency_levels() {
+        let endpoint = MockEndpoint::serve(|request: &RecordedRequest| {
+            Some(match request.path.as_str() {
+                "/" => account("Eventual"),
+                _ => document("0:-1#12"),
+            })
+        });
+
+        write_then_read(&endpoint).await;
+
+        let requests = endpoint.requests();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[2].header("x-ms-session-token"), None);
+    }
+
+    #[tokio::test]
+    async fn reads_the_account_again_after_a_failed_read() {
+        let account_reads = AtomicUsize::new(0);
+        let endpoint = MockEndpoint::serve(move |request: &RecordedRequest| {
+            Some(match request.path.as_str() {
+                "/" if account_reads.fetch_add(1, Ordering::SeqCst) == 0 => {
+                    MockResponse::new(400, r#"{"code":"BadRequest","message":"unavailable"}"#)
+                }
+                "/" => account("Eventual"),
+                _ => document("0:-1#12"),
+            })
+        });
+
+        write_then_read(&endpoint).await;
+        write_then_read(&endpoint).await;
+
+        let requests = endpoint.requests();
+        let reads: Vec<_> = requests
+            .iter()
+            .filter(|request| request.method == "GET")
+            .map(|request| (request.path.as_str(), request.header("x-ms-session-token")))
+            .collect();
+        assert_eq!(
+            reads,
+            [
+                ("/", None),
+                ("/dbs/db/colls/coll/docs/doc", Some("0:-1#12")),
+                ("/", None),
+                ("/dbs/db/colls/coll/docs/doc", None),
+            ]
+        );
+    }
+}
@@ -61,6 +61,8 @@ async fn attachment_operations() -> azure_core::error::Result<()> {
             indexing_mode: collection::IndexingMode::Consistent,
             included_paths: vec![ip],
             excluded_paths: vec![],
+            composite_indexes: vec![],
+            spatial_indexes: vec![],
         };
 
         database
@@ -75,6 +75,8 @@ async fn collection_operations() -> Result<(), BoxedError> {
         indexing_mode: IndexingMode::Consistent,
         included_paths: vec![include_path],
         excluded_paths: vec![],
+        composite_indexes: vec![],
+        spatial_indexes: vec![],
     };
 
     new_indexing_policy
@@ -44,6 +44,8 @@ async fn document_operations() {
         indexing_mode: IndexingMode::Consistent,
         included_paths: vec![],
         excluded_paths: vec![],
+        composite_indexes: vec![],
+        spatial_indexes: vec![],
     };
 
     database
@@ -45,6 +45,8 @@ async fn permission_token_usage() {
         indexing_mode: IndexingMode::Consistent,
         included_paths: vec![],
         excluded_paths: vec![],
+        composite_indexes: vec![],
+        spatial_indexes: vec![],
     };
 
     let create_collection_response = database
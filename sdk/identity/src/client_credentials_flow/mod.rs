@@ -39,10 +39,15 @@
 
 mod login_response;
 
+use crate::authority_hosts;
 use azure_core::error::{ErrorKind, Result, ResultExt};
 use login_response::LoginResponse;
 use url::form_urlencoded;
 
+/// The `client_assertion_type` for a JWT bearer client assertion
+pub(crate) const JWT_BEARER_ASSERTION_TYPE: &str =
+    "urn:ietf:params:oauth:client-assertion-type:jwt-bearer";
+
 /// Perform the client credentials flow
 pub async fn perform(
     client: reqwest::Client,
@@ -50,17 +55,61 @@ pub async fn perform(
     client_secret: &oauth2::ClientSecret,
     scopes: &[&str],
     tenant_id: &str,
+) -> Result<LoginResponse> {
+    request_token(
+        &client,
+        authority_hosts::AZURE_PUBLIC_CLOUD,
+        tenant_id,
+        &[
+            ("client_id", client_id.as_str()),
+            ("scope", &scopes.join(" ")),
+            ("client_secret", client_secret.secret()),
+            ("grant_type", "client_credentials"),
+        ],
+    )
+    .await
+}
+
+/// Perform the client credentials flow using a signed client assertion instead of a client secret
+///
+/// The assertion is usually a JWT signed with a certificate or issued by a federated identity
+/// provider trusted by the App Registration.
+pub async fn perform_with_assertion(
+    client: reqwest::Client,
+    client_id: &oauth2::ClientId,
+    client_assertion: &str,
+    scopes: &[&str],
+    tenant_id: &str,
+) -> Result<LoginResponse> {
+    request_token(
+        &client,
+        authority_hosts::AZURE_PUBLIC_CLOUD,
+        tenant_id,
+        &[
+            ("client_id", client_id.as_str()),
+            ("scope", &scopes.join(" ")),
+            ("client_assertion_type", JWT_BEARER_ASSERTION_TYPE),
+            ("client_assertion", client_assertion),
+            ("grant_type", "client_credentials"),
+        ],
+    )
+    .await
+}
+
+/// Post a form to the `/oauth2/v2.0/token` endpoint of the tenant and parse the login response
+pub(crate) async fn request_token(
+    client: &reqwest::Client,
+    authority_host: &str,
+    tenant_id: &str,
+    form: &[(&str, &str)],
 ) -> Result<LoginResponse> {
     let encoded: String = form_urlencoded::Serializer::new(String::new())
-        .append_pair("client_id", client_id.as_str())
-        .append_pair("scope", &scopes.join(" "))
-        .append_pair("client_secret", client_secret.secret())
-        .append_pair("grant_type", "client_credentials")
+        .extend_pairs(form)
         .finish();
 
     let url = url::Url::parse(&format!(
-        "https://login.microsoftonline.com/{}/oauth2/v2.0/token",
-        tenant_id
+        "{}/{}/oauth2/v2.0/token",
+        authority_host, tenant_id
     ))
     .with_context(ErrorKind::DataConversion, || {
         format!("The supplied tenant id could not be url encoded: {tenant_id}")
@@ -68,7 +117,7 @@ pub async fn perform(
 
     let response = client
         .post(url)
-        .header("ContentType", "Application / WwwFormUrlEncoded")
+        .header("Content-Type", "application/x-www-form-urlencoded")
         .body(encoded)
         .send()
         .await
@@ -5,6 +5,7 @@
 //! You can learn more about this authorization flow [here](https://docs.microsoft.com/azure/active-directory/develop/v2-oauth2-device-code).
 mod device_code_responses;
 
+use crate::authority_hosts;
 use azure_core::error::{ErrorKind, Result, ResultExt};
 pub use device_code_responses::*;
 
@@ -29,16 +30,37 @@ pub async fn start<'a, 'b, T>(
 where
     T: Into<Cow<'a, str>>,
 {
-    let mut encoded = form_urlencoded::Serializer::new(String::new());
-    let encoded = encoded.append_pair("client_id", client_id.as_str());
-    let encoded = encoded.append_pair("scope", &scopes.join(" "));
-    let encoded = encoded.finish();
+    start_with_authority_host(
+        client,
+        authority_hosts::AZURE_PUBLIC_CLOUD,
+        tenant_id,
+        client_id,
+        scopes,
+    )
+    .await
+}
+
+/// Start the device authorization grant flow against the given authority host.
+pub(crate) async fn start_with_authority_host<'a, 'b, T>(
+    client: &'a reqwest::Client,
+    authority_host: &'a str,
+    tenant_id: T,
+    client_id: &'a ClientId,
+    scopes: &'b [&'b str],
+) -> Result<DeviceCodePhaseOneResponse<'a>>
+where
+    T: Into<Cow<'a, str>>,
+{
+    let encoded = form_urlencoded::Serializer::new(String::new())
+        .append_pair("client_id", client_id.as_str())
+        .append_pair("scope", &scopes.join(" "))
+        .finish();
 
     let tenant_id = tenant_id.into();
 
     let url = url::Url::parse(&format!(
-        "https://login.microsoftonline.com/{}/oauth2/v2.0/devicecode",
-        tenant_id
+        "{}/{}/oauth2/v2.0/devicecode",
+        authority_host, tenant_id
     ))
     .with_context(ErrorKind::Credential, || {
         format!("the supplied tenant id could not be url encoded: {tenant_id}")
@@ -46,7 +68,7 @@ where
 
     let response = client
         .post(url)
-        .header("ContentType", "application/x-www-form-urlencoded")
+        .header("Content-Type", "application/x-www-form-urlencoded")
         .body(encoded)
         .send()
         .await
@@ -73,7 +95,7 @@ where
     )?;
 
     // we need to capture some variables that will be useful in
-    // the second phase (the client, the tenant_id and the client_id)
+    // the second phase (the client, the authority host, the tenant_id and the client_id)
     Ok(DeviceCodePhaseOneResponse {
         device_code: device_code_response.device_code,
         user_code: device_code_response.user_code,
@@ -82,6 +104,7 @@ where
         interval: device_code_response.interval,
         message: device_code_response.message,
         client: Some(client),
+        authority_host: Cow::Borrowed(authority_host),
         tenant_id,
         client_id: client_id.as_str().to_string(),
     })
@@ -101,6 +124,8 @@ pub struct DeviceCodePhaseOneResponse<'a> {
     #[serde(skip)]
     client: Option<&'a reqwest::Client>,
     #[serde(skip)]
+    authority_host: Cow<'a, str>,
+    #[serde(skip)]
     tenant_id: Cow<'a, str>,
     // We store the ClientId as string instead of the original type, because it
     // does not implement Default, and it's in another crate
@@ -114,6 +139,21 @@ impl<'a> DeviceCodePhaseOneResponse<'a> {
         &self.message
     }
 
+    /// The code the user has to enter on the verification page.
+    pub fn user_code(&self) -> &str {
+        &self.user_code
+    }
+
+    /// The URI of the page the user has to visit to sign in.
+    pub fn verification_uri(&self) -> &str {
+        &self.verification_uri
+    }
+
+    /// Number of seconds before the device code expires.
+    pub fn expires_in(&self) -> u64 {
+        self.expires_in
+    }
+
     /// Polls the token endpoint while the user signs in.
     /// This will continue until either success or error is returned.
     pub fn stream(&self) -> impl futures::Stream<Item = Result<DeviceCodeResponse>> + '_ {
@@ -127,8 +167,8 @@ impl<'a> DeviceCodePhaseOneResponse<'a> {
             match state {
                 NextState::Continue => {
                     let uri = format!(
-                        "https://login.microsoftonline.com/{}/oauth2/v2.0/token",
-                        self.tenant_id,
+                        "{}/{}/oauth2/v2.0/token",
+                        self.authority_host, self.tenant_id,
                     );
 
                     // Throttle down as specified by Azure. This could be
@@ -136,18 +176,17 @@ impl<'a> DeviceCodePhaseOneResponse<'a> {
                     // last poll and wait only the delta.
                     new_timer(Duration::from_secs(self.interval)).await;
 
-                    let mut encoded = form_urlencoded::Serializer::new(String::new());
-                    let encoded = encoded
-                        .append_pair("grant_type", "urn:ietf:params:oauth:grant-type:device_code");
-                    let encoded = encoded.append_pair("client_id", self.client_id.as_str());
-                    let encoded = encoded.append_pair("device_code", &self.device_code);
-                    let encoded = encoded.finish();
+                    let encoded = form_urlencoded::Serializer::new(String::new())
+                        .append_pair("grant_type", "urn:ietf:params:oauth:grant-type:device_code")
+                        .append_pair("client_id", self.client_id.as_str())
+                        .append_pair("device_code", &self.device_code)
+                        .finish();
 
                     let result = match self
                         .client
                         .unwrap()
                         .post(&uri)
-                        .header("ContentType", "application/x-www-form-urlencoded")
+                        .header("Content-Type", "application/x-www-form-urlencoded")
                         .body(encoded)
                         .send()
                         .await
@@ -47,7 +47,11 @@ pub mod client_credentials_flow;
 #[cfg(feature = "development")]
 pub mod development;
 pub mod device_code_flow;
+#[cfg(test)]
+mod mock_endpoint;
 pub mod refresh_token;
+pub mod token_cache;
+pub mod token_claims;
 mod token_credentials;
 
 pub use crate::token_credentials::*;
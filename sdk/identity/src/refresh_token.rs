@@ -1,5 +1,6 @@
 //! Refresh token utilities
 
+use crate::authority_hosts;
 use azure_core::error::{Error, ErrorKind, Result, ResultExt};
 use oauth2::{AccessToken, ClientId, ClientSecret};
 use serde::Deserialize;
@@ -14,26 +15,57 @@ pub async fn exchange(
     client_secret: Option<&ClientSecret>,
     refresh_token: &AccessToken,
 ) -> Result<RefreshTokenResponse> {
-    let mut encoded = form_urlencoded::Serializer::new(String::new());
-    let encoded = encoded.append_pair("grant_type", "refresh_token");
-    let encoded = encoded.append_pair("client_id", client_id.as_str());
-    // optionally add the client secret
-    let encoded = if let Some(client_secret) = client_secret {
-        encoded.append_pair("client_secret", client_secret.secret())
-    } else {
-        encoded
+    exchange_for_scope(
+        client,
+        authority_hosts::AZURE_PUBLIC_CLOUD,
+        tenant_id,
+        client_id,
+        client_secret,
+        refresh_token,
+        None,
+    )
+    .await
+}
+
+/// Exchange a refresh token against the given authority host, optionally for a different scope
+/// than the one the refresh token was originally issued for
+pub(crate) async fn exchange_for_scope(
+    client: &reqwest::Client,
+    authority_host: &str,
+    tenant_id: &str,
+    client_id: &ClientId,
+    client_secret: Option<&ClientSecret>,
+    refresh_token: &AccessToken,
+    scope: Option<&str>,
+) -> Result<RefreshTokenResponse> {
+    let encoded = {
+        let mut encoded = form_urlencoded::Serializer::new(String::new());
+        let encoded = encoded.append_pair("grant_type", "refresh_token");
+        let encoded = encoded.append_pair("client_id", client_id.as_str());
+        // optionally add the client secret
+        let encoded = if let Some(client_secret) = client_secret {
+            encoded.append_pair("client_secret", client_secret.secret())
+        } else {
+            encoded
+        };
+        // optionally add the scope
+        let encoded = if let Some(scope) = scope {
+            encoded.append_pair("scope", scope)
+        } else {
+            encoded
+        };
+        let encoded = encoded.append_pair("refresh_token", refresh_token.secret());
+        encoded.finish()
     };
-    let encoded = encoded.append_pair("refresh_token", refresh_token.secret());
-    let encoded = encoded.finish();
 
     let url = url::Url::parse(&format!(
-        "https://login.microsoftonline.com/{}/oauth2/v2.0/token",
-        tenant_id
+        "{}/{}/oauth2/v2.0/token",
+        authority_host, tenant_id
     ))?;
 
     let rsp = client
         .post(url)
-        .header("ContentType", "application/x-www-form-urlencoded")
+        .header("Content-Type", "application/x-www-form-urlencoded")
         .body(encoded)
         .send()
         .await
@@ -0,0 +1,534 @@
+//! Token caches that can be shared between credentials and processes
+//!
+//! By default the user credentials keep their tokens in memory, so every process has to
+//! authenticate again. A [`FileTokenCacheStorage`] persists the tokens to an encrypted file
+//! instead, which is locked while it is read or written so that several processes can share it.
+//!
+//! For example, to let every run of a tool reuse the device code sign in:
+//!
+//! ```no_run
+//! use azure_identity::token_cache::FileTokenCacheStorage;
+//! use azure_identity::{DeviceCodeCredential, TokenCredentialOptions};
+//! use std::sync::Arc;
+//!
+//! # fn main() -> azure_core::error::Result<()> {
+//! let storage = FileTokenCacheStorage::with_key_file("tokens.bin", "tokens.key")?;
+//! let credential = DeviceCodeCredential::new(
+//!     "tenant_id".to_owned(),
+//!     "client_id".to_owned(),
+//!     |device_code| eprintln!("{}", device_code.message()),
+//!     TokenCredentialOptions::default(),
+//! )
+//! .with_token_cache(Arc::new(storage));
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Other storage backends, such as an operating system keychain, can be used by implementing
+//! [`TokenCacheStorage`].
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use azure_core::error::{Error, ErrorKind, Result, ResultExt};
+use chrono::{DateTime, Utc};
+use fs2::FileExt;
+use oauth2::AccessToken;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Mutex};
+
+/// The size in bytes of the key used to encrypt a [`FileTokenCacheStorage`]
+pub const ENCRYPTION_KEY_LEN: usize = 32;
+
+/// The size in bytes of the AES-GCM nonce stored in front of the encrypted cache
+const NONCE_LEN: usize = 12;
+
+/// Identifies the tokens issued to an application for a set of scopes
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TokenCacheKey {
+    /// The authority host that issued the token
+    pub authority_host: String,
+    /// The tenant the token was issued by
+    pub tenant_id: String,
+    /// The client (application) id the token was issued to
+    pub client_id: String,
+    /// The space separated scopes the token is valid for
+    pub scopes: String,
+}
+
+impl TokenCacheKey {
+    /// Create a new `TokenCacheKey`
+    pub fn new<A, T, C, S>(authority_host: A, tenant_id: T, client_id: C, scopes: S) -> Self
+    where
+        A: Into<String>,
+        T: Into<String>,
+        C: Into<String>,
+        S: Into<String>,
+    {
+        Self {
+            authority_host: authority_host.into(),
+            tenant_id: tenant_id.into(),
+            client_id: client_id.into(),
+            scopes: scopes.into(),
+        }
+    }
+
+    /// Whether both keys belong to the same application in the same tenant and authority
+    pub fn same_client(&self, other: &TokenCacheKey) -> bool {
+        self.authority_host == other.authority_host
+            && self.tenant_id == other.tenant_id
+            && self.client_id == other.client_id
+    }
+}
+
+/// The tokens stored in a token cache
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedToken {
+    /// The access token
+    pub access_token: AccessToken,
+    /// When the access token expires
+    pub expires_on: DateTime<Utc>,
+    /// The refresh token issued alongside the access token, if any
+    pub refresh_token: Option<AccessToken>,
+}
+
+/// A storage backend for cached tokens
+///
+/// Implementations must make every operation atomic with regard to the other users of the
+/// storage, which may live in other processes. The operations are called from async code, so
+/// implementations doing blocking IO should move it off the calling task.
+#[async_trait::async_trait]
+pub trait TokenCacheStorage: std::fmt::Debug + Send + Sync {
+    /// Returns the token stored for the key, if any
+    async fn get(&self, key: &TokenCacheKey) -> Result<Option<CachedToken>>;
+
+    /// Stores the token for the key, replacing any previous token
+    async fn set(&self, key: &TokenCacheKey, token: &CachedToken) -> Result<()>;
+
+    /// Removes the token stored for the key, if any
+    async fn remove(&self, key: &TokenCacheKey) -> Result<()>;
+
+    /// Returns every stored token
+    async fn entries(&self) -> Result<Vec<(TokenCacheKey, CachedToken)>>;
+}
+
+/// A token cache kept in the memory of the current process
+#[derive(Debug, Default)]
+pub struct InMemoryTokenCacheStorage {
+    tokens: Mutex<HashMap<TokenCacheKey, CachedToken>>,
+}
+
+impl InMemoryTokenCacheStorage {
+    /// Create a new, empty, `InMemoryTokenCacheStorage`
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait::async_trait]
+impl TokenCacheStorage for InMemoryTokenCacheStorage {
+    async fn get(&self, key: &TokenCacheKey) -> Result<Option<CachedToken>> {
+        Ok(self.tokens.lock().unwrap().get(key).cloned())
+    }
+
+    async fn set(&self, key: &TokenCacheKey, token: &CachedToken) -> Result<()> {
+        self.tokens
+            .lock()
+            .unwrap()
+            .insert(key.clone(), token.clone());
+        Ok(())
+    }
+
+    async fn remove(&self, key: &TokenCacheKey) -> Result<()> {
+        self.tokens.lock().unwrap().remove(key);
+        Ok(())
+    }
+
+    async fn entries(&self) -> Result<Vec<(TokenCacheKey, CachedToken)>> {
+        Ok(self
+            .tokens
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(key, token)| (key.clone(), token.clone()))
+            .collect())
+    }
+}
+
+/// A token cache persisted to a file encrypted with AES-256-GCM
+///
+/// Every operation takes an exclusive lock on a `.lock` file next to the cache, so the cache can
+/// be shared by several processes. The cache is replaced atomically when it is written. The file
+/// IO of every file cache runs on one background thread.
+#[derive(Clone)]
+pub struct FileTokenCacheStorage {
+    path: PathBuf,
+    lock_path: PathBuf,
+    cipher: Aes256Gcm,
+}
+
+impl FileTokenCacheStorage {
+    /// Create a new `FileTokenCacheStorage` at `path`, encrypted with the given key
+    pub fn new<P>(path: P, encryption_key: [u8; ENCRYPTION_KEY_LEN]) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        let path = path.into();
+        let mut lock_path = path.clone().into_os_string();
+        lock_path.push(".lock");
+        Self {
+            path,
+            lock_path: lock_path.into(),
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&encryption_key)),
+        }
+    }
+
+    /// Create a new `FileTokenCacheStorage` at `path`, encrypted with the key stored at `key_path`
+    ///
+    /// A random key is generated if `key_path` does not exist yet. On Unix both the key and the
+    /// cache are only readable by the current user.
+    pub fn with_key_file<P, K>(path: P, key_path: K) -> Result<Self>
+    where
+        P: Into<PathBuf>,
+        K: AsRef<Path>,
+    {
+        let key_path = key_path.as_ref();
+        let key = match std::fs::read(key_path) {
+            Ok(key) => key,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                create_key_file(key_path, &Aes256Gcm::generate_key(OsRng))?
+            }
+            Err(error) => return Err(Error::new(ErrorKind::Io, error)),
+        };
+
+        let key: [u8; ENCRYPTION_KEY_LEN] = key.try_into().map_err(|_| {
+            Error::with_message(ErrorKind::DataConversion, || {
+                format!(
+                    "the key file {} must contain exactly {} bytes",
+                    key_path.display(),
+                    ENCRYPTION_KEY_LEN
+                )
+            })
+        })?;
+        Ok(Self::new(path, key))
+    }
+
+    /// Runs [`Self::with_locked_cache`] on the cache thread, so that waiting for the lock and
+    /// the file IO do not block the async runtime
+    async fn run_locked<F, T>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut Vec<(TokenCacheKey, CachedToken)>) -> (bool, T) + Send + 'static,
+        T: Send + 'static,
+    {
+        let storage = self.clone();
+        let (sender, receiver) = futures::channel::oneshot::channel();
+        run_on_cache_thread(Box::new(move || {
+            let _ = sender.send(storage.with_locked_cache(f));
+        }));
+        receiver
+            .await
+            .context(ErrorKind::Io, "the token cache thread stopped unexpectedly")?
+    }
+
+    /// Runs `f` on the decrypted cache while holding the cross-process lock, writing the cache
+    /// back if `f` returns `true`
+    fn with_locked_cache<F, T>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut Vec<(TokenCacheKey, CachedToken)>) -> (bool, T),
+    {
+        let lock = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&self.lock_path)
+            .with_context(ErrorKind::Io, || {
+                format!("failed to open the lock file {}", self.lock_path.display())
+            })?;
+        lock.lock_exclusive().with_context(ErrorKind::Io, || {
+            format!("failed to lock {}", self.lock_path.display())
+        })?;
+
+        let result = self.read().and_then(|mut entries| {
+            let (modified, result) = f(&mut entries);
+            if modified {
+                self.write(&entries)?;
+            }
+            Ok(result)
+        });
+
+        let _ = lock.unlock();
+        result
+    }
+
+    fn read(&self) -> Result<Vec<(TokenCacheKey, CachedToken)>> {
+        let mut data = Vec::new();
+        match File::open(&self.path) {
+            Ok(mut file) => file.read_to_end(&mut data).map_kind(ErrorKind::Io)?,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(error) => return Err(Error::new(ErrorKind::Io, error)),
+        };
+        if data.len() < NONCE_LEN {
+            return Err(Error::with_message(ErrorKind::DataConversion, || {
+                format!("the token cache {} is truncated", self.path.display())
+            }));
+        }
+
+        let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| {
+                Error::with_message(ErrorKind::Credential, || {
+                    format!(
+                        "failed to decrypt the token cache {}, it was written with another key",
+                        self.path.display()
+                    )
+                })
+            })?;
+        serde_json::from_slice(&plaintext).map_kind(ErrorKind::DataConversion)
+    }
+
+    fn write(&self, entries: &[(TokenCacheKey, CachedToken)]) -> Result<()> {
+        let plaintext = serde_json::to_vec(entries).map_kind(ErrorKind::DataConversion)?;
+        let nonce = Aes256Gcm::generate_nonce(OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, plaintext.as_slice())
+            .map_err(|_| Error::message(ErrorKind::Other, "failed to encrypt the token cache"))?;
+
+        let mut temp_path = self.path.clone().into_os_string();
+        temp_path.push(".tmp");
+        let temp_path = PathBuf::from(temp_path);
+        {
+            let mut file = private_file(&temp_path, true).with_context(ErrorKind::Io, || {
+                format!("failed to create {}", temp_path.display())
+            })?;
+            file.write_all(&nonce).map_kind(ErrorKind::Io)?;
+            file.write_all(&ciphertext).map_kind(ErrorKind::Io)?;
+            file.sync_all().map_kind(ErrorKind::Io)?;
+        }
+        std::fs::rename(&temp_path, &self.path).with_context(ErrorKind::Io, || {
+            format!("failed to replace the token cache {}", self.path.display())
+        })
+    }
+}
+
+impl std::fmt::Debug for FileTokenCacheStorage {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("FileTokenCacheStorage")
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+#[async_trait::async_trait]
+impl TokenCacheStorage for FileTokenCacheStorage {
+    async fn get(&self, key: &TokenCacheKey) -> Result<Option<CachedToken>> {
+        let key = key.clone();
+        self.run_locked(move |entries| {
+            let token = entries
+                .iter()
+                .find(|(k, _)| *k == key)
+                .map(|(_, token)| token.clone());
+            (false, token)
+        })
+        .await
+    }
+
+    async fn set(&self, key: &TokenCacheKey, token: &CachedToken) -> Result<()> {
+        let (key, token) = (key.clone(), token.clone());
+        self.run_locked(move |entries| {
+            entries.retain(|(k, _)| *k != key);
+            entries.push((key, token));
+            (true, ())
+        })
+        .await
+    }
+
+    async fn remove(&self, key: &TokenCacheKey) -> Result<()> {
+        let key = key.clone();
+        self.run_locked(move |entries| {
+            let len = entries.len();
+            entries.retain(|(k, _)| *k != key);
+            (entries.len() != len, ())
+        })
+        .await
+    }
+
+    async fn entries(&self) -> Result<Vec<(TokenCacheKey, CachedToken)>> {
+        self.run_locked(|entries| (false, entries.clone())).await
+    }
+}
+
+/// Writes a new key file without ever exposing a partial key: the key is written to a temporary
+/// file which is then linked into place. Returns the key stored in the file, which is the one of
+/// another process if it created the file first.
+fn create_key_file(key_path: &Path, key: &[u8]) -> Result<Vec<u8>> {
+    let mut temp_path = key_path.as_os_str().to_owned();
+    temp_path.push(format!(".{}.tmp", uuid::Uuid::new_v4()));
+    let temp_path = PathBuf::from(temp_path);
+    let linked = private_file(&temp_path, false)
+        .and_then(|mut file| {
+            file.write_all(key)?;
+            file.sync_all()
+        })
+        .and_then(|()| std::fs::hard_link(&temp_path, key_path));
+    let _ = std::fs::remove_file(&temp_path);
+
+    match linked {
+        Ok(()) => Ok(key.to_vec()),
+        // another process created the key in the meantime
+        Err(error) if error.kind() == std::io::ErrorKind::AlreadyExists => {
+            std::fs::read(key_path).map_kind(ErrorKind::Io)
+        }
+        Err(error) => Err(error).with_context(ErrorKind::Io, || {
+            format!("failed to create the key file {}", key_path.display())
+        }),
+    }
+}
+
+/// Opens a file only the current user can read, either truncating it or requiring it to be new
+fn private_file(path: &Path, truncate: bool) -> std::io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.write(true);
+    if truncate {
+        options.create(true).truncate(true);
+    } else {
+        options.create_new(true);
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)
+}
+
+type CacheJob = Box<dyn FnOnce() + Send>;
+
+/// The thread doing the blocking IO of every file token cache, started on first use
+static CACHE_THREAD: Mutex<Option<mpsc::Sender<CacheJob>>> = Mutex::new(None);
+
+/// Queues a job on the cache thread, starting it again if it is not running
+fn run_on_cache_thread(job: CacheJob) {
+    let mut cache_thread = CACHE_THREAD
+        .lock()
+        .unwrap_or_else(|error| error.into_inner());
+    let job = match cache_thread.as_ref() {
+        Some(sender) => match sender.send(job) {
+            Ok(()) => return,
+            Err(mpsc::SendError(job)) => job,
+        },
+        None => job,
+    };
+    let (sender, receiver) = mpsc::channel::<CacheJob>();
+    std::thread::Builder::new()
+        .name("azure-identity-token-cache".to_owned())
+        .spawn(move || receiver.into_iter().for_each(|job| job()))
+        .expect("failed to start the token cache thread");
+    // the receiver was just moved to the new thread so the job is delivered
+    let _ = sender.send(job);
+    *cache_thread = Some(sender);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("{}-{}", name, uuid::Uuid::new_v4()))
+    }
+
+    fn token(secret: &str) -> CachedToken {
+        CachedToken {
+            access_token: AccessToken::new(secret.to_owned()),
+            expires_on: Utc::now(),
+            refresh_token: Some(AccessToken::new(format!("{}_refresh", secret))),
+        }
+    }
+
+    #[tokio::test]
+    async fn file_cache_round_trips_between_instances() {
+        let path = temp_path("tokens");
+        let key_path = temp_path("tokens-key");
+        let key = TokenCacheKey::new("https://login.microsoftonline.com", "tenant", "client", "a");
+        let other_key =
+            TokenCacheKey::new("https://login.microsoftonline.com", "tenant", "client", "b");
+
+        let first = FileTokenCacheStorage::with_key_file(&path, &key_path).unwrap();
+        first.set(&key, &token("first")).await.unwrap();
+        first.set(&other_key, &token("other")).await.unwrap();
+        first.set(&key, &token("second")).await.unwrap();
+
+        let second = FileTokenCacheStorage::with_key_file(&path, &key_path).unwrap();
+        let cached = second.get(&key).await.unwrap().unwrap();
+        assert_eq!(cached.access_token.secret(), "second");
+        assert_eq!(cached.refresh_token.unwrap().secret(), "second_refresh");
+        assert_eq!(second.entries().await.unwrap().len(), 2);
+
+        second.remove(&key).await.unwrap();
+        assert!(first.get(&key).await.unwrap().is_none());
+        assert!(first.get(&other_key).await.unwrap().is_some());
+
+        let raw = std::fs::read(&path).unwrap();
+        assert!(!String::from_utf8_lossy(&raw).contains("other_refresh"));
+
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(&key_path).unwrap();
+        let _ = std::fs::remove_file(second.lock_path);
+    }
+
+    #[test]
+    fn concurrent_users_agree_on_the_key() {
+        let key_path = temp_path("tokens-key");
+        let threads: Vec<_> = (0..8)
+            .map(|_| {
+                let key_path = key_path.clone();
+                std::thread::spawn(move || {
+                    FileTokenCacheStorage::with_key_file("unused", &key_path).map(|_| ())
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap().unwrap();
+        }
+
+        assert_eq!(std::fs::read(&key_path).unwrap().len(), ENCRYPTION_KEY_LEN);
+        std::fs::remove_file(&key_path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn file_cache_rejects_wrong_key() {
+        let path = temp_path("tokens");
+        let key = TokenCacheKey::new("https://login.microsoftonline.com", "tenant", "client", "a");
+
+        let storage = FileTokenCacheStorage::new(&path, [1; ENCRYPTION_KEY_LEN]);
+        storage.set(&key, &token("first")).await.unwrap();
+        let error = FileTokenCacheStorage::new(&path, [2; ENCRYPTION_KEY_LEN])
+            .get(&key)
+            .await
+            .unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::Credential);
+
+        std::fs::remove_file(&path).unwrap();
+        let _ = std::fs::remove_file(storage.lock_path);
+    }
+
+    #[test]
+    fn reuses_one_thread_for_the_cache_io() {
+        let thread = || {
+            let (sender, receiver) = mpsc::channel();
+            run_on_cache_thread(Box::new(move || {
+                let _ = sender.send(std::thread::current().id());
+            }));
+            receiver.recv().unwrap()
+        };
+
+        let first = thread();
+        assert_ne!(first, std::thread::current().id());
+        assert_eq!(thread(), first);
+    }
+}
@@ -0,0 +1,574 @@
+//! Inspection of the claims in Azure Active Directory access tokens
+//!
+//! Access tokens are opaque to clients and should not be relied on for authorization decisions
+//! by them, but knowing which identity a token was issued to is invaluable when diagnosing
+//! authentication failures:
+//!
+//! ```no_run
+//! use azure_core::auth::TokenCredential;
+//! use azure_identity::token_claims::AccessTokenClaims;
+//! use azure_identity::DefaultAzureCredential;
+//!
+//! # async fn example() -> azure_core::error::Result<()> {
+//! let response = DefaultAzureCredential::default()
+//!     .get_token("https://management.azure.com")
+//!     .await?;
+//! let claims = AccessTokenClaims::decode(response.token.secret())?;
+//! log::info!("authenticated as {}", claims);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Services accepting Azure AD tokens can use `AccessTokenClaims::decode_verified`, with the
+//! `token_verification` feature, to also check the signature against the signing keys published
+//! by the tenant at `https://login.microsoftonline.com/{tenant}/discovery/v2.0/keys`, and that
+//! the token was issued for the service by the expected tenant. Azure AD signs the tokens of
+//! every tenant and every resource with the same keys, so a valid signature alone proves little.
+
+use azure_core::error::{Error, ErrorKind, Result, ResultExt};
+use chrono::{DateTime, Utc};
+#[cfg(feature = "token_verification")]
+use rsa::pkcs1v15::{Signature, VerifyingKey};
+#[cfg(feature = "token_verification")]
+use rsa::sha2::Sha256;
+#[cfg(feature = "token_verification")]
+use rsa::signature::Verifier;
+#[cfg(feature = "token_verification")]
+use rsa::{BigUint, RsaPublicKey};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+#[cfg(feature = "token_verification")]
+use std::time::Duration;
+
+/// How far the clocks of the issuer and of the service may drift apart by default
+#[cfg(feature = "token_verification")]
+const DEFAULT_LEEWAY: Duration = Duration::from_secs(5 * 60);
+
+/// The claims of an Azure Active Directory access token
+///
+/// Claims that are missing from the token are `None`. Claims not listed here are kept in
+/// `additional_claims`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AccessTokenClaims {
+    /// The object id of the user or service principal the token was issued to
+    pub oid: Option<String>,
+    /// The tenant the token was issued by
+    pub tid: Option<String>,
+    /// The application id of the client that requested the token (v1 tokens)
+    pub appid: Option<String>,
+    /// The application id of the client that requested the token (v2 tokens)
+    pub azp: Option<String>,
+    /// The user principal name of the user the token was issued to
+    pub upn: Option<String>,
+    /// The audiences, that is the resources, the token is valid for
+    #[serde(
+        default,
+        deserialize_with = "string_or_array",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub aud: Vec<String>,
+    /// The authority that issued the token, such as `https://sts.windows.net/{tenant}/`
+    pub iss: Option<String>,
+    /// The space separated delegated permissions granted to the client
+    pub scp: Option<String>,
+    /// The application permissions granted to the client
+    #[serde(default)]
+    pub roles: Vec<String>,
+    /// When the token expires
+    #[serde(default, with = "chrono::serde::ts_seconds_option")]
+    pub exp: Option<DateTime<Utc>>,
+    /// When the token becomes valid
+    #[serde(default, with = "chrono::serde::ts_seconds_option")]
+    pub nbf: Option<DateTime<Utc>>,
+    /// The Azure resource id of the managed identity the token was issued to
+    pub xms_mirid: Option<String>,
+    /// Every other claim of the token
+    #[serde(flatten)]
+    pub additional_claims: serde_json::Map<String, serde_json::Value>,
+}
+
+impl AccessTokenClaims {
+    /// Decodes the claims of a token without verifying its signature
+    pub fn decode(token: &str) -> Result<Self> {
+        let (_, payload, _) = split(token)?;
+        parse_segment(payload, "payload")
+    }
+
+    /// Decodes the claims of a token after verifying its signature against the key set,
+    /// checking that it is currently valid, and that it was issued by the expected issuer for
+    /// the expected audience
+    ///
+    /// Only RS256 signed tokens, which is what Azure AD issues, are supported.
+    #[cfg(feature = "token_verification")]
+    pub fn decode_verified(
+        token: &str,
+        keys: &JsonWebKeySet,
+        validation: &TokenValidation,
+    ) -> Result<Self> {
+        let (header, payload, signature) = split(token)?;
+        let header: JwtHeader = parse_segment(header, "header")?;
+        if header.alg != "RS256" {
+            return Err(Error::with_message(ErrorKind::Credential, || {
+                format!("unsupported token signing algorithm {}", header.alg)
+            }));
+        }
+
+        if header.kid.is_none() && keys.keys.len() > 1 {
+            return Err(Error::message(
+                ErrorKind::Credential,
+                "the token has no key id to pick one of the keys of the key set",
+            ));
+        }
+        let key = keys
+            .keys
+            .iter()
+            .find(|key| header.kid.is_none() || key.kid == header.kid)
+            .ok_or_else(|| {
+                Error::with_message(ErrorKind::Credential, || {
+                    format!(
+                        "no key in the key set matches the token key id {}",
+                        header.kid.as_deref().unwrap_or("")
+                    )
+                })
+            })?;
+        let signature = Signature::try_from(decode_segment(signature, "signature")?.as_slice())
+            .map_kind(ErrorKind::Credential)?;
+        key.verifying_key()?
+            .verify(
+                &token.as_bytes()[..header_and_payload_len(token)],
+                &signature,
+            )
+            .context(ErrorKind::Credential, "the token signature is invalid")?;
+
+        let claims: Self = parse_segment(payload, "payload")?;
+        claims.validate(validation)?;
+        Ok(claims)
+    }
+
+    #[cfg(feature = "token_verification")]
+    fn validate(&self, validation: &TokenValidation) -> Result<()> {
+        let leeway = chrono::Duration::from_std(validation.leeway)
+            .context(ErrorKind::DataConversion, "the leeway is too long")?;
+        let now = Utc::now();
+        let exp = self
+            .exp
+            .ok_or_else(|| Error::message(ErrorKind::Credential, "the token has no expiry"))?;
+        if exp
+            .checked_add_signed(leeway)
+            .is_some_and(|expires| expires <= now)
+        {
+            return Err(Error::with_message(ErrorKind::Credential, || {
+                format!("the token expired on {}", exp)
+            }));
+        }
+        if let Some(nbf) = self.nbf {
+            if nbf
+                .checked_sub_signed(leeway)
+                .is_some_and(|starts| starts > now)
+            {
+                return Err(Error::with_message(ErrorKind::Credential, || {
+                    format!("the token is not valid before {}", nbf)
+                }));
+            }
+        }
+        if !self.aud.contains(&validation.audience) {
+            return Err(Error::with_message(ErrorKind::Credential, || {
+                format!(
+                    "the token was issued for {:?}, not {}",
+                    self.aud, validation.audience
+                )
+            }));
+        }
+        if self.iss.as_deref() != Some(validation.issuer.as_str()) {
+            return Err(Error::with_message(ErrorKind::Credential, || {
+                format!(
+                    "the token was issued by {}, not {}",
+                    self.iss.as_deref().unwrap_or("an unknown issuer"),
+                    validation.issuer
+                )
+            }));
+        }
+        Ok(())
+    }
+
+    /// The delegated permissions granted to the client
+    pub fn scopes(&self) -> impl Iterator<Item = &str> {
+        self.scp.iter().flat_map(|scp| scp.split_whitespace())
+    }
+
+    /// The id of the client application, for both v1 and v2 tokens
+    pub fn client_id(&self) -> Option<&str> {
+        self.appid.as_deref().or(self.azp.as_deref())
+    }
+}
+
+/// Summarizes who the token was issued to, for logging
+impl fmt::Display for AccessTokenClaims {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let aud = self.aud.join(",");
+        let claims = [
+            ("oid", self.oid.as_deref()),
+            ("tid", self.tid.as_deref()),
+            ("appid", self.client_id()),
+            ("upn", self.upn.as_deref()),
+            ("aud", Some(aud.as_str()).filter(|aud| !aud.is_empty())),
+            ("xms_mirid", self.xms_mirid.as_deref()),
+        ];
+        let mut first = true;
+        for (name, value) in claims {
+            if let Some(value) = value {
+                if !first {
+                    f.write_str(" ")?;
+                }
+                write!(f, "{}={}", name, value)?;
+                first = false;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// What [`AccessTokenClaims::decode_verified`] expects of a token
+#[cfg(feature = "token_verification")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenValidation {
+    audience: String,
+    issuer: String,
+    leeway: Duration,
+}
+
+#[cfg(feature = "token_verification")]
+impl TokenValidation {
+    /// Expects tokens issued by `issuer` for `audience`
+    ///
+    /// The issuer is `https://sts.windows.net/{tenant}/` for v1 tokens and
+    /// `https://login.microsoftonline.com/{tenant}/v2.0` for v2 tokens.
+    pub fn new(audience: impl Into<String>, issuer: impl Into<String>) -> Self {
+        Self {
+            audience: audience.into(),
+            issuer: issuer.into(),
+            leeway: DEFAULT_LEEWAY,
+        }
+    }
+
+    /// Specifies how long before `nbf` and after `exp` tokens are still accepted, to allow for
+    /// clock skew. The default is 5 minutes.
+    pub fn with_leeway(mut self, leeway: Duration) -> Self {
+        self.leeway = leeway;
+        self
+    }
+}
+
+/// A JSON Web Key Set, as published by the `jwks_uri` of an OpenID Connect provider
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JsonWebKeySet {
+    /// The keys of the set
+    pub keys: Vec<JsonWebKey>,
+}
+
+/// A JSON Web Key; only RSA keys can be used for verification
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JsonWebKey {
+    /// The key type, `RSA` for the keys Azure AD signs tokens with
+    pub kty: String,
+    /// The key id tokens refer to in their header
+    pub kid: Option<String>,
+    /// The base64url encoded modulus of an RSA key
+    pub n: Option<String>,
+    /// The base64url encoded exponent of an RSA key
+    pub e: Option<String>,
+}
+
+#[cfg(feature = "token_verification")]
+impl JsonWebKey {
+    fn verifying_key(&self) -> Result<VerifyingKey<Sha256>> {
+        match (self.kty.as_str(), &self.n, &self.e) {
+            ("RSA", Some(n), Some(e)) => {
+                let key = RsaPublicKey::new(
+                    BigUint::from_bytes_be(&decode_segment(n, "key modulus")?),
+                    BigUint::from_bytes_be(&decode_segment(e, "key exponent")?),
+                )
+                .map_kind(ErrorKind::Credential)?;
+                Ok(VerifyingKey::new(key))
+            }
+            _ => Err(Error::with_message(ErrorKind::Credential, || {
+                format!("unsupported key type {}", self.kty)
+            })),
+        }
+    }
+}
+
+/// The `aud` claim is either a single audience or an array of them
+fn string_or_array<'de, D>(deserializer: D) -> std::result::Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Audience {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(match Option::<Audience>::deserialize(deserializer)? {
+        Some(Audience::One(aud)) => vec![aud],
+        Some(Audience::Many(aud)) => aud,
+        None => Vec::new(),
+    })
+}
+
+#[cfg(feature = "token_verification")]
+#[derive(Debug, Deserialize)]
+struct JwtHeader {
+    alg: String,
+    kid: Option<String>,
+}
+
+fn split(token: &str) -> Result<(&str, &str, &str)> {
+    let mut segments = token.split('.');
+    match (
+        segments.next(),
+        segments.next(),
+        segments.next(),
+        segments.next(),
+    ) {
+        (Some(header), Some(payload), Some(signature), None) => Ok((header, payload, signature)),
+        _ => Err(Error::message(
+            ErrorKind::DataConversion,
+            "the token is not a JSON web token",
+        )),
+    }
+}
+
+/// The length of the signed part of the token
+#[cfg(feature = "token_verification")]
+fn header_and_payload_len(token: &str) -> usize {
+    token.rfind('.').unwrap_or(token.len())
+}
+
+fn decode_segment(segment: &str, name: &str) -> Result<Vec<u8>> {
+    base64::decode_config(segment, base64::URL_SAFE_NO_PAD)
+        .with_context(ErrorKind::DataConversion, || {
+            format!("the token {} is not base64url encoded", name)
+        })
+}
+
+fn parse_segment<T>(segment: &str, name: &str) -> Result<T>
+where
+    T: serde::de::DeserializeOwned,
+{
+    serde_json::from_slice(&decode_segment(segment, name)?)
+        .with_context(ErrorKind::DataConversion, || {
+            format!("the token {} is not valid JSON", name)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "token_verification")]
+    use aes_gcm::aead::OsRng;
+    #[cfg(feature = "token_verification")]
+    use rsa::pkcs1v15::SigningKey;
+    #[cfg(feature = "token_verification")]
+    use rsa::signature::{SignatureEncoding, Signer};
+    #[cfg(feature = "token_verification")]
+    use rsa::traits::PublicKeyParts;
+    #[cfg(feature = "token_verification")]
+    use rsa::RsaPrivateKey;
+
+    fn encode(value: &serde_json::Value) -> String {
+        base64::encode_config(value.to_string(), base64::URL_SAFE_NO_PAD)
+    }
+
+    fn payload(exp: i64) -> serde_json::Value {
+        serde_json::json!({
+            "aud": "https://management.azure.com",
+            "iss": "https://sts.windows.net/72f988bf/",
+            "exp": exp,
+            "appid": "04b07795",
+            "oid": "f7b1c1d2",
+            "tid": "72f988bf",
+            "upn": "user@contoso.com",
+            "scp": "user_impersonation Files.Read",
+            "roles": ["Reader"],
+        })
+    }
+
+    #[cfg(feature = "token_verification")]
+    fn sign(key: &RsaPrivateKey, kid: &str, payload: &serde_json::Value) -> String {
+        let header = serde_json::json!({"typ": "JWT", "alg": "RS256", "kid": kid});
+        let signed = format!("{}.{}", encode(&header), encode(payload));
+        let signature = SigningKey::<Sha256>::new(key.clone()).sign(signed.as_bytes());
+        format!(
+            "{}.{}",
+            signed,
+            base64::encode_config(signature.to_bytes(), base64::URL_SAFE_NO_PAD)
+        )
+    }
+
+    #[cfg(feature = "token_verification")]
+    fn key_set(key: &RsaPrivateKey, kid: &str) -> JsonWebKeySet {
+        let public = key.to_public_key();
+        JsonWebKeySet {
+            keys: vec![JsonWebKey {
+                kty: "RSA".to_owned(),
+                kid: Some(kid.to_owned()),
+                n: Some(base64::encode_config(
+                    public.n().to_bytes_be(),
+                    base64::URL_SAFE_NO_PAD,
+                )),
+                e: Some(base64::encode_config(
+                    public.e().to_bytes_be(),
+                    base64::URL_SAFE_NO_PAD,
+                )),
+            }],
+        }
+    }
+
+    #[test]
+    fn decodes_claims_without_verification() {
+        let token = format!(
+            "{}.{}.signature",
+            encode(&serde_json::json!({"alg": "RS256"})),
+            encode(&payload(1893553445))
+        );
+
+        let claims = AccessTokenClaims::decode(&token).unwrap();
+        assert_eq!(claims.oid.as_deref(), Some("f7b1c1d2"));
+        assert_eq!(claims.client_id(), Some("04b07795"));
+        assert_eq!(claims.roles, ["Reader"]);
+        assert_eq!(
+            claims.scopes().collect::<Vec<_>>(),
+            ["user_impersonation", "Files.Read"]
+        );
+        assert_eq!(claims.exp.unwrap().timestamp(), 1893553445);
+        assert_eq!(claims.xms_mirid, None);
+        assert_eq!(claims.aud, ["https://management.azure.com"]);
+        assert_eq!(
+            claims.iss.as_deref(),
+            Some("https://sts.windows.net/72f988bf/")
+        );
+        assert_eq!(
+            claims.to_string(),
+            "oid=f7b1c1d2 tid=72f988bf appid=04b07795 upn=user@contoso.com aud=https://management.azure.com"
+        );
+
+        assert!(AccessTokenClaims::decode("not a token").is_err());
+    }
+
+    #[cfg(feature = "token_verification")]
+    const AUDIENCE: &str = "https://management.azure.com";
+    #[cfg(feature = "token_verification")]
+    const ISSUER: &str = "https://sts.windows.net/72f988bf/";
+
+    #[cfg(feature = "token_verification")]
+    fn verify(token: &str, keys: &JsonWebKeySet) -> Result<AccessTokenClaims> {
+        AccessTokenClaims::decode_verified(token, keys, &TokenValidation::new(AUDIENCE, ISSUER))
+    }
+
+    #[cfg(feature = "token_verification")]
+    #[test]
+    fn verifies_signature() {
+        let key = RsaPrivateKey::new(&mut OsRng, 1024).unwrap();
+        let other_key = RsaPrivateKey::new(&mut OsRng, 1024).unwrap();
+        let keys = key_set(&key, "key1");
+        let valid_until = Utc::now().timestamp() + 3600;
+
+        let token = sign(&key, "key1", &payload(valid_until));
+        let claims = verify(&token, &keys).unwrap();
+        assert_eq!(claims.tid.as_deref(), Some("72f988bf"));
+
+        let forged = sign(&other_key, "key1", &payload(valid_until));
+        let error = verify(&forged, &keys).unwrap_err();
+        assert_eq!(error.to_string(), "the token signature is invalid");
+
+        let unknown_key = sign(&key, "key2", &payload(valid_until));
+        assert!(verify(&unknown_key, &keys).is_err());
+
+        let expired = sign(&key, "key1", &payload(Utc::now().timestamp() - 600));
+        let error = verify(&expired, &keys).unwrap_err();
+        assert!(error.to_string().starts_with("the token expired"));
+    }
+
+    #[cfg(feature = "token_verification")]
+    #[test]
+    fn allows_for_clock_skew() {
+        let key = RsaPrivateKey::new(&mut OsRng, 1024).unwrap();
+        let keys = key_set(&key, "key1");
+        let now = Utc::now().timestamp();
+        let just_expired = sign(&key, "key1", &payload(now - 60));
+        let mut payload = payload(now + 3600);
+        payload["nbf"] = serde_json::json!(now + 60);
+        let almost_valid = sign(&key, "key1", &payload);
+
+        assert!(verify(&just_expired, &keys).is_ok());
+        assert!(verify(&almost_valid, &keys).is_ok());
+
+        let strict = TokenValidation::new(AUDIENCE, ISSUER).with_leeway(Duration::ZERO);
+        let error = AccessTokenClaims::decode_verified(&just_expired, &keys, &strict).unwrap_err();
+        assert!(error.to_string().starts_with("the token expired"));
+        let error = AccessTokenClaims::decode_verified(&almost_valid, &keys, &strict).unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("the token is not valid before"));
+    }
+
+    #[cfg(feature = "token_verification")]
+    #[test]
+    fn verifies_audience_issuer_and_validity_period() {
+        let key = RsaPrivateKey::new(&mut OsRng, 1024).unwrap();
+        let keys = key_set(&key, "key1");
+        let valid_until = Utc::now().timestamp() + 3600;
+        let with = |claim: &str, value: serde_json::Value| {
+            let mut payload = payload(valid_until);
+            payload[claim] = value;
+            sign(&key, "key1", &payload)
+        };
+
+        let several_audiences = with("aud", serde_json::json!(["api://other", AUDIENCE]));
+        let claims = verify(&several_audiences, &keys).unwrap();
+        assert_eq!(claims.aud, ["api://other", AUDIENCE]);
+
+        let other_audience = with("aud", serde_json::json!("api://other"));
+        let error = verify(&other_audience, &keys).unwrap_err();
+        assert!(error.to_string().starts_with("the token was issued for"));
+
+        let other_tenant = with("iss", serde_json::json!("https://sts.windows.net/other/"));
+        let error = verify(&other_tenant, &keys).unwrap_err();
+        assert!(error.to_string().starts_with("the token was issued by"));
+
+        let not_yet_valid = with("nbf", serde_json::json!(valid_until));
+        let error = verify(&not_yet_valid, &keys).unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("the token is not valid before"));
+    }
+
+    #[cfg(feature = "token_verification")]
+    #[test]
+    fn requires_a_key_id_with_several_keys() {
+        let key = RsaPrivateKey::new(&mut OsRng, 1024).unwrap();
+        let other_key = RsaPrivateKey::new(&mut OsRng, 1024).unwrap();
+        let header = serde_json::json!({"typ": "JWT", "alg": "RS256"});
+        let signed = format!(
+            "{}.{}",
+            encode(&header),
+            encode(&payload(Utc::now().timestamp() + 3600))
+        );
+        let signature = SigningKey::<Sha256>::new(key.clone()).sign(signed.as_bytes());
+        let token = format!(
+            "{}.{}",
+            signed,
+            base64::encode_config(signature.to_bytes(), base64::URL_SAFE_NO_PAD)
+        );
+
+        let single = key_set(&key, "key1");
+        assert!(verify(&token, &single).is_ok());
+
+        let mut several = key_set(&other_key, "key0");
+        several.keys.extend(single.keys);
+        let error = verify(&token, &several).unwrap_err();
+        assert!(error.to_string().contains("no key id"));
+    }
+}
@@ -1,10 +1,12 @@
+use crate::token_cache::{CachedToken, TokenCacheKey, TokenCacheStorage};
 use async_lock::RwLock;
 use azure_core::auth::{TokenCredential, TokenResponse};
 use azure_core::error::{Error, ErrorKind, Result};
 use chrono::{Duration, Utc};
+use log::debug;
 use std::sync::Arc;
 
-fn is_expired(token: &TokenResponse) -> bool {
+pub(crate) fn is_expired(token: &TokenResponse) -> bool {
     token.expires_on < Utc::now() + Duration::seconds(20)
 }
 
@@ -13,6 +15,7 @@ fn is_expired(token: &TokenResponse) -> bool {
 pub struct AutoRefreshingTokenCredential {
     credential: Arc<dyn TokenCredential>,
     current_token: Arc<RwLock<Option<Result<TokenResponse>>>>,
+    token_cache: Option<(Arc<dyn TokenCacheStorage>, TokenCacheKey)>,
 }
 
 impl std::fmt::Debug for AutoRefreshingTokenCredential {
@@ -29,8 +32,65 @@ impl AutoRefreshingTokenCredential {
         Self {
             credential: provider,
             current_token: Arc::new(RwLock::new(None)),
+            token_cache: None,
         }
     }
+
+    /// Also stores the tokens in the given storage, so they can be reused by other processes.
+    ///
+    /// The tokens are stored under the authority host, tenant and client id of the wrapped
+    /// credential, which are used to tell the tokens of different credentials apart.
+    pub fn with_token_cache<A, T, C>(
+        mut self,
+        storage: Arc<dyn TokenCacheStorage>,
+        authority_host: A,
+        tenant_id: T,
+        client_id: C,
+    ) -> Self
+    where
+        A: Into<String>,
+        T: Into<String>,
+        C: Into<String>,
+    {
+        let key = TokenCacheKey::new(authority_host, tenant_id, client_id, "");
+        self.token_cache = Some((storage, key));
+        self
+    }
+
+    async fn fetch_token(&self, resource: &str) -> Result<TokenResponse> {
+        let (storage, key) = match &self.token_cache {
+            Some((storage, key)) => (
+                storage,
+                TokenCacheKey {
+                    scopes: format!("{}/.default", resource),
+                    ..key.clone()
+                },
+            ),
+            None => return self.credential.get_token(resource).await,
+        };
+
+        match storage.get(&key).await {
+            Ok(Some(cached)) => {
+                let token = TokenResponse::new(cached.access_token, cached.expires_on);
+                if !is_expired(&token) {
+                    return Ok(token);
+                }
+            }
+            Ok(None) => {}
+            Err(error) => debug!("reading the token cache failed: {}", error),
+        }
+
+        let token = self.credential.get_token(resource).await?;
+        let cached = CachedToken {
+            access_token: token.token.clone(),
+            expires_on: token.expires_on,
+            refresh_token: None,
+        };
+        if let Err(error) = storage.set(&key, &cached).await {
+            debug!("writing the token cache failed: {}", error);
+        }
+        Ok(token)
+    }
 }
 
 #[async_trait::async_trait]
@@ -45,7 +105,7 @@ impl TokenCredential for AutoRefreshingTokenCredential {
             let mut guard = self.current_token.write().await;
             match guard.as_ref() {
                 None => {
-                    let res = self.credential.get_token(resource).await;
+                    let res = self.fetch_token(resource).await;
                     *guard = Some(res);
                 }
                 Some(Err(err)) => {
@@ -64,3 +124,65 @@ impl TokenCredential for AutoRefreshingTokenCredential {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::token_cache::InMemoryTokenCacheStorage;
+    use oauth2::AccessToken;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Debug, Default)]
+    struct CountingCredential {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl TokenCredential for CountingCredential {
+        async fn get_token(&self, _resource: &str) -> Result<TokenResponse> {
+            let calls = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+            Ok(TokenResponse::new(
+                AccessToken::new(format!("token_{}", calls)),
+                Utc::now() + Duration::hours(1),
+            ))
+        }
+    }
+
+    #[tokio::test]
+    async fn shares_tokens_through_the_token_cache() {
+        let storage: Arc<dyn TokenCacheStorage> = Arc::new(InMemoryTokenCacheStorage::new());
+        let inner = Arc::new(CountingCredential::default());
+        let credential = |storage: &Arc<dyn TokenCacheStorage>| {
+            AutoRefreshingTokenCredential::new(inner.clone()).with_token_cache(
+                storage.clone(),
+                "https://login.microsoftonline.com",
+                "tenant",
+                "client",
+            )
+        };
+
+        let first = credential(&storage)
+            .get_token("https://management.azure.com")
+            .await
+            .unwrap();
+        let second = credential(&storage)
+            .get_token("https://management.azure.com")
+            .await
+            .unwrap();
+        assert_eq!(first.token.secret(), "token_1");
+        assert_eq!(second.token.secret(), "token_1");
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 1);
+
+        let cached = storage
+            .get(&TokenCacheKey::new(
+                "https://login.microsoftonline.com",
+                "tenant",
+                "client",
+                "https://management.azure.com/.default",
+            ))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(cached.access_token.secret(), "token_1");
+    }
+}
@@ -0,0 +1,155 @@
+use super::cli_command::{validate_argument, CliCommand};
+use azure_core::auth::{TokenCredential, TokenResponse};
+use azure_core::error::{ErrorKind, Result, ResultExt};
+use chrono::{DateTime, Utc};
+use oauth2::AccessToken;
+use serde::Deserialize;
+use std::time::Duration;
+
+/// How long to wait for `azd` by default
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AzdTokenResponse {
+    pub token: AccessToken,
+    pub expires_on: DateTime<Utc>,
+}
+
+/// Enables authentication to Azure Active Directory using the Azure Developer CLI (`azd`) to obtain an access token.
+///
+/// The user must have signed in with `azd auth login`.
+#[derive(Debug, Clone)]
+pub struct AzureDeveloperCliCredential {
+    tenant_id: Option<String>,
+    command: CliCommand,
+}
+
+impl Default for AzureDeveloperCliCredential {
+    fn default() -> Self {
+        Self {
+            tenant_id: None,
+            command: CliCommand {
+                name: "Azure Developer CLI",
+                program: "azd",
+                cmd_script: true,
+                path: None,
+                timeout: DEFAULT_TIMEOUT,
+            },
+        }
+    }
+}
+
+impl AzureDeveloperCliCredential {
+    /// Create a new `AzureDeveloperCliCredential`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests tokens from the given tenant instead of the default tenant of the signed in user
+    pub fn with_tenant_id(mut self, tenant_id: impl Into<String>) -> Self {
+        self.tenant_id = Some(tenant_id.into());
+        self
+    }
+
+    /// Specifies how long to wait for `azd`. The default is 10 seconds.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.command.timeout = timeout;
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl TokenCredential for AzureDeveloperCliCredential {
+    async fn get_token(&self, resource: &str) -> Result<TokenResponse> {
+        validate_argument("resource", resource)?;
+        let scope = format!("{}/.default", resource);
+        let mut args = vec!["auth", "token", "--output", "json", "--scope", &scope];
+        if let Some(tenant_id) = &self.tenant_id {
+            validate_argument("tenant id", tenant_id)?;
+            args.push("--tenant-id");
+            args.push(tenant_id);
+        }
+
+        let output = self.command.output(&args).await?;
+        let response = serde_json::from_slice::<AzdTokenResponse>(&output).with_context(
+            ErrorKind::DataConversion,
+            || {
+                format!(
+                    "failed to parse the azd auth token output: {}",
+                    String::from_utf8_lossy(&output)
+                )
+            },
+        )?;
+        Ok(TokenResponse::new(response.token, response.expires_on))
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::super::cli_command::tests::fake_executable;
+    use super::*;
+    use chrono::TimeZone;
+
+    fn credential(script: &str) -> AzureDeveloperCliCredential {
+        let mut credential = AzureDeveloperCliCredential::new();
+        credential.command.path = Some(fake_executable("azd", script));
+        credential
+    }
+
+    #[tokio::test]
+    async fn parses_azd_token() {
+        let credential = credential(
+            r#"echo "$@" >&2
+[ "$*" = "auth token --output json --scope https://vault.azure.net/.default --tenant-id contoso" ] || exit 1
+echo '{"token":"azd_token","expiresOn":"2030-01-02T03:04:05Z"}'"#,
+        )
+        .with_tenant_id("contoso");
+
+        let token = credential
+            .get_token("https://vault.azure.net")
+            .await
+            .unwrap();
+        assert_eq!(token.token.secret(), "azd_token");
+        assert_eq!(
+            token.expires_on,
+            Utc.with_ymd_and_hms(2030, 1, 2, 3, 4, 5).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn reports_azd_errors() {
+        let credential =
+            credential("echo 'ERROR: not logged in, run `azd auth login` to login' >&2\nexit 1");
+
+        let error = credential
+            .get_token("https://vault.azure.net")
+            .await
+            .unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::Credential);
+        assert!(error.to_string().contains("azd auth login"));
+    }
+
+    #[tokio::test]
+    async fn times_out() {
+        let credential = credential("sleep 5").with_timeout(Duration::from_millis(100));
+
+        let error = credential
+            .get_token("https://vault.azure.net")
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("did not return a token"));
+    }
+
+    #[tokio::test]
+    async fn reports_missing_azd() {
+        let mut credential = AzureDeveloperCliCredential::new();
+        credential.command.path = Some(std::env::temp_dir().join("missing").into_os_string());
+
+        let error = credential
+            .get_token("https://vault.azure.net")
+            .await
+            .unwrap_err();
+        assert_eq!(error.to_string(), "Azure Developer CLI not installed");
+    }
+}
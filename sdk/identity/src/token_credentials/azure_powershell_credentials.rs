@@ -0,0 +1,184 @@
+use super::cli_command::{validate_argument, CliCommand};
+use azure_core::auth::{TokenCredential, TokenResponse};
+use azure_core::error::{Error, ErrorKind, Result, ResultExt};
+use chrono::{TimeZone, Utc};
+use oauth2::AccessToken;
+use serde::Deserialize;
+use std::time::Duration;
+
+/// How long to wait for PowerShell by default
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Printed by the script when the Az.Accounts module is not installed
+const NO_AZ_ACCOUNT_MODULE: &str = "NoAzAccountModule";
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct PowerShellTokenResponse {
+    pub token: AccessToken,
+    /// Seconds since the Unix epoch
+    pub expires_on: i64,
+}
+
+/// Enables authentication to Azure Active Directory using Azure PowerShell (`Get-AzAccessToken`) to obtain an access token.
+///
+/// The user must have signed in with `Connect-AzAccount`. PowerShell Core (`pwsh`) and the
+/// `Az.Accounts` module must be installed.
+#[derive(Debug, Clone)]
+pub struct AzurePowerShellCredential {
+    tenant_id: Option<String>,
+    command: CliCommand,
+}
+
+impl Default for AzurePowerShellCredential {
+    fn default() -> Self {
+        Self {
+            tenant_id: None,
+            command: CliCommand {
+                name: "Azure PowerShell",
+                program: "pwsh",
+                cmd_script: false,
+                path: None,
+                timeout: DEFAULT_TIMEOUT,
+            },
+        }
+    }
+}
+
+impl AzurePowerShellCredential {
+    /// Create a new `AzurePowerShellCredential`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests tokens from the given tenant instead of the tenant of the current Az context
+    pub fn with_tenant_id(mut self, tenant_id: impl Into<String>) -> Self {
+        self.tenant_id = Some(tenant_id.into());
+        self
+    }
+
+    /// Specifies how long to wait for PowerShell. The default is 10 seconds.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.command.timeout = timeout;
+        self
+    }
+
+    fn script(&self, resource: &str) -> String {
+        let tenant = match &self.tenant_id {
+            Some(tenant_id) => format!(" -TenantId '{}'", tenant_id),
+            None => String::new(),
+        };
+        format!(
+            "$ErrorActionPreference = 'Stop'
+Import-Module Az.Accounts -MinimumVersion 2.2.0 -ErrorAction SilentlyContinue
+if (-not (Get-Module Az.Accounts)) {{ Write-Output '{NO_AZ_ACCOUNT_MODULE}'; exit }}
+$token = Get-AzAccessToken -ResourceUrl '{resource}'{tenant}
+$secret = $token.Token
+if ($secret -is [System.Security.SecureString]) {{ $secret = ConvertFrom-SecureString $secret -AsPlainText }}
+@{{ Token = $secret; ExpiresOn = $token.ExpiresOn.ToUnixTimeSeconds() }} | ConvertTo-Json -Compress"
+        )
+    }
+}
+
+#[async_trait::async_trait]
+impl TokenCredential for AzurePowerShellCredential {
+    async fn get_token(&self, resource: &str) -> Result<TokenResponse> {
+        validate_argument("resource", resource)?;
+        if let Some(tenant_id) = &self.tenant_id {
+            validate_argument("tenant id", tenant_id)?;
+        }
+
+        let script = encode_command(&self.script(resource));
+        let output = self
+            .command
+            .output(&["-NoProfile", "-NonInteractive", "-EncodedCommand", &script])
+            .await?;
+        let output = String::from_utf8_lossy(&output);
+        if output.trim() == NO_AZ_ACCOUNT_MODULE {
+            return Err(Error::message(
+                ErrorKind::Other,
+                "the Az.Accounts PowerShell module is not installed",
+            ));
+        }
+
+        let response = serde_json::from_str::<PowerShellTokenResponse>(output.trim())
+            .with_context(ErrorKind::DataConversion, || {
+                format!("failed to parse the Get-AzAccessToken output: {}", output)
+            })?;
+        let expires_on = Utc
+            .timestamp_opt(response.expires_on, 0)
+            .single()
+            .ok_or_else(|| {
+                Error::with_message(ErrorKind::DataConversion, || {
+                    format!("invalid token expiry: {}", response.expires_on)
+                })
+            })?;
+        Ok(TokenResponse::new(response.token, expires_on))
+    }
+}
+
+/// Encodes a script for `-EncodedCommand`, which takes base64 encoded UTF-16LE and keeps the
+/// lines of the script intact whichever shell starts PowerShell
+fn encode_command(script: &str) -> String {
+    let bytes: Vec<u8> = script.encode_utf16().flat_map(u16::to_le_bytes).collect();
+    base64::encode(bytes)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::super::cli_command::tests::fake_executable;
+    use super::*;
+
+    fn credential(script: &str) -> AzurePowerShellCredential {
+        let mut credential = AzurePowerShellCredential::new();
+        credential.command.path = Some(fake_executable("pwsh", script));
+        credential
+    }
+
+    #[tokio::test]
+    async fn parses_powershell_token() {
+        let credential = credential(
+            r#"[ "$1 $2 $3" = "-NoProfile -NonInteractive -EncodedCommand" ] || exit 1
+script=$(echo "$4" | base64 -d | tr -d '\000')
+case "$script" in *"-ResourceUrl 'https://vault.azure.net' -TenantId 'contoso'"*) ;; *) exit 1 ;; esac
+echo '{"Token":"pwsh_token","ExpiresOn":1893553445}'"#,
+        )
+        .with_tenant_id("contoso");
+
+        let token = credential
+            .get_token("https://vault.azure.net")
+            .await
+            .unwrap();
+        assert_eq!(token.token.secret(), "pwsh_token");
+        assert_eq!(token.expires_on.timestamp(), 1893553445);
+    }
+
+    #[test]
+    fn encodes_the_script_as_utf16() {
+        let encoded = encode_command("a\nb");
+        assert_eq!(base64::decode(encoded).unwrap(), b"a\0\n\0b\0");
+    }
+
+    #[tokio::test]
+    async fn reports_missing_module() {
+        let credential = credential("echo NoAzAccountModule");
+
+        let error = credential
+            .get_token("https://vault.azure.net")
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("Az.Accounts"));
+    }
+
+    #[tokio::test]
+    async fn rejects_script_injection() {
+        let credential = credential("exit 1").with_tenant_id("x'; Remove-Item *; '");
+
+        let error = credential
+            .get_token("https://vault.azure.net")
+            .await
+            .unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::Credential);
+        assert!(error.to_string().starts_with("invalid tenant id"));
+    }
+}
@@ -0,0 +1,209 @@
+use async_lock::RwLock;
+use azure_core::auth::{TokenCredential, TokenResponse};
+use azure_core::error::{Error, ErrorKind, Result};
+use log::debug;
+use std::sync::Arc;
+
+/// Tries a list of `TokenCredential` sources in order until one of them provides a token.
+///
+/// The first source that succeeds is remembered and used on its own for every later request,
+/// until it fails, for example when a CLI login expires, and the sources are tried in order
+/// again. If every source fails, the returned error lists why each of them failed.
+pub struct ChainedTokenCredential {
+    sources: Vec<Arc<dyn TokenCredential>>,
+    selected: RwLock<Option<usize>>,
+}
+
+impl ChainedTokenCredential {
+    /// Create a new `ChainedTokenCredential` trying the sources in the order provided
+    pub fn new(sources: Vec<Arc<dyn TokenCredential>>) -> Self {
+        Self {
+            sources,
+            selected: RwLock::new(None),
+        }
+    }
+}
+
+impl std::fmt::Debug for ChainedTokenCredential {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("ChainedTokenCredential")
+            .field("sources", &self.sources.len())
+            .finish()
+    }
+}
+
+#[async_trait::async_trait]
+impl TokenCredential for ChainedTokenCredential {
+    /// Try to fetch a token using each of the credential sources until one succeeds
+    async fn get_token(&self, resource: &str) -> Result<TokenResponse> {
+        let mut failed = None;
+        let selected = *self.selected.read().await;
+        if let Some(index) = selected {
+            match self.sources[index].get_token(resource).await {
+                Ok(token) => return Ok(token),
+                Err(error) => {
+                    debug!("the selected credential source failed, trying every source again");
+                    *self.selected.write().await = None;
+                    failed = Some((index, error));
+                }
+            }
+        }
+
+        let mut errors = Vec::with_capacity(self.sources.len());
+        for (index, source) in self.sources.iter().enumerate() {
+            // the selected source is not asked twice
+            if let Some((failed_index, _)) = &failed {
+                if *failed_index == index {
+                    errors.extend(failed.take().map(|(_, error)| error));
+                    continue;
+                }
+            }
+            match source.get_token(resource).await {
+                Ok(token) => {
+                    *self.selected.write().await = Some(index);
+                    return Ok(token);
+                }
+                Err(error) => errors.push(error),
+            }
+        }
+        Err(Error::with_message(ErrorKind::Credential, || {
+            if errors.is_empty() {
+                "no credential sources were configured".to_owned()
+            } else {
+                format!(
+                    "Multiple errors were encountered while attempting to authenticate:\n{}",
+                    format_aggregate_error(&errors)
+                )
+            }
+        }))
+    }
+}
+
+/// Formats one line per error, each including the chain of its causes
+fn format_aggregate_error(errors: &[Error]) -> String {
+    errors
+        .iter()
+        .map(|error| {
+            let mut messages = vec![error.to_string()];
+            let mut cause = inner_error(error);
+            while let Some(error) = cause {
+                let message = error.to_string();
+                // errors without a message of their own display the error they wrap
+                if messages.last() != Some(&message) {
+                    messages.push(message);
+                }
+                cause = match error.downcast_ref::<Error>() {
+                    Some(error) => inner_error(error),
+                    None => error.source(),
+                };
+            }
+            messages.join(": ")
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+fn inner_error(error: &Error) -> Option<&(dyn std::error::Error + 'static)> {
+    error
+        .get_ref()
+        .map(|error| error as &(dyn std::error::Error + 'static))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use oauth2::AccessToken;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    #[derive(Default)]
+    struct FakeCredential {
+        token: Option<&'static str>,
+        expired: AtomicBool,
+        calls: AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl TokenCredential for FakeCredential {
+        async fn get_token(&self, _resource: &str) -> Result<TokenResponse> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let token = self.token.filter(|_| !self.expired.load(Ordering::SeqCst));
+            match token {
+                Some(token) => Ok(TokenResponse::new(
+                    AccessToken::new(token.to_owned()),
+                    Utc::now(),
+                )),
+                None => Err(Error::full(
+                    ErrorKind::Credential,
+                    Error::message(ErrorKind::Io, "source unavailable"),
+                    "fake failed",
+                )),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn remembers_the_first_successful_source() {
+        let failing = Arc::new(FakeCredential::default());
+        let first = Arc::new(FakeCredential {
+            token: Some("first"),
+            ..Default::default()
+        });
+        let second = Arc::new(FakeCredential {
+            token: Some("second"),
+            ..Default::default()
+        });
+        let credential =
+            ChainedTokenCredential::new(vec![failing.clone(), first.clone(), second.clone()]);
+
+        for _ in 0..2 {
+            let token = credential.get_token("resource").await.unwrap();
+            assert_eq!(token.token.secret(), "first");
+        }
+        assert_eq!(failing.calls.load(Ordering::SeqCst), 1);
+        assert_eq!(first.calls.load(Ordering::SeqCst), 2);
+        assert_eq!(second.calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn tries_every_source_again_when_the_selected_one_fails() {
+        let first = Arc::new(FakeCredential {
+            token: Some("first"),
+            ..Default::default()
+        });
+        let second = Arc::new(FakeCredential {
+            token: Some("second"),
+            ..Default::default()
+        });
+        let credential = ChainedTokenCredential::new(vec![first.clone(), second.clone()]);
+        let token = credential.get_token("resource").await.unwrap();
+        assert_eq!(token.token.secret(), "first");
+
+        first.expired.store(true, Ordering::SeqCst);
+        let token = credential.get_token("resource").await.unwrap();
+        assert_eq!(token.token.secret(), "second");
+        assert_eq!(first.calls.load(Ordering::SeqCst), 2);
+
+        let token = credential.get_token("resource").await.unwrap();
+        assert_eq!(token.token.secret(), "second");
+        assert_eq!(first.calls.load(Ordering::SeqCst), 2);
+        assert_eq!(second.calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn lists_every_failure() {
+        let credential = ChainedTokenCredential::new(vec![
+            Arc::new(FakeCredential::default()),
+            Arc::new(FakeCredential::default()),
+        ]);
+
+        let error = credential.get_token("resource").await.unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::Credential);
+        assert_eq!(
+            error.to_string(),
+            "Multiple errors were encountered while attempting to authenticate:\n\
+             fake failed: source unavailable\n\
+             fake failed: source unavailable"
+        );
+    }
+}
@@ -0,0 +1,211 @@
+use azure_core::error::{Error, ErrorKind, Result, ResultExt};
+use std::ffi::OsString;
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often to check whether the tool exited
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// A developer tool invoked to obtain an access token
+#[derive(Debug, Clone)]
+pub(crate) struct CliCommand {
+    /// The name of the tool used in error messages
+    pub name: &'static str,
+    /// The executable to run
+    pub program: &'static str,
+    /// Whether the tool is a cmd script on windows, which must be started through `cmd /C`
+    pub cmd_script: bool,
+    /// Overrides the `PATH` the executable is looked up in
+    pub path: Option<OsString>,
+    /// How long to wait for the tool before killing it
+    pub timeout: Duration,
+}
+
+impl CliCommand {
+    /// Runs the tool with the given arguments on a background thread and returns its standard
+    /// output, so that waiting for the tool does not block the async runtime
+    pub async fn output(&self, args: &[&str]) -> Result<Vec<u8>> {
+        let command = self.clone();
+        let args: Vec<String> = args.iter().map(|arg| (*arg).to_owned()).collect();
+        let (sender, receiver) = futures::channel::oneshot::channel();
+        thread::spawn(move || {
+            let args: Vec<&str> = args.iter().map(String::as_str).collect();
+            let _ = sender.send(command.output_blocking(&args));
+        });
+        receiver.await.with_context(ErrorKind::Other, || {
+            format!("{} stopped unexpectedly", self.name)
+        })?
+    }
+
+    /// The command running the tool with the given arguments
+    fn command(&self, args: &[&str], windows: bool) -> Command {
+        // on windows cmd scripts, such as az and azd, must be started through cmd
+        // see https://doc.rust-lang.org/nightly/std/process/struct.Command.html
+        let mut command = if windows && self.cmd_script {
+            let mut command = Command::new("cmd");
+            command.args(["/C", self.program]);
+            command
+        } else {
+            Command::new(self.program)
+        };
+        command.args(args);
+        command
+    }
+
+    fn output_blocking(&self, args: &[&str]) -> Result<Vec<u8>> {
+        let mut command = self.command(args, cfg!(target_os = "windows"));
+        command
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        if let Some(path) = &self.path {
+            command.env("PATH", path);
+        }
+
+        let mut child = match command.spawn() {
+            Ok(child) => child,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                return Err(Error::with_message(ErrorKind::Other, || {
+                    format!("{} not installed", self.name)
+                }));
+            }
+            Err(error) => {
+                return Err(Error::full(
+                    ErrorKind::Other,
+                    error,
+                    format!("failed to start {}", self.name),
+                ))
+            }
+        };
+
+        // read the pipes while waiting so that a chatty tool can not block on a full pipe
+        let stdout = read_in_background(child.stdout.take());
+        let stderr = read_in_background(child.stderr.take());
+
+        let started = Instant::now();
+        let status = loop {
+            match child.try_wait() {
+                Ok(Some(status)) => break status,
+                Ok(None) if started.elapsed() >= self.timeout => {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return Err(Error::with_message(ErrorKind::Credential, || {
+                        format!(
+                            "{} did not return a token within {} seconds",
+                            self.name,
+                            self.timeout.as_secs_f32()
+                        )
+                    }));
+                }
+                Ok(None) => thread::sleep(POLL_INTERVAL),
+                Err(error) => {
+                    return Err(Error::full(
+                        ErrorKind::Other,
+                        error,
+                        format!("failed to wait for {}", self.name),
+                    ))
+                }
+            }
+        };
+
+        let stdout = stdout.join().unwrap_or_default();
+        if status.success() {
+            Ok(stdout)
+        } else {
+            let stderr = stderr.join().unwrap_or_default();
+            let stderr = String::from_utf8_lossy(&stderr);
+            Err(Error::with_message(ErrorKind::Credential, || {
+                format!("{} failed: {}", self.name, stderr.trim())
+            }))
+        }
+    }
+}
+
+fn read_in_background<R>(pipe: Option<R>) -> thread::JoinHandle<Vec<u8>>
+where
+    R: Read + Send + 'static,
+{
+    thread::spawn(move || {
+        let mut output = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut output);
+        }
+        output
+    })
+}
+
+/// Checks that a value passed to a tool only contains characters valid in resources and tenants
+///
+/// This keeps values from being interpreted as options or scripts by the tool.
+pub(crate) fn validate_argument(name: &str, value: &str) -> Result<()> {
+    let valid = !value.is_empty()
+        && !value.starts_with('-')
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || ".-_:/".contains(c));
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::with_message(ErrorKind::Credential, || {
+            format!("invalid {}: {}", name, value)
+        }))
+    }
+}
+
+#[cfg(all(test, unix))]
+pub(crate) mod tests {
+    use std::ffi::OsString;
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::PathBuf;
+
+    /// Creates a directory containing an executable shell script named `program`
+    ///
+    /// The returned path is meant to be used as the `PATH` of a [`super::CliCommand`].
+    pub fn fake_executable(program: &str, script: &str) -> OsString {
+        let dir: PathBuf = std::env::temp_dir().join(format!("fake-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&dir).unwrap();
+        let file = dir.join(program);
+        std::fs::write(&file, format!("#!/bin/sh\n{}\n", script)).unwrap();
+        std::fs::set_permissions(&file, std::fs::Permissions::from_mode(0o755)).unwrap();
+        // keep the system tools available to the script
+        let mut path = dir.into_os_string();
+        path.push(":/bin:/usr/bin");
+        path
+    }
+
+    #[test]
+    fn starts_only_cmd_scripts_through_cmd() {
+        let argv = |cmd_script: bool, windows: bool| {
+            let command = super::CliCommand {
+                name: "tool",
+                program: "tool",
+                cmd_script,
+                path: None,
+                timeout: std::time::Duration::from_secs(1),
+            }
+            .command(&["-a", "line 1\nline 2"], windows);
+            std::iter::once(command.get_program())
+                .chain(command.get_args())
+                .map(|arg| arg.to_str().unwrap().to_owned())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            argv(true, true),
+            ["cmd", "/C", "tool", "-a", "line 1\nline 2"]
+        );
+        assert_eq!(argv(true, false), ["tool", "-a", "line 1\nline 2"]);
+        assert_eq!(argv(false, true), ["tool", "-a", "line 1\nline 2"]);
+    }
+
+    #[test]
+    fn validates_arguments() {
+        assert!(super::validate_argument("resource", "https://vault.azure.net").is_ok());
+        assert!(super::validate_argument("tenant", "72f988bf-86f1-41af-91ab").is_ok());
+        assert!(super::validate_argument("resource", "--help").is_err());
+        assert!(super::validate_argument("resource", "x'; Remove-Item *").is_err());
+        assert!(super::validate_argument("tenant", "").is_err());
+    }
+}
@@ -0,0 +1,167 @@
+use super::TokenCredentialOptions;
+use crate::client_credentials_flow::{self, JWT_BEARER_ASSERTION_TYPE};
+use azure_core::auth::{TokenCredential, TokenResponse};
+use azure_core::error::{ErrorKind, Result, ResultExt};
+use chrono::Utc;
+use futures::future::BoxFuture;
+use std::future::Future;
+
+/// Produces a client assertion for each token request
+pub(crate) type AssertionCallback = dyn Fn() -> BoxFuture<'static, Result<String>> + Send + Sync;
+
+/// Boxes an async assertion callback
+pub(crate) fn assertion_callback<F, Fut>(assertion: F) -> Box<AssertionCallback>
+where
+    F: Fn() -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<String>> + Send + 'static,
+{
+    Box::new(move || Box::pin(assertion()))
+}
+
+/// Enables authentication to Azure Active Directory using a client assertion provided by the caller.
+///
+/// The assertion is produced by an async callback every time a token is requested, which makes
+/// this credential suitable for workload identity federation (for example GitHub Actions OIDC
+/// tokens or SPIFFE SVIDs) as well as for assertions signed by an external signer such as an HSM.
+///
+/// More information on client assertions can be found here:
+/// <https://docs.microsoft.com/azure/active-directory/develop/active-directory-certificate-credentials>
+pub struct ClientAssertionCredential {
+    tenant_id: String,
+    client_id: oauth2::ClientId,
+    assertion: Box<AssertionCallback>,
+    options: TokenCredentialOptions,
+}
+
+impl ClientAssertionCredential {
+    /// Create a new ClientAssertionCredential
+    ///
+    /// `assertion` is called for every token request and must return the assertion to send
+    /// as the `client_assertion` parameter of the client credentials request.
+    pub fn new<F, Fut>(
+        tenant_id: String,
+        client_id: String,
+        assertion: F,
+        options: TokenCredentialOptions,
+    ) -> ClientAssertionCredential
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String>> + Send + 'static,
+    {
+        ClientAssertionCredential {
+            tenant_id,
+            client_id: oauth2::ClientId::new(client_id),
+            assertion: assertion_callback(assertion),
+            options,
+        }
+    }
+
+    fn options(&self) -> &TokenCredentialOptions {
+        &self.options
+    }
+}
+
+impl std::fmt::Debug for ClientAssertionCredential {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("ClientAssertionCredential")
+            .field("tenant_id", &self.tenant_id)
+            .field("client_id", &self.client_id)
+            .field("options", &self.options)
+            .finish()
+    }
+}
+
+#[async_trait::async_trait]
+impl TokenCredential for ClientAssertionCredential {
+    async fn get_token(&self, resource: &str) -> Result<TokenResponse> {
+        let client_assertion = (self.assertion)()
+            .await
+            .context(ErrorKind::Credential, "failed to obtain a client assertion")?;
+
+        let response = client_credentials_flow::request_token(
+            &reqwest::Client::new(),
+            self.options().authority_host(),
+            &self.tenant_id,
+            &[
+                ("client_id", self.client_id.as_str()),
+                ("scope", &format!("{}/.default", resource)),
+                ("client_assertion_type", JWT_BEARER_ASSERTION_TYPE),
+                ("client_assertion", &client_assertion),
+                ("grant_type", "client_credentials"),
+            ],
+        )
+        .await
+        .context(ErrorKind::Credential, "request token error")?;
+
+        Ok(TokenResponse::new(
+            response.access_token,
+            Utc::now() + chrono::Duration::seconds(response.expires_in as i64),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_endpoint::{MockEndpoint, MockResponse};
+    use azure_core::error::Error;
+
+    const TOKEN_RESPONSE: &str = r#"{"token_type":"Bearer","expires_in":3599,"ext_expires_in":3599,"access_token":"secret_token"}"#;
+
+    #[tokio::test]
+    async fn sends_assertion_to_authority_host() {
+        let endpoint = MockEndpoint::start(vec![MockResponse::new(200, TOKEN_RESPONSE)]);
+        let credential = ClientAssertionCredential::new(
+            "tenant".to_owned(),
+            "client".to_owned(),
+            || async { Ok("signed.jwt.assertion".to_owned()) },
+            TokenCredentialOptions::new(endpoint.url()),
+        );
+
+        let token = credential
+            .get_token("https://management.azure.com")
+            .await
+            .unwrap();
+        assert_eq!(token.token.secret(), "secret_token");
+
+        let requests = endpoint.requests();
+        assert_eq!(requests.len(), 1);
+        let request = &requests[0];
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/tenant/oauth2/v2.0/token");
+        assert_eq!(
+            request.header("content-type"),
+            Some("application/x-www-form-urlencoded")
+        );
+        assert_eq!(request.form_value("client_id").unwrap(), "client");
+        assert_eq!(
+            request.form_value("scope").unwrap(),
+            "https://management.azure.com/.default"
+        );
+        assert_eq!(
+            request.form_value("client_assertion").unwrap(),
+            "signed.jwt.assertion"
+        );
+        assert_eq!(
+            request.form_value("client_assertion_type").unwrap(),
+            JWT_BEARER_ASSERTION_TYPE
+        );
+        assert_eq!(
+            request.form_value("grant_type").unwrap(),
+            "client_credentials"
+        );
+    }
+
+    #[tokio::test]
+    async fn callback_errors_are_credential_errors() {
+        let credential = ClientAssertionCredential::new(
+            "tenant".to_owned(),
+            "client".to_owned(),
+            || async { Err(Error::message(ErrorKind::Io, "token file not found")) },
+            TokenCredentialOptions::default(),
+        );
+
+        let error = credential.get_token("resource").await.unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::Credential);
+    }
+}
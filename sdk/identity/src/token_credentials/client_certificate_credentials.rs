@@ -1,8 +1,8 @@
-use super::{authority_hosts, TokenCredential};
-use azure_core::auth::TokenResponse;
+use super::{authority_hosts, ClientAssertionCredential, TokenCredentialOptions};
+use azure_core::auth::{TokenCredential, TokenResponse};
+use azure_core::error::{Error, ErrorKind, Result, ResultExt};
 use base64::{CharacterSet, Config};
 use chrono::Utc;
-use oauth2::AccessToken;
 use openssl::{
     error::ErrorStack,
     hash::{hash, DigestBytes, MessageDigest},
@@ -11,8 +11,7 @@ use openssl::{
     sign::Signer,
     x509::X509,
 };
-use serde::Deserialize;
-use std::{str, time::Duration};
+use std::str;
 
 /// Refresh time to use in seconds
 const DEFAULT_REFRESH_TIME: i64 = 300;
@@ -73,12 +72,11 @@ impl CertificateCredentialOptions {
 ///
 /// In order to use subject name validation send_cert_chain option must be set to true
 /// The certificate is expected to be in base64 encoded PKCS12 format
+///
+/// The certificate is used to sign a client assertion, which is then exchanged for a token
+/// through a [`ClientAssertionCredential`].
 pub struct ClientCertificateCredential {
-    tenant_id: String,
-    client_id: String,
-    client_certificate: String,
-    client_certificate_pass: String,
-    options: CertificateCredentialOptions,
+    credential: ClientAssertionCredential,
 }
 
 impl ClientCertificateCredential {
@@ -90,26 +88,37 @@ impl ClientCertificateCredential {
         client_certificate_pass: String,
         options: CertificateCredentialOptions,
     ) -> ClientCertificateCredential {
-        ClientCertificateCredential {
+        let token_url = format!(
+            "{}/{}/oauth2/v2.0/token",
+            options.authority_host(),
+            tenant_id
+        );
+        let assertion_client_id = client_id.clone();
+        let send_certificate_chain = options.send_certificate_chain();
+        let credential = ClientAssertionCredential::new(
             tenant_id,
             client_id,
-            client_certificate,
-            client_certificate_pass,
-            options,
-        }
-    }
-
-    fn options(&self) -> &CertificateCredentialOptions {
-        &self.options
+            move || {
+                futures::future::ready(ClientCertificateCredential::build_assertion(
+                    &token_url,
+                    &assertion_client_id,
+                    &client_certificate,
+                    &client_certificate_pass,
+                    send_certificate_chain,
+                ))
+            },
+            TokenCredentialOptions::new(options.authority_host().to_owned()),
+        );
+        ClientCertificateCredential { credential }
     }
 
-    fn sign(jwt: &str, pkey: &PKey<Private>) -> Result<Vec<u8>, ErrorStack> {
+    fn sign(jwt: &str, pkey: &PKey<Private>) -> std::result::Result<Vec<u8>, ErrorStack> {
         let mut signer = Signer::new(MessageDigest::sha256(), pkey)?;
         signer.update(jwt.as_bytes())?;
         signer.sign_to_vec()
     }
 
-    fn get_thumbprint(cert: &X509) -> Result<DigestBytes, ErrorStack> {
+    fn get_thumbprint(cert: &X509) -> std::result::Result<DigestBytes, ErrorStack> {
         let der = cert.to_der()?;
         let digest = hash(MessageDigest::sha1(), &der)?;
         Ok(digest)
@@ -118,75 +127,58 @@ impl ClientCertificateCredential {
     fn as_jwt_part(part: &[u8]) -> String {
         base64::encode_config(part, BASE64_URL_SAFE)
     }
-}
-
-#[allow(missing_docs)]
-#[non_exhaustive]
-#[derive(Debug, thiserror::Error)]
-pub enum ClientCertificateCredentialError {
-    #[error("Failed to perform base64 decode {0}")]
-    DecodeError(base64::DecodeError),
-    #[error("Openssl operation failed {0}")]
-    OpensslError(ErrorStack),
-    #[error("HTTP operation failed {0}")]
-    ReqwestError(reqwest::Error),
-}
-
-#[derive(Deserialize, Debug, Default)]
-#[serde(default)]
-struct AadTokenResponse {
-    token_type: String,
-    expires_in: u64,
-    ext_expires_in: u64,
-    access_token: String,
-}
-
-fn get_encoded_cert(cert: &X509) -> Result<String, ClientCertificateCredentialError> {
-    Ok(format!(
-        "\"{}\"",
-        base64::encode(
-            cert.to_pem()
-                .map_err(ClientCertificateCredentialError::OpensslError)?
-        )
-    ))
-}
 
-#[async_trait::async_trait]
-impl TokenCredential for ClientCertificateCredential {
-    type Error = ClientCertificateCredentialError;
-
-    async fn get_token(&self, resource: &str) -> Result<TokenResponse, Self::Error> {
-        let options = self.options();
-        let url = &format!(
-            "{}/{}/oauth2/v2.0/token",
-            options.authority_host(),
-            self.tenant_id
-        );
-
-        let certificate = base64::decode(&self.client_certificate)
-            .map_err(ClientCertificateCredentialError::DecodeError)?;
+    /// Builds the signed JWT used as client assertion for the token endpoint `url`
+    pub(crate) fn build_assertion(
+        url: &str,
+        client_id: &str,
+        client_certificate: &str,
+        client_certificate_pass: &str,
+        send_certificate_chain: bool,
+    ) -> Result<String> {
+        let certificate = base64::decode(client_certificate).context(
+            ErrorKind::DataConversion,
+            "failed to base64 decode the client certificate",
+        )?;
         let certificate = Pkcs12::from_der(&certificate)
-            .map_err(ClientCertificateCredentialError::OpensslError)?
-            .parse(&self.client_certificate_pass)
-            .map_err(ClientCertificateCredentialError::OpensslError)?;
+            .context(
+                ErrorKind::Credential,
+                "failed to parse the PKCS12 certificate",
+            )?
+            .parse2(client_certificate_pass)
+            .context(
+                ErrorKind::Credential,
+                "failed to open the PKCS12 certificate",
+            )?;
+        let (cert, pkey) = match (certificate.cert, certificate.pkey) {
+            (Some(cert), Some(pkey)) => (cert, pkey),
+            _ => {
+                return Err(Error::message(
+                    ErrorKind::Credential,
+                    "the PKCS12 certificate must contain both a certificate and a private key",
+                ))
+            }
+        };
 
-        let thumbprint = ClientCertificateCredential::get_thumbprint(&certificate.cert)
-            .map_err(ClientCertificateCredentialError::OpensslError)?;
+        let thumbprint = ClientCertificateCredential::get_thumbprint(&cert).context(
+            ErrorKind::Credential,
+            "failed to compute the certificate thumbprint",
+        )?;
 
         let uuid = uuid::Uuid::new_v4();
         let current_time = Utc::now().timestamp();
         let expiry_time = current_time + DEFAULT_REFRESH_TIME;
-        let x5t = base64::encode(&thumbprint);
+        let x5t = base64::encode(thumbprint);
 
-        let header = match options.send_certificate_chain {
+        let header = match send_certificate_chain {
             true => {
-                let base_signature = get_encoded_cert(&certificate.cert)?;
-                let x5c = match certificate.chain {
+                let base_signature = get_encoded_cert(&cert)?;
+                let x5c = match certificate.ca {
                     Some(chain) => {
                         let chain = chain
                             .into_iter()
                             .map(|x| get_encoded_cert(&x))
-                            .collect::<Result<Vec<String>, ClientCertificateCredentialError>>()?
+                            .collect::<Result<Vec<String>>>()?
                             .join(",");
                         format! {"{},{}", base_signature, chain}
                     }
@@ -203,54 +195,31 @@ impl TokenCredential for ClientCertificateCredential {
 
         let payload = format!(
             r#"{{"aud":"{}","exp":{},"iss": "{}", "jti": "{}", "nbf": {}, "sub": "{}"}}"#,
-            url, expiry_time, self.client_id, uuid, current_time, self.client_id
+            url, expiry_time, client_id, uuid, current_time, client_id
         );
         let payload = ClientCertificateCredential::as_jwt_part(payload.as_bytes());
 
         let jwt = format!("{}.{}", header, payload);
-        let signature = ClientCertificateCredential::sign(&jwt, &certificate.pkey)
-            .map_err(ClientCertificateCredentialError::OpensslError)?;
+        let signature = ClientCertificateCredential::sign(&jwt, &pkey)
+            .context(ErrorKind::Credential, "failed to sign the client assertion")?;
         let sig = ClientCertificateCredential::as_jwt_part(&signature);
-        let client_assertion = format!("{}.{}", jwt, sig);
-
-        let form_data = vec![
-            ("client_id", self.client_id.to_owned()),
-            ("scope", format!("{}/.default", resource)),
-            (
-                "client_assertion_type",
-                "urn:ietf:params:oauth:client-assertion-type:jwt-bearer".to_owned(),
-            ),
-            ("client_assertion", client_assertion),
-            ("grant_type", "client_credentials".to_owned()),
-        ];
-
-        let client = reqwest::Client::new();
-        let response: AadTokenResponse = client
-            .post(url)
-            .form(&form_data)
-            .send()
-            .await
-            .map_err(ClientCertificateCredentialError::ReqwestError)?
-            .json()
-            .await
-            .map_err(ClientCertificateCredentialError::ReqwestError)?;
-
-        Ok(TokenResponse::new(
-            AccessToken::new(response.access_token.to_string()),
-            Utc::now()
-                + chrono::Duration::from_std(Duration::from_secs(response.expires_in)).unwrap(),
-        ))
+        Ok(format!("{}.{}", jwt, sig))
     }
 }
 
+fn get_encoded_cert(cert: &X509) -> Result<String> {
+    Ok(format!(
+        "\"{}\"",
+        base64::encode(cert.to_pem().context(
+            ErrorKind::Credential,
+            "failed to encode the certificate as PEM"
+        )?)
+    ))
+}
+
 #[async_trait::async_trait]
-impl azure_core::auth::TokenCredential for ClientCertificateCredential {
-    async fn get_token(
-        &self,
-        resource: &str,
-    ) -> Result<azure_core::auth::TokenResponse, azure_core::Error> {
-        TokenCredential::get_token(self, resource)
-            .await
-            .map_err(|error| azure_core::Error::GetToken(Box::new(error)))
+impl TokenCredential for ClientCertificateCredential {
+    async fn get_token(&self, resource: &str) -> Result<TokenResponse> {
+        self.credential.get_token(resource).await
     }
 }
@@ -1,6 +1,10 @@
-use super::{AzureCliCredential, EnvironmentCredential, ImdsManagedIdentityCredential};
+use super::{
+    AzureCliCredential, AzureDeveloperCliCredential, AzurePowerShellCredential,
+    ChainedTokenCredential, EnvironmentCredential, ManagedIdentityCredential,
+};
 use azure_core::auth::{TokenCredential, TokenResponse};
-use azure_core::error::{Error, ErrorKind, Result, ResultExt};
+use azure_core::error::{ErrorKind, Result, ResultExt};
+use std::sync::Arc;
 
 #[derive(Debug)]
 /// Provides a mechanism of selectively disabling credentials used for a `DefaultAzureCredential` instance
@@ -8,6 +12,8 @@ pub struct DefaultAzureCredentialBuilder {
     include_environment_credential: bool,
     include_managed_identity_credential: bool,
     include_azure_cli_credential: bool,
+    include_azure_developer_cli_credential: bool,
+    include_azure_powershell_credential: bool,
 }
 
 impl Default for DefaultAzureCredentialBuilder {
@@ -16,6 +22,8 @@ impl Default for DefaultAzureCredentialBuilder {
             include_environment_credential: true,
             include_managed_identity_credential: true,
             include_azure_cli_credential: true,
+            include_azure_developer_cli_credential: false,
+            include_azure_powershell_credential: false,
         }
     }
 }
@@ -44,11 +52,25 @@ impl DefaultAzureCredentialBuilder {
         self
     }
 
+    /// Include using credentials from the Azure Developer CLI, tried after the Azure CLI
+    pub fn include_azure_developer_cli_credential(&mut self) -> &mut Self {
+        self.include_azure_developer_cli_credential = true;
+        self
+    }
+
+    /// Include using credentials from Azure PowerShell, tried after the Azure Developer CLI
+    pub fn include_azure_powershell_credential(&mut self) -> &mut Self {
+        self.include_azure_powershell_credential = true;
+        self
+    }
+
     /// Create a `DefaultAzureCredential` from this builder.
     pub fn build(&self) -> DefaultAzureCredential {
-        let source_count = self.include_azure_cli_credential as usize
+        let source_count = self.include_environment_credential as usize
             + self.include_azure_cli_credential as usize
-            + self.include_managed_identity_credential as usize;
+            + self.include_managed_identity_credential as usize
+            + self.include_azure_developer_cli_credential as usize
+            + self.include_azure_powershell_credential as usize;
         let mut sources = Vec::<DefaultAzureCredentialEnum>::with_capacity(source_count);
         if self.include_environment_credential {
             sources.push(DefaultAzureCredentialEnum::Environment(
@@ -57,12 +79,22 @@ impl DefaultAzureCredentialBuilder {
         }
         if self.include_managed_identity_credential {
             sources.push(DefaultAzureCredentialEnum::ManagedIdentity(
-                ImdsManagedIdentityCredential::default(),
+                ManagedIdentityCredential::default(),
             ))
         }
         if self.include_azure_cli_credential {
             sources.push(DefaultAzureCredentialEnum::AzureCli(AzureCliCredential {}));
         }
+        if self.include_azure_developer_cli_credential {
+            sources.push(DefaultAzureCredentialEnum::AzureDeveloperCli(
+                AzureDeveloperCliCredential::default(),
+            ));
+        }
+        if self.include_azure_powershell_credential {
+            sources.push(DefaultAzureCredentialEnum::AzurePowerShell(
+                AzurePowerShellCredential::default(),
+            ));
+        }
         DefaultAzureCredential::with_sources(sources)
     }
 }
@@ -72,9 +104,13 @@ pub enum DefaultAzureCredentialEnum {
     /// `TokenCredential` from environment variable.
     Environment(EnvironmentCredential),
     /// `TokenCredential` from managed identity that has been assigned in this deployment environment.
-    ManagedIdentity(ImdsManagedIdentityCredential),
+    ManagedIdentity(ManagedIdentityCredential),
     /// `TokenCredential` from Azure CLI.
     AzureCli(AzureCliCredential),
+    /// `TokenCredential` from the Azure Developer CLI.
+    AzureDeveloperCli(AzureDeveloperCliCredential),
+    /// `TokenCredential` from Azure PowerShell.
+    AzurePowerShell(AzurePowerShellCredential),
 }
 
 #[async_trait::async_trait]
@@ -99,6 +135,18 @@ impl TokenCredential for DefaultAzureCredentialEnum {
                     "error getting token credential from Azure CLI",
                 )
             }
+            DefaultAzureCredentialEnum::AzureDeveloperCli(credential) => {
+                credential.get_token(resource).await.context(
+                    ErrorKind::Credential,
+                    "error getting token credential from Azure Developer CLI",
+                )
+            }
+            DefaultAzureCredentialEnum::AzurePowerShell(credential) => {
+                credential.get_token(resource).await.context(
+                    ErrorKind::Credential,
+                    "error getting token credential from Azure PowerShell",
+                )
+            }
         }
     }
 }
@@ -109,9 +157,15 @@ impl TokenCredential for DefaultAzureCredentialEnum {
 /// - EnvironmentCredential
 /// - ManagedIdentityCredential
 /// - AzureCliCredential
+/// - AzureDeveloperCliCredential (only if included)
+/// - AzurePowerShellCredential (only if included)
+///
+/// The sources are chained with a [`ChainedTokenCredential`], so the first one that succeeds is
+/// used for every later request, and the error returned when all of them fail lists each failure.
 /// Consult the documentation of these credential types for more information on how they attempt authentication.
 pub struct DefaultAzureCredential {
-    sources: Vec<DefaultAzureCredentialEnum>,
+    sources: Vec<Arc<DefaultAzureCredentialEnum>>,
+    chain: ChainedTokenCredential,
 }
 
 impl DefaultAzureCredential {
@@ -119,21 +173,43 @@ impl DefaultAzureCredential {
     ///
     /// These sources will be tried in the order provided in the `TokenCredential` authentication flow.
     pub fn with_sources(sources: Vec<DefaultAzureCredentialEnum>) -> Self {
-        DefaultAzureCredential { sources }
+        let sources: Vec<_> = sources.into_iter().map(Arc::new).collect();
+        let chain = ChainedTokenCredential::new(
+            sources
+                .iter()
+                .map(|source| source.clone() as Arc<dyn TokenCredential>)
+                .collect(),
+        );
+        DefaultAzureCredential { sources, chain }
+    }
+}
+
+impl std::fmt::Debug for DefaultAzureCredential {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let sources = self
+            .sources
+            .iter()
+            .map(|source| match source.as_ref() {
+                DefaultAzureCredentialEnum::Environment(_) => "EnvironmentCredential",
+                DefaultAzureCredentialEnum::ManagedIdentity(_) => "ManagedIdentityCredential",
+                DefaultAzureCredentialEnum::AzureCli(_) => "AzureCliCredential",
+                DefaultAzureCredentialEnum::AzureDeveloperCli(_) => "AzureDeveloperCliCredential",
+                DefaultAzureCredentialEnum::AzurePowerShell(_) => "AzurePowerShellCredential",
+            })
+            .collect::<Vec<_>>();
+        f.debug_struct("DefaultAzureCredential")
+            .field("sources", &sources)
+            .finish()
     }
 }
 
 impl Default for DefaultAzureCredential {
     fn default() -> Self {
-        DefaultAzureCredential {
-            sources: vec![
-                DefaultAzureCredentialEnum::Environment(EnvironmentCredential::default()),
-                DefaultAzureCredentialEnum::ManagedIdentity(
-                    ImdsManagedIdentityCredential::default(),
-                ),
-                DefaultAzureCredentialEnum::AzureCli(AzureCliCredential {}),
-            ],
-        }
+        DefaultAzureCredential::with_sources(vec![
+            DefaultAzureCredentialEnum::Environment(EnvironmentCredential::default()),
+            DefaultAzureCredentialEnum::ManagedIdentity(ManagedIdentityCredential::default()),
+            DefaultAzureCredentialEnum::AzureCli(AzureCliCredential {}),
+        ])
     }
 }
 
@@ -141,32 +217,10 @@ impl Default for DefaultAzureCredential {
 impl TokenCredential for DefaultAzureCredential {
     /// Try to fetch a token using each of the credential sources until one succeeds
     async fn get_token(&self, resource: &str) -> Result<TokenResponse> {
-        let mut errors = Vec::new();
-        for source in &self.sources {
-            let token_res = source.get_token(resource).await;
-
-            match token_res {
-                Ok(token) => return Ok(token),
-                Err(error) => errors.push(error),
-            }
-        }
-        Err(Error::with_message(ErrorKind::Credential, || {
-            format!(
-                "Multiple errors were encountered while attempting to authenticate:\n{}",
-                format_aggregate_error(&errors)
-            )
-        }))
+        self.chain.get_token(resource).await
     }
 }
 
-fn format_aggregate_error(errors: &[Error]) -> String {
-    errors
-        .iter()
-        .map(|error| error.to_string())
-        .collect::<Vec<String>>()
-        .join("\n")
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -200,7 +254,7 @@ mod tests {
 
     macro_rules! contains_credential {
         ($creds:expr, $p:pat) => {
-            $creds.sources.iter().any(|x| matches!(x, $p))
+            $creds.sources.iter().any(|x| matches!(x.as_ref(), $p))
         };
     }
 
@@ -266,6 +320,24 @@ mod tests {
             DefaultAzureCredentialEnum::ManagedIdentity(_)
         ));
 
+        // add the developer tool sources
+
+        let mut with_tools = DefaultAzureCredentialBuilder::new();
+        with_tools
+            .include_azure_developer_cli_credential()
+            .include_azure_powershell_credential();
+        let credential = with_tools.build();
+
+        assert_eq!(credential.sources.len(), 5);
+        assert!(matches!(
+            credential.sources[3].as_ref(),
+            DefaultAzureCredentialEnum::AzureDeveloperCli(_)
+        ));
+        assert!(matches!(
+            credential.sources[4].as_ref(),
+            DefaultAzureCredentialEnum::AzurePowerShell(_)
+        ));
+
         // remove managed identity source
 
         builder.exclude_managed_identity_credential();
@@ -0,0 +1,243 @@
+use super::user_token_cache::{UserTokenCache, OFFLINE_ACCESS_SCOPE};
+use super::TokenCredentialOptions;
+use crate::device_code_flow::{self, DeviceCodePhaseOneResponse, DeviceCodeResponse};
+use crate::token_cache::TokenCacheStorage;
+use async_lock::Mutex;
+use azure_core::auth::{TokenCredential, TokenResponse};
+use azure_core::error::{Error, ErrorKind, Result};
+use futures::StreamExt;
+use oauth2::ClientId;
+use std::sync::Arc;
+
+type PromptCallback = dyn Fn(&DeviceCodePhaseOneResponse) + Send + Sync;
+
+/// Enables authentication to Azure Active Directory using the device code flow.
+///
+/// On the first token request the prompt callback receives the device code response, whose
+/// `message()` tells the user which page to visit and which code to enter. The credential then
+/// polls until the user signed in. Later requests, including requests for other resources, are
+/// served from the cache or silently refreshed with the refresh token.
+///
+/// The App Registration must allow public client flows.
+///
+/// You can learn more about this authorization flow [here](https://docs.microsoft.com/azure/active-directory/develop/v2-oauth2-device-code).
+pub struct DeviceCodeCredential {
+    tenant_id: String,
+    client_id: ClientId,
+    prompt: Box<PromptCallback>,
+    options: TokenCredentialOptions,
+    cache: UserTokenCache,
+    sign_in: Mutex<()>,
+}
+
+impl DeviceCodeCredential {
+    /// Create a new DeviceCodeCredential
+    ///
+    /// `prompt` is called whenever the user needs to sign in, for example:
+    ///
+    /// ```no_run
+    /// use azure_identity::{DeviceCodeCredential, TokenCredentialOptions};
+    ///
+    /// let credential = DeviceCodeCredential::new(
+    ///     "tenant_id".to_owned(),
+    ///     "client_id".to_owned(),
+    ///     |device_code| eprintln!("{}", device_code.message()),
+    ///     TokenCredentialOptions::default(),
+    /// );
+    /// ```
+    pub fn new<F>(
+        tenant_id: String,
+        client_id: String,
+        prompt: F,
+        options: TokenCredentialOptions,
+    ) -> DeviceCodeCredential
+    where
+        F: Fn(&DeviceCodePhaseOneResponse) + Send + Sync + 'static,
+    {
+        let client_id = ClientId::new(client_id);
+        DeviceCodeCredential {
+            cache: UserTokenCache::new(
+                options.authority_host().to_owned(),
+                tenant_id.clone(),
+                client_id.clone(),
+            ),
+            tenant_id,
+            client_id,
+            prompt: Box::new(prompt),
+            options,
+            sign_in: Mutex::new(()),
+        }
+    }
+
+    /// Stores the tokens in the given storage instead of in memory.
+    ///
+    /// With a [`crate::token_cache::FileTokenCacheStorage`] the sign in is shared by every process
+    /// using the same cache file.
+    pub fn with_token_cache(mut self, storage: Arc<dyn TokenCacheStorage>) -> Self {
+        self.cache.set_storage(storage);
+        self
+    }
+
+    fn options(&self) -> &TokenCredentialOptions {
+        &self.options
+    }
+
+    async fn sign_in(&self, scope: &str) -> Result<TokenResponse> {
+        let client = reqwest::Client::new();
+        let device_code = device_code_flow::start_with_authority_host(
+            &client,
+            self.options().authority_host(),
+            self.tenant_id.as_str(),
+            &self.client_id,
+            &[scope, OFFLINE_ACCESS_SCOPE],
+        )
+        .await?;
+
+        (self.prompt)(&device_code);
+
+        let mut stream = Box::pin(device_code.stream());
+        while let Some(response) = stream.next().await {
+            if let DeviceCodeResponse::AuthorizationSucceeded(authorization) = response? {
+                return Ok(self
+                    .cache
+                    .insert(
+                        scope,
+                        authorization.access_token().clone(),
+                        authorization.expires_in,
+                        authorization.refresh_token().cloned(),
+                    )
+                    .await);
+            }
+        }
+        Err(Error::message(
+            ErrorKind::Credential,
+            "the device code flow ended without a token",
+        ))
+    }
+}
+
+impl std::fmt::Debug for DeviceCodeCredential {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("DeviceCodeCredential")
+            .field("tenant_id", &self.tenant_id)
+            .field("client_id", &self.client_id)
+            .field("options", &self.options)
+            .finish()
+    }
+}
+
+#[async_trait::async_trait]
+impl TokenCredential for DeviceCodeCredential {
+    async fn get_token(&self, resource: &str) -> Result<TokenResponse> {
+        let scope = format!("{}/.default", resource);
+        if let Some(token) = self.cache.get_token_silently(&scope).await {
+            return Ok(token);
+        }
+
+        // only one sign in at a time, a concurrent one may have already succeeded
+        let _guard = self.sign_in.lock().await;
+        if let Some(token) = self.cache.get_token_silently(&scope).await {
+            return Ok(token);
+        }
+        self.sign_in(&scope).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_endpoint::{MockEndpoint, MockResponse};
+    use std::sync::{Arc, Mutex};
+
+    const DEVICE_CODE_RESPONSE: &str = r#"{"device_code":"the_device_code","user_code":"ABCD-EFGH","verification_uri":"https://microsoft.com/devicelogin","expires_in":900,"interval":1,"message":"To sign in, enter ABCD-EFGH"}"#;
+    const PENDING_RESPONSE: &str = r#"{"error":"authorization_pending","error_description":"pending","error_uri":"https://login.microsoftonline.com/error"}"#;
+    const TOKEN_RESPONSE: &str = r#"{"token_type":"Bearer","scope":"https://management.azure.com/.default","expires_in":3599,"access_token":"first_token","refresh_token":"first_refresh"}"#;
+    const REFRESH_RESPONSE: &str = r#"{"token_type":"Bearer","scope":"https://vault.azure.net/.default","expires_in":3599,"ext_expires_in":3599,"access_token":"second_token","refresh_token":"second_refresh"}"#;
+
+    #[tokio::test]
+    async fn prompts_once_then_refreshes() {
+        let endpoint = MockEndpoint::start(vec![
+            MockResponse::new(200, DEVICE_CODE_RESPONSE),
+            MockResponse::new(400, PENDING_RESPONSE),
+            MockResponse::new(200, TOKEN_RESPONSE),
+            MockResponse::new(200, REFRESH_RESPONSE),
+        ]);
+        let messages = Arc::new(Mutex::new(Vec::new()));
+        let prompted = messages.clone();
+        let credential = DeviceCodeCredential::new(
+            "tenant".to_owned(),
+            "client".to_owned(),
+            move |device_code| {
+                prompted
+                    .lock()
+                    .unwrap()
+                    .push(device_code.message().to_owned())
+            },
+            TokenCredentialOptions::new(endpoint.url()),
+        );
+
+        let token = credential
+            .get_token("https://management.azure.com")
+            .await
+            .unwrap();
+        assert_eq!(token.token.secret(), "first_token");
+        let token = credential
+            .get_token("https://management.azure.com")
+            .await
+            .unwrap();
+        assert_eq!(token.token.secret(), "first_token");
+        let token = credential
+            .get_token("https://vault.azure.net")
+            .await
+            .unwrap();
+        assert_eq!(token.token.secret(), "second_token");
+
+        assert_eq!(
+            messages.lock().unwrap().as_slice(),
+            ["To sign in, enter ABCD-EFGH"]
+        );
+
+        let requests = endpoint.requests();
+        assert_eq!(requests.len(), 4);
+        assert_eq!(requests[0].path, "/tenant/oauth2/v2.0/devicecode");
+        assert_eq!(
+            requests[0].form_value("scope").unwrap(),
+            "https://management.azure.com/.default offline_access"
+        );
+        assert_eq!(
+            requests[2].form_value("device_code").unwrap(),
+            "the_device_code"
+        );
+        assert_eq!(
+            requests[3].form_value("grant_type").unwrap(),
+            "refresh_token"
+        );
+        assert_eq!(
+            requests[3].form_value("refresh_token").unwrap(),
+            "first_refresh"
+        );
+    }
+
+    #[tokio::test]
+    async fn declined_sign_in_is_an_error() {
+        let endpoint = MockEndpoint::start(vec![
+            MockResponse::new(200, DEVICE_CODE_RESPONSE),
+            MockResponse::new(
+                400,
+                r#"{"error":"authorization_declined","error_description":"declined","error_uri":"https://login.microsoftonline.com/error"}"#,
+            ),
+        ]);
+        let credential = DeviceCodeCredential::new(
+            "tenant".to_owned(),
+            "client".to_owned(),
+            |_| {},
+            TokenCredentialOptions::new(endpoint.url()),
+        );
+
+        let error = credential
+            .get_token("https://management.azure.com")
+            .await
+            .unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::Credential);
+    }
+}
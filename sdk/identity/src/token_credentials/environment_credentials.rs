@@ -1,4 +1,4 @@
-use super::{ClientSecretCredential, TokenCredentialOptions};
+use super::{ClientSecretCredential, TokenCredentialOptions, UsernamePasswordCredential};
 use azure_core::auth::{TokenCredential, TokenResponse};
 use azure_core::error::{Error, ErrorKind, Result, ResultExt};
 
@@ -18,9 +18,11 @@ const AZURE_CLIENT_CERTIFICATE_PATH_ENV_KEY: &str = "AZURE_CLIENT_CERTIFICATE_PA
 /// | `AZURE_TENANT_ID`                   | The Azure Active Directory tenant(directory) ID. |
 /// | `AZURE_CLIENT_ID`                   | The client(application) ID of an App Registration in the tenant. |
 /// | `AZURE_CLIENT_SECRET`               | A client secret that was generated for the App Registration. |
+/// | `AZURE_USERNAME`                    | The username of a user, used when no client secret is set. |
+/// | `AZURE_PASSWORD`                    | The password of that user.                       |
 ///
-/// This credential ultimately uses a `ClientSecretCredential` to perform the authentication using
-/// these details.
+/// This credential ultimately uses a `ClientSecretCredential` or a `UsernamePasswordCredential`
+/// to perform the authentication using these details.
 /// Please consult the documentation of that class for more details.
 #[derive(Clone, Debug, Default)]
 pub struct EnvironmentCredential {
@@ -65,9 +67,15 @@ impl TokenCredential for EnvironmentCredential {
                 self.options.clone(),
             );
             return credential.get_token(resource).await;
-        } else if username.is_ok() && password.is_ok() {
-            // Could use multiple if-let with #![feature(let_chains)] once stabilised - see https://github.com/rust-lang/rust/issues/53667
-            // TODO: username & password credential
+        } else if let (Ok(username), Ok(password)) = (username, password) {
+            let credential = UsernamePasswordCredential::new(
+                tenant_id,
+                client_id,
+                username,
+                password,
+                self.options.clone(),
+            );
+            return credential.get_token(resource).await;
         } else if let Ok(_path) = client_certificate_path {
             // TODO: client certificate credential
             todo!()
@@ -133,8 +133,18 @@ fn expires_on_string<'de, D>(deserializer: D) -> std::result::Result<DateTime<Ut
 where
     D: Deserializer<'de>,
 {
-    let v = String::deserialize(deserializer)?;
-    let as_i64 = v.parse::<i64>().map_err(de::Error::custom)?;
+    // Service Fabric returns the epoch time as a number, the other sources as a string
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StringOrNumber {
+        String(String),
+        Number(i64),
+    }
+
+    let as_i64 = match StringOrNumber::deserialize(deserializer)? {
+        StringOrNumber::String(v) => v.parse::<i64>().map_err(de::Error::custom)?,
+        StringOrNumber::Number(v) => v,
+    };
     Ok(Utc.timestamp(as_i64, 0))
 }
 
@@ -142,7 +152,7 @@ where
 // https://docs.microsoft.com/en-us/azure/app-service/overview-managed-identity?tabs=dotnet#rest-protocol-examples
 #[derive(Debug, Clone, Deserialize)]
 #[allow(unused)]
-struct MsiTokenResponse {
+pub(super) struct MsiTokenResponse {
     pub access_token: AccessToken,
     #[serde(deserialize_with = "expires_on_string")]
     pub expires_on: DateTime<Utc>,
@@ -168,4 +178,13 @@ mod tests {
             serde_json::from_str(as_string).expect("deserialize should succeed");
         assert_eq!(expected, parsed.date);
     }
+
+    #[test]
+    fn check_expires_on_number() {
+        let as_number = r#"{"date": 1586984735}"#;
+        let expected = Utc.ymd(2020, 4, 15).and_hms(21, 5, 35);
+        let parsed: TestExpires =
+            serde_json::from_str(as_number).expect("deserialize should succeed");
+        assert_eq!(expected, parsed.date);
+    }
 }
@@ -0,0 +1,520 @@
+use super::user_token_cache::{UserTokenCache, OFFLINE_ACCESS_SCOPE};
+use super::TokenCredentialOptions;
+use crate::token_cache::TokenCacheStorage;
+use async_lock::Mutex;
+use azure_core::auth::{TokenCredential, TokenResponse};
+use azure_core::error::{Error, ErrorKind, Result, ResultExt};
+use log::debug;
+use oauth2::basic::BasicClient;
+use oauth2::reqwest::async_http_client;
+use oauth2::{
+    AuthType, AuthUrl, AuthorizationCode, ClientId, CsrfToken, PkceCodeChallenge, RedirectUrl,
+    Scope, TokenUrl,
+};
+use std::io::{BufRead, BufReader, ErrorKind as IoErrorKind, Write};
+use std::net::TcpListener;
+use std::process::Command;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use url::Url;
+
+/// How long to wait for the user to complete the sign in by default
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(300);
+
+type AuthorizeUrlHandler = dyn Fn(&Url) -> Result<()> + Send + Sync;
+
+/// Enables authentication to Azure Active Directory by signing in a user through the system browser.
+///
+/// On the first token request the credential opens the authorization page in the browser (or
+/// prints its URL if no browser can be started) and listens on a loopback redirect for the
+/// authorization code. The code is exchanged using PKCE, so no client secret is needed. Later
+/// requests, including requests for other resources, are served from the cache or silently
+/// refreshed with the refresh token.
+///
+/// The App Registration must allow public client flows and have `http://localhost` as a
+/// redirect URI of the "Mobile and desktop applications" platform.
+///
+/// You can learn more about this authorization flow [here](https://docs.microsoft.com/azure/active-directory/develop/v2-oauth2-auth-code-flow).
+pub struct InteractiveBrowserCredential {
+    tenant_id: String,
+    client_id: ClientId,
+    options: TokenCredentialOptions,
+    redirect_port: u16,
+    timeout: Duration,
+    authorize_url_handler: Box<AuthorizeUrlHandler>,
+    cache: UserTokenCache,
+    sign_in: Mutex<()>,
+}
+
+impl InteractiveBrowserCredential {
+    /// Create a new InteractiveBrowserCredential
+    pub fn new(
+        tenant_id: String,
+        client_id: String,
+        options: TokenCredentialOptions,
+    ) -> InteractiveBrowserCredential {
+        let client_id = ClientId::new(client_id);
+        InteractiveBrowserCredential {
+            cache: UserTokenCache::new(
+                options.authority_host().to_owned(),
+                tenant_id.clone(),
+                client_id.clone(),
+            ),
+            tenant_id,
+            client_id,
+            options,
+            redirect_port: 0,
+            timeout: DEFAULT_TIMEOUT,
+            authorize_url_handler: Box::new(open_browser),
+            sign_in: Mutex::new(()),
+        }
+    }
+
+    /// Specifies the loopback port to listen on for the redirect.
+    ///
+    /// By default a random free port is used.
+    pub fn with_redirect_port(mut self, redirect_port: u16) -> Self {
+        self.redirect_port = redirect_port;
+        self
+    }
+
+    /// Specifies how long to wait for the user to complete the sign in. The default is 5 minutes.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Specifies how the authorization URL is presented to the user.
+    ///
+    /// By default the system browser is opened and, if that fails, the URL is printed to stderr.
+    pub fn with_authorize_url_handler<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(&Url) -> Result<()> + Send + Sync + 'static,
+    {
+        self.authorize_url_handler = Box::new(handler);
+        self
+    }
+
+    /// Stores the tokens in the given storage instead of in memory.
+    ///
+    /// With a [`crate::token_cache::FileTokenCacheStorage`] the sign in is shared by every process
+    /// using the same cache file.
+    pub fn with_token_cache(mut self, storage: Arc<dyn TokenCacheStorage>) -> Self {
+        self.cache.set_storage(storage);
+        self
+    }
+
+    fn options(&self) -> &TokenCredentialOptions {
+        &self.options
+    }
+
+    async fn sign_in(&self, scope: &str) -> Result<TokenResponse> {
+        let authority_host = self.options().authority_host();
+        let auth_url = AuthUrl::from_url(
+            Url::parse(&format!(
+                "{}/{}/oauth2/v2.0/authorize",
+                authority_host, self.tenant_id
+            ))
+            .with_context(ErrorKind::Credential, || {
+                format!(
+                    "failed to construct authorize endpoint with tenant id {}",
+                    self.tenant_id
+                )
+            })?,
+        );
+        let token_url = TokenUrl::from_url(
+            Url::parse(&format!(
+                "{}/{}/oauth2/v2.0/token",
+                authority_host, self.tenant_id
+            ))
+            .with_context(ErrorKind::Credential, || {
+                format!(
+                    "failed to construct token endpoint with tenant id {}",
+                    self.tenant_id
+                )
+            })?,
+        );
+
+        let listener = TcpListener::bind(("127.0.0.1", self.redirect_port))
+            .context(ErrorKind::Io, "failed to listen on the loopback redirect")?;
+        let port = listener.local_addr().map_kind(ErrorKind::Io)?.port();
+        let redirect_url = Url::parse(&format!("http://localhost:{}", port))
+            .map_kind(ErrorKind::DataConversion)?;
+
+        let client = BasicClient::new(self.client_id.clone(), None, auth_url, Some(token_url))
+            .set_auth_type(AuthType::RequestBody)
+            .set_redirect_uri(RedirectUrl::from_url(redirect_url));
+
+        let (pkce_code_challenge, pkce_code_verifier) = PkceCodeChallenge::new_random_sha256();
+        let (authorize_url, csrf_state) = client
+            .authorize_url(CsrfToken::new_random)
+            .add_scope(Scope::new(scope.to_owned()))
+            .add_scope(Scope::new(OFFLINE_ACCESS_SCOPE.to_owned()))
+            .set_pkce_challenge(pkce_code_challenge)
+            .url();
+
+        let redirect = wait_for_redirect(listener, self.timeout);
+        (self.authorize_url_handler)(&authorize_url)?;
+        let code = redirect.await?.into_code(csrf_state.secret())?;
+
+        let token = client
+            .exchange_code(code)
+            .set_pkce_verifier(pkce_code_verifier)
+            .request_async(async_http_client)
+            .await
+            .context(ErrorKind::Credential, "request token error")?;
+
+        use oauth2::TokenResponse as _;
+        Ok(self
+            .cache
+            .insert(
+                scope,
+                oauth2::AccessToken::new(token.access_token().secret().to_owned()),
+                token.expires_in().unwrap_or_default().as_secs(),
+                token
+                    .refresh_token()
+                    .map(|token| oauth2::AccessToken::new(token.secret().to_owned())),
+            )
+            .await)
+    }
+}
+
+impl std::fmt::Debug for InteractiveBrowserCredential {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("InteractiveBrowserCredential")
+            .field("tenant_id", &self.tenant_id)
+            .field("client_id", &self.client_id)
+            .field("options", &self.options)
+            .field("redirect_port", &self.redirect_port)
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+#[async_trait::async_trait]
+impl TokenCredential for InteractiveBrowserCredential {
+    async fn get_token(&self, resource: &str) -> Result<TokenResponse> {
+        let scope = format!("{}/.default", resource);
+        if let Some(token) = self.cache.get_token_silently(&scope).await {
+            return Ok(token);
+        }
+
+        // only one sign in at a time, a concurrent one may have already succeeded
+        let _guard = self.sign_in.lock().await;
+        if let Some(token) = self.cache.get_token_silently(&scope).await {
+            return Ok(token);
+        }
+        self.sign_in(&scope).await
+    }
+}
+
+/// Opens the URL in the system browser, printing it if no browser could be started
+fn open_browser(url: &Url) -> Result<()> {
+    let (program, args): (&str, Vec<&str>) = if cfg!(target_os = "windows") {
+        (
+            "rundll32",
+            vec!["url.dll,FileProtocolHandler", url.as_str()],
+        )
+    } else if cfg!(target_os = "macos") {
+        ("open", vec![url.as_str()])
+    } else {
+        ("xdg-open", vec![url.as_str()])
+    };
+
+    if let Err(error) = Command::new(program).args(args).spawn() {
+        debug!("failed to open the browser with {}: {}", program, error);
+        eprintln!(
+            "To sign in, open the following URL in a browser:\n{}",
+            url.as_str()
+        );
+    }
+    Ok(())
+}
+
+/// The query of the request the browser was redirected with
+#[derive(Debug)]
+struct Redirect(Url);
+
+impl Redirect {
+    fn query_value(&self, key: &str) -> Option<String> {
+        self.0
+            .query_pairs()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.into_owned())
+    }
+
+    /// Validates the state and returns the authorization code
+    fn into_code(self, expected_state: &str) -> Result<AuthorizationCode> {
+        // anything can reach the loopback port, only a redirect with the state is trusted
+        let state = self
+            .query_value("state")
+            .ok_or_else(|| Error::message(ErrorKind::Credential, "query pair not found: state"))?;
+        if state != expected_state {
+            return Err(Error::message(
+                ErrorKind::Credential,
+                "the state returned by the authorization server does not match",
+            ));
+        }
+
+        if let Some(error) = self.query_value("error") {
+            let description = self.query_value("error_description").unwrap_or_default();
+            return Err(Error::with_message(ErrorKind::Credential, || {
+                format!("authorization failed: {}. {}", error, description)
+            }));
+        }
+
+        self.query_value("code")
+            .map(AuthorizationCode::new)
+            .ok_or_else(|| Error::message(ErrorKind::Credential, "query pair not found: code"))
+    }
+}
+
+/// Waits on a background thread for the browser to be redirected to the loopback listener
+async fn wait_for_redirect(listener: TcpListener, timeout: Duration) -> Result<Redirect> {
+    let (sender, receiver) = futures::channel::oneshot::channel();
+    std::thread::spawn(move || {
+        let _ = sender.send(accept_redirect(listener, timeout));
+    });
+    receiver
+        .await
+        .context(ErrorKind::Io, "the redirect listener stopped unexpectedly")?
+}
+
+fn accept_redirect(listener: TcpListener, timeout: Duration) -> Result<Redirect> {
+    listener.set_nonblocking(true).map_kind(ErrorKind::Io)?;
+    let deadline = Instant::now() + timeout;
+
+    loop {
+        let mut stream = match listener.accept() {
+            Ok((stream, _)) => stream,
+            Err(error) if error.kind() == IoErrorKind::WouldBlock => {
+                if Instant::now() > deadline {
+                    return Err(Error::message(
+                        ErrorKind::Credential,
+                        "timed out waiting for the user to sign in",
+                    ));
+                }
+                std::thread::sleep(Duration::from_millis(100));
+                continue;
+            }
+            Err(error) => return Err(Error::new(ErrorKind::Io, error)),
+        };
+        stream.set_nonblocking(false).map_kind(ErrorKind::Io)?;
+        // a connection that sends nothing must not keep the listener waiting past the deadline
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        stream
+            .set_read_timeout(Some(remaining.max(Duration::from_millis(1))))
+            .map_kind(ErrorKind::Io)?;
+
+        let mut request_line = String::new();
+        if let Err(error) = BufReader::new(&stream).read_line(&mut request_line) {
+            debug!("ignoring a connection to the redirect listener: {}", error);
+            continue;
+        }
+        let redirect = request_line
+            .split_whitespace()
+            .nth(1)
+            .and_then(|path| Url::parse(&format!("http://localhost{}", path)).ok())
+            .map(Redirect);
+
+        match redirect {
+            Some(redirect)
+                if redirect.query_value("code").is_some()
+                    || redirect.query_value("error").is_some() =>
+            {
+                let message = "Authentication complete. You can close this window now.";
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-length: {}\r\n\r\n{}",
+                    message.len(),
+                    message
+                );
+                let _ = stream.write_all(response.as_bytes());
+                return Ok(redirect);
+            }
+            // the browser may also ask for other resources, such as the favicon
+            _ => {
+                let _ = stream.write_all(b"HTTP/1.1 404 Not Found\r\ncontent-length: 0\r\n\r\n");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_endpoint::{MockEndpoint, MockResponse};
+    use std::io::Read;
+    use std::net::TcpStream;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    const CODE_RESPONSE: &str = r#"{"token_type":"Bearer","scope":"https://management.azure.com/.default","expires_in":3599,"ext_expires_in":3599,"access_token":"first_token","refresh_token":"first_refresh"}"#;
+    const REFRESH_RESPONSE: &str = r#"{"token_type":"Bearer","scope":"https://vault.azure.net/.default","expires_in":3599,"ext_expires_in":3599,"access_token":"second_token","refresh_token":"second_refresh"}"#;
+
+    /// Plays the browser: follows the redirect with the given query
+    fn redirect(url: &Url, query: impl Fn(&str) -> String) {
+        let redirect_uri = url
+            .query_pairs()
+            .find(|(key, _)| key == "redirect_uri")
+            .map(|(_, value)| Url::parse(&value).unwrap())
+            .unwrap();
+        let state = url
+            .query_pairs()
+            .find(|(key, _)| key == "state")
+            .map(|(_, value)| value.into_owned())
+            .unwrap();
+        let request = format!(
+            "GET /?{} HTTP/1.1\r\nhost: localhost\r\n\r\n",
+            query(&state)
+        );
+        let port = redirect_uri.port().unwrap();
+        std::thread::spawn(move || {
+            let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+            stream.write_all(request.as_bytes()).unwrap();
+            let mut response = String::new();
+            let _ = stream.read_to_string(&mut response);
+        });
+    }
+
+    #[tokio::test]
+    async fn signs_in_once_then_refreshes() {
+        let endpoint = MockEndpoint::start(vec![
+            MockResponse::new(200, CODE_RESPONSE),
+            MockResponse::new(200, REFRESH_RESPONSE),
+        ]);
+        let prompts = Arc::new(AtomicUsize::new(0));
+        let counter = prompts.clone();
+        let credential = InteractiveBrowserCredential::new(
+            "tenant".to_owned(),
+            "client".to_owned(),
+            TokenCredentialOptions::new(endpoint.url()),
+        )
+        .with_authorize_url_handler(move |url| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            let query = |key: &str| {
+                url.query_pairs()
+                    .find(|(k, _)| k == key)
+                    .map(|(_, value)| value.into_owned())
+            };
+            assert_eq!(query("code_challenge_method").unwrap(), "S256");
+            assert!(query("code_challenge").is_some());
+            assert_eq!(
+                query("scope").unwrap(),
+                "https://management.azure.com/.default offline_access"
+            );
+            redirect(url, |state| format!("code=the_code&state={}", state));
+            Ok(())
+        });
+
+        let token = credential
+            .get_token("https://management.azure.com")
+            .await
+            .unwrap();
+        assert_eq!(token.token.secret(), "first_token");
+        let token = credential
+            .get_token("https://management.azure.com")
+            .await
+            .unwrap();
+        assert_eq!(token.token.secret(), "first_token");
+        let token = credential
+            .get_token("https://vault.azure.net")
+            .await
+            .unwrap();
+        assert_eq!(token.token.secret(), "second_token");
+        assert_eq!(prompts.load(Ordering::SeqCst), 1);
+
+        let requests = endpoint.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(
+            requests[0].form_value("grant_type").unwrap(),
+            "authorization_code"
+        );
+        assert_eq!(requests[0].form_value("code").unwrap(), "the_code");
+        assert!(requests[0].form_value("code_verifier").is_some());
+        assert_eq!(
+            requests[1].form_value("grant_type").unwrap(),
+            "refresh_token"
+        );
+        assert_eq!(
+            requests[1].form_value("refresh_token").unwrap(),
+            "first_refresh"
+        );
+        assert_eq!(
+            requests[1].form_value("scope").unwrap(),
+            "https://vault.azure.net/.default offline_access"
+        );
+    }
+
+    #[test]
+    fn silent_connections_do_not_outlast_the_timeout() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let _silent = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+
+        let started = Instant::now();
+        let error = accept_redirect(listener, Duration::from_millis(300)).unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::Credential);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn rejects_mismatched_state() {
+        let credential = InteractiveBrowserCredential::new(
+            "tenant".to_owned(),
+            "client".to_owned(),
+            TokenCredentialOptions::default(),
+        )
+        .with_authorize_url_handler(|url| {
+            redirect(url, |_| "code=the_code&state=forged".to_owned());
+            Ok(())
+        });
+
+        let error = credential
+            .get_token("https://management.azure.com")
+            .await
+            .unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::Credential);
+    }
+
+    #[tokio::test]
+    async fn reports_authorization_errors() {
+        let credential = InteractiveBrowserCredential::new(
+            "tenant".to_owned(),
+            "client".to_owned(),
+            TokenCredentialOptions::default(),
+        )
+        .with_authorize_url_handler(|url| {
+            redirect(url, |state| {
+                format!(
+                    "error=access_denied&error_description=denied&state={}",
+                    state
+                )
+            });
+            Ok(())
+        });
+
+        let error = credential
+            .get_token("https://management.azure.com")
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("access_denied"));
+    }
+
+    #[test]
+    fn checks_the_state_before_reporting_errors() {
+        let redirect =
+            |query: &str| Redirect(Url::parse(&format!("http://localhost/?{}", query)).unwrap());
+
+        let error = redirect("error=access_denied")
+            .into_code("expected")
+            .unwrap_err();
+        assert!(!error.to_string().contains("access_denied"));
+        let error = redirect("error=access_denied&state=forged")
+            .into_code("expected")
+            .unwrap_err();
+        assert!(!error.to_string().contains("access_denied"));
+        let error = redirect("error=access_denied&state=expected")
+            .into_code("expected")
+            .unwrap_err();
+        assert!(error.to_string().contains("access_denied"));
+    }
+}
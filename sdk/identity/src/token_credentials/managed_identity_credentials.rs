@@ -0,0 +1,600 @@
+use super::imds_managed_identity_credentials::MsiTokenResponse;
+use super::ImdsManagedIdentityCredential;
+use azure_core::auth::{TokenCredential, TokenResponse};
+use azure_core::error::{Error, ErrorKind, Result, ResultExt};
+use log::debug;
+use reqwest::StatusCode;
+use std::path::{Path, PathBuf};
+use url::Url;
+
+const IDENTITY_ENDPOINT_ENV_KEY: &str = "IDENTITY_ENDPOINT";
+const IDENTITY_HEADER_ENV_KEY: &str = "IDENTITY_HEADER";
+const IDENTITY_SERVER_THUMBPRINT_ENV_KEY: &str = "IDENTITY_SERVER_THUMBPRINT";
+const IMDS_ENDPOINT_ENV_KEY: &str = "IMDS_ENDPOINT";
+const MSI_ENDPOINT_ENV_KEY: &str = "MSI_ENDPOINT";
+
+const ARC_API_VERSION: &str = "2020-06-01";
+#[cfg(feature = "service_fabric")]
+const SERVICE_FABRIC_API_VERSION: &str = "2019-07-01-preview";
+
+/// The largest secret file the Azure Arc agent is expected to write
+const ARC_MAX_KEY_FILE_SIZE: u64 = 4096;
+
+/// The user assigned identity to request a token for
+#[derive(Debug, Clone, PartialEq)]
+enum UserAssignedId {
+    Object(String),
+    Client(String),
+    Resource(String),
+}
+
+/// The managed identity endpoint available in the current environment
+#[derive(Debug, Clone, PartialEq)]
+enum ManagedIdentitySource {
+    AppService,
+    ServiceFabric {
+        endpoint: String,
+        secret: String,
+        thumbprint: String,
+    },
+    AzureArc {
+        endpoint: String,
+        key_directory: PathBuf,
+    },
+    CloudShell {
+        endpoint: String,
+    },
+    Imds,
+}
+
+impl ManagedIdentitySource {
+    /// Detects the source from the environment variables each hosting environment sets
+    fn detect<F>(var: F) -> Self
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        match (
+            var(IDENTITY_ENDPOINT_ENV_KEY),
+            var(IDENTITY_HEADER_ENV_KEY),
+            var(IDENTITY_SERVER_THUMBPRINT_ENV_KEY),
+            var(IMDS_ENDPOINT_ENV_KEY),
+            var(MSI_ENDPOINT_ENV_KEY),
+        ) {
+            (Some(endpoint), Some(secret), Some(thumbprint), _, _) => Self::ServiceFabric {
+                endpoint,
+                secret,
+                thumbprint,
+            },
+            (Some(_), Some(_), None, _, _) => Self::AppService,
+            (Some(endpoint), None, _, Some(_), _) => Self::AzureArc {
+                endpoint,
+                key_directory: arc_key_directory(),
+            },
+            (None, _, _, _, Some(endpoint)) => Self::CloudShell { endpoint },
+            _ => Self::Imds,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::AppService => "App Service",
+            Self::ServiceFabric { .. } => "Service Fabric",
+            Self::AzureArc { .. } => "Azure Arc",
+            Self::CloudShell { .. } => "Cloud Shell",
+            Self::Imds => "IMDS",
+        }
+    }
+}
+
+/// The directory the Azure Arc agent writes its challenge secrets to
+fn arc_key_directory() -> PathBuf {
+    if cfg!(target_os = "windows") {
+        let program_data =
+            std::env::var("ProgramData").unwrap_or_else(|_| "C:\\ProgramData".to_owned());
+        Path::new(&program_data)
+            .join("AzureConnectedMachineAgent")
+            .join("Tokens")
+    } else {
+        PathBuf::from("/var/opt/azcmagent/tokens")
+    }
+}
+
+/// Attempts authentication using a managed identity, detecting which managed identity endpoint
+/// is available in the deployment environment.
+///
+/// The following sources are supported, in order of precedence:
+///
+/// | Source          | Detected by                                                          | User assigned identity |
+/// |-----------------|----------------------------------------------------------------------|------------------------|
+/// | Service Fabric  | `IDENTITY_ENDPOINT`, `IDENTITY_HEADER`, `IDENTITY_SERVER_THUMBPRINT` | no                     |
+/// | App Service     | `IDENTITY_ENDPOINT`, `IDENTITY_HEADER`                               | yes                    |
+/// | Azure Arc       | `IDENTITY_ENDPOINT`, `IMDS_ENDPOINT`                                 | no                     |
+/// | Cloud Shell     | `MSI_ENDPOINT`                                                       | no                     |
+/// | IMDS            | fallback for Azure VMs and other Azure hosts                        | yes                    |
+///
+/// App Service and IMDS are handled by [`ImdsManagedIdentityCredential`]. Service Fabric pins
+/// the certificate of its endpoint with rustls, which the `service_fabric` feature enables.
+#[derive(Debug, Clone, Default)]
+pub struct ManagedIdentityCredential {
+    user_assigned_id: Option<UserAssignedId>,
+}
+
+impl ManagedIdentityCredential {
+    /// Create a new `ManagedIdentityCredential` using the system assigned identity.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Specifies the object id associated with a user assigned managed service identity resource that should be used to retrieve the access token.
+    pub fn with_object_id<A>(mut self, object_id: A) -> Self
+    where
+        A: Into<String>,
+    {
+        self.user_assigned_id = Some(UserAssignedId::Object(object_id.into()));
+        self
+    }
+
+    /// Specifies the application id (client id) associated with a user assigned managed service identity resource that should be used to retrieve the access token.
+    pub fn with_client_id<A>(mut self, client_id: A) -> Self
+    where
+        A: Into<String>,
+    {
+        self.user_assigned_id = Some(UserAssignedId::Client(client_id.into()));
+        self
+    }
+
+    /// Specifies the ARM resource id of the user assigned managed service identity resource that should be used to retrieve the access token.
+    pub fn with_identity<A>(mut self, msi_res_id: A) -> Self
+    where
+        A: Into<String>,
+    {
+        self.user_assigned_id = Some(UserAssignedId::Resource(msi_res_id.into()));
+        self
+    }
+
+    async fn get_token_from(
+        &self,
+        source: &ManagedIdentitySource,
+        resource: &str,
+    ) -> Result<TokenResponse> {
+        debug!("requesting managed identity token from {}", source.name());
+        match source {
+            ManagedIdentitySource::AppService | ManagedIdentitySource::Imds => {
+                self.imds_credential().get_token(resource).await
+            }
+            source if self.user_assigned_id.is_some() => {
+                Err(Error::with_message(ErrorKind::Credential, || {
+                    format!(
+                        "{} managed identity does not support user assigned identities",
+                        source.name()
+                    )
+                }))
+            }
+            ManagedIdentitySource::ServiceFabric {
+                endpoint,
+                secret,
+                thumbprint,
+            } => service_fabric_token(endpoint, secret, thumbprint, resource).await,
+            ManagedIdentitySource::AzureArc {
+                endpoint,
+                key_directory,
+            } => arc_token(endpoint, key_directory, resource).await,
+            ManagedIdentitySource::CloudShell { endpoint } => {
+                cloud_shell_token(endpoint, resource).await
+            }
+        }
+    }
+
+    fn imds_credential(&self) -> ImdsManagedIdentityCredential {
+        let credential = ImdsManagedIdentityCredential::default();
+        match &self.user_assigned_id {
+            Some(UserAssignedId::Object(id)) => credential.with_object_id(id),
+            Some(UserAssignedId::Client(id)) => credential.with_client_id(id),
+            Some(UserAssignedId::Resource(id)) => credential.with_identity(id),
+            None => credential,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl TokenCredential for ManagedIdentityCredential {
+    async fn get_token(&self, resource: &str) -> Result<TokenResponse> {
+        let source = ManagedIdentitySource::detect(|key| std::env::var(key).ok());
+        self.get_token_from(&source, resource).await
+    }
+}
+
+/// Azure Arc answers the first request with a challenge naming a file only a local
+/// administrator can read. The content of that file authenticates the second request.
+async fn arc_token(endpoint: &str, key_directory: &Path, resource: &str) -> Result<TokenResponse> {
+    let url = Url::parse_with_params(
+        endpoint,
+        &[("api-version", ARC_API_VERSION), ("resource", resource)],
+    )
+    .context(
+        ErrorKind::DataConversion,
+        "error parsing url for Azure Arc endpoint",
+    )?;
+
+    let client = reqwest::Client::new();
+    let challenge = client
+        .get(url.clone())
+        .header("Metadata", "true")
+        .send()
+        .await
+        .map_kind(ErrorKind::Io)?;
+    if challenge.status() != StatusCode::UNAUTHORIZED {
+        return Err(Error::with_message(ErrorKind::Credential, || {
+            format!(
+                "expected a 401 challenge from the Azure Arc endpoint, got {}",
+                challenge.status()
+            )
+        }));
+    }
+
+    let realm = challenge
+        .headers()
+        .get("WWW-Authenticate")
+        .and_then(|header| header.to_str().ok())
+        .and_then(|header| header.split_once("Basic realm="))
+        .map(|(_, realm)| realm.trim().to_owned())
+        .ok_or_else(|| {
+            Error::message(
+                ErrorKind::Credential,
+                "the Azure Arc challenge did not contain a Basic realm",
+            )
+        })?;
+    let key = read_arc_key(Path::new(&realm), key_directory)?;
+
+    let response = client
+        .get(url)
+        .header("Metadata", "true")
+        .header("Authorization", format!("Basic {}", key))
+        .send()
+        .await
+        .map_kind(ErrorKind::Io)?;
+    parse_token_response(response).await
+}
+
+/// Reads the challenge secret, refusing files the Azure Arc agent would not have written
+fn read_arc_key(path: &Path, key_directory: &Path) -> Result<String> {
+    if path.parent() != Some(key_directory) {
+        return Err(Error::with_message(ErrorKind::Credential, || {
+            format!(
+                "the Azure Arc challenge file {} is not in {}",
+                path.display(),
+                key_directory.display()
+            )
+        }));
+    }
+    if path.extension().and_then(|extension| extension.to_str()) != Some("key") {
+        return Err(Error::with_message(ErrorKind::Credential, || {
+            format!(
+                "the Azure Arc challenge file {} is not a .key file",
+                path.display()
+            )
+        }));
+    }
+    let metadata = std::fs::metadata(path).with_context(ErrorKind::Credential, || {
+        format!(
+            "unable to read the Azure Arc challenge file {}",
+            path.display()
+        )
+    })?;
+    if metadata.len() > ARC_MAX_KEY_FILE_SIZE {
+        return Err(Error::with_message(ErrorKind::Credential, || {
+            format!(
+                "the Azure Arc challenge file {} is larger than {} bytes",
+                path.display(),
+                ARC_MAX_KEY_FILE_SIZE
+            )
+        }));
+    }
+    std::fs::read_to_string(path).with_context(ErrorKind::Credential, || {
+        format!(
+            "unable to read the Azure Arc challenge file {}",
+            path.display()
+        )
+    })
+}
+
+/// Service Fabric serves the token endpoint with a self-signed certificate whose thumbprint is
+/// given to the application. The thumbprint is checked during the TLS handshake, so the secret
+/// is only ever sent to the server holding that certificate.
+#[cfg(feature = "service_fabric")]
+async fn service_fabric_token(
+    endpoint: &str,
+    secret: &str,
+    thumbprint: &str,
+    resource: &str,
+) -> Result<TokenResponse> {
+    let url = Url::parse_with_params(
+        endpoint,
+        &[
+            ("api-version", SERVICE_FABRIC_API_VERSION),
+            ("resource", resource),
+        ],
+    )
+    .context(
+        ErrorKind::DataConversion,
+        "error parsing url for Service Fabric endpoint",
+    )?;
+
+    let tls = rustls::ClientConfig::builder()
+        .with_safe_defaults()
+        .with_custom_certificate_verifier(std::sync::Arc::new(ThumbprintVerifier {
+            thumbprint: thumbprint.to_owned(),
+        }))
+        .with_no_client_auth();
+    let client = reqwest::Client::builder()
+        .use_preconfigured_tls(tls)
+        .build()
+        .context(ErrorKind::Io, "failed to build the Service Fabric client")?;
+
+    let response = client
+        .get(url)
+        .header("secret", secret)
+        .send()
+        .await
+        .context(
+            ErrorKind::Io,
+            "failed to reach the Service Fabric endpoint, its certificate must match IDENTITY_SERVER_THUMBPRINT",
+        )?;
+    parse_token_response(response).await
+}
+
+#[cfg(not(feature = "service_fabric"))]
+async fn service_fabric_token(
+    _endpoint: &str,
+    _secret: &str,
+    _thumbprint: &str,
+    _resource: &str,
+) -> Result<TokenResponse> {
+    Err(Error::message(
+        ErrorKind::Credential,
+        "Service Fabric managed identity requires the service_fabric feature",
+    ))
+}
+
+/// Accepts the server certificate only if its thumbprint is the expected one
+#[cfg(feature = "service_fabric")]
+struct ThumbprintVerifier {
+    thumbprint: String,
+}
+
+#[cfg(feature = "service_fabric")]
+impl rustls::client::ServerCertVerifier for ThumbprintVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &rustls::Certificate,
+        _intermediates: &[rustls::Certificate],
+        _server_name: &rustls::ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        _now: std::time::SystemTime,
+    ) -> std::result::Result<rustls::client::ServerCertVerified, rustls::Error> {
+        if thumbprint_matches(&end_entity.0, &self.thumbprint) {
+            Ok(rustls::client::ServerCertVerified::assertion())
+        } else {
+            Err(rustls::Error::General(
+                "the Service Fabric endpoint certificate does not match IDENTITY_SERVER_THUMBPRINT"
+                    .to_owned(),
+            ))
+        }
+    }
+}
+
+/// Compares the SHA-1 thumbprint of a DER encoded certificate with a hex encoded thumbprint
+#[cfg(feature = "service_fabric")]
+fn thumbprint_matches(certificate: &[u8], thumbprint: &str) -> bool {
+    use sha1::{Digest, Sha1};
+    let digest = Sha1::digest(certificate);
+    let actual: String = digest.iter().map(|byte| format!("{:02X}", byte)).collect();
+    actual.eq_ignore_ascii_case(thumbprint.trim())
+}
+
+async fn cloud_shell_token(endpoint: &str, resource: &str) -> Result<TokenResponse> {
+    let response = reqwest::Client::new()
+        .post(endpoint)
+        .header("Metadata", "true")
+        .form(&[("resource", resource)])
+        .send()
+        .await
+        .map_kind(ErrorKind::Io)?;
+    parse_token_response(response).await
+}
+
+async fn parse_token_response(response: reqwest::Response) -> Result<TokenResponse> {
+    let rsp_status = response.status();
+    let rsp_body = response.bytes().await.map_kind(ErrorKind::Io)?;
+    if !rsp_status.is_success() {
+        return Err(
+            ErrorKind::http_response_from_body(rsp_status.as_u16(), &rsp_body).into_error(),
+        )
+        .map_kind(ErrorKind::Credential);
+    }
+    let token_response: MsiTokenResponse =
+        serde_json::from_slice(&rsp_body).map_kind(ErrorKind::DataConversion)?;
+    Ok(TokenResponse::new(
+        token_response.access_token,
+        token_response.expires_on,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_endpoint::{MockEndpoint, MockResponse};
+    use std::collections::HashMap;
+
+    const TOKEN_RESPONSE: &str = r#"{"access_token":"secret_token","expires_on":"1586984735","resource":"https://management.azure.com","token_type":"Bearer"}"#;
+
+    fn detect(vars: &[(&str, &str)]) -> ManagedIdentitySource {
+        let vars: HashMap<_, _> = vars.iter().cloned().collect();
+        ManagedIdentitySource::detect(|key| vars.get(key).map(|value| value.to_string()))
+    }
+
+    #[test]
+    fn detects_source_from_environment() {
+        assert_eq!(detect(&[]), ManagedIdentitySource::Imds);
+        assert_eq!(
+            detect(&[
+                (
+                    IDENTITY_ENDPOINT_ENV_KEY,
+                    "http://localhost:42356/msi/token"
+                ),
+                (IDENTITY_HEADER_ENV_KEY, "header"),
+            ]),
+            ManagedIdentitySource::AppService
+        );
+        assert_eq!(
+            detect(&[
+                (
+                    IDENTITY_ENDPOINT_ENV_KEY,
+                    "https://10.0.0.4:2377/metadata/identity"
+                ),
+                (IDENTITY_HEADER_ENV_KEY, "header"),
+                (IDENTITY_SERVER_THUMBPRINT_ENV_KEY, "ABCDEF"),
+            ]),
+            ManagedIdentitySource::ServiceFabric {
+                endpoint: "https://10.0.0.4:2377/metadata/identity".to_owned(),
+                secret: "header".to_owned(),
+                thumbprint: "ABCDEF".to_owned(),
+            }
+        );
+        assert_eq!(
+            detect(&[
+                (
+                    IDENTITY_ENDPOINT_ENV_KEY,
+                    "http://localhost:40342/metadata/identity/oauth2/token"
+                ),
+                (IMDS_ENDPOINT_ENV_KEY, "http://localhost:40342"),
+            ]),
+            ManagedIdentitySource::AzureArc {
+                endpoint: "http://localhost:40342/metadata/identity/oauth2/token".to_owned(),
+                key_directory: arc_key_directory(),
+            }
+        );
+        assert_eq!(
+            detect(&[(MSI_ENDPOINT_ENV_KEY, "http://localhost:50342/oauth2/token")]),
+            ManagedIdentitySource::CloudShell {
+                endpoint: "http://localhost:50342/oauth2/token".to_owned(),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn azure_arc_answers_the_challenge() {
+        let key_directory = std::env::temp_dir().join(format!("arc-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&key_directory).unwrap();
+        let key_file = key_directory.join("challenge.key");
+        std::fs::write(&key_file, "challenge_secret").unwrap();
+
+        let endpoint = MockEndpoint::start(vec![
+            MockResponse::new(401, "").with_header(
+                "WWW-Authenticate",
+                &format!("Basic realm={}", key_file.display()),
+            ),
+            MockResponse::new(200, TOKEN_RESPONSE),
+        ]);
+        let source = ManagedIdentitySource::AzureArc {
+            endpoint: format!("{}/metadata/identity/oauth2/token", endpoint.url()),
+            key_directory: key_directory.clone(),
+        };
+
+        let token = ManagedIdentityCredential::new()
+            .get_token_from(&source, "https://management.azure.com")
+            .await
+            .unwrap();
+        std::fs::remove_dir_all(&key_directory).unwrap();
+        assert_eq!(token.token.secret(), "secret_token");
+
+        let requests = endpoint.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].header("Metadata"), Some("true"));
+        assert_eq!(requests[0].header("Authorization"), None);
+        assert_eq!(
+            requests[0].query_value("api-version").unwrap(),
+            ARC_API_VERSION
+        );
+        assert_eq!(
+            requests[1].header("Authorization"),
+            Some("Basic challenge_secret")
+        );
+    }
+
+    #[test]
+    fn azure_arc_rejects_files_outside_the_key_directory() {
+        let key_directory = arc_key_directory();
+        assert!(read_arc_key(Path::new("/etc/passwd"), &key_directory).is_err());
+        assert!(read_arc_key(&key_directory.join("secret.txt"), &key_directory).is_err());
+    }
+
+    #[tokio::test]
+    async fn cloud_shell_posts_the_resource() {
+        let endpoint = MockEndpoint::start(vec![MockResponse::new(200, TOKEN_RESPONSE)]);
+        let source = ManagedIdentitySource::CloudShell {
+            endpoint: format!("{}/oauth2/token", endpoint.url()),
+        };
+
+        let token = ManagedIdentityCredential::new()
+            .get_token_from(&source, "https://management.azure.com")
+            .await
+            .unwrap();
+        assert_eq!(token.token.secret(), "secret_token");
+
+        let requests = endpoint.requests();
+        assert_eq!(requests[0].method, "POST");
+        assert_eq!(requests[0].header("Metadata"), Some("true"));
+        assert_eq!(
+            requests[0].form_value("resource").unwrap(),
+            "https://management.azure.com"
+        );
+    }
+
+    #[tokio::test]
+    async fn user_assigned_ids_are_rejected_where_unsupported() {
+        let source = ManagedIdentitySource::CloudShell {
+            endpoint: "http://127.0.0.1:1/oauth2/token".to_owned(),
+        };
+        let error = ManagedIdentityCredential::new()
+            .with_client_id("client")
+            .get_token_from(&source, "https://management.azure.com")
+            .await
+            .unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::Credential);
+    }
+
+    #[cfg(feature = "service_fabric")]
+    #[test]
+    fn thumbprint_comparison_ignores_case() {
+        // SHA-1 of the bytes "abc"
+        let thumbprint = "A9993E364706816ABA3E25717850C26C9CD0D89D";
+        assert!(thumbprint_matches(b"abc", thumbprint));
+        assert!(thumbprint_matches(b"abc", &thumbprint.to_lowercase()));
+        assert!(!thumbprint_matches(b"abd", thumbprint));
+    }
+
+    #[cfg(feature = "service_fabric")]
+    #[test]
+    fn handshake_rejects_certificates_with_another_thumbprint() {
+        use rustls::client::ServerCertVerifier;
+
+        let verifier = ThumbprintVerifier {
+            thumbprint: "A9993E364706816ABA3E25717850C26C9CD0D89D".to_owned(),
+        };
+        let server_name = rustls::ServerName::try_from("localhost").unwrap();
+        let verify = |certificate: &[u8]| {
+            verifier.verify_server_cert(
+                &rustls::Certificate(certificate.to_vec()),
+                &[],
+                &server_name,
+                &mut std::iter::empty(),
+                &[],
+                std::time::SystemTime::now(),
+            )
+        };
+        assert!(verify(b"abc").is_ok());
+        assert!(verify(b"abd").is_err());
+    }
+}
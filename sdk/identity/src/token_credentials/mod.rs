@@ -3,22 +3,50 @@
 //! Supported means currently include:
 //! * The environment
 //! * Azure CLI credentials cache
+//! * Azure Developer CLI
+//! * Azure PowerShell
 //! * Managed identity
 //! * Client secret
+//! * Client assertion
+//! * Interactive browser sign in
+//! * Device code sign in
+//! * On-behalf-of flow
+//! * Username and password
+//! * A chain of any of the above
 mod auto_refreshing_credentials;
 mod azure_cli_credentials;
+mod azure_developer_cli_credentials;
+mod azure_powershell_credentials;
+mod chained_credentials;
+mod cli_command;
+mod client_assertion_credentials;
 #[cfg(feature = "client_certificate")]
 mod client_certificate_credentials;
 mod client_secret_credentials;
 mod default_credentials;
+mod device_code_credentials;
 mod environment_credentials;
 mod imds_managed_identity_credentials;
+mod interactive_browser_credentials;
+mod managed_identity_credentials;
+mod on_behalf_of_credentials;
+mod user_token_cache;
+mod username_password_credentials;
 
 pub use auto_refreshing_credentials::*;
 pub use azure_cli_credentials::*;
+pub use azure_developer_cli_credentials::*;
+pub use azure_powershell_credentials::*;
+pub use chained_credentials::*;
+pub use client_assertion_credentials::*;
 #[cfg(feature = "client_certificate")]
 pub use client_certificate_credentials::*;
 pub use client_secret_credentials::*;
 pub use default_credentials::*;
+pub use device_code_credentials::*;
 pub use environment_credentials::*;
 pub use imds_managed_identity_credentials::*;
+pub use interactive_browser_credentials::*;
+pub use managed_identity_credentials::*;
+pub use on_behalf_of_credentials::*;
+pub use username_password_credentials::*;
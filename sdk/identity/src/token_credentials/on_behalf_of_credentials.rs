@@ -0,0 +1,265 @@
+use super::client_assertion_credentials::{assertion_callback, AssertionCallback};
+use super::TokenCredentialOptions;
+#[cfg(feature = "client_certificate")]
+use super::{CertificateCredentialOptions, ClientCertificateCredential};
+use crate::client_credentials_flow::{self, JWT_BEARER_ASSERTION_TYPE};
+use azure_core::auth::{TokenCredential, TokenResponse};
+use azure_core::error::{ErrorKind, Result, ResultExt};
+use chrono::Utc;
+use std::future::Future;
+
+/// The grant type of the on-behalf-of flow
+const JWT_BEARER_GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:jwt-bearer";
+
+/// How the middle-tier application authenticates itself
+enum ClientAuthentication {
+    Secret(oauth2::ClientSecret),
+    Assertion(Box<AssertionCallback>),
+}
+
+/// Enables authentication to Azure Active Directory using the on-behalf-of flow.
+///
+/// A middle-tier service uses the access token it received from its caller (the user assertion)
+/// to request a token for a downstream API, on behalf of the same user. The service
+/// authenticates itself with a client secret, a client certificate or a client assertion.
+///
+/// You can learn more about this authorization flow [here](https://docs.microsoft.com/azure/active-directory/develop/v2-oauth2-on-behalf-of-flow).
+pub struct OnBehalfOfCredential {
+    tenant_id: String,
+    client_id: oauth2::ClientId,
+    client_authentication: ClientAuthentication,
+    user_assertion: String,
+    options: TokenCredentialOptions,
+}
+
+impl OnBehalfOfCredential {
+    /// Create a new OnBehalfOfCredential authenticating the application with a client secret
+    pub fn new(
+        tenant_id: String,
+        client_id: String,
+        client_secret: String,
+        user_assertion: String,
+        options: TokenCredentialOptions,
+    ) -> OnBehalfOfCredential {
+        OnBehalfOfCredential {
+            tenant_id,
+            client_id: oauth2::ClientId::new(client_id),
+            client_authentication: ClientAuthentication::Secret(oauth2::ClientSecret::new(
+                client_secret,
+            )),
+            user_assertion,
+            options,
+        }
+    }
+
+    /// Create a new OnBehalfOfCredential authenticating the application with a client assertion
+    ///
+    /// `assertion` is called for every token request, see [`super::ClientAssertionCredential`].
+    pub fn with_assertion<F, Fut>(
+        tenant_id: String,
+        client_id: String,
+        assertion: F,
+        user_assertion: String,
+        options: TokenCredentialOptions,
+    ) -> OnBehalfOfCredential
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String>> + Send + 'static,
+    {
+        OnBehalfOfCredential {
+            tenant_id,
+            client_id: oauth2::ClientId::new(client_id),
+            client_authentication: ClientAuthentication::Assertion(assertion_callback(assertion)),
+            user_assertion,
+            options,
+        }
+    }
+
+    /// Create a new OnBehalfOfCredential authenticating the application with a client certificate
+    ///
+    /// The certificate is expected to be in base64 encoded PKCS12 format.
+    #[cfg(feature = "client_certificate")]
+    pub fn with_certificate(
+        tenant_id: String,
+        client_id: String,
+        client_certificate: String,
+        client_certificate_pass: String,
+        user_assertion: String,
+        options: CertificateCredentialOptions,
+    ) -> OnBehalfOfCredential {
+        let token_url = format!(
+            "{}/{}/oauth2/v2.0/token",
+            options.authority_host(),
+            tenant_id
+        );
+        let assertion_client_id = client_id.clone();
+        let send_certificate_chain = options.send_certificate_chain();
+        Self::with_assertion(
+            tenant_id,
+            client_id,
+            move || {
+                futures::future::ready(ClientCertificateCredential::build_assertion(
+                    &token_url,
+                    &assertion_client_id,
+                    &client_certificate,
+                    &client_certificate_pass,
+                    send_certificate_chain,
+                ))
+            },
+            user_assertion,
+            TokenCredentialOptions::new(options.authority_host().to_owned()),
+        )
+    }
+
+    fn options(&self) -> &TokenCredentialOptions {
+        &self.options
+    }
+}
+
+impl std::fmt::Debug for OnBehalfOfCredential {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("OnBehalfOfCredential")
+            .field("tenant_id", &self.tenant_id)
+            .field("client_id", &self.client_id)
+            .field("options", &self.options)
+            .finish()
+    }
+}
+
+#[async_trait::async_trait]
+impl TokenCredential for OnBehalfOfCredential {
+    async fn get_token(&self, resource: &str) -> Result<TokenResponse> {
+        let scope = format!("{}/.default", resource);
+        let mut form = vec![
+            ("client_id", self.client_id.as_str()),
+            ("grant_type", JWT_BEARER_GRANT_TYPE),
+            ("requested_token_use", "on_behalf_of"),
+            ("assertion", self.user_assertion.as_str()),
+            ("scope", scope.as_str()),
+        ];
+
+        let client_assertion;
+        match &self.client_authentication {
+            ClientAuthentication::Secret(client_secret) => {
+                form.push(("client_secret", client_secret.secret()))
+            }
+            ClientAuthentication::Assertion(assertion) => {
+                client_assertion = assertion()
+                    .await
+                    .context(ErrorKind::Credential, "failed to obtain a client assertion")?;
+                form.push(("client_assertion_type", JWT_BEARER_ASSERTION_TYPE));
+                form.push(("client_assertion", client_assertion.as_str()));
+            }
+        }
+
+        let response = client_credentials_flow::request_token(
+            &reqwest::Client::new(),
+            self.options().authority_host(),
+            &self.tenant_id,
+            &form,
+        )
+        .await
+        .context(ErrorKind::Credential, "request token error")?;
+
+        Ok(TokenResponse::new(
+            response.access_token,
+            Utc::now() + chrono::Duration::seconds(response.expires_in as i64),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_endpoint::{MockEndpoint, MockResponse};
+
+    const TOKEN_RESPONSE: &str = r#"{"token_type":"Bearer","scope":"https://graph.microsoft.com/.default","expires_in":3599,"ext_expires_in":3599,"access_token":"downstream_token","refresh_token":"refresh"}"#;
+
+    #[tokio::test]
+    async fn exchanges_user_assertion_with_client_secret() {
+        let endpoint = MockEndpoint::start(vec![MockResponse::new(200, TOKEN_RESPONSE)]);
+        let credential = OnBehalfOfCredential::new(
+            "tenant".to_owned(),
+            "client".to_owned(),
+            "secret".to_owned(),
+            "incoming.user.token".to_owned(),
+            TokenCredentialOptions::new(endpoint.url()),
+        );
+
+        let token = credential
+            .get_token("https://graph.microsoft.com")
+            .await
+            .unwrap();
+        assert_eq!(token.token.secret(), "downstream_token");
+
+        let request = &endpoint.requests()[0];
+        assert_eq!(request.path, "/tenant/oauth2/v2.0/token");
+        assert_eq!(
+            request.form_value("grant_type").unwrap(),
+            JWT_BEARER_GRANT_TYPE
+        );
+        assert_eq!(
+            request.form_value("requested_token_use").unwrap(),
+            "on_behalf_of"
+        );
+        assert_eq!(
+            request.form_value("assertion").unwrap(),
+            "incoming.user.token"
+        );
+        assert_eq!(request.form_value("client_secret").unwrap(), "secret");
+        assert_eq!(request.form_value("client_assertion"), None);
+        assert_eq!(
+            request.form_value("scope").unwrap(),
+            "https://graph.microsoft.com/.default"
+        );
+    }
+
+    #[tokio::test]
+    async fn exchanges_user_assertion_with_client_assertion() {
+        let endpoint = MockEndpoint::start(vec![MockResponse::new(200, TOKEN_RESPONSE)]);
+        let credential = OnBehalfOfCredential::with_assertion(
+            "tenant".to_owned(),
+            "client".to_owned(),
+            || async { Ok("client.assertion".to_owned()) },
+            "incoming.user.token".to_owned(),
+            TokenCredentialOptions::new(endpoint.url()),
+        );
+
+        credential
+            .get_token("https://graph.microsoft.com")
+            .await
+            .unwrap();
+
+        let request = &endpoint.requests()[0];
+        assert_eq!(request.form_value("client_secret"), None);
+        assert_eq!(
+            request.form_value("client_assertion").unwrap(),
+            "client.assertion"
+        );
+        assert_eq!(
+            request.form_value("client_assertion_type").unwrap(),
+            JWT_BEARER_ASSERTION_TYPE
+        );
+    }
+
+    #[tokio::test]
+    async fn token_endpoint_errors_are_credential_errors() {
+        let endpoint = MockEndpoint::start(vec![MockResponse::new(
+            400,
+            r#"{"error":"invalid_grant","error_description":"AADSTS50013: Assertion failed signature validation."}"#,
+        )]);
+        let credential = OnBehalfOfCredential::new(
+            "tenant".to_owned(),
+            "client".to_owned(),
+            "secret".to_owned(),
+            "tampered.user.token".to_owned(),
+            TokenCredentialOptions::new(endpoint.url()),
+        );
+
+        let error = credential
+            .get_token("https://graph.microsoft.com")
+            .await
+            .unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::Credential);
+    }
+}
@@ -0,0 +1,130 @@
+use super::auto_refreshing_credentials::is_expired;
+use crate::refresh_token;
+use crate::token_cache::{
+    CachedToken, InMemoryTokenCacheStorage, TokenCacheKey, TokenCacheStorage,
+};
+use azure_core::auth::TokenResponse;
+use chrono::Utc;
+use log::debug;
+use oauth2::{AccessToken, ClientId};
+use std::sync::Arc;
+
+/// The scope requested alongside the resource scope so that a refresh token is issued
+pub(crate) const OFFLINE_ACCESS_SCOPE: &str = "offline_access";
+
+/// Caches the tokens a user credential obtained, per scope, and silently refreshes them
+///
+/// Azure AD refresh tokens are valid for every resource the user consented to, so a refresh token
+/// obtained for one scope is also used to get tokens for other scopes without prompting again.
+///
+/// Storage errors are logged and treated as cache misses, so a broken persistent cache only
+/// means that the user has to sign in again.
+#[derive(Debug)]
+pub(crate) struct UserTokenCache {
+    authority_host: String,
+    tenant_id: String,
+    client_id: ClientId,
+    storage: Arc<dyn TokenCacheStorage>,
+}
+
+impl UserTokenCache {
+    pub fn new(authority_host: String, tenant_id: String, client_id: ClientId) -> Self {
+        Self {
+            authority_host,
+            tenant_id,
+            client_id,
+            storage: Arc::new(InMemoryTokenCacheStorage::new()),
+        }
+    }
+
+    /// Replaces the storage backend of the cache
+    pub fn set_storage(&mut self, storage: Arc<dyn TokenCacheStorage>) {
+        self.storage = storage;
+    }
+
+    fn key(&self, scope: &str) -> TokenCacheKey {
+        TokenCacheKey::new(
+            self.authority_host.as_str(),
+            self.tenant_id.as_str(),
+            self.client_id.as_str(),
+            scope,
+        )
+    }
+
+    /// Returns a valid access token for the scope without user interaction, if possible
+    pub async fn get_token_silently(&self, scope: &str) -> Option<TokenResponse> {
+        let key = self.key(scope);
+        let cached = self.storage.get(&key).await.unwrap_or_else(|error| {
+            debug!("reading the token cache failed: {}", error);
+            None
+        });
+        if let Some(cached) = &cached {
+            let access_token = TokenResponse::new(cached.access_token.clone(), cached.expires_on);
+            if !is_expired(&access_token) {
+                return Some(access_token);
+            }
+        }
+
+        let refresh_token = match cached.and_then(|cached| cached.refresh_token) {
+            Some(refresh_token) => refresh_token,
+            None => self
+                .storage
+                .entries()
+                .await
+                .unwrap_or_else(|error| {
+                    debug!("reading the token cache failed: {}", error);
+                    Vec::new()
+                })
+                .into_iter()
+                .filter(|(other, _)| other.same_client(&key))
+                .find_map(|(_, cached)| cached.refresh_token)?,
+        };
+
+        let response = refresh_token::exchange_for_scope(
+            &reqwest::Client::new(),
+            &self.authority_host,
+            &self.tenant_id,
+            &self.client_id,
+            None,
+            &refresh_token,
+            Some(&format!("{} {}", scope, OFFLINE_ACCESS_SCOPE)),
+        )
+        .await;
+
+        match response {
+            Ok(response) => Some(
+                self.insert(
+                    scope,
+                    response.access_token().clone(),
+                    response.expires_in(),
+                    Some(response.refresh_token().clone()),
+                )
+                .await,
+            ),
+            Err(error) => {
+                debug!("refreshing the token for {} failed: {}", scope, error);
+                None
+            }
+        }
+    }
+
+    /// Stores the tokens obtained for a scope and returns the access token
+    pub async fn insert(
+        &self,
+        scope: &str,
+        access_token: AccessToken,
+        expires_in: u64,
+        refresh_token: Option<AccessToken>,
+    ) -> TokenResponse {
+        let expires_on = Utc::now() + chrono::Duration::seconds(expires_in as i64);
+        let cached = CachedToken {
+            access_token: access_token.clone(),
+            expires_on,
+            refresh_token,
+        };
+        if let Err(error) = self.storage.set(&self.key(scope), &cached).await {
+            debug!("writing the token cache failed: {}", error);
+        }
+        TokenResponse::new(access_token, expires_on)
+    }
+}
@@ -0,0 +1,134 @@
+use super::TokenCredentialOptions;
+use crate::client_credentials_flow;
+use azure_core::auth::{TokenCredential, TokenResponse};
+use azure_core::error::{ErrorKind, Result, ResultExt};
+use chrono::Utc;
+
+/// Enables authentication to Azure Active Directory using a user's username and password.
+///
+/// This uses the resource owner password credentials (ROPC) flow, which does not support multi
+/// factor authentication, personal Microsoft accounts or federated users. Prefer an interactive
+/// or device code credential wherever possible.
+///
+/// You can learn more about this authorization flow [here](https://docs.microsoft.com/azure/active-directory/develop/v2-oauth-ropc).
+pub struct UsernamePasswordCredential {
+    tenant_id: String,
+    client_id: oauth2::ClientId,
+    username: String,
+    password: oauth2::ClientSecret,
+    options: TokenCredentialOptions,
+}
+
+impl UsernamePasswordCredential {
+    /// Create a new UsernamePasswordCredential
+    pub fn new(
+        tenant_id: String,
+        client_id: String,
+        username: String,
+        password: String,
+        options: TokenCredentialOptions,
+    ) -> UsernamePasswordCredential {
+        UsernamePasswordCredential {
+            tenant_id,
+            client_id: oauth2::ClientId::new(client_id),
+            username,
+            password: oauth2::ClientSecret::new(password),
+            options,
+        }
+    }
+
+    fn options(&self) -> &TokenCredentialOptions {
+        &self.options
+    }
+}
+
+impl std::fmt::Debug for UsernamePasswordCredential {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("UsernamePasswordCredential")
+            .field("tenant_id", &self.tenant_id)
+            .field("client_id", &self.client_id)
+            .field("username", &self.username)
+            .field("options", &self.options)
+            .finish()
+    }
+}
+
+#[async_trait::async_trait]
+impl TokenCredential for UsernamePasswordCredential {
+    async fn get_token(&self, resource: &str) -> Result<TokenResponse> {
+        let response = client_credentials_flow::request_token(
+            &reqwest::Client::new(),
+            self.options().authority_host(),
+            &self.tenant_id,
+            &[
+                ("client_id", self.client_id.as_str()),
+                ("grant_type", "password"),
+                ("username", &self.username),
+                ("password", self.password.secret()),
+                ("scope", &format!("{}/.default", resource)),
+            ],
+        )
+        .await
+        .context(ErrorKind::Credential, "request token error")?;
+
+        Ok(TokenResponse::new(
+            response.access_token,
+            Utc::now() + chrono::Duration::seconds(response.expires_in as i64),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_endpoint::{MockEndpoint, MockResponse};
+
+    #[tokio::test]
+    async fn sends_username_and_password() {
+        let endpoint = MockEndpoint::start(vec![MockResponse::new(
+            200,
+            r#"{"token_type":"Bearer","scope":"https://management.azure.com/.default","expires_in":3599,"ext_expires_in":3599,"access_token":"user_token"}"#,
+        )]);
+        let credential = UsernamePasswordCredential::new(
+            "tenant".to_owned(),
+            "client".to_owned(),
+            "user@contoso.com".to_owned(),
+            "p@ss&word".to_owned(),
+            TokenCredentialOptions::new(endpoint.url()),
+        );
+
+        let token = credential
+            .get_token("https://management.azure.com")
+            .await
+            .unwrap();
+        assert_eq!(token.token.secret(), "user_token");
+
+        let request = &endpoint.requests()[0];
+        assert_eq!(request.path, "/tenant/oauth2/v2.0/token");
+        assert_eq!(request.form_value("grant_type").unwrap(), "password");
+        assert_eq!(request.form_value("username").unwrap(), "user@contoso.com");
+        assert_eq!(request.form_value("password").unwrap(), "p@ss&word");
+        assert_eq!(request.form_value("client_id").unwrap(), "client");
+    }
+
+    #[tokio::test]
+    async fn invalid_credentials_are_credential_errors() {
+        let endpoint = MockEndpoint::start(vec![MockResponse::new(
+            400,
+            r#"{"error":"invalid_grant","error_description":"AADSTS50126: Error validating credentials due to invalid username or password."}"#,
+        )]);
+        let credential = UsernamePasswordCredential::new(
+            "tenant".to_owned(),
+            "client".to_owned(),
+            "user@contoso.com".to_owned(),
+            "wrong".to_owned(),
+            TokenCredentialOptions::new(endpoint.url()),
+        );
+
+        let error = credential
+            .get_token("https://management.azure.com")
+            .await
+            .unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::Credential);
+    }
+}